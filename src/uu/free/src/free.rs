@@ -8,6 +8,7 @@ mod windows_util;
 
 use bytesize::{ByteSize, GB, GIB, KB, KIB, MB, MIB, PB, PIB, TB, TIB};
 use clap::{arg, crate_version, ArgAction, ArgGroup, ArgMatches, Command};
+use std::cell::Cell;
 use std::env;
 
 #[cfg(target_os = "linux")]
@@ -26,7 +27,10 @@ use uucore::{
 const ABOUT: &str = help_about!("free.md");
 const USAGE: &str = help_usage!("free.md");
 
-/// The unit of number is [UnitMultiplier::Bytes]
+/// Width of the columns of values, unless the totals need more
+const COLUMN_WIDTH: usize = 12;
+
+/// The unit of number is KiB, as in `/proc/meminfo`
 #[derive(Default, Clone)]
 struct MemInfo {
     total: u64,
@@ -45,13 +49,35 @@ struct MemInfo {
     high_free: u64,
     commit_limit: u64,
     committed: u64,
+    active_file: u64,
+    inactive_file: u64,
+    kernel_reclaimable: u64,
+    zone_watermarks: Option<ZoneWatermarks>,
+}
+
+/// Sums over all memory zones, see `calculate_totalreserve_pages()` and `si_mem_available()` in
+/// the kernel sources. The unit of number is KiB, like [MemInfo]
+#[derive(Default, Clone)]
+struct ZoneWatermarks {
+    low: u64,
+    reserved: u64,
 }
 
 #[cfg(target_os = "linux")]
 fn parse_meminfo() -> Result<MemInfo, Error> {
     // kernel docs: https://www.kernel.org/doc/html/latest/filesystems/proc.html#meminfo
-    let contents = fs::read_to_string("/proc/meminfo")?;
+    let mut mem_info = parse_meminfo_contents(&fs::read_to_string("/proc/meminfo")?)?;
+
+    // /proc/zoneinfo is not always readable (e.g. masked in containers), only --explain needs it
+    mem_info.zone_watermarks = parse_zoneinfo().ok();
+
+    Ok(mem_info)
+}
+
+#[cfg(target_os = "linux")]
+fn parse_meminfo_contents(contents: &str) -> Result<MemInfo, Error> {
     let mut mem_info = MemInfo::default();
+    let mut has_lohi = false;
 
     for line in contents.lines() {
         if let Some((key, value)) = line.split_once(':') {
@@ -66,33 +92,61 @@ fn parse_meminfo() -> Result<MemInfo, Error> {
                 "SwapTotal" => mem_info.swap_total = parsed_value,
                 "SwapFree" => mem_info.swap_free = parsed_value,
                 "SReclaimable" => mem_info.reclaimable = parsed_value,
-                "LowTotal" => mem_info.low_total = parsed_value,
+                "LowTotal" => {
+                    mem_info.low_total = parsed_value;
+                    has_lohi = true;
+                }
                 "LowFree" => mem_info.low_free = parsed_value,
                 "HighTotal" => mem_info.high_total = parsed_value,
                 "HighFree" => mem_info.high_free = parsed_value,
                 "CommitLimit" => mem_info.commit_limit = parsed_value,
                 "Committed_AS" => mem_info.committed = parsed_value,
+                "Active(file)" => mem_info.active_file = parsed_value,
+                "Inactive(file)" => mem_info.inactive_file = parsed_value,
+                "KReclaimable" => mem_info.kernel_reclaimable = parsed_value,
                 _ => {}
             }
         }
     }
-    // as far as i understand the kernel doc everything that is not highmem (out of all the memory) is lowmem
-    // from kernel doc: "Highmem is all memory above ~860MB of physical memory."
-    // it would be better to implement this via optionals, etc. but that would require a refactor so lets not do that right now
-
-    if mem_info.low_total == u64::default() {
-        mem_info.low_total = mem_info.total - mem_info.high_total;
+    // Only kernels with highmem (32-bit ones) expose the low and high memory, without highmem all
+    // of the memory is low memory, as procps considers it.
+    if !has_lohi {
+        mem_info.low_total = mem_info.total;
+        mem_info.low_free = mem_info.free;
     }
 
-    if mem_info.low_free == u64::default() {
-        mem_info.low_free = mem_info.free - mem_info.high_free;
-    }
+    mem_info.swap_used = mem_info.swap_total.saturating_sub(mem_info.swap_free);
 
-    mem_info.swap_used = mem_info.swap_total - mem_info.swap_free;
+    // KReclaimable (since 4.20) includes SReclaimable plus other reclaimable kernel allocations
+    if mem_info.kernel_reclaimable == u64::default() {
+        mem_info.kernel_reclaimable = mem_info.reclaimable;
+    }
 
     Ok(mem_info)
 }
 
+#[cfg(target_os = "linux")]
+fn parse_zoneinfo() -> Result<ZoneWatermarks, Error> {
+    let zoneinfo = uu_vmstat::Zoneinfo::current()?;
+    let page_size_kib = page_size_kib();
+
+    Ok(ZoneWatermarks {
+        low: zoneinfo.low_watermark() * page_size_kib,
+        reserved: zoneinfo.reserved() * page_size_kib,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn page_size_kib() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if page_size > 0 {
+        page_size as u64 / 1024
+    } else {
+        4
+    }
+}
+
 #[cfg(target_os = "macos")]
 fn parse_meminfo() -> Result<MemInfo, Box<dyn std::error::Error>> {
     let mut sys = sysinfo::System::new_all();
@@ -116,6 +170,10 @@ fn parse_meminfo() -> Result<MemInfo, Box<dyn std::error::Error>> {
         high_free: 0,
         commit_limit: 0,
         committed: 0,
+        active_file: 0,
+        inactive_file: 0,
+        kernel_reclaimable: 0,
+        zone_watermarks: None,
     };
 
     Ok(mem_info)
@@ -161,7 +219,14 @@ fn parse_meminfo() -> Result<MemInfo, Box<dyn std::error::Error>> {
 
 // print total - used - free combo that is used for everything except memory for now
 // free can be negative if the memory is overcommitted so it has to be signed
-fn construct_tuf_combo_str<F>(name: &str, total: u64, used: u64, free: i128, f: F) -> String
+fn construct_tuf_combo_str<F>(
+    name: &str,
+    total: u64,
+    used: u64,
+    free: i128,
+    width: usize,
+    f: F,
+) -> String
 where
     F: Fn(u64) -> String,
 {
@@ -173,7 +238,7 @@ where
     };
 
     format!(
-        "{:8}{:>12}{:>12}{:>12}\n",
+        "{:8}{:>width$}{:>width$}{:>width$}\n",
         name,
         f(total),
         f(used),
@@ -195,7 +260,8 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         ));
     }
 
-    if seconds == Some(0.0) {
+    // NaN and infinite delays would silently turn into no delay at all
+    if seconds.is_some_and(|seconds| !(seconds > 0.0 && seconds.is_finite())) {
         return Err(USimpleError::new(
             1,
             "seconds argument must be greater than 0",
@@ -287,7 +353,25 @@ pub fn uu_app() -> Command {
                 .value_name("N")
                 .value_parser(clap::value_parser!(u64)),
             arg!(-L --line "show output on a single line").action(ArgAction::SetTrue),
+            arg!(   --rows <ROWS> "show only the given comma-separated rows")
+                .value_delimiter(',')
+                .value_parser(["mem", "low", "high", "swap", "total", "comm"])
+                .action(ArgAction::Append)
+                .conflicts_with_all(["line", "lohi", "total", "committed"]),
+            // accepted for the scripts written for older versions, the buffers/cache adjusted
+            // line it used to hide is not shown anymore
+            arg!(-o --old "old format, does nothing")
+                .action(ArgAction::SetTrue)
+                .hide(true),
             arg!(-w --wide "wide output").action(ArgAction::SetTrue),
+            arg!(   --"no-swap" [WHEN] "omit the swap row, always or only without swap (auto)")
+                .value_parser(["always", "auto"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("always")
+                .conflicts_with_all(["line", "rows"]),
+            arg!(   --explain "explain how the available memory is estimated")
+                .action(ArgAction::SetTrue),
             arg!(   --help "display this help and exit").action(ArgAction::Help),
         ])
 }
@@ -320,7 +404,12 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
     let total = matches.get_flag("total");
     let lohi = matches.get_flag("lohi");
     let committed = matches.get_flag("committed");
+    let explain = matches.get_flag("explain");
     let one_line = matches.get_flag("line");
+    let no_swap = matches.get_one::<String>("no-swap").cloned();
+    let rows = matches
+        .get_many::<String>("rows")
+        .map(|rows| rows.cloned().collect::<Vec<_>>());
 
     let convert = detect_unit(matches);
 
@@ -333,29 +422,56 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
         }
     };
 
+    // Only grows, so that the columns of the samples of -s and -c line up
+    let columns = Cell::new(COLUMN_WIDTH);
+
     move |mem_info: &MemInfo| {
+        columns.set(columns.get().max(column_width(mem_info, &n2s)));
+        let width = columns.get();
         if one_line {
-            construct_one_line_str(mem_info, &n2s)
-        } else {
-            let mut str = String::new();
-            if wide {
-                str += &construct_wide_str(mem_info, &n2s);
-            } else {
-                str += &construct_str(mem_info, &n2s);
+            construct_one_line_str(mem_info, width, &n2s)
+        } else if let Some(rows) = &rows {
+            let mut str = construct_header_str(wide, width);
+            for row in rows {
+                str += &match row.as_str() {
+                    "mem" => construct_mem_str(mem_info, wide, width, &n2s),
+                    "low" => construct_low_str(mem_info, width, &n2s),
+                    "high" => construct_high_str(mem_info, width, &n2s),
+                    "swap" => construct_swap_str(mem_info, width, &n2s),
+                    "total" => construct_total_str(mem_info, width, &n2s),
+                    "comm" => construct_committed_str(mem_info, width, &n2s),
+                    _ => unreachable!("rows are checked by clap"),
+                };
             }
+            str
+        } else {
+            let mut str = construct_header_str(wide, width);
+            str += &construct_mem_str(mem_info, wide, width, &n2s);
 
             if lohi {
-                str += &construct_lohi_str(mem_info, &n2s);
+                str += &construct_lohi_str(mem_info, width, &n2s);
             }
 
-            str += &construct_swap_str(mem_info, &n2s);
+            // The totals still account for the swap when its row is omitted
+            let show_swap = match no_swap.as_deref() {
+                None => true,
+                Some("auto") => mem_info.swap_total != 0,
+                Some(_) => false,
+            };
+            if show_swap {
+                str += &construct_swap_str(mem_info, width, &n2s);
+            }
 
             if total {
-                str += &construct_total_str(mem_info, &n2s);
+                str += &construct_total_str(mem_info, width, &n2s);
             }
 
             if committed {
-                str += &construct_committed_str(mem_info, &n2s);
+                str += &construct_committed_str(mem_info, width, &n2s);
+            }
+
+            if explain {
+                str += &construct_explain_str(mem_info, width, &n2s);
             }
 
             str
@@ -363,9 +479,26 @@ fn parse_output_format(matches: &ArgMatches) -> impl Fn(&MemInfo) -> String {
     }
 }
 
-fn construct_one_line_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+/// The width of the columns of values, wide enough for the totals.
+///
+/// The totals don't change between samples, unlike the values which would shift the columns
+/// when they cross a digit boundary.
+fn column_width(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> usize {
+    let widest = [mem_info.total + mem_info.swap_total, mem_info.commit_limit]
+        .into_iter()
+        .map(|total| n2s(total).len())
+        .max()
+        .unwrap_or_default();
+    // Keep a space before the value
+    COLUMN_WIDTH.max(widest + 1)
+}
+
+fn construct_one_line_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
+    // The columns of the single line are narrower, and grow as much as the others
+    let extra = width - COLUMN_WIDTH;
+    let (w11, w10) = (11 + extra, 10 + extra);
     format!(
-        "{:8}{:>11} {:8}{:>11}  {:8}{:>10} {:8}{:>11}\n",
+        "{:8}{:>w11$} {:8}{:>w11$}  {:8}{:>w10$} {:8}{:>w11$}\n",
         "SwapUse",
         n2s(mem_info.swap_used),
         "CachUse",
@@ -377,113 +510,202 @@ fn construct_one_line_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> St
     )
 }
 
-fn construct_wide_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
-    format!(
-        "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
-        " ", "total", "used", "free", "shared", "buffers", "cache", "available",
-    ) + &format!(
-        "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
-        "Mem:",
-        n2s(mem_info.total),
-        n2s(mem_info.total - mem_info.available),
-        n2s(mem_info.free),
-        n2s(mem_info.shared),
-        n2s(mem_info.buffers),
-        n2s(mem_info.cached + mem_info.reclaimable),
-        n2s(mem_info.available),
-    )
+fn construct_header_str(wide: bool, width: usize) -> String {
+    if wide {
+        format!(
+            "{:8}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}\n",
+            " ", "total", "used", "free", "shared", "buffers", "cache", "available",
+        )
+    } else {
+        format!(
+            "{:8}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}\n",
+            " ", "total", "used", "free", "shared", "buff/cache", "available",
+        )
+    }
 }
 
-fn construct_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
-    format!(
-        "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
-        " ", "total", "used", "free", "shared", "buff/cache", "available",
-    ) + &format!(
-        "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
-        "Mem:",
-        n2s(mem_info.total),
-        n2s(mem_info.total - mem_info.available),
-        n2s(mem_info.free),
-        n2s(mem_info.shared),
-        n2s(mem_info.buffers + mem_info.cached + mem_info.reclaimable),
-        n2s(mem_info.available),
-    )
+fn construct_mem_str(
+    mem_info: &MemInfo,
+    wide: bool,
+    width: usize,
+    n2s: &dyn Fn(u64) -> String,
+) -> String {
+    if wide {
+        format!(
+            "{:8}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}\n",
+            "Mem:",
+            n2s(mem_info.total),
+            n2s(mem_info.total - mem_info.available),
+            n2s(mem_info.free),
+            n2s(mem_info.shared),
+            n2s(mem_info.buffers),
+            n2s(mem_info.cached + mem_info.reclaimable),
+            n2s(mem_info.available),
+        )
+    } else {
+        format!(
+            "{:8}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}{:>width$}\n",
+            "Mem:",
+            n2s(mem_info.total),
+            n2s(mem_info.total - mem_info.available),
+            n2s(mem_info.free),
+            n2s(mem_info.shared),
+            n2s(mem_info.buffers + mem_info.cached + mem_info.reclaimable),
+            n2s(mem_info.available),
+        )
+    }
+}
+
+fn construct_lohi_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
+    construct_low_str(mem_info, width, n2s) + &construct_high_str(mem_info, width, n2s)
 }
 
-fn construct_lohi_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_low_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
     construct_tuf_combo_str(
         "Low:",
         mem_info.low_total,
-        mem_info.low_total - mem_info.low_free,
+        mem_info.low_total.saturating_sub(mem_info.low_free),
         mem_info.low_free.into(),
+        width,
         n2s,
-    ) + &construct_tuf_combo_str(
+    )
+}
+
+fn construct_high_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
+    construct_tuf_combo_str(
         "High:",
         mem_info.high_total,
-        mem_info.high_total - mem_info.high_free,
+        mem_info.high_total.saturating_sub(mem_info.high_free),
         mem_info.high_free.into(),
+        width,
         n2s,
     )
 }
 
-fn construct_swap_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_swap_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
     construct_tuf_combo_str(
         "Swap:",
         mem_info.swap_total,
         mem_info.swap_used,
         mem_info.swap_free.into(),
+        width,
         n2s,
     )
 }
 
-fn construct_total_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_total_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
     construct_tuf_combo_str(
         "Total:",
         mem_info.total + mem_info.swap_total,
         mem_info.total - mem_info.available + mem_info.swap_used,
         (mem_info.free + mem_info.swap_free).into(),
+        width,
         n2s,
     )
 }
 
-fn construct_committed_str(mem_info: &MemInfo, n2s: &dyn Fn(u64) -> String) -> String {
+fn construct_committed_str(
+    mem_info: &MemInfo,
+    width: usize,
+    n2s: &dyn Fn(u64) -> String,
+) -> String {
     construct_tuf_combo_str(
         "Comm:",
         mem_info.commit_limit,
         mem_info.committed,
         (mem_info.commit_limit as i128) - (mem_info.committed as i128),
+        width,
         n2s,
     )
 }
 
+// Mirrors `si_mem_available()` of the kernel: free pages minus the reserved pages, plus the page
+// cache and the reclaimable kernel memory which can be dropped without pushing the system below the
+// low watermark.
+fn construct_explain_str(mem_info: &MemInfo, width: usize, n2s: &dyn Fn(u64) -> String) -> String {
+    let Some(zones) = &mem_info.zone_watermarks else {
+        return "\nAvailable: cannot explain estimate, /proc/zoneinfo is not readable\n".into();
+    };
+
+    let page_cache = mem_info.active_file + mem_info.inactive_file;
+    let page_cache_kept = (page_cache / 2).min(zones.low);
+    let slab = mem_info.kernel_reclaimable;
+    let slab_kept = (slab / 2).min(zones.low);
+    let estimate = (mem_info.free + page_cache - page_cache_kept + slab - slab_kept)
+        .saturating_sub(zones.reserved);
+
+    let line = |sign: &str, name: &str, value: u64, note: String| {
+        format!("{sign:>2} {name:<20}{:>width$}  {note}\n", n2s(value))
+    };
+
+    String::from("\nAvailable:\n")
+        + &line(" ", "free", mem_info.free, "MemFree".into())
+        + &line(
+            "-",
+            "reserved",
+            zones.reserved,
+            "zone high watermarks and lowmem reserves".into(),
+        )
+        + &line(
+            "+",
+            "page cache",
+            page_cache - page_cache_kept,
+            format!(
+                "file pages {} minus {} kept for the low watermark",
+                n2s(page_cache),
+                n2s(page_cache_kept)
+            ),
+        )
+        + &line(
+            "+",
+            "reclaimable slab",
+            slab - slab_kept,
+            format!(
+                "KReclaimable {} minus {} kept for the low watermark",
+                n2s(slab),
+                n2s(slab_kept)
+            ),
+        )
+        + &line(
+            "=",
+            "available",
+            estimate,
+            format!("MemAvailable {}", n2s(mem_info.available)),
+        )
+}
+
 // Here's the `-h` `--human` flag processing logic
 // See: https://github.com/uutils/procps/pull/431
+/// Formats `kib` like procps: with the first unit in which the value fits in 4 characters with
+/// --si, 5 characters otherwise (counting the "i" of the binary units), preferring one decimal.
 fn humanized(kib: u64, si: bool) -> String {
-    let b = ByteSize::kib(kib).0;
-    let units = ['B', 'K', 'M', 'G', 'T', 'P'];
-    let mut level = 0;
-    let mut divisor = 1;
-    while level < units.len() - 1 && divisor * 100 <= b {
-        divisor *= if si { 1000 } else { 1024 };
-        level += 1;
-    }
-    if level == 0 {
-        return format!("{}{}", b, units[level]);
+    let bytes = ByteSize::kib(kib).0;
+    let formatted = format!("{bytes}B");
+    if formatted.len() <= 4 {
+        return formatted;
     }
 
-    let value = (b as f64) / (divisor as f64);
-    let formatted_value = if (value * 10.0).round() < 100.0 {
-        format!("{:.1}", (value * 10.0).round() / 10.0)
+    let (base, suffix, width) = if si {
+        (1000.0, "", 4)
     } else {
-        (value as u64).to_string()
+        (1024.0, "i", 5)
     };
+    let mut formatted = formatted;
+    for (exponent, unit) in (1..).zip(['K', 'M', 'G', 'T', 'P']) {
+        let value = bytes as f64 / f64::powi(base, exponent);
+        // procps prints a float, which can round differently than a double
+        formatted = format!("{:.1}{unit}{suffix}", value as f32);
+        if formatted.len() <= width {
+            return formatted;
+        }
+        formatted = format!("{}{unit}{suffix}", value as u64);
+        if formatted.len() <= width {
+            return formatted;
+        }
+    }
 
-    format!(
-        "{}{}{}",
-        formatted_value,
-        units[level].to_owned(),
-        if si { "" } else { "i" }
-    )
+    // Doesn't fit in the column with more than an exbibyte
+    formatted
 }
 
 fn detect_unit(arg: &ArgMatches) -> fn(u64) -> u64 {
@@ -542,6 +764,184 @@ mod test {
         }
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_lohi() {
+        let n2s = |value: u64| value.to_string();
+        let meminfo = "MemTotal:        1000 kB\nMemFree:          400 kB\n";
+        let mem_info = parse_meminfo_contents(meminfo).unwrap();
+        // Without highmem, all of the memory is low memory
+        assert_eq!(
+            construct_lohi_str(&mem_info, COLUMN_WIDTH, &n2s),
+            format!(
+                "{:8}{:>12}{:>12}{:>12}\n{:8}{:>12}{:>12}{:>12}\n",
+                "Low:", 1000, 600, 400, "High:", 0, 0, 0
+            )
+        );
+
+        let meminfo = format!(
+            "{meminfo}HighTotal:        700 kB\nHighFree:         350 kB\n\
+             LowTotal:         300 kB\nLowFree:            0 kB\n"
+        );
+        let mem_info = parse_meminfo_contents(&meminfo).unwrap();
+        assert_eq!(
+            construct_lohi_str(&mem_info, COLUMN_WIDTH, &n2s),
+            format!(
+                "{:8}{:>12}{:>12}{:>12}\n{:8}{:>12}{:>12}{:>12}\n",
+                "Low:", 300, 300, 0, "High:", 700, 350, 350
+            )
+        );
+    }
+
+    #[test]
+    fn test_committed() {
+        let n2s = |value: u64| value.to_string();
+        let comm = |commit_limit, committed| {
+            construct_committed_str(
+                &MemInfo {
+                    commit_limit,
+                    committed,
+                    ..Default::default()
+                },
+                COLUMN_WIDTH,
+                &n2s,
+            )
+        };
+
+        assert_eq!(
+            comm(1000, 400),
+            format!("{:8}{:>12}{:>12}{:>12}\n", "Comm:", 1000, 400, 600)
+        );
+        // Overcommitted, the headroom is negative
+        assert_eq!(
+            comm(1000, 1500),
+            format!("{:8}{:>12}{:>12}{:>12}\n", "Comm:", 1000, 1500, -500)
+        );
+    }
+
+    #[test]
+    fn test_wide() {
+        let mem_info = MemInfo {
+            total: 1000,
+            free: 400,
+            available: 600,
+            buffers: 100,
+            cached: 200,
+            reclaimable: 50,
+            ..Default::default()
+        };
+        let n2s = |value: u64| value.to_string();
+
+        assert_eq!(
+            construct_header_str(false, COLUMN_WIDTH)
+                + &construct_mem_str(&mem_info, false, COLUMN_WIDTH, &n2s),
+            format!(
+                "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n\
+                 {:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
+                " ",
+                "total",
+                "used",
+                "free",
+                "shared",
+                "buff/cache",
+                "available",
+                "Mem:",
+                1000,
+                400,
+                400,
+                0,
+                350,
+                600,
+            )
+        );
+        // The reclaimable slabs are part of the cache
+        assert_eq!(
+            construct_header_str(true, COLUMN_WIDTH)
+                + &construct_mem_str(&mem_info, true, COLUMN_WIDTH, &n2s),
+            format!(
+                "{:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n\
+                 {:8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}\n",
+                " ",
+                "total",
+                "used",
+                "free",
+                "shared",
+                "buffers",
+                "cache",
+                "available",
+                "Mem:",
+                1000,
+                400,
+                400,
+                0,
+                100,
+                250,
+                600,
+            )
+        );
+    }
+
+    #[test]
+    fn test_no_swap() {
+        let output = |args: &[&str], swap_total| {
+            let matches = uu_app()
+                .try_get_matches_from(std::iter::once("free").chain(args.iter().copied()))
+                .unwrap();
+            let mem_info = MemInfo {
+                total: 1000,
+                free: 400,
+                available: 600,
+                swap_total,
+                swap_free: swap_total / 2,
+                swap_used: swap_total / 2,
+                ..Default::default()
+            };
+            parse_output_format(&matches)(&mem_info)
+        };
+
+        assert!(output(&[], 0).contains("Swap:"));
+        assert!(!output(&["--no-swap"], 200).contains("Swap:"));
+        assert!(!output(&["--no-swap=auto"], 0).contains("Swap:"));
+        assert!(output(&["--no-swap=auto"], 200).contains("Swap:"));
+
+        // The totals are the same whether the swap row is shown or not
+        let total = |output: String| output.lines().last().unwrap().to_string();
+        assert_eq!(
+            total(output(&["-t", "-k", "--no-swap"], 200)),
+            total(output(&["-t", "-k"], 200))
+        );
+        assert_eq!(
+            total(output(&["-t", "-k", "--no-swap"], 200)),
+            format!("{:8}{:>12}{:>12}{:>12}", "Total:", 1200, 500, 500)
+        );
+    }
+
+    #[test]
+    fn test_stable_width() {
+        let matches = uu_app().try_get_matches_from(["free", "-b", "-t"]).unwrap();
+        let construct_str = parse_output_format(&matches);
+        let sample = |free| MemInfo {
+            // 2 TiB, 13 digits in bytes
+            total: 2 << 30,
+            free,
+            available: free,
+            ..Default::default()
+        };
+
+        // The widths follow the totals, not the values crossing a digit boundary
+        let lengths = |output: String| output.lines().map(str::len).collect::<Vec<_>>();
+        let first = construct_str(&sample(5));
+        assert_eq!(
+            lengths(first.clone()),
+            [8 + 6 * 14, 8 + 6 * 14, 8 + 3 * 14, 8 + 3 * 14]
+        );
+        assert_eq!(lengths(construct_str(&sample(1 << 30))), lengths(first));
+        assert_eq!(
+            column_width(&sample(5), &|value| value.to_string()),
+            COLUMN_WIDTH
+        );
+    }
+
     #[test]
     fn test_humanized_unit() {
         let test_cases = [
@@ -556,6 +956,13 @@ mod test {
             (8500, false, "8.3Mi"),
             (10138, false, "9.9Mi"),
             (10230, false, "9Mi"),
+            (100, false, "100Ki"),
+            (100, true, "102K"),
+            (999, true, "1.0M"),
+            (9_999_999, true, "10G"),
+            (16_000_000, false, "15Gi"),
+            (7_654_321, false, "7.3Gi"),
+            (2 * 1024 * 1024 * 1024, false, "2.0Ti"),
         ];
         for &(kib, si, expected) in &test_cases {
             assert_eq!(humanized(kib, si), expected);
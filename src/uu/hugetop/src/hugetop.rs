@@ -3,20 +3,28 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+mod metrics;
+mod tui;
+
 use clap::{arg, crate_version, ArgAction, Command};
 use std::env;
 use std::fs;
-use std::io::Error;
+use std::io::{Error, IsTerminal};
 use std::path::Path;
 use std::process;
-use uu_pmap::smaps_format_parser::parse_smap_entries;
+use uu_pgrep::cgroup::{Cgroups, HugetlbStats};
+use uu_pmap::smaps_format_parser::parse_smaps;
 use uu_pmap::smaps_format_parser::SmapEntry;
 use uu_top::header;
-use uucore::uptime::get_formatted_time;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use uu_w::clock;
+use uucore::{
+    error::{FromIo, UResult},
+    format_usage, help_about, help_section, help_usage,
+};
 
 const ABOUT: &str = help_about!("hugetop.md");
 const USAGE: &str = help_usage!("hugetop.md");
+const AFTER_HELP: &str = help_section!("after help", "hugetop.md");
 
 #[derive(Debug)]
 struct ProcessHugepageInfo {
@@ -30,6 +38,7 @@ struct HugePageSizeInfo {
     size_kb: u64,
     free: u64,
     total: u64,
+    reserved: u64,
 }
 
 impl std::fmt::Display for HugePageSizeInfo {
@@ -61,11 +70,10 @@ fn parse_hugepage() -> Result<Vec<HugePageSizeInfo>, Error> {
     for entry in info_dir {
         let entry = entry?;
 
-        let mut info = HugePageSizeInfo::default();
-
-        info.total = parse_hugepage_value(&entry.path().join("nr_hugepages"))?;
-        info.free = parse_hugepage_value(&entry.path().join("free_hugepages"))?;
-        info.size_kb = entry
+        let total = parse_hugepage_value(&entry.path().join("nr_hugepages"))?;
+        let free = parse_hugepage_value(&entry.path().join("free_hugepages"))?;
+        let reserved = parse_hugepage_value(&entry.path().join("resv_hugepages"))?;
+        let size_kb = entry
             .file_name()
             .into_string()
             .unwrap()
@@ -81,7 +89,12 @@ fn parse_hugepage() -> Result<Vec<HugePageSizeInfo>, Error> {
                 )
             })?;
 
-        sizes.push(info);
+        sizes.push(HugePageSizeInfo {
+            size_kb,
+            free,
+            total,
+            reserved,
+        });
     }
 
     Ok(sizes)
@@ -97,7 +110,7 @@ fn parse_process_info(p: &fs::DirEntry) -> Option<ProcessHugepageInfo> {
     let name = fs::read_to_string(p.path().join("status"))
         .ok()?
         .lines()
-        .nth(0)
+        .next()
         .unwrap_or_default()
         .split(":")
         .nth(1)
@@ -106,8 +119,9 @@ fn parse_process_info(p: &fs::DirEntry) -> Option<ProcessHugepageInfo> {
         .to_string();
 
     let contents = fs::read_to_string(p.path().join("smaps")).ok()?;
-    let smap_entries = parse_smap_entries(&contents).ok()?;
-    let smap_entries: Vec<_> = smap_entries
+    let smap_entries: Vec<_> = parse_smaps(&contents)
+        .ok()?
+        .entries
         .into_iter()
         .filter(|entry| entry.kernel_page_size_in_kb >= 2024)
         .collect();
@@ -140,8 +154,54 @@ fn parse_process_hugepages() -> Result<Vec<ProcessHugepageInfo>, Error> {
 
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    let matches = uu_app().try_get_matches_from(args)?;
+
+    if matches.get_flag("cgroups") {
+        let stats = Cgroups::new().ok().and_then(|cgroups| cgroups.hugetlb());
+        let Some(stats) = stats else {
+            eprintln!("hugetop: the hugetlb cgroup controller is not available");
+            process::exit(1);
+        };
+        match parse_hugepage() {
+            Ok(sys_info) => {
+                print!("{}", construct_system_str(sys_info));
+                print!("{}", format_cgroup_str(&stats));
+            }
+            Err(e) => {
+                eprintln!("hugetop: failed to read hugepage info: {}", e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let interactive = !matches.get_flag("once")
+        && !matches.get_flag("metrics")
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal();
+    if interactive {
+        return tui::run(|| {
+            let sys_info =
+                parse_hugepage().map_err_context(|| "failed to read hugepage info".into())?;
+            let p_info = parse_process_hugepages()
+                .map_err_context(|| "failed to read process hugepage info".into())?;
+            let mut header = construct_system_str(sys_info)
+                .lines()
+                .map(String::from)
+                .collect::<Vec<_>>();
+            header.push(process_header());
+            Ok(tui::Frame {
+                header,
+                rows: process_rows(&p_info),
+            })
+        });
+    }
+
     match parse_hugepage() {
         Ok(sys_info) => match parse_process_hugepages() {
+            Ok(p_info) if matches.get_flag("metrics") => {
+                print!("{}", metrics::render(&sys_info, &p_info));
+            }
             Ok(p_info) => {
                 print!("{}", construct_str(sys_info, &p_info,));
             }
@@ -164,10 +224,21 @@ pub fn uu_app() -> Command {
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
+        .after_help(AFTER_HELP)
         .args_override_self(true)
         .infer_long_args(true)
         .disable_help_flag(true)
-        .arg(arg!(--help "display this help and exit").action(ArgAction::SetTrue))
+        .arg(arg!(--help "display this help and exit").action(ArgAction::Help))
+        .arg(
+            arg!(--metrics "print the hugepage usage in the Prometheus text format")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(arg!(-o --once "display the usage once instead of updating it on a terminal"))
+        .arg(
+            arg!(--cgroups "show the hugepage usage and limit of each cgroup")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("metrics"),
+        )
 }
 
 fn construct_str(sys: Vec<HugePageSizeInfo>, processes: &[ProcessHugepageInfo]) -> String {
@@ -180,24 +251,63 @@ fn construct_str(sys: Vec<HugePageSizeInfo>, processes: &[ProcessHugepageInfo])
 }
 
 fn format_process_str(processes: &[ProcessHugepageInfo]) -> String {
+    let mut output = process_header();
+    output.push('\n');
+
+    for row in process_rows(processes) {
+        output.push_str(&row);
+        output.push('\n');
+    }
+
+    output
+}
+
+fn process_header() -> String {
+    format!(
+        "{:<8} {:<12} {:<12} {:<12}",
+        "PID", "Private", "Shared", "Process"
+    )
+}
+
+/// A row for each hugetlb mapping of the processes.
+fn process_rows(processes: &[ProcessHugepageInfo]) -> Vec<String> {
+    processes
+        .iter()
+        .flat_map(|process| {
+            process.entries.iter().map(|smap_entry| {
+                format!(
+                    "{:<8} {:<12} {:<12} {:<12}",
+                    process.pid,
+                    smap_entry.private_hugetlb_in_kb,
+                    smap_entry.shared_hugetlb_in_kb,
+                    process.name
+                )
+            })
+        })
+        .collect()
+}
+
+fn format_cgroup_str(stats: &[HugetlbStats]) -> String {
     let mut output = String::new();
     let header = format!(
         "{:<8} {:<12} {:<12} {:<12}\n",
-        "PID", "Private", "Shared", "Process"
+        "Size", "Usage", "Limit", "Cgroup"
     );
 
     output.push_str(&header);
 
-    for process in processes {
-        for smap_entry in &process.entries {
-            output.push_str(&format!(
-                "{:<8} {:<12} {:<12} {:<12}\n",
-                process.pid,
-                smap_entry.private_hugetlb_in_kb,
-                smap_entry.shared_hugetlb_in_kb,
-                process.name
-            ));
-        }
+    // Values are in kB like the ones of the processes
+    for stat in stats {
+        let limit = stat
+            .limit
+            .map_or("max".to_string(), |limit| (limit / 1024).to_string());
+        output.push_str(&format!(
+            "{:<8} {:<12} {:<12} {:<12}\n",
+            stat.page_size,
+            stat.usage / 1024,
+            limit,
+            stat.cgroup
+        ));
     }
 
     output
@@ -207,14 +317,14 @@ fn construct_system_str(sys: Vec<HugePageSizeInfo>) -> String {
     let mut output = String::new();
     output.push_str(&format!(
         "top - {time} {uptime}, {user}\n",
-        time = get_formatted_time(),
+        time = clock::now(),
         uptime = header::uptime(),
         user = header::user(),
     ));
 
     for (i, info) in sys.iter().enumerate() {
         if i < sys.len() - 1 {
-            output.push_str(&format!("{}, ", info.to_string()));
+            output.push_str(&format!("{info}, "));
         } else {
             output.push_str(&info.to_string());
             output.push('\n');
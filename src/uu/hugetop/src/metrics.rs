@@ -0,0 +1,131 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::{HugePageSizeInfo, ProcessHugepageInfo};
+use std::fmt::Write;
+use uu_pmap::smaps_format_parser::SmapEntry;
+use uucore_procps::prometheus::{escape, header};
+
+/// Renders the hugepage pools and their users in the Prometheus text exposition format.
+pub(crate) fn render(sys: &[HugePageSizeInfo], processes: &[ProcessHugepageInfo]) -> String {
+    let mut output = String::new();
+
+    let mut sys = sys.iter().collect::<Vec<_>>();
+    sys.sort_by_key(|info| info.size_kb);
+    let pools = [
+        (
+            "hugepages_total",
+            "Number of huge pages in the pool.",
+            sys.iter().map(|info| info.total).collect::<Vec<_>>(),
+        ),
+        (
+            "hugepages_free",
+            "Number of huge pages in the pool not yet allocated.",
+            sys.iter().map(|info| info.free).collect(),
+        ),
+        (
+            "hugepages_reserved",
+            "Number of huge pages reserved but not yet allocated.",
+            sys.iter().map(|info| info.reserved).collect(),
+        ),
+    ];
+    for (metric, help, values) in pools {
+        header(&mut output, metric, "gauge", help);
+        for (info, value) in sys.iter().zip(values) {
+            let _ = writeln!(output, "{metric}{{size=\"{}kB\"}} {value}", info.size_kb);
+        }
+    }
+
+    let mut processes = processes.iter().collect::<Vec<_>>();
+    processes.sort_by_key(|process| process.pid);
+    let sum = |process: &ProcessHugepageInfo, field: fn(&SmapEntry) -> u64| {
+        process.entries.iter().map(field).sum::<u64>()
+    };
+    let usages = [
+        (
+            "hugepages_process_private_bytes",
+            "Private hugetlb memory mapped by the process.",
+            processes
+                .iter()
+                .map(|process| sum(process, |it| it.private_hugetlb_in_kb))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "hugepages_process_shared_bytes",
+            "Shared hugetlb memory mapped by the process.",
+            processes
+                .iter()
+                .map(|process| sum(process, |it| it.shared_hugetlb_in_kb))
+                .collect(),
+        ),
+    ];
+    for (metric, help, values) in usages {
+        header(&mut output, metric, "gauge", help);
+        for (process, kib) in processes.iter().zip(values) {
+            let _ = writeln!(
+                output,
+                "{metric}{{pid=\"{}\",name=\"{}\"}} {}",
+                process.pid,
+                escape(&process.name),
+                kib * 1024
+            );
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let sys = [
+            HugePageSizeInfo {
+                size_kb: 1048576,
+                free: 1,
+                total: 2,
+                reserved: 0,
+            },
+            HugePageSizeInfo {
+                size_kb: 2048,
+                free: 3,
+                total: 8,
+                reserved: 2,
+            },
+        ];
+        let processes = [ProcessHugepageInfo {
+            pid: 42,
+            name: "my \"db\"".into(),
+            entries: vec![
+                SmapEntry {
+                    private_hugetlb_in_kb: 2048,
+                    shared_hugetlb_in_kb: 0,
+                    ..Default::default()
+                },
+                SmapEntry {
+                    private_hugetlb_in_kb: 4096,
+                    shared_hugetlb_in_kb: 2048,
+                    ..Default::default()
+                },
+            ],
+        }];
+
+        let output = render(&sys, &processes);
+        assert!(output.contains("# TYPE hugepages_total gauge\n"));
+        assert!(output.contains(
+            "hugepages_total{size=\"2048kB\"} 8\nhugepages_total{size=\"1048576kB\"} 2\n"
+        ));
+        assert!(output.contains("hugepages_free{size=\"2048kB\"} 3\n"));
+        assert!(output.contains("hugepages_reserved{size=\"2048kB\"} 2\n"));
+        assert!(output.contains(
+            "hugepages_process_private_bytes{pid=\"42\",name=\"my \\\"db\\\"\"} 6291456\n"
+        ));
+        assert!(output.contains(
+            "hugepages_process_shared_bytes{pid=\"42\",name=\"my \\\"db\\\"\"} 2097152\n"
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crossterm::cursor::MoveTo;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, Clear, ClearType};
+use std::io::{stdout, Write};
+use uu_top::tui::{self as top, Request, Wake, DELAY};
+use uucore::error::UResult;
+
+/// What a frame shows: the lines kept at the top of the screen, then the rows which scroll.
+pub(crate) struct Frame {
+    pub(crate) header: Vec<String>,
+    pub(crate) rows: Vec<String>,
+}
+
+/// The part of the rows on screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Viewport {
+    /// Index of the first row shown
+    offset: usize,
+    /// Number of rows fitting on screen
+    height: usize,
+}
+
+impl Viewport {
+    /// Keeps the last page full when the rows are fewer than before or the screen grew.
+    fn clamp(self, total: usize) -> Self {
+        Self {
+            offset: self.offset.min(total.saturating_sub(self.height)),
+            ..self
+        }
+    }
+
+    fn scroll(self, key: KeyCode, total: usize) -> Self {
+        let page = self.height.max(1);
+        let offset = match key {
+            KeyCode::Up | KeyCode::Char('k') => self.offset.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.offset + 1,
+            KeyCode::PageUp | KeyCode::Char('b') => self.offset.saturating_sub(page),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.offset + page,
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => total,
+            _ => self.offset,
+        };
+        Self { offset, ..self }.clamp(total)
+    }
+
+    /// Where the rows on screen are among all of them, as rows run off screen otherwise.
+    fn indicator(self, total: usize) -> String {
+        let end = (self.offset + self.height).min(total);
+        if end == 0 {
+            format!("showing 0 of {total}")
+        } else {
+            format!("showing {}–{end} of {total}", self.offset + 1)
+        }
+    }
+}
+
+/// Runs hugetop interactively until it is asked to quit, restoring the terminal afterwards.
+pub(crate) fn run(frame: impl Fn() -> UResult<Frame>) -> UResult<()> {
+    top::install_handlers()?;
+    top::enter()?;
+
+    let result = event_loop(frame);
+
+    top::leave();
+    result
+}
+
+fn event_loop(frame: impl Fn() -> UResult<Frame>) -> UResult<()> {
+    let mut viewport = Viewport::default();
+    let mut current = frame()?;
+
+    loop {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        // Keep a line for the indicator
+        viewport.height = height.saturating_sub(current.header.len() + 1).max(1);
+        viewport = viewport.clamp(current.rows.len());
+        draw(&current, viewport, width, height)?;
+
+        match top::wait(DELAY)? {
+            Wake::Timeout => {
+                current = frame()?;
+                continue;
+            }
+            Wake::Event => {}
+            Wake::Request(Request::Quit) => return Ok(()),
+            Wake::Request(Request::Suspend) => {
+                top::suspend()?;
+                continue;
+            }
+            Wake::Request(Request::Resume) => {
+                top::leave();
+                top::enter()?;
+                continue;
+            }
+        }
+        // Other events, such as resizing, redraw the screen
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        // Raw mode turns off the signals of these keys
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('c') => return Ok(()),
+                KeyCode::Char('z') => {
+                    top::suspend()?;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Enter => current = frame()?,
+            code => viewport = viewport.scroll(code, current.rows.len()),
+        }
+    }
+}
+
+fn draw(frame: &Frame, viewport: Viewport, width: usize, height: usize) -> UResult<()> {
+    let mut stdout = stdout().lock();
+    execute!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+
+    let rows = frame
+        .rows
+        .iter()
+        .skip(viewport.offset)
+        .take(viewport.height)
+        .cloned();
+    let indicator = viewport.indicator(frame.rows.len());
+    let lines = frame
+        .header
+        .iter()
+        .cloned()
+        .chain(rows)
+        .chain(std::iter::once(indicator))
+        .take(height)
+        .map(|line| line.chars().take(width).collect::<String>())
+        .collect::<Vec<_>>();
+    // Raw mode does not translate newlines
+    write!(stdout, "{}", lines.join("\r\n"))?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(offset: usize, height: usize) -> Viewport {
+        Viewport { offset, height }
+    }
+
+    #[test]
+    fn test_scroll() {
+        let start = viewport(0, 10);
+        assert_eq!(start.scroll(KeyCode::Down, 25), viewport(1, 10));
+        assert_eq!(start.scroll(KeyCode::Up, 25), start);
+        assert_eq!(start.scroll(KeyCode::PageDown, 25), viewport(10, 10));
+        // The last page stays full
+        assert_eq!(
+            viewport(10, 10).scroll(KeyCode::PageDown, 25),
+            viewport(15, 10)
+        );
+        assert_eq!(start.scroll(KeyCode::End, 25), viewport(15, 10));
+        assert_eq!(viewport(15, 10).scroll(KeyCode::Home, 25), start);
+        assert_eq!(
+            viewport(15, 10).scroll(KeyCode::PageUp, 25),
+            viewport(5, 10)
+        );
+        // Everything fits, nothing scrolls
+        assert_eq!(start.scroll(KeyCode::Down, 5), start);
+    }
+
+    #[test]
+    fn test_clamp() {
+        assert_eq!(viewport(15, 10).clamp(20), viewport(10, 10));
+        assert_eq!(viewport(15, 10).clamp(3), viewport(0, 10));
+        assert_eq!(viewport(5, 10).clamp(100), viewport(5, 10));
+    }
+
+    #[test]
+    fn test_indicator() {
+        assert_eq!(viewport(0, 10).indicator(25), "showing 1–10 of 25");
+        assert_eq!(viewport(15, 10).indicator(25), "showing 16–25 of 25");
+        assert_eq!(viewport(0, 10).indicator(3), "showing 1–3 of 3");
+        assert_eq!(viewport(0, 10).indicator(0), "showing 0 of 0");
+    }
+}
@@ -0,0 +1,69 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Time of matching the processes of a synthetic `/proc` of 10k processes, run with
+//! `cargo bench -p uu_pgrep`. The number of matches is checked before timing, so that the
+//! benchmark doesn't time a broken matcher.
+
+#[path = "../src/fixture.rs"]
+#[allow(dead_code)]
+mod fixture;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use fixture::{FakeProcess, ProcFixture};
+use std::hint::black_box;
+use uu_pgrep::process_matcher::{find_matching_pids, get_match_settings};
+
+const PROCESSES: usize = 10_000;
+/// Above the PIDs Linux can give, so that the benchmark never matches itself.
+const BASE_PID: usize = 1 << 22;
+
+fn processes() -> Vec<FakeProcess> {
+    (0..PROCESSES)
+        .map(|i| {
+            let name = ["bash", "sshd", "postgres", "nginx", "kworker/0:1"][i % 5];
+            let mut process = FakeProcess::new(BASE_PID + i, name);
+            process.ppid = BASE_PID + i / 10;
+            process.uid = (i % 4) as u32 * 1000;
+            process.euid = process.uid;
+            process.cmdline = vec![name.into(), "--config".into(), format!("/etc/{i}.conf")];
+            process.environ = vec![format!("INDEX={i}"), "LANG=C".into()];
+            process
+        })
+        .collect()
+}
+
+fn bench_matching(c: &mut Criterion) {
+    let fixture = ProcFixture::new(&processes());
+
+    let parent = (BASE_PID + 500).to_string();
+    for (args, expected) in [
+        (&["bash"][..], 2000),
+        (&["-x", "postgres"], 2000),
+        // The 10 configurations of 990 to 999 and the 100 of 9900 to 9999
+        (&["-f", "/etc/99[0-9]+.conf"], 110),
+        (&["-i", "-v", "SSHD"], 8000),
+        // The children of the 500th process with the second UID
+        (&["-U", "1000", "-P", parent.as_str()], 3),
+        (&["--env", "LANG=C", "nginx"], 2000),
+        (&["-n", "bash"], 1),
+    ] {
+        let matches = uu_pgrep::uu_app()
+            .try_get_matches_from(std::iter::once("pgrep").chain(args.iter().copied()))
+            .unwrap();
+        let mut settings = get_match_settings(&matches).unwrap();
+        settings.proc_root = fixture.root().to_path_buf();
+
+        let count = find_matching_pids(&settings).unwrap().len();
+        assert_eq!(count, expected, "{args:?}");
+
+        c.bench_function(&args.join(" "), |b| {
+            b.iter(|| find_matching_pids(black_box(&settings)).unwrap());
+        });
+    }
+}
+
+criterion_group!(benches, bench_matching);
+criterion_main!(benches);
@@ -0,0 +1,615 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Resolution of the cgroups of processes and access to their controller files, for both the
+//! v1 hierarchies and the v2 unified one.
+
+use crate::process::CgroupMembership;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Limits of v1 controllers at or above this value mean "unlimited", the kernel reports the
+/// largest page-aligned `i64` rather than a dedicated keyword.
+const V1_UNLIMITED: u64 = 0x7FFF_FFFF_FFFF_F000;
+
+/// Options of v1 hierarchies found along with their controllers in the mount options.
+const V1_OPTIONS: [&str; 6] = [
+    "rw",
+    "ro",
+    "xattr",
+    "noprefix",
+    "clone_children",
+    "cpuset_v2_mode",
+];
+
+/// How the cgroup hierarchies are mounted on the system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgroupMode {
+    /// Only v1 hierarchies
+    Legacy,
+    /// v1 hierarchies along with the v2 one
+    Hybrid,
+    /// Only the v2 hierarchy
+    Unified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CgroupMount {
+    /// Cgroup of the hierarchy found at the mount point
+    root: String,
+    mount_point: PathBuf,
+    /// Controllers of a v1 hierarchy, [None] for the v2 one
+    controllers: Option<Vec<String>>,
+}
+
+impl CgroupMount {
+    fn is_v2(&self) -> bool {
+        self.controllers.is_none()
+    }
+
+    /// Path of the cgroup found at a directory of this hierarchy, the reverse of [`Self::dir`].
+    fn cgroup_path(&self, dir: &Path) -> String {
+        let relative = dir.strip_prefix(&self.mount_point).unwrap_or(dir);
+        let root = self.root.trim_end_matches('/');
+        match relative.to_string_lossy() {
+            relative if relative.is_empty() && root.is_empty() => "/".into(),
+            relative if relative.is_empty() => root.into(),
+            relative => format!("{root}/{relative}"),
+        }
+    }
+
+    /// Directory of a cgroup of this hierarchy.
+    fn dir(&self, cgroup_path: &str) -> PathBuf {
+        let relative = cgroup_path
+            .strip_prefix(&self.root)
+            .unwrap_or(cgroup_path)
+            .trim_start_matches('/');
+        self.mount_point.join(relative)
+    }
+}
+
+/// Memory use of a cgroup, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub usage: u64,
+    pub limit: Option<u64>,
+}
+
+/// CPU time used by a cgroup and its bandwidth limit, in microseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuStats {
+    pub usage_usec: u64,
+    /// CPU time the cgroup may use in each period
+    pub quota_usec: Option<u64>,
+    pub period_usec: u64,
+}
+
+/// Number of tasks in a cgroup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PidsStats {
+    pub current: u64,
+    pub limit: Option<u64>,
+}
+
+/// Huge pages of one size used by a cgroup, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HugetlbStats {
+    pub cgroup: String,
+    /// Size of the pages as named by the kernel, such as "2MB"
+    pub page_size: String,
+    pub usage: u64,
+    pub limit: Option<u64>,
+}
+
+/// The cgroup hierarchies mounted on the system.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cgroups {
+    mounts: Vec<CgroupMount>,
+}
+
+impl Cgroups {
+    /// Hierarchies visible to the current process.
+    pub fn new() -> io::Result<Self> {
+        Ok(Self::from_mountinfo(&fs::read_to_string(
+            "/proc/self/mountinfo",
+        )?))
+    }
+
+    /// Hierarchies listed in the content of a `/proc/<pid>/mountinfo` file.
+    pub fn from_mountinfo(mountinfo: &str) -> Self {
+        let mounts = mountinfo
+            .lines()
+            .filter_map(|line| {
+                // The optional fields end with a single "-"
+                let (fields, filesystem) = line.split_once(" - ")?;
+                let fields = fields.split(' ').collect::<Vec<_>>();
+                let mut filesystem = filesystem.split(' ');
+                let (fs_type, _source, options) =
+                    (filesystem.next()?, filesystem.next()?, filesystem.next()?);
+
+                let controllers = match fs_type {
+                    "cgroup2" => None,
+                    // Named hierarchies such as "name=systemd" have no controllers
+                    "cgroup" => Some(
+                        options
+                            .split(',')
+                            .filter(|it| !V1_OPTIONS.contains(it) && !it.contains('='))
+                            .map(String::from)
+                            .collect(),
+                    ),
+                    _ => return None,
+                };
+
+                Some(CgroupMount {
+                    root: unescape(fields.get(3)?),
+                    mount_point: unescape(fields.get(4)?).into(),
+                    controllers,
+                })
+            })
+            .collect();
+
+        Self { mounts }
+    }
+
+    /// How the hierarchies are mounted, [None] without any cgroup filesystem.
+    pub fn mode(&self) -> Option<CgroupMode> {
+        let v2 = self.mounts.iter().any(CgroupMount::is_v2);
+        let v1 = self.mounts.iter().any(|mount| !mount.is_v2());
+        match (v1, v2) {
+            (true, true) => Some(CgroupMode::Hybrid),
+            (true, false) => Some(CgroupMode::Legacy),
+            (false, true) => Some(CgroupMode::Unified),
+            (false, false) => None,
+        }
+    }
+
+    /// Directory of the cgroup handling `controller` among the ones of a process, the
+    /// v1 hierarchy of the controller being preferred over the v2 one.
+    pub fn controller_path(
+        &self,
+        memberships: &[CgroupMembership],
+        controller: &str,
+    ) -> Option<PathBuf> {
+        self.resolve(memberships, controller).map(|(_, dir)| dir)
+    }
+
+    fn resolve(
+        &self,
+        memberships: &[CgroupMembership],
+        controller: &str,
+    ) -> Option<(&CgroupMount, PathBuf)> {
+        let v1 = self.mounts.iter().find_map(|mount| {
+            mount
+                .controllers
+                .as_ref()?
+                .iter()
+                .any(|it| it == controller)
+                .then_some(())?;
+            let membership = memberships
+                .iter()
+                .find(|it| it.controllers.iter().any(|it| it == controller))?;
+            Some((mount, mount.dir(&membership.cgroup_path)))
+        });
+        if v1.is_some() {
+            return v1;
+        }
+
+        let mount = self.mounts.iter().find(|mount| mount.is_v2())?;
+        let membership = memberships.iter().find(|it| it.hierarchy_id == 0)?;
+        // Controllers have to be enabled in the v2 hierarchy before they can be used
+        let enabled = fs::read_to_string(mount.mount_point.join("cgroup.controllers")).ok()?;
+        enabled
+            .split_whitespace()
+            .any(|it| it == controller)
+            .then(|| (mount, mount.dir(&membership.cgroup_path)))
+    }
+
+    fn resolve_for_pid(&self, pid: usize, controller: &str) -> io::Result<(bool, PathBuf)> {
+        self.resolve(&memberships(pid)?, controller)
+            .map(|(mount, dir)| (mount.is_v2(), dir))
+            .ok_or(io::ErrorKind::NotFound.into())
+    }
+
+    /// Memory used by the cgroup of the process and its limit.
+    pub fn memory(&self, pid: usize) -> io::Result<MemoryStats> {
+        let (v2, dir) = self.resolve_for_pid(pid, "memory")?;
+        let (usage, limit) = if v2 {
+            ("memory.current", "memory.max")
+        } else {
+            ("memory.usage_in_bytes", "memory.limit_in_bytes")
+        };
+
+        Ok(MemoryStats {
+            usage: parse_number(&read_value(&dir.join(usage))?)?,
+            limit: parse_limit(&read_value(&dir.join(limit))?)?,
+        })
+    }
+
+    /// CPU time used by the cgroup of the process and its bandwidth limit.
+    pub fn cpu(&self, pid: usize) -> io::Result<CpuStats> {
+        let (v2, dir) = self.resolve_for_pid(pid, "cpu")?;
+        if v2 {
+            let usage = fs::read_to_string(dir.join("cpu.stat"))?
+                .lines()
+                .find_map(|line| line.strip_prefix("usage_usec "))
+                .ok_or(io::Error::from(io::ErrorKind::InvalidData))
+                .and_then(parse_number)?;
+            let (quota, period) = parse_cpu_max(&read_value(&dir.join("cpu.max"))?)?;
+
+            return Ok(CpuStats {
+                usage_usec: usage,
+                quota_usec: quota,
+                period_usec: period,
+            });
+        }
+
+        // The usage is accounted by a controller of its own in v1
+        let (_, cpuacct) = self.resolve_for_pid(pid, "cpuacct")?;
+        let usage_nsec = parse_number(&read_value(&cpuacct.join("cpuacct.usage"))?)?;
+        let quota = read_value(&dir.join("cpu.cfs_quota_us"))?;
+
+        Ok(CpuStats {
+            usage_usec: usage_nsec / 1000,
+            // -1 when there is no limit
+            quota_usec: quota.parse().ok(),
+            period_usec: parse_number(&read_value(&dir.join("cpu.cfs_period_us"))?)?,
+        })
+    }
+
+    /// Number of tasks in the cgroup of the process and its limit.
+    pub fn pids(&self, pid: usize) -> io::Result<PidsStats> {
+        // The files are named the same in v1 and v2
+        let (_, dir) = self.resolve_for_pid(pid, "pids")?;
+
+        Ok(PidsStats {
+            current: parse_number(&read_value(&dir.join("pids.current"))?)?,
+            limit: parse_limit(&read_value(&dir.join("pids.max"))?)?,
+        })
+    }
+
+    /// Huge page use of every cgroup of the hierarchy handling the hugetlb controller, sorted by
+    /// cgroup, [None] when the controller isn't available.
+    pub fn hugetlb(&self) -> Option<Vec<HugetlbStats>> {
+        let mount = self
+            .mounts
+            .iter()
+            .find(|mount| {
+                mount
+                    .controllers
+                    .as_ref()
+                    .is_some_and(|it| it.iter().any(|it| it == "hugetlb"))
+            })
+            .or_else(|| {
+                let mount = self.mounts.iter().find(|mount| mount.is_v2())?;
+                let enabled =
+                    fs::read_to_string(mount.mount_point.join("cgroup.controllers")).ok()?;
+                enabled
+                    .split_whitespace()
+                    .any(|it| it == "hugetlb")
+                    .then_some(mount)
+            })?;
+
+        let mut stats = Vec::new();
+        collect_hugetlb(mount, &mount.mount_point, &mut stats);
+        stats.sort_by(|a, b| (&a.cgroup, &a.page_size).cmp(&(&b.cgroup, &b.page_size)));
+        Some(stats)
+    }
+}
+
+/// Adds the huge page use of the cgroup at `dir` and of its descendants to `stats`, skipping the
+/// cgroups which can't be read.
+fn collect_hugetlb(mount: &CgroupMount, dir: &Path, stats: &mut Vec<HugetlbStats>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let (usage_suffix, limit_suffix) = if mount.is_v2() {
+        (".current", ".max")
+    } else {
+        (".usage_in_bytes", ".limit_in_bytes")
+    };
+
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|it| it.is_dir()) {
+            collect_hugetlb(mount, &entry.path(), stats);
+            continue;
+        }
+        let name = entry.file_name();
+        let Some(page_size) = name
+            .to_str()
+            .and_then(|it| it.strip_prefix("hugetlb."))
+            .and_then(|it| it.strip_suffix(usage_suffix))
+        else {
+            continue;
+        };
+        // The reservations are accounted in "hugetlb.<size>.rsvd.*"
+        if page_size.contains('.') {
+            continue;
+        }
+
+        let usage = read_value(&entry.path()).and_then(|it| parse_number(&it));
+        let limit = read_value(&dir.join(format!("hugetlb.{page_size}{limit_suffix}")))
+            .and_then(|it| parse_limit(&it));
+        if let (Ok(usage), Ok(limit)) = (usage, limit) {
+            stats.push(HugetlbStats {
+                cgroup: mount.cgroup_path(dir),
+                page_size: page_size.into(),
+                usage,
+                limit,
+            });
+        }
+    }
+}
+
+/// Cgroups of the process, from `/proc/<pid>/cgroup`.
+pub fn memberships(pid: usize) -> io::Result<Vec<CgroupMembership>> {
+    fs::read_to_string(format!("/proc/{pid}/cgroup"))?
+        .lines()
+        .map(CgroupMembership::try_from)
+        .collect()
+}
+
+/// Undoes the octal escapes of the spaces, tabs, newlines and backslashes of mountinfo fields.
+fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let escaped = rest.get(index + 1..index + 4);
+        match escaped.and_then(|it| u8::from_str_radix(it, 8).ok()) {
+            Some(byte) => {
+                result.push(byte.into());
+                rest = &rest[index + 4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn read_value(path: &Path) -> io::Result<String> {
+    Ok(fs::read_to_string(path)?.trim().to_string())
+}
+
+fn parse_number(value: &str) -> io::Result<u64> {
+    value.parse().map_err(|_| io::ErrorKind::InvalidData.into())
+}
+
+/// Parses a limit, which is either "max" or a number.
+fn parse_limit(value: &str) -> io::Result<Option<u64>> {
+    if value == "max" {
+        return Ok(None);
+    }
+    let limit = parse_number(value)?;
+    Ok((limit < V1_UNLIMITED).then_some(limit))
+}
+
+/// Parses the "$MAX $PERIOD" content of `cpu.max`.
+fn parse_cpu_max(value: &str) -> io::Result<(Option<u64>, u64)> {
+    let (quota, period) = value
+        .split_once(' ')
+        .ok_or(io::Error::from(io::ErrorKind::InvalidData))?;
+    Ok((parse_limit(quota)?, parse_number(period)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYBRID_MOUNTINFO: &str = "\
+23 28 0:22 / /proc rw,relatime - proc proc rw
+32 24 0:28 / /sys/fs/cgroup rw,relatime - tmpfs tmpfs rw,mode=755
+33 32 0:29 / /sys/fs/cgroup/cpu,cpuacct rw,relatime shared:9 - cgroup cgroup rw,cpu,cpuacct
+36 32 0:32 /docker/abc /sys/fs/cgroup/memory rw,relatime - cgroup cgroup rw,memory
+41 32 0:37 / /sys/fs/cgroup/systemd rw,relatime - cgroup cgroup rw,xattr,name=systemd
+42 32 0:38 / /sys/fs/cgroup/unified rw,relatime - cgroup2 cgroup2 rw,nsdelegate
+";
+
+    fn membership(hierarchy_id: u32, controllers: &str, path: &str) -> CgroupMembership {
+        CgroupMembership::try_from(format!("{hierarchy_id}:{controllers}:{path}").as_str()).unwrap()
+    }
+
+    #[test]
+    fn test_mode() {
+        let cgroups = Cgroups::from_mountinfo(HYBRID_MOUNTINFO);
+        assert_eq!(cgroups.mode(), Some(CgroupMode::Hybrid));
+        assert_eq!(
+            cgroups.mounts[0].controllers,
+            Some(vec!["cpu".into(), "cpuacct".into()])
+        );
+        assert_eq!(cgroups.mounts[2].controllers, Some(vec![]));
+
+        let unified = "29 24 0:26 / /sys/fs/cgroup rw,nosuid - cgroup2 cgroup2 rw,nsdelegate";
+        assert_eq!(
+            Cgroups::from_mountinfo(unified).mode(),
+            Some(CgroupMode::Unified)
+        );
+
+        let legacy = HYBRID_MOUNTINFO
+            .lines()
+            .filter(|it| !it.contains("cgroup2"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            Cgroups::from_mountinfo(&legacy).mode(),
+            Some(CgroupMode::Legacy)
+        );
+
+        assert_eq!(Cgroups::from_mountinfo("").mode(), None);
+    }
+
+    #[test]
+    fn test_controller_path() {
+        let cgroups = Cgroups::from_mountinfo(HYBRID_MOUNTINFO);
+        let memberships = [
+            membership(4, "memory", "/docker/abc/app"),
+            membership(2, "cpu,cpuacct", "/user.slice"),
+            membership(0, "", "/user.slice/session-1.scope"),
+        ];
+
+        assert_eq!(
+            cgroups.controller_path(&memberships, "cpu"),
+            Some("/sys/fs/cgroup/cpu,cpuacct/user.slice".into())
+        );
+        // The mounted hierarchy starts at the cgroup of the container
+        assert_eq!(
+            cgroups.controller_path(&memberships, "memory"),
+            Some("/sys/fs/cgroup/memory/app".into())
+        );
+    }
+
+    #[test]
+    fn test_v2_controller_path() {
+        let dir = std::env::temp_dir().join(format!("uu_pgrep_test_cgroup_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("cgroup.controllers"), "cpu memory pids\n").unwrap();
+
+        let mountinfo = format!(
+            "29 24 0:26 / {} rw,nosuid - cgroup2 cgroup2 rw,nsdelegate",
+            dir.display()
+        );
+        let cgroups = Cgroups::from_mountinfo(&mountinfo);
+        let memberships = [membership(0, "", "/system.slice/cron.service")];
+        let pids = cgroups.controller_path(&memberships, "pids");
+        let hugetlb = cgroups.controller_path(&memberships, "hugetlb");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pids, Some(dir.join("system.slice/cron.service")));
+        // Not enabled in the hierarchy
+        assert_eq!(hugetlb, None);
+    }
+
+    #[test]
+    fn test_hugetlb() {
+        let dir =
+            std::env::temp_dir().join(format!("uu_pgrep_test_hugetlb_{}", std::process::id()));
+        let pod = dir.join("kubepods/pod1");
+        fs::create_dir_all(&pod).unwrap();
+        fs::write(dir.join("cgroup.controllers"), "memory hugetlb\n").unwrap();
+        for (file, value) in [
+            ("hugetlb.2MB.current", "4194304\n"),
+            ("hugetlb.2MB.max", "8388608\n"),
+            ("hugetlb.2MB.rsvd.current", "0\n"),
+            ("hugetlb.2MB.rsvd.max", "max\n"),
+            ("hugetlb.1GB.current", "0\n"),
+            ("hugetlb.1GB.max", "max\n"),
+        ] {
+            fs::write(pod.join(file), value).unwrap();
+        }
+
+        let v2 = format!(
+            "29 24 0:26 / {} rw,nosuid - cgroup2 cgroup2 rw,nsdelegate",
+            dir.display()
+        );
+        let stats = Cgroups::from_mountinfo(&v2).hugetlb();
+        let v1 = format!(
+            "29 24 0:26 /docker {} rw - cgroup cgroup rw,hugetlb",
+            dir.display()
+        );
+        let v1_stats = Cgroups::from_mountinfo(&v1).hugetlb();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            stats,
+            Some(vec![
+                HugetlbStats {
+                    cgroup: "/kubepods/pod1".into(),
+                    page_size: "1GB".into(),
+                    usage: 0,
+                    limit: None,
+                },
+                HugetlbStats {
+                    cgroup: "/kubepods/pod1".into(),
+                    page_size: "2MB".into(),
+                    usage: 4194304,
+                    limit: Some(8388608),
+                },
+            ])
+        );
+        // The v1 files are named differently
+        assert_eq!(v1_stats, Some(vec![]));
+        let legacy = HYBRID_MOUNTINFO
+            .lines()
+            .filter(|it| !it.contains("cgroup2"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(Cgroups::from_mountinfo(&legacy).hugetlb(), None);
+    }
+
+    #[test]
+    fn test_cgroup_path() {
+        let mount = CgroupMount {
+            root: "/docker/abc".into(),
+            mount_point: "/sys/fs/cgroup/memory".into(),
+            controllers: Some(vec!["memory".into()]),
+        };
+        assert_eq!(
+            mount.cgroup_path(Path::new("/sys/fs/cgroup/memory")),
+            "/docker/abc"
+        );
+        assert_eq!(
+            mount.cgroup_path(Path::new("/sys/fs/cgroup/memory/app")),
+            "/docker/abc/app"
+        );
+
+        let mount = CgroupMount {
+            root: "/".into(),
+            ..mount
+        };
+        assert_eq!(mount.cgroup_path(Path::new("/sys/fs/cgroup/memory")), "/");
+        assert_eq!(
+            mount.cgroup_path(Path::new("/sys/fs/cgroup/memory/app")),
+            "/app"
+        );
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("/mnt/my\\040cgroups"), "/mnt/my cgroups");
+        assert_eq!(unescape("/a\\134b"), "/a\\b");
+        assert_eq!(unescape("/plain"), "/plain");
+        assert_eq!(unescape("/trailing\\"), "/trailing\\");
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse_limit("max").unwrap(), None);
+        assert_eq!(parse_limit("1048576").unwrap(), Some(1048576));
+        assert_eq!(parse_limit("9223372036854771712").unwrap(), None);
+        assert!(parse_limit("lots").is_err());
+
+        assert_eq!(parse_cpu_max("max 100000").unwrap(), (None, 100000));
+        assert_eq!(
+            parse_cpu_max("50000 100000").unwrap(),
+            (Some(50000), 100000)
+        );
+        assert!(parse_cpu_max("50000").is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_stats_of_current_process() {
+        let Ok(cgroups) = Cgroups::new() else {
+            return;
+        };
+        let pid = std::process::id() as usize;
+
+        // Controllers might not be available, in containers for example
+        if let Ok(memory) = cgroups.memory(pid) {
+            assert!(memory.usage > 0);
+        }
+        if let Ok(pids) = cgroups.pids(pid) {
+            assert!(pids.current >= 1);
+        }
+        if let Ok(cpu) = cgroups.cpu(pid) {
+            assert!(cpu.period_usec > 0);
+        }
+    }
+}
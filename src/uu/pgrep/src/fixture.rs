@@ -0,0 +1,164 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Synthetic `/proc` trees, for testing and benchmarking the matching of processes without
+//! depending on the processes which happen to run on the machine.
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A process to write in a [`ProcFixture`], with the fields read by the matchers.
+#[derive(Debug, Clone)]
+pub struct FakeProcess {
+    pub pid: usize,
+    pub ppid: usize,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    pub state: char,
+    pub uid: u32,
+    pub euid: u32,
+    pub gid: u32,
+    pub pgrp: usize,
+    pub session: usize,
+    pub start_time: u64,
+    pub tty: Option<String>,
+    pub cgroup: String,
+    pub environ: Vec<String>,
+    pub threads: Vec<usize>,
+    /// Inodes of the namespaces, by name
+    pub namespaces: Vec<(&'static str, u64)>,
+}
+
+/// The namespaces of the processes of the host, with the inodes the kernel gives them.
+const INIT_NAMESPACES: [(&str, u64); 8] = [
+    ("ipc", 4026531839),
+    ("mnt", 4026531841),
+    ("net", 4026531840),
+    ("pid", 4026531836),
+    ("user", 4026531837),
+    ("uts", 4026531838),
+    ("cgroup", 4026531835),
+    ("time", 4026531834),
+];
+
+impl FakeProcess {
+    /// A sleeping process of root, alone in its session and in the namespaces of the host,
+    /// running `name` without arguments.
+    pub fn new(pid: usize, name: &str) -> Self {
+        Self {
+            pid,
+            ppid: 1,
+            name: name.into(),
+            cmdline: vec![name.into()],
+            state: 'S',
+            uid: 0,
+            euid: 0,
+            gid: 0,
+            pgrp: pid,
+            session: pid,
+            start_time: pid as u64,
+            tty: None,
+            cgroup: "/".into(),
+            environ: vec![],
+            threads: vec![],
+            namespaces: INIT_NAMESPACES.to_vec(),
+        }
+    }
+
+    fn stat(&self, pid: usize) -> String {
+        let mut fields = vec!["0".to_string(); 52];
+        fields[0] = pid.to_string();
+        fields[1] = format!("({})", self.name);
+        fields[2] = self.state.to_string();
+        fields[3] = self.ppid.to_string();
+        fields[4] = self.pgrp.to_string();
+        fields[5] = self.session.to_string();
+        fields[6] = self.tty.as_deref().map_or(0, tty_nr).to_string();
+        fields[21] = self.start_time.to_string();
+        fields.join(" ") + "\n"
+    }
+
+    fn status(&self, pid: usize) -> String {
+        let (uid, euid, gid) = (self.uid, self.euid, self.gid);
+        format!(
+            "Name:\t{}\nState:\t{}\nTgid:\t{}\nPid:\t{pid}\nPPid:\t{}\n\
+             Uid:\t{uid}\t{euid}\t{euid}\t{euid}\nGid:\t{gid}\t{gid}\t{gid}\t{gid}\n\
+             Threads:\t{}\nSigCgt:\t0000000000004000\n",
+            self.name,
+            self.state,
+            self.pid,
+            self.ppid,
+            self.threads.len() + 1,
+        )
+    }
+
+    fn write(&self, dir: &Path, pid: usize) {
+        fs::create_dir_all(dir.join("fd")).unwrap();
+        fs::create_dir_all(dir.join("ns")).unwrap();
+        fs::write(dir.join("stat"), self.stat(pid)).unwrap();
+        fs::write(dir.join("status"), self.status(pid)).unwrap();
+        let mut cmdline = self.cmdline.join("\0");
+        cmdline.push('\0');
+        fs::write(dir.join("cmdline"), cmdline).unwrap();
+        fs::write(dir.join("environ"), self.environ.join("\0")).unwrap();
+        fs::write(dir.join("cgroup"), format!("0::{}\n", self.cgroup)).unwrap();
+        for (namespace, inode) in &self.namespaces {
+            symlink(
+                format!("{namespace}:[{inode}]"),
+                dir.join("ns").join(namespace),
+            )
+            .unwrap();
+        }
+        if let Some(tty) = &self.tty {
+            symlink(tty, dir.join("fd").join("0")).unwrap();
+        }
+    }
+}
+
+/// The device number of a terminal such as `/dev/pts/3`, as in the `tty_nr` field of `stat`.
+fn tty_nr(tty: &str) -> u64 {
+    let (major, minor) = if let Some(pts) = tty.strip_prefix("/dev/pts/") {
+        let pts = pts.parse::<u64>().unwrap();
+        (136 + pts / 256, pts % 256)
+    } else if let Some(serial) = tty.strip_prefix("/dev/ttyS") {
+        (4, 64 + serial.parse::<u64>().unwrap())
+    } else {
+        (4, tty.strip_prefix("/dev/tty").unwrap().parse().unwrap())
+    };
+    (major << 8) | (minor & 0xff) | ((minor & !0xff) << 12)
+}
+
+/// A directory laid out like `/proc`, removed when dropped.
+pub struct ProcFixture {
+    dir: TempDir,
+}
+
+impl ProcFixture {
+    pub fn new(processes: &[FakeProcess]) -> Self {
+        let fixture = Self {
+            dir: TempDir::new().unwrap(),
+        };
+        // 100000 seconds, the processes started a bit more than 50000 seconds ago at 100 Hz
+        fs::write(fixture.root().join("uptime"), "100000.00 400000.00\n").unwrap();
+        for process in processes {
+            fixture.add(process);
+        }
+        fixture
+    }
+
+    pub fn add(&self, process: &FakeProcess) {
+        let dir = self.root().join(process.pid.to_string());
+        process.write(&dir, process.pid);
+        for tid in std::iter::once(process.pid).chain(process.threads.iter().copied()) {
+            process.write(&dir.join("task").join(tid.to_string()), tid);
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+}
@@ -4,10 +4,14 @@
 // file that was distributed with this source code.
 
 // Pid utils
+pub mod cgroup;
+#[cfg(all(test, target_os = "linux"))]
+mod fixture;
 pub mod process;
 pub mod process_matcher;
 
 use clap::{arg, crate_version, Command};
+use std::{thread::sleep, time::Duration};
 use uucore::{error::UResult, format_usage, help_about, help_usage};
 
 const ABOUT: &str = help_about!("pgrep.md");
@@ -32,7 +36,16 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     settings.threads = matches.get_flag("lightweight");
 
     // Collect pids
-    let pids = process_matcher::find_matching_pids(&settings)?;
+    let mut pids = process_matcher::find_matching_pids(&settings)?;
+    if let Some(condition) = matches.get_one::<CountCondition>("until-count") {
+        let poll = *matches.get_one::<Duration>("poll").unwrap();
+        while !condition.is_met(pids.len()) {
+            sleep(poll);
+            pids = process_matcher::find_matching_pids(&settings)?;
+        }
+        // The wait succeeded, even when the condition is about having no match
+        uucore::error::set_exit_code(0);
+    }
 
     // Processing output
     let output = if matches.get_flag("count") {
@@ -70,6 +83,68 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     Ok(())
 }
 
+/// Condition on the number of matches given to `--until-count`, such as `>=3` or `==0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountCondition {
+    operator: CountOperator,
+    count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CountOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl CountCondition {
+    pub fn is_met(&self, count: usize) -> bool {
+        match self.operator {
+            CountOperator::Equal => count == self.count,
+            CountOperator::NotEqual => count != self.count,
+            CountOperator::Less => count < self.count,
+            CountOperator::LessEqual => count <= self.count,
+            CountOperator::Greater => count > self.count,
+            CountOperator::GreaterEqual => count >= self.count,
+        }
+    }
+}
+
+fn parse_count_condition(input: &str) -> Result<CountCondition, String> {
+    // Two characters operators first, so that `>=` is not read as `>`
+    let operators = [
+        ("==", CountOperator::Equal),
+        ("!=", CountOperator::NotEqual),
+        ("<=", CountOperator::LessEqual),
+        (">=", CountOperator::GreaterEqual),
+        ("<", CountOperator::Less),
+        (">", CountOperator::Greater),
+        ("=", CountOperator::Equal),
+    ];
+
+    let (operator, count) = operators
+        .iter()
+        .find_map(|(prefix, operator)| Some((*operator, input.strip_prefix(prefix)?)))
+        .unwrap_or((CountOperator::Equal, input));
+
+    let count = count
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| format!("invalid count condition '{input}'"))?;
+
+    Ok(CountCondition { operator, count })
+}
+
+fn parse_poll_interval(input: &str) -> Result<Duration, String> {
+    match input.parse::<f64>().map(Duration::try_from_secs_f64) {
+        Ok(Ok(interval)) if !interval.is_zero() => Ok(interval),
+        _ => Err(format!("invalid poll interval '{input}'")),
+    }
+}
+
 #[allow(clippy::cognitive_complexity)]
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
@@ -84,9 +159,51 @@ pub fn uu_app() -> Command {
             arg!(-l     --"list-name"           "list PID and process name"),
             arg!(-a     --"list-full"           "list PID and full command line"),
             arg!(-w     --lightweight           "list all TID"),
+            arg!(--"until-count" <condition>        "wait until the match count satisfies e.g. >=3")
+                .value_parser(parse_count_condition),
+            arg!(--poll <seconds>                   "interval between checks of --until-count")
+                .value_parser(parse_poll_interval)
+                .default_value("1")
+                .requires("until-count"),
         ])
         .args(process_matcher::clap_args(
             "Name of the program to find the PID of",
             true,
         ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_count_condition() {
+        let condition = parse_count_condition(">=3").unwrap();
+        assert!(!condition.is_met(2));
+        assert!(condition.is_met(3));
+        assert!(condition.is_met(4));
+
+        let condition = parse_count_condition("==0").unwrap();
+        assert!(condition.is_met(0));
+        assert!(!condition.is_met(1));
+
+        assert!(parse_count_condition("<2").unwrap().is_met(1));
+        assert!(!parse_count_condition(">2").unwrap().is_met(2));
+        assert!(parse_count_condition("!=1").unwrap().is_met(2));
+        assert!(parse_count_condition("5").unwrap().is_met(5));
+
+        assert!(parse_count_condition(">=").is_err());
+        assert!(parse_count_condition("~3").is_err());
+        assert!(parse_count_condition(">-1").is_err());
+    }
+
+    #[test]
+    fn test_parse_poll_interval() {
+        assert_eq!(parse_poll_interval("0.5"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_poll_interval("2"), Ok(Duration::from_secs(2)));
+
+        for input in ["0", "-1", "inf", "NaN", "1e300", "soon"] {
+            assert!(parse_poll_interval(input).is_err(), "{input}");
+        }
+    }
+}
@@ -11,7 +11,7 @@ use std::{
     collections::HashMap,
     fmt::{self, Display, Formatter},
     fs, io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 use walkdir::{DirEntry, WalkDir};
@@ -27,14 +27,31 @@ pub enum Teletype {
 impl Display for Teletype {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
-            Self::Tty(id) => write!(f, "/dev/pts/{id}"),
-            Self::TtyS(id) => write!(f, "/dev/tty{id}"),
-            Self::Pts(id) => write!(f, "/dev/ttyS{id}"),
+            Self::Tty(id) => write!(f, "/dev/tty{id}"),
+            Self::TtyS(id) => write!(f, "/dev/ttyS{id}"),
+            Self::Pts(id) => write!(f, "/dev/pts/{id}"),
             Self::Unknown => write!(f, "?"),
         }
     }
 }
 
+impl Teletype {
+    /// The terminal of a device number, as in the `tty_nr` field of `/proc/<pid>/stat`.
+    ///
+    /// - [Linux allocated devices](https://docs.kernel.org/admin-guide/devices.html)
+    pub fn from_tty_nr(tty_nr: u64) -> Self {
+        let major = (tty_nr >> 8) & 0xfff;
+        let minor = (tty_nr & 0xff) | ((tty_nr >> 12) & 0xfff00);
+        match major {
+            4 if minor < 64 => Self::Tty(minor),
+            4 => Self::TtyS(minor - 64),
+            // Unix98 pseudo terminals span 8 majors of 256 minors
+            136..=143 => Self::Pts((major - 136) * 256 + minor),
+            _ => Self::Unknown,
+        }
+    }
+}
+
 impl TryFrom<String> for Teletype {
     type Error = ();
 
@@ -224,6 +241,9 @@ pub struct ProcessInformation {
     pub pid: usize,
     pub cmdline: String,
 
+    /// Directory of the process, such as `/proc/<pid>` or `/proc/<pid>/task/<tid>`
+    path: PathBuf,
+
     inner_status: String,
     inner_stat: String,
 
@@ -268,16 +288,19 @@ impl ProcessInformation {
                 .parse::<usize>()
                 .map_err(|_| io::ErrorKind::InvalidData)?
         };
-        let cmdline = fs::read_to_string(dir_append(value.clone(), "cmdline".into()))?
-            .replace('\0', " ")
-            .trim_end()
-            .into();
+        // Read as a whole whatever its size, arguments are not necessarily valid UTF-8
+        let cmdline =
+            String::from_utf8_lossy(&fs::read(dir_append(value.clone(), "cmdline".into()))?)
+                .replace('\0', " ")
+                .trim_end()
+                .into();
 
         Ok(Self {
             pid,
             cmdline,
             inner_status: fs::read_to_string(dir_append(value.clone(), "status".into()))?,
-            inner_stat: fs::read_to_string(dir_append(value, "stat".into()))?,
+            inner_stat: fs::read_to_string(dir_append(value.clone(), "stat".into()))?,
+            path: value,
             ..Default::default()
         })
     }
@@ -382,6 +405,25 @@ impl ProcessInformation {
         self.get_numeric_stat_field(5)
     }
 
+    fn get_numeric_status_field(&mut self, field: &str) -> Result<u64, io::Error> {
+        self.status()
+            .get(field)
+            .ok_or(io::ErrorKind::InvalidData)?
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    /// Thread group ID, which is the PID of the process a thread belongs to
+    pub fn tgid(&mut self) -> Result<u64, io::Error> {
+        self.get_numeric_status_field("Tgid")
+    }
+
+    /// Number of threads of the process
+    pub fn num_threads(&mut self) -> Result<u64, io::Error> {
+        self.get_numeric_status_field("Threads")
+    }
+
     fn get_uid_or_gid_field(&mut self, field: &str, index: usize) -> Result<u32, io::Error> {
         self.status()
             .get(field)
@@ -459,12 +501,29 @@ impl ProcessInformation {
 
     // Root directory of the process (which can be changed by chroot)
     pub fn root(&mut self) -> Result<PathBuf, io::Error> {
-        read_link(format!("/proc/{}/root", self.pid))
+        read_link(self.path.join("root"))
+    }
+
+    /// The inode identifying a namespace of the process, such as `net`, from the target of
+    /// `/proc/<pid>/ns/net` as in `net:[4026531840]`.
+    ///
+    /// Reading it needs the permission to trace the process.
+    pub fn namespace(&self, namespace: &str) -> Result<u64, io::Error> {
+        let target = read_link(self.path.join("ns").join(namespace))?;
+        target
+            .to_str()
+            .and_then(|it| {
+                it.strip_prefix(namespace)?
+                    .strip_prefix(":[")?
+                    .strip_suffix(']')
+            })
+            .and_then(|inode| inode.parse().ok())
+            .ok_or(io::ErrorKind::InvalidData.into())
     }
 
     /// Returns cgroups (both v1 and v2) that the process belongs to.
     pub fn cgroups(&mut self) -> Result<Vec<CgroupMembership>, io::Error> {
-        fs::read_to_string(format!("/proc/{}/cgroup", self.pid))?
+        fs::read_to_string(self.path.join("cgroup"))?
             .lines()
             .map(CgroupMembership::try_from)
             .collect()
@@ -491,28 +550,18 @@ impl ProcessInformation {
         RunState::try_from(self.stat().get(2).unwrap().as_str())
     }
 
-    /// This function will scan the `/proc/<pid>/fd` directory
+    /// The controlling terminal, from the `tty_nr` field of `/proc/<pid>/stat`
     ///
-    /// If the process does not belong to any terminal and mismatched permission,
-    /// the result will contain [TerminalType::Unknown].
+    /// Unlike the file descriptors, the field is readable for the processes of every user, and
+    /// doesn't mistake any terminal opened by the process for its controlling terminal.
     ///
-    /// Otherwise [TerminalType::Unknown] does not appear in the result.
+    /// The processes without a controlling terminal, or with one which is neither a virtual
+    /// console, a serial port nor a pseudo terminal, have [Teletype::Unknown].
     pub fn tty(&self) -> Teletype {
-        let path = PathBuf::from(format!("/proc/{}/fd", self.pid));
-
-        let Ok(result) = fs::read_dir(path) else {
-            return Teletype::Unknown;
-        };
-
-        for dir in result.flatten().filter(|it| it.path().is_symlink()) {
-            if let Ok(path) = fs::read_link(dir.path()) {
-                if let Ok(tty) = Teletype::try_from(path) {
-                    return tty;
-                }
-            }
-        }
-
-        Teletype::Unknown
+        stat_split(&self.inner_stat)
+            .get(6)
+            .and_then(|tty_nr| tty_nr.parse().ok())
+            .map_or(Teletype::Unknown, Teletype::from_tty_nr)
     }
 
     pub fn thread_ids(&mut self) -> Rc<Vec<usize>> {
@@ -520,7 +569,7 @@ impl ProcessInformation {
             return Rc::clone(c);
         }
 
-        let tids_dir = format!("/proc/{}/task", self.pid);
+        let tids_dir = self.proc_root().join(self.pid.to_string()).join("task");
         let result = Rc::new(
             WalkDir::new(tids_dir)
                 .min_depth(1)
@@ -540,8 +589,38 @@ impl ProcessInformation {
         Rc::clone(&result)
     }
 
+    /// Information of each thread of the process, read from `/proc/<pid>/task/<tid>`
+    ///
+    /// Threads which exit while being read are skipped.
+    pub fn threads(&mut self) -> Vec<ProcessInformation> {
+        let tasks = self.proc_root().join(self.pid.to_string()).join("task");
+        let mut threads = self
+            .thread_ids()
+            .iter()
+            .flat_map(|tid| Self::try_new(tasks.join(tid.to_string())))
+            .collect::<Vec<_>>();
+        threads.sort_by_key(|it| it.pid);
+        threads
+    }
+
+    /// The directory holding the directories of the processes, `/proc` outside of the tests
+    fn proc_root(&self) -> &Path {
+        let parent = self.path.parent().unwrap_or(&self.path);
+        if parent.ends_with("task") {
+            // Threads have their directory in the one of their process
+            parent.parent().and_then(Path::parent).unwrap_or(parent)
+        } else {
+            parent
+        }
+    }
+
+    /// Contents of the `maps` file, listing the memory mappings of the process
+    pub fn maps(&self) -> Result<String, io::Error> {
+        fs::read_to_string(self.path.join("maps"))
+    }
+
     pub fn env_vars(&self) -> Result<HashMap<String, String>, io::Error> {
-        let content = fs::read_to_string(format!("/proc/{}/environ", self.pid))?;
+        let content = fs::read_to_string(self.path.join("environ"))?;
 
         let mut env_vars = HashMap::new();
         for entry in content.split('\0') {
@@ -593,26 +672,50 @@ fn stat_split(stat: &str) -> Vec<String> {
 
 /// Iterating pid in current system
 pub fn walk_process() -> impl Iterator<Item = ProcessInformation> {
-    WalkDir::new("/proc/")
+    walk_process_in(Path::new("/proc"))
+}
+
+/// Iterating the processes of a procfs mounted at `root`, or of a copy of one
+///
+/// All the PIDs are listed before the files of the first process are read, so the processes
+/// started during the walk are left out. The processes exiting in the meantime are skipped.
+pub fn walk_process_in(root: &Path) -> impl Iterator<Item = ProcessInformation> {
+    let pids = WalkDir::new(root)
+        .min_depth(1)
         .max_depth(1)
         .follow_links(false)
         .into_iter()
         .flatten()
-        .filter(|it| it.path().is_dir())
-        .flat_map(ProcessInformation::try_from)
+        .filter(|it| it.file_type().is_dir() && it.file_name().to_str().is_some_and(is_pid))
+        .map(DirEntry::into_path)
+        .collect::<Vec<_>>();
+
+    pids.into_iter().flat_map(ProcessInformation::try_new)
+}
+
+fn is_pid(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|it| it.is_ascii_digit())
 }
 
-static THREAD_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^/proc/[0-9]+$|^/proc/[0-9]+/task$|^/proc/[0-9]+/task/[0-9]+$").unwrap()
-});
+static THREAD_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[0-9]+$|^[0-9]+/task$|^[0-9]+/task/[0-9]+$").unwrap());
 
 pub fn walk_threads() -> impl Iterator<Item = ProcessInformation> {
-    WalkDir::new("/proc/")
+    walk_threads_in(Path::new("/proc"))
+}
+
+pub fn walk_threads_in(root: &Path) -> impl Iterator<Item = ProcessInformation> {
+    let root = root.to_path_buf();
+    WalkDir::new(&root)
         .min_depth(1)
         .max_depth(3)
         .follow_links(false)
         .into_iter()
-        .filter_entry(|e| THREAD_REGEX.is_match(e.path().as_os_str().to_string_lossy().as_ref()))
+        .filter_entry(move |e| {
+            e.path().strip_prefix(&root).is_ok_and(|path| {
+                THREAD_REGEX.is_match(path.as_os_str().to_string_lossy().as_ref())
+            })
+        })
         .flatten()
         .filter(|it| it.path().as_os_str().to_string_lossy().contains("/task/"))
         .flat_map(ProcessInformation::try_from)
@@ -644,6 +747,39 @@ mod tests {
         assert!(RunState::try_from("Rg").is_err());
     }
 
+    #[test]
+    fn test_from_tty_nr() {
+        assert_eq!(Teletype::from_tty_nr(0), Teletype::Unknown);
+        assert_eq!(Teletype::from_tty_nr(34819), Teletype::Pts(3));
+        assert_eq!(Teletype::from_tty_nr(35116), Teletype::Pts(300));
+        assert_eq!(Teletype::from_tty_nr(1025), Teletype::Tty(1));
+        assert_eq!(Teletype::from_tty_nr(1088), Teletype::TtyS(0));
+        // /dev/console
+        assert_eq!(Teletype::from_tty_nr(1281), Teletype::Unknown);
+
+        assert_eq!(Teletype::Pts(3).to_string(), "/dev/pts/3");
+        assert_eq!(Teletype::Tty(1).to_string(), "/dev/tty1");
+        assert_eq!(Teletype::TtyS(0).to_string(), "/dev/ttyS0");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_walk_process_in() {
+        use crate::fixture::{FakeProcess, ProcFixture};
+
+        let fixture = ProcFixture::new(&[FakeProcess::new(1, "init"), FakeProcess::new(7, "sh")]);
+        // Neither processes, nor a process which exited before its files were read
+        fs::create_dir(fixture.root().join("sys")).unwrap();
+        fs::create_dir(fixture.root().join("42")).unwrap();
+        std::os::unix::fs::symlink("7", fixture.root().join("self")).unwrap();
+
+        let mut pids = walk_process_in(fixture.root())
+            .map(|it| it.pid)
+            .collect::<Vec<_>>();
+        pids.sort_unstable();
+        assert_eq!(pids, [1, 7]);
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_walk_pid() {
@@ -671,6 +807,17 @@ mod tests {
         assert!(result.contains(&pid_entry.tty()));
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_namespace() {
+        use std::os::unix::fs::MetadataExt;
+
+        let pid_entry = ProcessInformation::current_process_info().unwrap();
+        let inode = fs::metadata("/proc/self/ns/net").unwrap().ino();
+        assert_eq!(pid_entry.namespace("net").unwrap(), inode);
+        assert!(pid_entry.namespace("bogus").is_err());
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn test_thread_ids() {
@@ -688,6 +835,24 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_threads() {
+        std::thread::spawn(move || {
+            let mut pid_entry = ProcessInformation::current_process_info().unwrap();
+            let new_thread_tid = unsafe { uucore::libc::gettid() } as usize;
+
+            let mut threads = pid_entry.threads();
+            assert!(threads.iter().any(|it| it.pid == new_thread_tid));
+            assert!(threads
+                .iter_mut()
+                .all(|it| it.tgid().unwrap() == pid_entry.pid as u64));
+            assert!(pid_entry.num_threads().unwrap() >= 2);
+        })
+        .join()
+        .unwrap();
+    }
+
     #[test]
     fn test_stat_split() {
         let case = "32 (idle_inject/3) S 2 0 0 0 -1 69238848 0 0 0 0 0 0 0 0 -51 0 1 0 34 0 0 18446744073709551615 0 0 0 0 0 0 0 2147483647 0 0 0 0 17 3 50 1 0 0 0 0 0 0 0 0 0 0 0";
@@ -765,4 +930,29 @@ mod tests {
             std::env::var("HOME").unwrap()
         );
     }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_long_cmdline() {
+        let dir = std::env::temp_dir()
+            .join(format!("uu_pgrep_test_long_cmdline_{}", getpid()))
+            .join("4242");
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["status", "stat"] {
+            fs::copy(format!("/proc/self/{file}"), dir.join(file)).unwrap();
+        }
+
+        // Much larger than a page, with an argument which isn't valid UTF-8
+        let argument = "a".repeat(300_000);
+        let mut cmdline = format!("java\0-cp\0{argument}\0").into_bytes();
+        cmdline.extend_from_slice(b"\xff\0");
+        fs::write(dir.join("cmdline"), cmdline).unwrap();
+
+        let pid_entry = ProcessInformation::try_new(dir.clone());
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+
+        let pid_entry = pid_entry.unwrap();
+        assert_eq!(pid_entry.pid, 4242);
+        assert_eq!(pid_entry.cmdline, format!("java -cp {argument} \u{fffd}"));
+    }
 }
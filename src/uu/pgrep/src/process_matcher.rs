@@ -8,7 +8,11 @@
 use std::hash::Hash;
 #[cfg(unix)]
 use std::os::fd::AsRawFd;
-use std::{collections::HashSet, io};
+use std::path::{Path, PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+};
 
 use clap::{arg, Arg, ArgAction, ArgMatches};
 use regex::Regex;
@@ -23,7 +27,7 @@ use uucore::{
 
 use uucore::error::{UResult, USimpleError};
 
-use crate::process::{walk_process, walk_threads, ProcessInformation, Teletype};
+use crate::process::{walk_process_in, walk_threads_in, ProcessInformation, Teletype};
 
 pub struct Settings {
     pub regex: Regex,
@@ -34,7 +38,8 @@ pub struct Settings {
     pub inverse: bool,
     pub newest: bool,
     pub oldest: bool,
-    pub older: Option<u64>,
+    pub older: Option<Age>,
+    pub newer: Option<Age>,
     pub parent: Option<HashSet<u64>>,
     pub runstates: Option<String>,
     pub terminal: Option<HashSet<Teletype>>,
@@ -48,11 +53,18 @@ pub struct Settings {
     pub session: Option<HashSet<u64>>,
     pub cgroup: Option<HashSet<String>>,
     pub env: Option<HashSet<String>>,
+    /// The process whose namespaces the matches share, the ones of `nslist` or all of them
+    pub ns: Option<usize>,
+    pub nslist: Option<Vec<String>>,
     pub threads: bool,
 
     pub pidfile: Option<String>,
     pub logpidfile: bool,
     pub ignore_ancestors: bool,
+    pub exclude_descendants: bool,
+
+    /// Where procfs is mounted, a synthetic tree in the tests
+    pub proc_root: PathBuf,
 }
 
 pub fn get_match_settings(matches: &ArgMatches) -> UResult<Settings> {
@@ -71,7 +83,8 @@ pub fn get_match_settings(matches: &ArgMatches) -> UResult<Settings> {
             .get_many::<u64>("parent")
             .map(|parents| parents.copied().collect()),
         runstates: matches.get_one::<String>("runstates").cloned(),
-        older: matches.get_one::<u64>("older").copied(),
+        older: matches.get_one::<Age>("older").copied(),
+        newer: matches.get_one::<Age>("newer").copied(),
         terminal: matches.get_many::<String>("terminal").map(|ttys| {
             ttys.cloned()
                 .flat_map(Teletype::try_from)
@@ -109,22 +122,35 @@ pub fn get_match_settings(matches: &ArgMatches) -> UResult<Settings> {
             })
             .collect()
         }),
-        cgroup: matches
-            .get_many::<String>("cgroup")
-            .map(|groups| groups.cloned().collect()),
+        cgroup: matches.get_many::<String>("cgroup").map(|groups| {
+            groups
+                .map(|group| match group.trim_end_matches('/') {
+                    // The root of the hierarchy
+                    "" => "/".to_string(),
+                    group => group.to_string(),
+                })
+                .collect()
+        }),
         env: matches
             .get_many::<String>("env")
             .map(|env_vars| env_vars.cloned().collect()),
+        ns: matches.get_one::<usize>("ns").copied(),
+        nslist: matches
+            .get_many::<String>("nslist")
+            .map(|namespaces| namespaces.cloned().collect()),
         threads: false,
         pidfile: matches.get_one::<String>("pidfile").cloned(),
         logpidfile: matches.get_flag("logpidfile"),
         ignore_ancestors: matches.get_flag("ignore-ancestors"),
+        exclude_descendants: matches.get_flag("exclude-descendants"),
+        proc_root: PathBuf::from("/proc"),
     };
 
     if !settings.newest
         && !settings.oldest
         && settings.runstates.is_none()
         && settings.older.is_none()
+        && settings.newer.is_none()
         && settings.parent.is_none()
         && settings.terminal.is_none()
         && settings.uid.is_none()
@@ -134,6 +160,7 @@ pub fn get_match_settings(matches: &ArgMatches) -> UResult<Settings> {
         && settings.session.is_none()
         && settings.cgroup.is_none()
         && settings.env.is_none()
+        && settings.ns.is_none()
         && !settings.require_handler
         && settings.pidfile.is_none()
         && pattern.is_empty()
@@ -203,13 +230,136 @@ fn any_matches<T: Eq + Hash>(optional_ids: &Option<HashSet<T>>, id: T) -> bool {
     optional_ids.as_ref().is_none_or(|ids| ids.contains(&id))
 }
 
+/// What `--older` and `--newer` compare the processes with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Age {
+    /// Seconds since the processes started
+    Seconds(u64),
+    /// The start of another process, as in `pid:<PID>`
+    Process(usize),
+}
+
+/// An [Age] as a start time, in clock ticks since boot like in `/proc/<pid>/stat`.
+#[derive(Debug, Clone, Copy)]
+struct StartLimit {
+    ticks: i128,
+    /// Whether the processes started exactly then are older, they are for ages in seconds but
+    /// a process is neither older nor newer than itself
+    inclusive: bool,
+}
+
+impl StartLimit {
+    fn new(age: Age, proc_root: &Path) -> UResult<Self> {
+        match age {
+            Age::Seconds(seconds) => {
+                let uptime = fs::read_to_string(proc_root.join("uptime"))
+                    .ok()
+                    .and_then(|it| it.split_whitespace().next()?.parse::<f64>().ok())
+                    .ok_or_else(|| USimpleError::new(1, "cannot read the uptime"))?;
+                let hertz = clock_ticks() as f64;
+                Ok(Self {
+                    ticks: (uptime * hertz) as i128 - (seconds as f64 * hertz) as i128,
+                    inclusive: true,
+                })
+            }
+            Age::Process(pid) => {
+                let start_time = ProcessInformation::try_new(proc_root.join(pid.to_string()))
+                    .and_then(|mut process| process.start_time())
+                    .map_err(|_| USimpleError::new(1, format!("no process with PID {pid}")))?;
+                Ok(Self {
+                    ticks: start_time.into(),
+                    inclusive: false,
+                })
+            }
+        }
+    }
+
+    fn is_older(self, start_time: u64) -> bool {
+        let start_time = i128::from(start_time);
+        start_time < self.ticks || (self.inclusive && start_time == self.ticks)
+    }
+
+    fn is_newer(self, start_time: u64) -> bool {
+        i128::from(start_time) > self.ticks
+    }
+}
+
+/// Clock ticks per second, the unit of the times in `/proc/<pid>/stat`
+#[cfg(unix)]
+pub fn clock_ticks() -> u64 {
+    static CLOCK_TICKS: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    // SAFETY: sysconf has no preconditions
+    *CLOCK_TICKS.get_or_init(
+        || match unsafe { uucore::libc::sysconf(uucore::libc::_SC_CLK_TCK) } {
+            ticks if ticks > 0 => ticks as u64,
+            _ => 100,
+        },
+    )
+}
+
+#[cfg(not(unix))]
+pub fn clock_ticks() -> u64 {
+    100
+}
+
+/// The namespaces which `--ns` compares, those shown in `/proc/<pid>/ns`.
+const NAMESPACES: [&str; 8] = ["ipc", "mnt", "net", "pid", "user", "uts", "cgroup", "time"];
+
+/// The inodes of the namespaces of the reference process of `--ns`, by name.
+///
+/// The namespaces missing from the kernel are left out, unless they are asked for.
+fn reference_namespaces(
+    pid: usize,
+    nslist: Option<&[String]>,
+    proc_root: &Path,
+) -> UResult<Vec<(String, u64)>> {
+    let process = ProcessInformation::try_new(proc_root.join(pid.to_string()))
+        .map_err(|_| USimpleError::new(1, format!("no process with PID {pid}")))?;
+    let read_error = || {
+        USimpleError::new(
+            1,
+            format!("cannot read the namespaces of the process with PID {pid}"),
+        )
+    };
+
+    match nslist {
+        Some(nslist) => nslist
+            .iter()
+            .map(|namespace| {
+                let inode = process.namespace(namespace).map_err(|_| read_error())?;
+                Ok((namespace.clone(), inode))
+            })
+            .collect(),
+        None => {
+            let namespaces = NAMESPACES
+                .iter()
+                .filter_map(|namespace| {
+                    Some((namespace.to_string(), process.namespace(namespace).ok()?))
+                })
+                .collect::<Vec<_>>();
+            if namespaces.is_empty() {
+                Err(read_error())
+            } else {
+                Ok(namespaces)
+            }
+        }
+    }
+}
+
+/// `pid` and its ancestors, up to init or to the first one which is gone
 fn get_ancestors(process_infos: &mut [ProcessInformation], mut pid: usize) -> HashSet<usize> {
     let mut ret = HashSet::from([pid]);
     while pid != 1 {
-        if let Some(process) = process_infos.iter_mut().find(|p| p.pid == pid) {
-            pid = process.ppid().unwrap() as usize;
-            ret.insert(pid);
-        } else {
+        let Some(Ok(ppid)) = process_infos
+            .iter_mut()
+            .find(|p| p.pid == pid)
+            .map(|process| process.ppid())
+        else {
+            break;
+        };
+        pid = ppid as usize;
+        // Stop on a loop while processes are reparented
+        if !ret.insert(pid) {
             break;
         }
     }
@@ -218,14 +368,27 @@ fn get_ancestors(process_infos: &mut [ProcessInformation], mut pid: usize) -> Ha
 
 /// Collect pids with filter construct from command line arguments
 fn collect_matched_pids(settings: &Settings) -> UResult<Vec<ProcessInformation>> {
+    let older = settings
+        .older
+        .map(|age| StartLimit::new(age, &settings.proc_root))
+        .transpose()?;
+    let newer = settings
+        .newer
+        .map(|age| StartLimit::new(age, &settings.proc_root))
+        .transpose()?;
+    let namespaces = settings
+        .ns
+        .map(|pid| reference_namespaces(pid, settings.nslist.as_deref(), &settings.proc_root))
+        .transpose()?;
+
     // Filtration general parameters
     let filtered: Vec<ProcessInformation> = {
         let mut tmp_vec = Vec::new();
 
         let mut pids = if settings.threads {
-            walk_threads().collect::<Vec<_>>()
+            walk_threads_in(&settings.proc_root).collect::<Vec<_>>()
         } else {
-            walk_process().collect::<Vec<_>>()
+            walk_process_in(&settings.proc_root).collect::<Vec<_>>()
         };
         let our_pid = std::process::id() as usize;
         let ignored_pids = if settings.ignore_ancestors {
@@ -234,6 +397,15 @@ fn collect_matched_pids(settings: &Settings) -> UResult<Vec<ProcessInformation>>
             HashSet::from([our_pid])
         };
 
+        // The whole tree, as the matches may descend from each other through other processes
+        let parents = if settings.exclude_descendants {
+            pids.iter_mut()
+                .filter_map(|it| Some((it.pid, it.ppid().ok()? as usize)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
         let pid_from_pidfile = settings
             .pidfile
             .as_ref()
@@ -253,28 +425,28 @@ fn collect_matched_pids(settings: &Settings) -> UResult<Vec<ProcessInformation>>
                 _ => true,
             };
 
-            let name = pid.name().unwrap();
-            let name = if settings.ignore_case {
-                name.to_lowercase()
-            } else {
-                name
-            };
             let pattern_matched = {
-                let want = if settings.full {
+                let want = if settings.full && !pid.cmdline.is_empty() {
                     // Equals `cmdline` in /proc/<pid>/cmdline
-                    &pid.cmdline
+                    pid.cmdline.clone()
                 } else {
-                    // Equals `Name` in /proc/<pid>/status
-                    &name
+                    // Equals `Name` in /proc/<pid>/status, kernel threads have no command line
+                    pid.name().unwrap()
+                };
+                let want = if settings.ignore_case {
+                    want.to_lowercase()
+                } else {
+                    want
                 };
 
-                settings.regex.is_match(want)
+                settings.regex.is_match(&want)
             };
 
             let tty_matched = any_matches(&settings.terminal, pid.tty());
 
-            let arg_older = settings.older.unwrap_or(0);
-            let older_matched = pid.start_time().unwrap() >= arg_older;
+            let start_time = pid.start_time().unwrap();
+            let older_matched = older.is_none_or(|limit| limit.is_older(start_time));
+            let newer_matched = newer.is_none_or(|limit| limit.is_newer(start_time));
 
             let parent_matched = any_matches(&settings.parent, pid.ppid().unwrap());
             let pgroup_matched = any_matches(&settings.pgroup, pid.pgid().unwrap());
@@ -300,6 +472,12 @@ fn collect_matched_pids(settings: &Settings) -> UResult<Vec<ProcessInformation>>
                 None => true,
             };
 
+            let ns_matched = namespaces.as_ref().is_none_or(|namespaces| {
+                namespaces
+                    .iter()
+                    .all(|(namespace, inode)| pid.namespace(namespace).ok() == Some(*inode))
+            });
+
             let ids_matched = any_matches(&settings.uid, pid.uid().unwrap())
                 && any_matches(&settings.euid, pid.euid().unwrap())
                 && any_matches(&settings.gid, pid.gid().unwrap());
@@ -327,11 +505,13 @@ fn collect_matched_pids(settings: &Settings) -> UResult<Vec<ProcessInformation>>
                 && pattern_matched
                 && tty_matched
                 && older_matched
+                && newer_matched
                 && parent_matched
                 && pgroup_matched
                 && session_matched
                 && cgroup_matched
                 && env_matched
+                && ns_matched
                 && ids_matched
                 && handler_matched
                 && pidfile_matched)
@@ -340,12 +520,44 @@ fn collect_matched_pids(settings: &Settings) -> UResult<Vec<ProcessInformation>>
                 tmp_vec.push(pid);
             }
         }
-        tmp_vec
+
+        if settings.exclude_descendants {
+            exclude_descendants(tmp_vec, &parents)
+        } else {
+            tmp_vec
+        }
     };
 
     Ok(filtered)
 }
 
+/// Keeps the topmost matches, such as a wrapper script rather than the commands it runs.
+fn exclude_descendants(
+    matched: Vec<ProcessInformation>,
+    parents: &HashMap<usize, usize>,
+) -> Vec<ProcessInformation> {
+    let pids = matched.iter().map(|it| it.pid).collect::<HashSet<_>>();
+
+    matched
+        .into_iter()
+        .filter(|process| {
+            let mut seen = HashSet::from([process.pid]);
+            let mut pid = process.pid;
+            while let Some(&parent) = parents.get(&pid) {
+                if pids.contains(&parent) {
+                    return false;
+                }
+                // Stop at the root of the tree, or on a loop while processes are reparented
+                if !seen.insert(parent) {
+                    break;
+                }
+                pid = parent;
+            }
+            true
+        })
+        .collect()
+}
+
 /// Sorting pids for flag `-o` and `-n`.
 ///
 /// This function can also be used as a filter to filter out process information.
@@ -374,9 +586,9 @@ fn process_flag_o_n(
             .collect::<Vec<_>>();
 
         if settings.newest {
-            filtered.sort_by(|a, b| b.pid.cmp(&a.pid));
+            filtered.sort_by_key(|it| std::cmp::Reverse(it.pid));
         } else {
-            filtered.sort_by(|a, b| a.pid.cmp(&b.pid));
+            filtered.sort_by_key(|it| it.pid);
         }
 
         vec![filtered.first().cloned().unwrap().clone()]
@@ -423,6 +635,14 @@ pub unsafe fn getsid(_pid: u32) -> u32 {
     panic!("unsupported on this platform");
 }
 
+fn parse_age(age: &str) -> Result<Age, String> {
+    match age.strip_prefix("pid:") {
+        Some(pid) => pid.parse().map(Age::Process),
+        None => age.parse().map(Age::Seconds),
+    }
+    .map_err(|e| e.to_string())
+}
+
 fn parse_uid_or_username(uid_or_username: &str) -> io::Result<u32> {
     uid_or_username
         .parse::<u32>()
@@ -528,8 +748,10 @@ pub fn clap_args(pattern_help: &'static str, enable_v_flag: bool) -> Vec<Arg> {
             .group("oldest_newest_inverse"),
         arg!(-o --oldest               "select least recently started")
             .group("oldest_newest_inverse"),
-        arg!(-O --older <seconds>      "select where older than seconds")
-            .value_parser(clap::value_parser!(u64)),
+        arg!(-O --older <seconds>      "select where older than seconds, or than pid:<PID>")
+            .value_parser(parse_age),
+        arg!(-N --newer <seconds>      "select where newer than seconds, or than pid:<PID>")
+            .value_parser(parse_age),
         arg!(-P --parent <PPID>        "match only child processes of the given parent")
             .value_delimiter(',')
             .value_parser(clap::value_parser!(u64)),
@@ -550,15 +772,267 @@ pub fn clap_args(pattern_help: &'static str, enable_v_flag: bool) -> Vec<Arg> {
         arg!(-L --logpidfile           "fail if PID file is not locked"),
         arg!(-r --runstates <state>    "match runstates [D,S,Z,...]"),
         arg!(-A --"ignore-ancestors"   "exclude our ancestors from results"),
+        arg!(--"exclude-descendants"   "exclude the descendants of other matches from results"),
         arg!(--cgroup <grp>            "match by cgroup v2 names").value_delimiter(','),
         arg!(--env <"name[=val],...">      "match on environment variable").value_delimiter(','),
-        // arg!(--ns <PID>                "match the processes that belong to the same namespace as <pid>"),
-        // arg!(--nslist <ns>             "list which namespaces will be considered for the --ns option.")
-        //     .value_delimiter(',')
-        //     .value_parser(["ipc", "mnt", "net", "pid", "user", "uts"]),
+        arg!(--ns <PID>                "match the processes that belong to the same namespace as <pid>")
+            .value_parser(clap::value_parser!(usize)),
+        arg!(--nslist <ns>             "list which namespaces will be considered for the --ns option.")
+            .value_delimiter(',')
+            .value_parser(NAMESPACES)
+            .requires("ns"),
         Arg::new("pattern")
             .help(pattern_help)
             .action(ArgAction::Append)
             .index(1),
     ]
 }
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+    use crate::fixture::{FakeProcess, ProcFixture};
+
+    /// Far above `pid_max`, so that the processes can't collide with the test itself
+    const BASE: usize = 5_000_000;
+
+    fn fixture() -> ProcFixture {
+        let mut init = FakeProcess::new(BASE, "init");
+        init.cmdline = vec!["/sbin/init".into(), "splash".into()];
+
+        let mut shell = FakeProcess::new(BASE + 1, "bash");
+        shell.ppid = BASE;
+        shell.uid = 1000;
+        shell.euid = 1000;
+        shell.gid = 100;
+        shell.tty = Some("/dev/pts/3".into());
+        shell.environ = vec!["LANG=C".into(), "EDITOR=vi".into()];
+
+        let mut editor = FakeProcess::new(BASE + 2, "vim");
+        editor.ppid = BASE + 1;
+        editor.pgrp = BASE + 2;
+        editor.session = BASE + 1;
+        editor.uid = 1000;
+        editor.euid = 0;
+        editor.gid = 100;
+        editor.state = 'R';
+        editor.tty = Some("/dev/pts/3".into());
+        editor.cmdline = vec!["vim".into(), "notes.txt".into()];
+        editor.environ = vec!["LANG=fr_FR".into()];
+
+        let mut server = FakeProcess::new(BASE + 3, "Xorg");
+        server.cgroup = "/system.slice/display.service".into();
+        server.threads = vec![BASE + 10, BASE + 11];
+
+        let mut zombie = FakeProcess::new(BASE + 4, "bash");
+        zombie.ppid = BASE + 1;
+        zombie.state = 'Z';
+
+        ProcFixture::new(&[init, shell, editor, server, zombie])
+    }
+
+    fn matching(fixture: &ProcFixture, args: &[&str]) -> Vec<usize> {
+        let args = std::iter::once("pgrep").chain(args.iter().copied());
+        let matches = crate::uu_app().try_get_matches_from(args).unwrap();
+        let mut settings = get_match_settings(&matches).unwrap();
+        settings.threads = matches.get_flag("lightweight");
+        settings.proc_root = fixture.root().to_path_buf();
+
+        let mut pids = find_matching_pids(&settings)
+            .unwrap()
+            .into_iter()
+            .map(|it| it.pid - BASE)
+            .collect::<Vec<_>>();
+        pids.sort_unstable();
+        pids
+    }
+
+    #[test]
+    fn test_pattern() {
+        let fixture = fixture();
+        assert_eq!(matching(&fixture, &["bash"]), [1, 4]);
+        assert_eq!(matching(&fixture, &["^v"]), [2]);
+        assert_eq!(matching(&fixture, &["-x", "vi"]), [] as [usize; 0]);
+        assert_eq!(matching(&fixture, &["-x", "vim"]), [2]);
+        assert_eq!(matching(&fixture, &["xorg"]), [] as [usize; 0]);
+        assert_eq!(matching(&fixture, &["-i", "xorg"]), [3]);
+        assert_eq!(matching(&fixture, &["-f", "notes"]), [2]);
+        assert_eq!(matching(&fixture, &["-f", "splash$"]), [0]);
+        assert_eq!(matching(&fixture, &["-f", "-i", "NOTES"]), [2]);
+        assert_eq!(matching(&fixture, &["-v", "bash|vim"]), [0, 3]);
+
+        // Kernel threads have no command line, -f matches their name instead
+        let mut kworker = FakeProcess::new(BASE + 5, "kworker/0:1");
+        kworker.ppid = 2;
+        kworker.cmdline = vec![];
+        fixture.add(&kworker);
+        assert_eq!(matching(&fixture, &["-f", "^kworker"]), [5]);
+    }
+
+    #[test]
+    fn test_ids() {
+        let fixture = fixture();
+        assert_eq!(matching(&fixture, &["-U", "1000"]), [1, 2]);
+        assert_eq!(matching(&fixture, &["-u", "1000"]), [1]);
+        assert_eq!(matching(&fixture, &["-u", "0", "-U", "1000"]), [2]);
+        assert_eq!(matching(&fixture, &["-G", "100"]), [1, 2]);
+        assert_eq!(matching(&fixture, &["-G", "100", "vim"]), [2]);
+
+        // Names are looked up in the passwd and group databases, and mix with numeric IDs
+        assert_eq!(matching(&fixture, &["-U", "root"]), [0, 3, 4]);
+        assert_eq!(matching(&fixture, &["-u", "root,1000", "bash"]), [1, 4]);
+        assert_eq!(
+            matching(&fixture, &["-G", "root,100", "-U", "1000"]),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn test_process_relationships() {
+        let fixture = fixture();
+        let ppid = (BASE + 1).to_string();
+        assert_eq!(matching(&fixture, &["-P", &ppid]), [2, 4]);
+        assert_eq!(matching(&fixture, &["-P", &ppid, "bash"]), [4]);
+        assert_eq!(matching(&fixture, &["-s", &ppid]), [1, 2]);
+        let pgrp = (BASE + 2).to_string();
+        assert_eq!(matching(&fixture, &["-g", &pgrp]), [2]);
+        assert_eq!(matching(&fixture, &["-v", "-s", &ppid, "."]), [0, 3, 4]);
+
+        // The test itself, started from the shell
+        let mut test = FakeProcess::new(std::process::id() as usize, "cargo-test");
+        test.ppid = BASE + 1;
+        fixture.add(&test);
+        assert_eq!(matching(&fixture, &["bash"]), [1, 4]);
+        assert_eq!(matching(&fixture, &["-A", "bash"]), [4]);
+        assert_eq!(matching(&fixture, &["-A", "-v", "bash"]), [2, 3]);
+        assert_eq!(matching(&fixture, &["-A", "-P", &ppid]), [2, 4]);
+    }
+
+    #[test]
+    fn test_state_terminal_cgroup_and_env() {
+        let fixture = fixture();
+        assert_eq!(matching(&fixture, &["-r", "Z"]), [4]);
+        assert_eq!(matching(&fixture, &["-r", "R,Z"]), [2, 4]);
+        assert_eq!(matching(&fixture, &["-t", "pts/3"]), [1, 2]);
+        assert_eq!(matching(&fixture, &["-t", "pts/3", "-r", "S"]), [1]);
+        assert_eq!(
+            matching(&fixture, &["--cgroup", "/system.slice/display.service"]),
+            [3]
+        );
+        // The path of the cgroup, not of its parents
+        assert_eq!(
+            matching(&fixture, &["--cgroup", "/system.slice"]),
+            [] as [usize; 0]
+        );
+        assert_eq!(
+            matching(&fixture, &["--cgroup", "/,/system.slice/display.service/"]),
+            [0, 1, 2, 3, 4]
+        );
+        assert_eq!(matching(&fixture, &["--env", "LANG"]), [1, 2]);
+        assert_eq!(matching(&fixture, &["--env", "LANG=C"]), [1]);
+        assert_eq!(matching(&fixture, &["--env", "EDITOR,LANG=fr_FR"]), [1, 2]);
+
+        let mut getty = FakeProcess::new(BASE + 5, "agetty");
+        getty.tty = Some("/dev/ttyS0".into());
+        fixture.add(&getty);
+        assert_eq!(matching(&fixture, &["-t", "ttyS0,/dev/pts/3"]), [1, 2, 5]);
+        assert_eq!(matching(&fixture, &["-t", "?"]), [0, 3, 4]);
+
+        // Letters may also be given together, as procps looks them up in the list
+        let mut stuck = FakeProcess::new(BASE + 6, "rsync");
+        stuck.state = 'D';
+        fixture.add(&stuck);
+        assert_eq!(matching(&fixture, &["-r", "D"]), [6]);
+        assert_eq!(matching(&fixture, &["--runstates", "DZ"]), [4, 6]);
+        assert_eq!(matching(&fixture, &["-r", "D", "bash"]), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn test_newest_oldest_and_threads() {
+        let fixture = fixture();
+        assert_eq!(matching(&fixture, &["-n", "bash"]), [4]);
+        assert_eq!(matching(&fixture, &["-o", "bash"]), [1]);
+        assert_eq!(matching(&fixture, &["-w", "Xorg"]), [3, 10, 11]);
+    }
+
+    #[test]
+    fn test_older_newer() {
+        let fixture = fixture();
+        // The processes started at their PID in clock ticks since boot
+        assert_eq!(matching(&fixture, &["-O", "49999"]), [0, 1, 2, 3, 4]);
+        assert_eq!(matching(&fixture, &["-O", "50000"]), [0]);
+        let reference = format!("pid:{}", BASE + 2);
+        assert_eq!(matching(&fixture, &["--older", &reference]), [0, 1]);
+        assert_eq!(matching(&fixture, &["--newer", &reference]), [3, 4]);
+        assert_eq!(matching(&fixture, &["-N", &reference, "bash"]), [4]);
+
+        let mut fresh = FakeProcess::new(BASE + 5, "bash");
+        fresh.start_time = 9_990_000;
+        fixture.add(&fresh);
+        assert_eq!(matching(&fixture, &["-N", "200"]), [5]);
+        assert_eq!(
+            matching(&fixture, &["-O", "200", "-N", "60000", "bash"]),
+            [1, 4]
+        );
+    }
+
+    #[test]
+    fn test_exclude_descendants() {
+        let fixture = fixture();
+        assert_eq!(matching(&fixture, &["--exclude-descendants", "bash"]), [1]);
+        assert_eq!(
+            matching(&fixture, &["--exclude-descendants", "-v", "Xorg"]),
+            [0]
+        );
+        // vim descends from init through bash, which doesn't match
+        assert_eq!(
+            matching(&fixture, &["--exclude-descendants", "init|vim"]),
+            [0]
+        );
+        assert_eq!(
+            matching(&fixture, &["--exclude-descendants", "vim|Xorg"]),
+            [2, 3]
+        );
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let fixture = fixture();
+        // A container with its own network and PIDs
+        let mut container = FakeProcess::new(BASE + 6, "containerd-shim");
+        for (namespace, inode) in &mut container.namespaces {
+            if ["net", "pid"].contains(namespace) {
+                *inode += 1000;
+            }
+        }
+        let mut nginx = container.clone();
+        nginx.pid = BASE + 7;
+        nginx.ppid = BASE + 6;
+        nginx.name = "nginx".into();
+        nginx.cmdline = vec!["nginx".into()];
+        fixture.add(&container);
+        fixture.add(&nginx);
+
+        let host = (BASE + 1).to_string();
+        let shim = (BASE + 6).to_string();
+        assert_eq!(matching(&fixture, &["--ns", &host]), [0, 1, 2, 3, 4]);
+        assert_eq!(matching(&fixture, &["--ns", &shim]), [6, 7]);
+        assert_eq!(matching(&fixture, &["--ns", &shim, "nginx"]), [7]);
+        assert_eq!(
+            matching(&fixture, &["--ns", &shim, "--nslist", "uts"]),
+            [0, 1, 2, 3, 4, 6, 7]
+        );
+        assert_eq!(
+            matching(&fixture, &["--ns", &host, "--nslist", "ipc,net"]),
+            [0, 1, 2, 3, 4]
+        );
+
+        let matches = crate::uu_app()
+            .try_get_matches_from(["pgrep", "--ns", &(BASE + 99).to_string()])
+            .unwrap();
+        let mut settings = get_match_settings(&matches).unwrap();
+        settings.proc_root = fixture.root().to_path_buf();
+        assert!(find_matching_pids(&settings).is_err());
+    }
+}
@@ -3,15 +3,25 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use clap::{arg, crate_version, Command};
+use clap::{arg, crate_version, value_parser, Arg, Command};
+use std::time::Duration;
 use uu_pgrep::process_matcher;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
-use wait::wait;
+use uucore::{
+    error::{set_exit_code, UResult, USimpleError},
+    format_usage, help_about, help_section, help_usage,
+};
+use wait::{wait, Outcome};
 
 mod wait;
 
 const ABOUT: &str = help_about!("pidwait.md");
 const USAGE: &str = help_usage!("pidwait.md");
+const AFTER_HELP: &str = help_section!("after help", "pidwait.md");
+
+/// The timeout expired before every process ended.
+const EXIT_TIMEOUT: i32 = 3;
+/// At least one of the processes was killed by a signal.
+const EXIT_KILLED: i32 = 4;
 
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
@@ -20,9 +30,17 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let settings = process_matcher::get_match_settings(&matches)?;
     let mut proc_infos = process_matcher::find_matching_pids(&settings)?;
 
+    let timeout = match matches.get_one::<f64>("timeout") {
+        Some(seconds) => Some(
+            Duration::try_from_secs_f64(*seconds)
+                .map_err(|_| USimpleError::new(2, format!("invalid timeout '{seconds}'")))?,
+        ),
+        None => None,
+    };
+
     // For empty result
     if proc_infos.is_empty() {
-        uucore::error::set_exit_code(1);
+        set_exit_code(1);
     }
 
     // Process outputs
@@ -42,7 +60,23 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         }
     }
 
-    wait(&proc_infos);
+    let names: Vec<_> = proc_infos
+        .iter_mut()
+        .map(|it| it.name().unwrap_or_default())
+        .collect();
+    let outcomes = wait(&proc_infos, timeout);
+
+    if matches.get_flag("verbose") {
+        for ((proc, name), outcome) in proc_infos.iter().zip(&names).zip(&outcomes) {
+            println!("{name} (pid {}) {}", proc.pid, outcome.describe());
+        }
+    }
+
+    if outcomes.contains(&Outcome::Running) {
+        set_exit_code(EXIT_TIMEOUT);
+    } else if outcomes.iter().any(|it| matches!(it, Outcome::Killed(_))) {
+        set_exit_code(EXIT_KILLED);
+    }
 
     Ok(())
 }
@@ -52,8 +86,17 @@ pub fn uu_app() -> Command {
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
+        .after_help(AFTER_HELP)
         .infer_long_args(true)
-        .args([arg!(-e --echo                      "display PIDs before waiting")])
+        .args([
+            arg!(-e --echo                      "display PIDs before waiting"),
+            arg!(--verbose                      "report how each process ended"),
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .value_parser(value_parser!(f64))
+                .help("stop waiting after SECONDS"),
+        ])
         .args(process_matcher::clap_args(
             "Name of the program to wait for",
             true,
@@ -3,51 +3,129 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+use std::time::Duration;
 use uu_pgrep::process::ProcessInformation;
 
-// Dirty, but it works.
-// TODO: Use better implementation instead
+/// How a waited-on process ended, as sampled from its last state in /proc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// The process exited, with its status when it could be read before the process was reaped.
+    Exited(Option<i32>),
+    /// The process was terminated by the given signal.
+    Killed(i32),
+    /// The process was stopped.
+    Stopped,
+    /// The timeout expired before the process ended.
+    Running,
+}
+
+impl Outcome {
+    /// Decodes an exit code in the form reported by waitpid(2).
+    fn from_wait_status(status: i32) -> Self {
+        match status & 0x7f {
+            0 => Self::Exited(Some((status >> 8) & 0xff)),
+            signal => Self::Killed(signal),
+        }
+    }
+
+    pub(crate) fn describe(self) -> String {
+        match self {
+            Self::Exited(Some(status)) => format!("exited with status {status}"),
+            Self::Exited(None) => "exited".into(),
+            Self::Killed(signal) => match uucore::signals::signal_name_by_value(signal as usize) {
+                Some(name) => format!("was killed by signal {signal} (SIG{name})"),
+                None => format!("was killed by signal {signal}"),
+            },
+            Self::Stopped => "was stopped".into(),
+            Self::Running => "is still running".into(),
+        }
+    }
+}
+
+/// Waits for `procs` to end or for `timeout` to expire, returning the outcome of each process in
+/// the same order.
 #[cfg(target_os = "linux")]
-pub(crate) fn wait(procs: &[ProcessInformation]) {
-    use std::{thread::sleep, time::Duration};
+pub(crate) fn wait(procs: &[ProcessInformation], timeout: Option<Duration>) -> Vec<Outcome> {
+    use std::{thread::sleep, time::Instant};
 
-    let mut list = procs.to_vec();
+    let start = Instant::now();
+    let mut outcomes = vec![Outcome::Running; procs.len()];
 
+    // Dirty, but it works.
+    // TODO: Use better implementation instead of polling
     loop {
-        for proc in &list.clone() {
-            // Check is running
-            if !is_running(proc.pid) {
-                list.retain(|it| it.pid != proc.pid);
+        for (proc, outcome) in procs.iter().zip(outcomes.iter_mut()) {
+            if *outcome == Outcome::Running {
+                *outcome = sample(proc.pid);
             }
         }
 
-        if list.is_empty() {
-            return;
+        if !outcomes.contains(&Outcome::Running)
+            || timeout.is_some_and(|timeout| start.elapsed() >= timeout)
+        {
+            return outcomes;
         }
 
         sleep(Duration::from_millis(50));
     }
 }
+
+/// The current outcome of `pid`, [`Outcome::Running`] while it is running.
 #[cfg(target_os = "linux")]
-fn is_running(pid: usize) -> bool {
+fn sample(pid: usize) -> Outcome {
     use std::{path::PathBuf, str::FromStr};
     use uu_pgrep::process::RunState;
 
     let proc = PathBuf::from_str(&format!("/proc/{pid}")).unwrap();
 
     if !proc.exists() {
-        return false;
+        return Outcome::Exited(None);
     }
 
-    match ProcessInformation::try_new(proc) {
-        Ok(mut proc) => proc
-            .run_state()
-            .map(|it| it != RunState::Stopped)
-            .unwrap_or(false),
-        Err(_) => false,
+    let Ok(mut proc) = ProcessInformation::try_new(proc) else {
+        return Outcome::Exited(None);
+    };
+
+    match proc.run_state() {
+        Ok(RunState::Stopped) => Outcome::Stopped,
+        // The exit code stays available until the parent of the process reaps it.
+        Ok(RunState::Zombie) => proc
+            .stat()
+            .get(51)
+            .and_then(|it| it.parse().ok())
+            .map_or(Outcome::Exited(None), Outcome::from_wait_status),
+        Ok(_) => Outcome::Running,
+        Err(_) => Outcome::Exited(None),
     }
 }
 
 // Just for passing compile on other system.
 #[cfg(not(target_os = "linux"))]
-pub(crate) fn wait(_procs: &[ProcessInformation]) {}
+pub(crate) fn wait(procs: &[ProcessInformation], _timeout: Option<Duration>) -> Vec<Outcome> {
+    vec![Outcome::Exited(None); procs.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_wait_status() {
+        assert_eq!(Outcome::from_wait_status(0), Outcome::Exited(Some(0)));
+        assert_eq!(Outcome::from_wait_status(3 << 8), Outcome::Exited(Some(3)));
+        assert_eq!(Outcome::from_wait_status(9), Outcome::Killed(9));
+        // Killed with a core dump
+        assert_eq!(Outcome::from_wait_status(0x80 | 11), Outcome::Killed(11));
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(Outcome::Exited(Some(3)).describe(), "exited with status 3");
+        assert_eq!(Outcome::Exited(None).describe(), "exited");
+        assert_eq!(
+            Outcome::Killed(9).describe(),
+            "was killed by signal 9 (SIGKILL)"
+        );
+        assert_eq!(Outcome::Running.describe(), "is still running");
+    }
+}
@@ -12,7 +12,6 @@ use nix::{
 };
 #[cfg(unix)]
 use std::io::Error;
-#[cfg(unix)]
 use uu_pgrep::process::ProcessInformation;
 use uu_pgrep::process_matcher;
 #[cfg(unix)]
@@ -36,7 +35,15 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     handle_obsolete(&mut args);
 
     let matches = uu_app().try_get_matches_from(&args)?;
-    let settings = process_matcher::get_match_settings(&matches)?;
+    let mut settings = process_matcher::get_match_settings(&matches)?;
+
+    // With a limit, -o and -n only tell which processes go first
+    let max_count = matches.get_one::<u64>("max-count").map(|it| *it as usize);
+    let (oldest, newest) = (settings.oldest, settings.newest);
+    if max_count.is_some() {
+        settings.oldest = false;
+        settings.newest = false;
+    }
 
     #[cfg(unix)]
     let sig_name = signal_name_by_value(settings.signal);
@@ -53,14 +60,18 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     };
 
     // Collect pids
-    let pids = process_matcher::find_matching_pids(&settings)?;
+    let mut pids = process_matcher::find_matching_pids(&settings)?;
+    if let Some(max_count) = max_count {
+        limit_matches(&mut pids, max_count, oldest, newest);
+    }
 
     // Send signal
-    // TODO: Implement -q
     #[cfg(unix)]
     let echo = matches.get_flag("echo");
     #[cfg(unix)]
-    kill(&pids, sig, echo);
+    let queue = matches.get_one::<i32>("queue").copied();
+    #[cfg(unix)]
+    kill(&pids, sig, queue, echo);
 
     if matches.get_flag("count") {
         println!("{}", pids.len());
@@ -69,6 +80,19 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     Ok(())
 }
 
+/// Keeps the first `max_count` processes, by start time for `-o`/`-n` and by PID otherwise.
+fn limit_matches(pids: &mut Vec<ProcessInformation>, max_count: usize, oldest: bool, newest: bool) {
+    if oldest || newest {
+        pids.sort_by_cached_key(|it| (it.clone().start_time().unwrap_or_default(), it.pid));
+        if newest {
+            pids.reverse();
+        }
+    } else {
+        pids.sort_by_key(|it| it.pid);
+    }
+    pids.truncate(max_count);
+}
+
 #[cfg(unix)]
 fn handle_obsolete(args: &mut [String]) {
     // Sanity check
@@ -86,10 +110,40 @@ fn handle_obsolete(args: &mut [String]) {
     }
 }
 
+/// Sends the signal to `pid`, along with `value` through sigqueue(3) when given.
+#[cfg(unix)]
+fn send(pid: i32, sig: Option<Signal>, value: Option<i32>) -> nix::Result<()> {
+    match value {
+        #[cfg(target_os = "linux")]
+        Some(value) => {
+            use nix::libc::{c_int, pid_t, sigval};
+
+            // Not bound by the libc crate, but both glibc and musl have it
+            extern "C" {
+                fn sigqueue(pid: pid_t, sig: c_int, value: sigval) -> c_int;
+            }
+
+            let mut sigval = sigval {
+                sival_ptr: std::ptr::null_mut(),
+            };
+            // The integer member of the union is at its start, whatever the endianness
+            // SAFETY: sigval is at least as large as an int
+            unsafe { *(&mut sigval as *mut sigval).cast::<c_int>() = value };
+            let sig = sig.map_or(0, |sig| sig as c_int);
+            // SAFETY: sigqueue has no preconditions
+            let result = unsafe { sigqueue(pid, sig, sigval) };
+            nix::errno::Errno::result(result).map(drop)
+        }
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => Err(nix::errno::Errno::ENOSYS),
+        None => signal::kill(Pid::from_raw(pid), sig),
+    }
+}
+
 #[cfg(unix)]
-fn kill(pids: &Vec<ProcessInformation>, sig: Option<Signal>, echo: bool) {
+fn kill(pids: &Vec<ProcessInformation>, sig: Option<Signal>, queue: Option<i32>, echo: bool) {
     for pid in pids {
-        if let Err(e) = signal::kill(Pid::from_raw(pid.pid as i32), sig) {
+        if let Err(e) = send(pid.pid as i32, sig, queue) {
             show!(Error::from_raw_os_error(e as i32)
                 .map_err_context(|| format!("killing pid {} failed", pid.pid)));
         } else if echo {
@@ -111,11 +165,54 @@ pub fn uu_app() -> Command {
         .args_override_self(true)
         .args([
             // arg!(-<sig>                    "signal to send (either number or name)"),
-            // arg!(-q --queue <value>        "integer value to be sent with the signal"),
+            arg!(-q --queue <value>        "integer value to be sent with the signal")
+                .value_parser(clap::value_parser!(i32))
+                .allow_negative_numbers(true),
             arg!(-e --echo                 "display what is killed"),
+            arg!(--"max-count" <N>         "signal at most N processes, see -o and -n")
+                .value_parser(clap::value_parser!(u64).range(1..)),
         ])
         .args(process_matcher::clap_args(
             "Name of the process to kill",
             false,
         ))
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use nix::libc::{c_int, c_void, siginfo_t, sigval};
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet};
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+    extern "C" fn record_value(_: c_int, info: *mut siginfo_t, _: *mut c_void) {
+        // SAFETY: the kernel passes the information of the signal being handled.
+        let value = unsafe { (*info).si_value() };
+        // SAFETY: the value was sent as an int, at the start of the union.
+        let value = unsafe { *(&value as *const sigval).cast::<c_int>() };
+        RECEIVED.store(value, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_send_queue() {
+        let action = SigAction::new(
+            SigHandler::SigAction(record_value),
+            SaFlags::SA_SIGINFO,
+            SigSet::empty(),
+        );
+        // SAFETY: the handler only stores to an atomic.
+        unsafe { sigaction(Signal::SIGUSR2, &action) }.unwrap();
+
+        send(std::process::id() as i32, Some(Signal::SIGUSR2), Some(1234)).unwrap();
+        // Any thread of the process may handle the signal
+        for _ in 0..100 {
+            if RECEIVED.load(Ordering::Relaxed) != 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(RECEIVED.load(Ordering::Relaxed), 1234);
+    }
+}
@@ -0,0 +1,112 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use nix::errno::Errno;
+use nix::sys::ptrace::{self, Event, Options};
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use std::collections::HashSet;
+use std::io::Error;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by SIGUSR1 to report the mappings of the processes which are still running.
+static REPORT_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_report(_: nix::libc::c_int) {
+    REPORT_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Runs `command` through the shell, tracing it along with the processes it forks, and calls
+/// `report` with the pid of each of them right before it exits, while its mappings still exist.
+/// `report` is also called with every running process on SIGUSR1.
+///
+/// Returns the exit status of the command, 128 plus the signal number when it was killed.
+pub(crate) fn trace(command: &str, mut report: impl FnMut(&str)) -> Result<i32, Error> {
+    // Without SA_RESTART the signal interrupts waitpid, so that it is handled right away.
+    let action = SigAction::new(
+        SigHandler::Handler(request_report),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    // SAFETY: the handler only stores to an atomic.
+    unsafe { sigaction(Signal::SIGUSR1, &action) }?;
+
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    // SAFETY: ptrace(PTRACE_TRACEME) is async-signal-safe.
+    unsafe {
+        shell.pre_exec(|| ptrace::traceme().map_err(Error::from));
+    }
+    let child = Pid::from_raw(shell.spawn()?.id() as i32);
+
+    // The child stops with SIGTRAP once it has executed the shell, the later executions are
+    // reported as events instead because of PTRACE_O_TRACEEXEC.
+    waitpid(child, None)?;
+    ptrace::setoptions(
+        child,
+        Options::PTRACE_O_TRACEFORK
+            | Options::PTRACE_O_TRACEVFORK
+            | Options::PTRACE_O_TRACEEXEC
+            | Options::PTRACE_O_TRACEEXIT
+            | Options::PTRACE_O_EXITKILL,
+    )?;
+    ptrace::cont(child, None)?;
+
+    let mut traced = HashSet::from([child]);
+    let mut status = 0;
+    while !traced.is_empty() {
+        let wait_status = match waitpid(None, Some(WaitPidFlag::__WALL)) {
+            Ok(wait_status) => wait_status,
+            Err(Errno::EINTR) => {
+                if REPORT_REQUESTED.swap(false, Ordering::Relaxed) {
+                    let mut pids: Vec<_> = traced.iter().copied().collect();
+                    pids.sort();
+                    for pid in pids {
+                        report(&pid.to_string());
+                    }
+                }
+                continue;
+            }
+            Err(Errno::ECHILD) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        match wait_status {
+            WaitStatus::PtraceEvent(pid, _, event) => {
+                if event == Event::PTRACE_EVENT_EXIT as i32 {
+                    report(&pid.to_string());
+                }
+                // The processes are gone when they are resumed after exiting.
+                let _ = ptrace::cont(pid, None);
+            }
+            // The forked processes start traced, stopped by a SIGSTOP which isn't theirs to keep.
+            WaitStatus::Stopped(pid, Signal::SIGSTOP) if traced.insert(pid) => {
+                let _ = ptrace::cont(pid, None);
+            }
+            WaitStatus::Stopped(pid, signal) => {
+                traced.insert(pid);
+                let _ = ptrace::cont(pid, signal);
+            }
+            WaitStatus::Exited(pid, code) => {
+                traced.remove(&pid);
+                if pid == child {
+                    status = code;
+                }
+            }
+            WaitStatus::Signaled(pid, signal, _) => {
+                traced.remove(&pid);
+                if pid == child {
+                    status = 128 + signal as i32;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(status)
+}
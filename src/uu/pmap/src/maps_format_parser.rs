@@ -138,7 +138,65 @@ fn parse_device(device: &str) -> Result<String, Error> {
     Ok(format!("{major:0>3}:{minor:0>5}"))
 }
 
+// Merges the runs of adjacent anonymous mappings with the same permissions and name. Returns each
+// resulting mapping along with the number of mappings it stands for.
+pub fn collapse_anon(map_lines: Vec<MapLine>) -> Vec<(MapLine, usize)> {
+    collapse_anon_by(
+        map_lines,
+        |map_line| map_line,
+        |last, map_line| last.size_in_kb += map_line.size_in_kb,
+    )
+}
+
+// Same as `collapse_anon` for any record describing a mapping, `merge` adds a record to the
+// previous one of the run.
+pub fn collapse_anon_by<T>(
+    items: Vec<T>,
+    map_line: impl Fn(&T) -> &MapLine,
+    merge: impl Fn(&mut T, &T),
+) -> Vec<(T, usize)> {
+    let mut collapsed: Vec<(T, usize)> = Vec::new();
+
+    for item in items {
+        if let Some((last, count)) = collapsed.last_mut() {
+            let (previous, current) = (map_line(last), map_line(&item));
+            if current.is_anonymous()
+                && previous.is_anonymous()
+                && previous.perms == current.perms
+                && previous.mapping == current.mapping
+            {
+                merge(last, &item);
+                *count += 1;
+                continue;
+            }
+        }
+        collapsed.push((item, 1));
+    }
+
+    collapsed
+}
+
 impl MapLine {
+    // Whether the mapping shares at least one address with the inclusive range `low..=high`.
+    pub fn overlaps(&self, low: u64, high: u64) -> bool {
+        let start = u64::from_str_radix(&self.address, 16).unwrap_or_default();
+        let end = start + self.size_in_kb * 1024;
+        start <= high && end > low
+    }
+
+    // Whether the mapping isn't backed by a file, named anonymous mappings included.
+    pub fn is_anonymous(&self) -> bool {
+        self.mapping.is_empty() || self.mapping.starts_with("[anon:")
+    }
+
+    // Whether the mapping is anonymous but was given a name, either by the process with
+    // `PR_SET_VMA_ANON_NAME` or by the kernel for anonymous inodes.
+    fn is_named_anonymous(&self) -> bool {
+        ["[anon:", "[anon_shmem:", "anon_inode:"]
+            .iter()
+            .any(|prefix| self.mapping.starts_with(prefix))
+    }
+
     pub fn parse_mapping(&self, pmap_config: &PmapConfig) -> String {
         if pmap_config.custom_format_enabled {
             if self.mapping.starts_with('[') {
@@ -153,6 +211,10 @@ impl MapLine {
                 || self.mapping.starts_with('[')
                 || self.mapping.starts_with("anon")
             {
+                // The name given to an anonymous mapping is part of its full path
+                if pmap_config.show_path && self.is_named_anonymous() {
+                    return self.mapping.clone();
+                }
                 return "  [ anon ]".into();
             }
         }
@@ -282,6 +344,68 @@ mod test {
         assert!(parse_device("1234").is_err());
     }
 
+    #[test]
+    fn test_collapse_anon() {
+        let rw = Perms::from("rw-p");
+        let r = Perms::from("r--p");
+        let map_lines = vec![
+            create_map_line("0000000000001000", 4, rw, "0", "000:00000", 0, ""),
+            create_map_line("0000000000002000", 8, rw, "0", "000:00000", 0, ""),
+            create_map_line("0000000000004000", 4, r, "0", "000:00000", 0, ""),
+            create_map_line("0000000000005000", 4, r, "0", "000:00000", 0, ""),
+            create_map_line("0000000000006000", 4, r, "0", "000:00000", 0, "[anon:jit]"),
+            create_map_line("0000000000007000", 4, r, "0", "000:00000", 0, "[anon:jit]"),
+            create_map_line(
+                "0000000000008000",
+                4,
+                r,
+                "0",
+                "103:00002",
+                42,
+                "/usr/bin/cat",
+            ),
+            create_map_line(
+                "0000000000009000",
+                4,
+                r,
+                "0",
+                "103:00002",
+                42,
+                "/usr/bin/cat",
+            ),
+            create_map_line("000000000000a000", 4, r, "0", "000:00000", 0, ""),
+        ];
+
+        let collapsed = collapse_anon(map_lines);
+        let summary = collapsed
+            .iter()
+            .map(|(map_line, count)| (map_line.address.as_str(), map_line.size_in_kb, *count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("0000000000001000", 12, 2),
+                ("0000000000004000", 8, 2),
+                ("0000000000006000", 8, 2),
+                ("0000000000008000", 4, 1),
+                ("0000000000009000", 4, 1),
+                ("000000000000a000", 4, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let map_line =
+            parse_map_line("7ffc3f8df000-7ffc3f900000 rw-p 00000000 00:00 0  [stack]").unwrap();
+        assert!(map_line.overlaps(0, u64::MAX));
+        assert!(map_line.overlaps(0x7ffc3f8df000, 0x7ffc3f8df000));
+        assert!(map_line.overlaps(0x7ffc3f8fffff, 0x7ffc3f8fffff));
+        assert!(map_line.overlaps(0x1000, 0x7ffc3f8df000));
+        assert!(!map_line.overlaps(0x7ffc3f900000, u64::MAX));
+        assert!(!map_line.overlaps(0, 0x7ffc3f8defff));
+    }
+
     #[test]
     fn test_parse_mapping() {
         let mut mapline = MapLine::default();
@@ -316,13 +440,23 @@ mod test {
         pmap_config.show_path = false;
         assert_eq!("  [ anon ]", mapline.parse_mapping(&pmap_config));
         pmap_config.show_path = true;
-        assert_eq!("  [ anon ]", mapline.parse_mapping(&pmap_config));
+        assert_eq!("anon_inode:i915.gem", mapline.parse_mapping(&pmap_config));
         pmap_config.custom_format_enabled = true;
         pmap_config.show_path = false;
         assert_eq!("anon_inode:i915.gem", mapline.parse_mapping(&pmap_config));
         pmap_config.show_path = true;
         assert_eq!("anon_inode:i915.gem", mapline.parse_mapping(&pmap_config));
 
+        mapline.mapping = "[anon:glibc malloc arena]".to_string();
+        pmap_config.custom_format_enabled = false;
+        pmap_config.show_path = false;
+        assert_eq!("  [ anon ]", mapline.parse_mapping(&pmap_config));
+        pmap_config.show_path = true;
+        assert_eq!(
+            "[anon:glibc malloc arena]",
+            mapline.parse_mapping(&pmap_config)
+        );
+
         mapline.mapping = "[stack]".to_string();
         pmap_config.custom_format_enabled = false;
         pmap_config.show_path = false;
@@ -352,5 +486,15 @@ mod test {
             "/usr/lib/ld-linux-x86-64.so.2",
             mapline.parse_mapping(&pmap_config)
         );
+
+        mapline.mapping = "/tmp/some dir/data.bin (deleted)".to_string();
+        pmap_config.custom_format_enabled = false;
+        pmap_config.show_path = false;
+        assert_eq!("data.bin (deleted)", mapline.parse_mapping(&pmap_config));
+        pmap_config.show_path = true;
+        assert_eq!(
+            "/tmp/some dir/data.bin (deleted)",
+            mapline.parse_mapping(&pmap_config)
+        );
     }
 }
@@ -3,17 +3,20 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use clap::{crate_version, Arg, ArgAction, Command};
-use maps_format_parser::{parse_map_line, MapLine};
-use pmap_config::{create_rc, pmap_field_name, PmapConfig};
-use smaps_format_parser::{parse_smaps, SmapTable};
+use clap::{crate_version, Arg, ArgAction, ArgMatches, Command};
+use maps_format_parser::{collapse_anon, collapse_anon_by, parse_map_line, MapLine};
+use pmap_config::{create_rc, pmap_field_name, PmapConfig, FIELD_LIST};
+use smaps_format_parser::{parse_smaps, SmapEntry, SmapTable, SmapTableInfo};
 use std::env;
 use std::fs;
 use std::io::Error;
-use uucore::error::{set_exit_code, UResult};
+use uucore::error::{set_exit_code, UResult, USimpleError};
 use uucore::{format_usage, help_about, help_usage};
 
+#[cfg(target_os = "linux")]
+mod command;
 mod maps_format_parser;
+mod pmap_config;
 pub mod smaps_format_parser;
 
 const ABOUT: &str = help_about!("pmap.md");
@@ -32,6 +35,10 @@ mod options {
     pub const QUIET: &str = "quiet";
     pub const SHOW_PATH: &str = "show-path";
     pub const RANGE: &str = "range";
+    pub const COLLAPSE_ANON: &str = "collapse-anon";
+    pub const JSON: &str = "json";
+    pub const SEPARATOR: &str = "separator";
+    pub const COMMAND: &str = "command";
 }
 
 #[uucore::main]
@@ -86,7 +93,7 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         }
         pmap_config.read_rc(&path)?;
     } else if let Some(path) = matches.get_one::<String>(options::READ_RC_FROM) {
-        let path = std::fs::canonicalize(path)?;
+        let path = std::path::PathBuf::from(path);
         if !std::fs::exists(&path)? {
             eprintln!("pmap: couldn't read the rc file");
             set_exit_code(1);
@@ -100,42 +107,106 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     if matches.get_flag(options::SHOW_PATH) {
         pmap_config.show_path = true;
     }
+    pmap_config.collapse_anon = matches.get_flag(options::COLLAPSE_ANON);
+    if let Some(range) = matches.get_one::<String>(options::RANGE) {
+        pmap_config.range = Some(parse_range(range)?);
+    }
+
+    let separator = matches.get_one::<char>(options::SEPARATOR).copied();
+
+    if let (Some(separator), false) = (separator, pmap_config.quiet) {
+        println!(
+            "{}",
+            ["PID", "Address", "Kbytes", "RSS", "PSS", "Mode", "Mapping"]
+                .join(&separator.to_string())
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(command) = matches.get_one::<String>(options::COMMAND) {
+        let status = command::trace(command, |pid| {
+            output_process(pid, &matches, separator, &mut pmap_config);
+        })
+        .map_err(|e| USimpleError::new(1, format!("cannot trace the command: {e}")))?;
+        set_exit_code(status);
+        return Ok(());
+    }
 
     let pids = matches
         .get_many::<String>(options::PID)
         .expect("PID required");
 
     for pid in pids {
-        match parse_cmdline(pid) {
-            Ok(cmdline) => {
-                println!("{pid}:   {cmdline}");
-            }
-            Err(_) => {
-                set_exit_code(42);
-                continue;
-            }
-        }
+        output_process(pid, &matches, separator, &mut pmap_config);
+    }
+
+    Ok(())
+}
+
+fn output_process(
+    pid: &str,
+    matches: &ArgMatches,
+    separator: Option<char>,
+    pmap_config: &mut PmapConfig,
+) {
+    let Ok(cmdline) = parse_cmdline(pid) else {
+        set_exit_code(42);
+        return;
+    };
+
+    let result = if matches.get_flag(options::JSON) {
+        output_json_format(pid, &cmdline, pmap_config)
+    } else if let Some(separator) = separator {
+        output_separated_format(pid, separator, pmap_config)
+    } else {
+        println!("{pid}:   {cmdline}");
 
         if matches.get_flag(options::EXTENDED) {
-            output_extended_format(pid, &pmap_config)
-                .map_err(|_| set_exit_code(1))
-                .ok();
+            output_extended_format(pid, pmap_config)
         } else if matches.get_flag(options::DEVICE) {
-            output_device_format(pid, &pmap_config)
-                .map_err(|_| set_exit_code(1))
-                .ok();
+            output_device_format(pid, pmap_config)
         } else if pmap_config.custom_format_enabled {
-            output_custom_format(pid, &mut pmap_config)
-                .map_err(|_| set_exit_code(1))
-                .ok();
+            output_custom_format(pid, pmap_config)
         } else {
-            output_default_format(pid, &pmap_config)
-                .map_err(|_| set_exit_code(1))
-                .ok();
+            output_default_format(pid, pmap_config)
         }
+    };
+    result.map_err(|_| set_exit_code(1)).ok();
+}
+
+// Parses the hexadecimal bounds of -A given as `low,high`. A missing low bound starts at 0 and a
+// missing high bound extends to the end of the address space, while a single address without a
+// comma selects the mapping containing it.
+fn parse_range(range: &str) -> UResult<(u64, u64)> {
+    let (low, high) = range.split_once(',').unwrap_or((range, range));
+
+    let parse = |bound: &str, default: u64| {
+        let bound = bound.trim();
+        if bound.is_empty() {
+            return Ok(default);
+        }
+        let digits = bound
+            .strip_prefix("0x")
+            .or_else(|| bound.strip_prefix("0X"))
+            .unwrap_or(bound);
+        u64::from_str_radix(digits, 16)
+            .map_err(|_| USimpleError::new(1, format!("invalid address '{bound}'")))
+    };
+    let (low, high) = (parse(low, 0)?, parse(high, u64::MAX)?);
+    if low > high {
+        return Err(USimpleError::new(
+            1,
+            "the low address of the range is greater than the high one",
+        ));
     }
 
-    Ok(())
+    Ok((low, high))
+}
+
+fn in_range(map_line: &MapLine, pmap_config: &PmapConfig) -> bool {
+    pmap_config
+        .range
+        .is_none_or(|(low, high)| map_line.overlaps(low, high))
 }
 
 fn parse_cmdline(pid: &str) -> Result<String, Error> {
@@ -152,41 +223,87 @@ fn parse_cmdline(pid: &str) -> Result<String, Error> {
     Ok(cmdline.into())
 }
 
-fn process_maps<F>(pid: &str, header: Option<&str>, mut process_line: F) -> Result<(), Error>
+// Calls `process_line` with each mapping and the number of mappings it stands for, which is
+// only greater than one for the runs of anonymous mappings merged by `--collapse-anon`.
+fn process_maps<F>(
+    pid: &str,
+    header: Option<&str>,
+    pmap_config: &PmapConfig,
+    mut process_line: F,
+) -> Result<(), Error>
 where
-    F: FnMut(&MapLine),
+    F: FnMut(&MapLine, usize),
 {
     let path = format!("/proc/{pid}/maps");
     let contents = fs::read_to_string(path)?;
+    let map_lines = contents
+        .lines()
+        .map(parse_map_line)
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|map_line| in_range(map_line, pmap_config))
+        .collect::<Vec<_>>();
+    let map_lines = if pmap_config.collapse_anon {
+        collapse_anon(map_lines)
+    } else {
+        map_lines
+            .into_iter()
+            .map(|map_line| (map_line, 1))
+            .collect()
+    };
 
     if let Some(header) = header {
         println!("{header}");
     }
 
-    for line in contents.lines() {
-        let map_line = parse_map_line(line)?;
-        process_line(&map_line);
+    for (map_line, count) in &map_lines {
+        process_line(map_line, *count);
     }
 
     Ok(())
 }
 
-fn get_smap_table(pid: &str) -> Result<SmapTable, Error> {
+fn format_mapping(map_line: &MapLine, count: usize, pmap_config: &PmapConfig) -> String {
+    let mapping = map_line.parse_mapping(pmap_config);
+    if count > 1 {
+        format!("{mapping} ({count} mappings)")
+    } else {
+        mapping
+    }
+}
+
+fn get_smap_table(pid: &str, pmap_config: &PmapConfig) -> Result<SmapTable, Error> {
     let path = format!("/proc/{pid}/smaps");
     let contents = fs::read_to_string(path)?;
-    parse_smaps(&contents)
+    if pmap_config.range.is_none() {
+        return parse_smaps(&contents);
+    }
+
+    // Drop the records out of range before parsing, so that the totals only cover the others
+    let mut keep = false;
+    let mut filtered = String::new();
+    for line in contents.lines() {
+        if let Ok(map_line) = parse_map_line(line) {
+            keep = in_range(&map_line, pmap_config);
+        }
+        if keep {
+            filtered.push_str(line);
+            filtered.push('\n');
+        }
+    }
+    parse_smaps(&filtered)
 }
 
 fn output_default_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Error> {
     let mut total = 0;
 
-    process_maps(pid, None, |map_line| {
+    process_maps(pid, None, pmap_config, |map_line, count| {
         println!(
             "{} {:>6}K {} {}",
             map_line.address,
             map_line.size_in_kb,
             map_line.perms.mode(),
-            map_line.parse_mapping(pmap_config)
+            format_mapping(map_line, count, pmap_config)
         );
         total += map_line.size_in_kb;
     })?;
@@ -199,13 +316,27 @@ fn output_default_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Erro
 }
 
 fn output_extended_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Error> {
-    let smap_table = get_smap_table(pid)?;
+    let smap_table = get_smap_table(pid, pmap_config)?;
 
     if !pmap_config.quiet {
         println!("Address           Kbytes     RSS   Dirty Mode  Mapping");
     }
 
-    for smap_entry in smap_table.entries {
+    let entries = if pmap_config.collapse_anon {
+        collapse_anon_by(
+            smap_table.entries,
+            |smap_entry| &smap_entry.map_line,
+            SmapEntry::absorb,
+        )
+    } else {
+        smap_table
+            .entries
+            .into_iter()
+            .map(|smap_entry| (smap_entry, 1))
+            .collect()
+    };
+
+    for (smap_entry, count) in entries {
         println!(
             "{} {:>7} {:>7} {:>7} {} {}",
             smap_entry.map_line.address,
@@ -213,7 +344,7 @@ fn output_extended_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Err
             smap_entry.rss_in_kb,
             smap_entry.shared_dirty_in_kb + smap_entry.private_dirty_in_kb,
             smap_entry.map_line.perms.mode(),
-            smap_entry.map_line.parse_mapping(pmap_config)
+            format_mapping(&smap_entry.map_line, count, pmap_config)
         );
     }
 
@@ -230,8 +361,32 @@ fn output_extended_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Err
     Ok(())
 }
 
+// Lists the enabled fields of the custom format. The -X and --XX formats follow the keys found
+// in smaps instead of a fixed list, so that the fields added by newer kernels show up as well.
+fn get_field_list<'a>(pmap_config: &PmapConfig, info: &'a SmapTableInfo) -> Vec<&'a str> {
+    if !pmap_config.smaps_fields {
+        return FIELD_LIST
+            .into_iter()
+            .filter(|field_name| pmap_config.is_enabled(field_name))
+            .collect();
+    }
+
+    // These come from the header line of each mapping rather than from its keys
+    let map_line_fields = [
+        pmap_field_name::PERM,
+        pmap_field_name::OFFSET,
+        pmap_field_name::DEVICE,
+        pmap_field_name::INODE,
+    ];
+    map_line_fields
+        .into_iter()
+        .chain(info.field_names.iter().map(String::as_str))
+        .filter(|field_name| pmap_config.is_enabled(field_name) || !FIELD_LIST.contains(field_name))
+        .collect()
+}
+
 fn output_custom_format(pid: &str, pmap_config: &mut PmapConfig) -> Result<(), Error> {
-    let smap_table = get_smap_table(pid)?;
+    let smap_table = get_smap_table(pid, pmap_config)?;
 
     if !smap_table.info.has_ksm {
         pmap_config.disable_field(pmap_field_name::KSM);
@@ -240,6 +395,8 @@ fn output_custom_format(pid: &str, pmap_config: &mut PmapConfig) -> Result<(), E
         pmap_config.disable_field(pmap_field_name::PROTECTION_KEY);
     }
 
+    let field_list = get_field_list(pmap_config, &smap_table.info);
+
     // Header
     if !pmap_config.quiet {
         let mut line = format!(
@@ -249,18 +406,16 @@ fn output_custom_format(pid: &str, pmap_config: &mut PmapConfig) -> Result<(), E
         );
 
         pmap_config.quiet = true;
-        for field_name in pmap_config.get_field_list() {
-            if pmap_config.is_enabled(field_name) {
-                // If there is any field that needs footer, we can't suppress the footer
-                if pmap_config.needs_footer(field_name) {
-                    pmap_config.quiet = false;
-                }
-                line += &format!(
-                    "{:>width$} ",
-                    field_name,
-                    width = smap_table.info.get_width(field_name)
-                );
+        for &field_name in &field_list {
+            // If there is any field that needs footer, we can't suppress the footer
+            if pmap_config.needs_footer(field_name) {
+                pmap_config.quiet = false;
             }
+            line += &format!(
+                "{:>width$} ",
+                field_name,
+                width = smap_table.info.get_width(field_name)
+            );
         }
         if pmap_config.is_enabled(pmap_field_name::MAPPING) {
             line += pmap_field_name::MAPPING;
@@ -275,14 +430,12 @@ fn output_custom_format(pid: &str, pmap_config: &mut PmapConfig) -> Result<(), E
             smap_entry.get_field(pmap_field_name::ADDRESS),
             width = smap_table.info.get_width(pmap_field_name::ADDRESS)
         );
-        for field_name in pmap_config.get_field_list() {
-            if pmap_config.is_enabled(field_name) {
-                line += &format!(
-                    "{:>width$} ",
-                    smap_entry.get_field(field_name),
-                    width = smap_table.info.get_width(field_name)
-                );
-            }
+        for &field_name in &field_list {
+            line += &format!(
+                "{:>width$} ",
+                smap_entry.get_field(field_name),
+                width = smap_table.info.get_width(field_name)
+            );
         }
         if pmap_config.is_enabled(pmap_field_name::MAPPING) {
             line += &smap_entry.map_line.parse_mapping(pmap_config);
@@ -298,8 +451,8 @@ fn output_custom_format(pid: &str, pmap_config: &mut PmapConfig) -> Result<(), E
             "",
             width = smap_table.info.get_width(pmap_field_name::ADDRESS)
         );
-        for field_name in pmap_config.get_field_list() {
-            if pmap_config.is_enabled(field_name) && field_name != pmap_field_name::VMFLAGS {
+        for &field_name in &field_list {
+            if field_name != pmap_field_name::VMFLAGS {
                 if pmap_config.needs_footer(field_name) {
                     line += &format!(
                         "{:=>width$} ",
@@ -323,8 +476,8 @@ fn output_custom_format(pid: &str, pmap_config: &mut PmapConfig) -> Result<(), E
             "",
             width = smap_table.info.get_width(pmap_field_name::ADDRESS)
         );
-        for field_name in pmap_config.get_field_list() {
-            if pmap_config.is_enabled(field_name) && field_name != pmap_field_name::VMFLAGS {
+        for &field_name in &field_list {
+            if field_name != pmap_field_name::VMFLAGS {
                 if pmap_config.needs_footer(field_name) {
                     line += &format!(
                         "{:>width$} ",
@@ -358,7 +511,8 @@ fn output_device_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Error
         } else {
             None
         },
-        |map_line| {
+        pmap_config,
+        |map_line, count| {
             println!(
                 "{} {:>7} {} {} {} {}",
                 map_line.address,
@@ -366,7 +520,7 @@ fn output_device_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Error
                 map_line.perms.mode(),
                 map_line.offset,
                 map_line.device,
-                map_line.parse_mapping(pmap_config)
+                format_mapping(map_line, count, pmap_config)
             );
             total_mapped += map_line.size_in_kb;
 
@@ -389,6 +543,80 @@ fn output_device_format(pid: &str, pmap_config: &PmapConfig) -> Result<(), Error
     Ok(())
 }
 
+// Prints the mappings of the process as a single JSON object on its own line, so that several
+// processes make a stream of JSON lines.
+fn output_json_format(pid: &str, cmdline: &str, pmap_config: &PmapConfig) -> Result<(), Error> {
+    let smap_table = get_smap_table(pid, pmap_config)?;
+
+    let mappings = smap_table
+        .entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "{{\"address\":\"{}\",\"size_kb\":{},\"rss_kb\":{},\"pss_kb\":{},\"flags\":\"{}\",\"path\":{}}}",
+                entry.map_line.address,
+                entry.map_line.size_in_kb,
+                entry.rss_in_kb,
+                entry.pss_in_kb,
+                entry.map_line.perms,
+                json_string(&entry.map_line.mapping)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    println!(
+        "{{\"pid\":{pid},\"cmdline\":{},\"mappings\":[{}]}}",
+        json_string(cmdline),
+        mappings.join(",")
+    );
+
+    Ok(())
+}
+
+// Prints a row per mapping with the fields separated by the given character. The path comes last
+// so that it can be split off even if it contains the separator.
+fn output_separated_format(
+    pid: &str,
+    separator: char,
+    pmap_config: &PmapConfig,
+) -> Result<(), Error> {
+    let smap_table = get_smap_table(pid, pmap_config)?;
+
+    for entry in &smap_table.entries {
+        println!(
+            "{}",
+            [
+                pid.to_string(),
+                entry.map_line.address.clone(),
+                entry.map_line.size_in_kb.to_string(),
+                entry.rss_in_kb.to_string(),
+                entry.pss_in_kb.to_string(),
+                entry.map_line.perms.to_string(),
+                entry.map_line.mapping.clone(),
+            ]
+            .join(&separator.to_string())
+        );
+    }
+
+    Ok(())
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
 pub fn uu_app() -> Command {
     Command::new(env!("CARGO_PKG_NAME"))
         .version(crate_version!())
@@ -398,9 +626,9 @@ pub fn uu_app() -> Command {
         .arg(
             Arg::new(options::PID)
                 .help("Process ID")
-                .required_unless_present_any(["create-rc", "create-rc-to"]) // Adjusted for -n, -N note
+                .required_unless_present_any(["create-rc", "create-rc-to", "command"]) // Adjusted for -n, -N note
                 .action(ArgAction::Append)
-                .conflicts_with_all(["create-rc", "create-rc-to"]),
+                .conflicts_with_all(["create-rc", "create-rc-to", "command"]),
         ) // Ensure pid is not used with -n, -N
         .arg(
             Arg::new(options::EXTENDED)
@@ -542,11 +770,56 @@ pub fn uu_app() -> Command {
                 .help("show path in the mapping")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::COLLAPSE_ANON)
+                .long("collapse-anon")
+                .help(
+                    "merge adjacent anonymous mappings in the default, extended and device formats",
+                )
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::RANGE)
                 .short('A')
                 .long("range")
-                .num_args(1..=2)
-                .help("limit results to the given range"),
+                .num_args(1)
+                .value_name("LOW[,HIGH]")
+                .help("limit results to the given range of hexadecimal addresses"),
+        )
+        .arg(
+            Arg::new(options::JSON)
+                .long("json")
+                .help("print the mappings of each process as a line of JSON")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    "extended",
+                    "more-extended",
+                    "most-extended",
+                    "device",
+                    "read-rc",
+                    "read-rc-from",
+                    "separator",
+                ]),
+        )
+        .arg(
+            Arg::new(options::SEPARATOR)
+                .long("separator")
+                .value_name("CHAR")
+                .value_parser(clap::value_parser!(char))
+                .help("print the mappings as rows of fields separated by CHAR")
+                .conflicts_with_all([
+                    "extended",
+                    "more-extended",
+                    "most-extended",
+                    "device",
+                    "read-rc",
+                    "read-rc-from",
+                ]),
+        )
+        .arg(
+            Arg::new(options::COMMAND)
+                .long("command")
+                .value_name("COMMAND")
+                .help("run COMMAND and show the mappings of its processes as they exit, or on SIGUSR1"),
         )
 }
@@ -41,6 +41,40 @@ pub mod pmap_field_name {
     pub const MAPPING: &str = "Mapping";
 }
 
+// Every field known to the custom formats.
+// Note: Address and Mapping are treated separately from other fields.
+pub const FIELD_LIST: [&str; 29] = [
+    pmap_field_name::PERM,
+    pmap_field_name::OFFSET,
+    pmap_field_name::DEVICE,
+    pmap_field_name::INODE,
+    pmap_field_name::SIZE,
+    pmap_field_name::KERNEL_PAGE_SIZE,
+    pmap_field_name::MMU_PAGE_SIZE,
+    pmap_field_name::RSS,
+    pmap_field_name::PSS,
+    pmap_field_name::PSS_DIRTY,
+    pmap_field_name::SHARED_CLEAN,
+    pmap_field_name::SHARED_DIRTY,
+    pmap_field_name::PRIVATE_CLEAN,
+    pmap_field_name::PRIVATE_DIRTY,
+    pmap_field_name::REFERENCED,
+    pmap_field_name::ANONYMOUS,
+    pmap_field_name::KSM,
+    pmap_field_name::LAZY_FREE,
+    pmap_field_name::ANON_HUGE_PAGES,
+    pmap_field_name::SHMEM_PMD_MAPPED,
+    pmap_field_name::FILE_PMD_MAPPED,
+    pmap_field_name::SHARED_HUGETLB,
+    pmap_field_name::PRIVATE_HUGETLB,
+    pmap_field_name::SWAP,
+    pmap_field_name::SWAP_PSS,
+    pmap_field_name::LOCKED,
+    pmap_field_name::THP_ELIGIBLE,
+    pmap_field_name::PROTECTION_KEY,
+    pmap_field_name::VMFLAGS,
+];
+
 // Represents the configuration for enabling specific fields.
 // Note: Address field is always enabled.
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -80,43 +114,17 @@ pub struct PmapConfig {
     pub show_path: bool,
     // Misc
     pub quiet: bool,
+    pub collapse_anon: bool,
+    // Inclusive address range of -A, only the mappings overlapping it are shown
+    pub range: Option<(u64, u64)>,
     pub custom_format_enabled: bool,
+    // The columns follow the keys found in smaps, used by -X and --XX
+    pub smaps_fields: bool,
 }
 
 impl PmapConfig {
     pub fn get_field_list(&self) -> [&'static str; 29] {
-        // Note: Address and Mapping are treated separately from other fields.
-        [
-            pmap_field_name::PERM,
-            pmap_field_name::OFFSET,
-            pmap_field_name::DEVICE,
-            pmap_field_name::INODE,
-            pmap_field_name::SIZE,
-            pmap_field_name::KERNEL_PAGE_SIZE,
-            pmap_field_name::MMU_PAGE_SIZE,
-            pmap_field_name::RSS,
-            pmap_field_name::PSS,
-            pmap_field_name::PSS_DIRTY,
-            pmap_field_name::SHARED_CLEAN,
-            pmap_field_name::SHARED_DIRTY,
-            pmap_field_name::PRIVATE_CLEAN,
-            pmap_field_name::PRIVATE_DIRTY,
-            pmap_field_name::REFERENCED,
-            pmap_field_name::ANONYMOUS,
-            pmap_field_name::KSM,
-            pmap_field_name::LAZY_FREE,
-            pmap_field_name::ANON_HUGE_PAGES,
-            pmap_field_name::SHMEM_PMD_MAPPED,
-            pmap_field_name::FILE_PMD_MAPPED,
-            pmap_field_name::SHARED_HUGETLB,
-            pmap_field_name::PRIVATE_HUGETLB,
-            pmap_field_name::SWAP,
-            pmap_field_name::SWAP_PSS,
-            pmap_field_name::LOCKED,
-            pmap_field_name::THP_ELIGIBLE,
-            pmap_field_name::PROTECTION_KEY,
-            pmap_field_name::VMFLAGS,
-        ]
+        FIELD_LIST
     }
 
     pub fn needs_footer(&self, field_name: &str) -> bool {
@@ -215,6 +223,7 @@ impl PmapConfig {
     // Preset for more-extended option
     pub fn set_more_extended(&mut self) {
         self.custom_format_enabled = true;
+        self.smaps_fields = true;
         self.perm = true;
         self.offset = true;
         self.device = true;
@@ -4,7 +4,8 @@
 // file that was distributed with this source code.
 
 use crate::maps_format_parser::{parse_map_line, MapLine};
-use crate::pmap_config::pmap_field_name;
+use crate::pmap_config::{pmap_field_name, FIELD_LIST};
+use std::collections::BTreeMap;
 use std::io::{Error, ErrorKind};
 
 // Represents a parsed single entry from /proc/<PID>/smaps for the extended formats.
@@ -35,6 +36,8 @@ pub struct SmapEntry {
     pub thp_eligible: u64,
     pub protection_key: u64,
     pub vmflags: String,
+    // Numeric fields unknown to pmap, such as the ones added by newer kernels
+    pub other_fields: BTreeMap<String, u64>,
 }
 
 impl SmapEntry {
@@ -71,7 +74,38 @@ impl SmapEntry {
             pmap_field_name::PROTECTION_KEY => self.protection_key.to_string(),
             pmap_field_name::VMFLAGS => self.vmflags.clone(),
             pmap_field_name::MAPPING => self.map_line.mapping.clone(),
-            _ => String::new(),
+            _ => self
+                .other_fields
+                .get(field_name)
+                .map(u64::to_string)
+                .unwrap_or_default(),
+        }
+    }
+
+    // Adds the sizes of the mapping following this one, the page sizes and flags are kept.
+    pub fn absorb(&mut self, other: &SmapEntry) {
+        self.map_line.size_in_kb += other.map_line.size_in_kb;
+        self.rss_in_kb += other.rss_in_kb;
+        self.pss_in_kb += other.pss_in_kb;
+        self.pss_dirty_in_kb += other.pss_dirty_in_kb;
+        self.shared_clean_in_kb += other.shared_clean_in_kb;
+        self.shared_dirty_in_kb += other.shared_dirty_in_kb;
+        self.private_clean_in_kb += other.private_clean_in_kb;
+        self.private_dirty_in_kb += other.private_dirty_in_kb;
+        self.referenced_in_kb += other.referenced_in_kb;
+        self.anonymous_in_kb += other.anonymous_in_kb;
+        self.ksm_in_kb += other.ksm_in_kb;
+        self.lazy_free_in_kb += other.lazy_free_in_kb;
+        self.anon_huge_pages_in_kb += other.anon_huge_pages_in_kb;
+        self.shmem_pmd_mapped_in_kb += other.shmem_pmd_mapped_in_kb;
+        self.file_pmd_mapped_in_kb += other.file_pmd_mapped_in_kb;
+        self.shared_hugetlb_in_kb += other.shared_hugetlb_in_kb;
+        self.private_hugetlb_in_kb += other.private_hugetlb_in_kb;
+        self.swap_in_kb += other.swap_in_kb;
+        self.swap_pss_in_kb += other.swap_pss_in_kb;
+        self.locked_in_kb += other.locked_in_kb;
+        for (key, val) in &other.other_fields {
+            *self.other_fields.entry(key.clone()).or_default() += val;
         }
     }
 }
@@ -81,6 +115,8 @@ impl SmapEntry {
 pub struct SmapTableInfo {
     pub has_ksm: bool,
     pub has_protection_key: bool,
+    // Keys of smaps in the order they first appear, without the unknown non-numeric ones
+    pub field_names: Vec<String>,
     // Total value
     pub total_size_in_kb: u64,
     pub total_kernel_page_size_in_kb: u64,
@@ -132,6 +168,9 @@ pub struct SmapTableInfo {
     pub thp_eligible_width: usize,
     pub protection_key_width: usize,
     pub vmflags_width: usize,
+    // Total and width of the fields unknown to pmap
+    pub other_totals: BTreeMap<String, u64>,
+    pub other_widths: BTreeMap<String, usize>,
 }
 
 impl Default for SmapTableInfo {
@@ -139,6 +178,7 @@ impl Default for SmapTableInfo {
         Self {
             has_ksm: false,
             has_protection_key: false,
+            field_names: Vec::new(),
 
             total_size_in_kb: 0,
             total_kernel_page_size_in_kb: 0,
@@ -190,6 +230,9 @@ impl Default for SmapTableInfo {
             thp_eligible_width: pmap_field_name::THP_ELIGIBLE.len(),
             protection_key_width: pmap_field_name::PROTECTION_KEY.len(),
             vmflags_width: pmap_field_name::VMFLAGS.len(),
+
+            other_totals: BTreeMap::new(),
+            other_widths: BTreeMap::new(),
         }
     }
 }
@@ -228,7 +271,7 @@ impl SmapTableInfo {
             pmap_field_name::THP_ELIGIBLE => self.thp_eligible_width,
             pmap_field_name::PROTECTION_KEY => self.protection_key_width,
             pmap_field_name::VMFLAGS => self.vmflags_width,
-            _ => 0,
+            _ => self.other_widths.get(field_name).copied().unwrap_or(0),
         }
     }
 
@@ -258,7 +301,7 @@ impl SmapTableInfo {
             pmap_field_name::LOCKED => self.total_locked_in_kb,
             pmap_field_name::THP_ELIGIBLE => self.total_thp_eligible,
             pmap_field_name::PROTECTION_KEY => self.total_protection_key,
-            _ => 0,
+            _ => self.other_totals.get(field_name).copied().unwrap_or(0),
         }
     }
 }
@@ -296,6 +339,22 @@ pub fn parse_smaps(contents: &str) -> Result<SmapTable, Error> {
                 .ok_or_else(|| Error::from(ErrorKind::InvalidData))?;
             let val = val.trim();
 
+            if !FIELD_LIST.contains(&key) {
+                // Only the numeric fields can be summed up and displayed
+                let Ok(val) = get_smap_item_value(val.strip_suffix(" kB").unwrap_or(val)) else {
+                    continue;
+                };
+                if !smap_table.info.field_names.iter().any(|it| it == key) {
+                    smap_table.info.field_names.push(key.into());
+                }
+                smap_entry.other_fields.insert(key.into(), val);
+                *smap_table.info.other_totals.entry(key.into()).or_default() += val;
+                continue;
+            }
+            if !smap_table.info.field_names.iter().any(|it| it == key) {
+                smap_table.info.field_names.push(key.into());
+            }
+
             if key == pmap_field_name::VMFLAGS {
                 smap_entry.vmflags = val.into();
                 smap_table.info.vmflags_width =
@@ -304,11 +363,10 @@ pub fn parse_smaps(contents: &str) -> Result<SmapTable, Error> {
                 let val = val.strip_suffix(" kB").unwrap_or(val);
                 let val = get_smap_item_value(val)?;
                 match key {
-                    pmap_field_name::SIZE => {
-                        if smap_entry.map_line.size_in_kb != val {
-                            return Err(Error::from(ErrorKind::InvalidData));
-                        }
+                    pmap_field_name::SIZE if smap_entry.map_line.size_in_kb != val => {
+                        return Err(Error::from(ErrorKind::InvalidData));
                     }
+                    pmap_field_name::SIZE => {}
                     pmap_field_name::KERNEL_PAGE_SIZE => {
                         smap_entry.kernel_page_size_in_kb = val;
                         smap_table.info.total_kernel_page_size_in_kb += val;
@@ -527,10 +585,39 @@ pub fn parse_smaps(contents: &str) -> Result<SmapTable, Error> {
         .info
         .protection_key_width
         .max(smap_table.info.total_protection_key.to_string().len());
+    for (key, total) in &smap_table.info.other_totals {
+        smap_table
+            .info
+            .other_widths
+            .insert(key.clone(), key.len().max(total.to_string().len()));
+    }
 
     Ok(smap_table)
 }
 
+// Summary of the mappings of a process, for the tools listing many processes such as ps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MappingSummary {
+    pub count: usize,
+    // Size of the mappings backed by a file, anonymous and special mappings have no inode
+    pub file_backed_in_kb: u64,
+}
+
+// Summarizes the mappings of /proc/<PID>/maps. The contents of /proc/<PID>/smaps are accepted
+// too, the lines other than the headers of the mappings being skipped.
+pub fn summarize_mappings(contents: &str) -> MappingSummary {
+    contents
+        .lines()
+        .filter_map(|line| parse_map_line(line).ok())
+        .fold(MappingSummary::default(), |mut summary, map_line| {
+            summary.count += 1;
+            if map_line.inode != 0 {
+                summary.file_backed_in_kb += map_line.size_in_kb;
+            }
+            summary
+        })
+}
+
 fn get_smap_item_value(val: &str) -> Result<u64, Error> {
     val.parse::<u64>()
         .map_err(|_| Error::from(ErrorKind::InvalidData))
@@ -609,17 +696,52 @@ mod test {
             thp_eligible,
             protection_key,
             vmflags: vmflags.to_string(),
+            other_fields: BTreeMap::new(),
         }
     }
 
+    #[test]
+    fn test_absorb() {
+        let mut smap_entry = SmapEntry {
+            kernel_page_size_in_kb: 4,
+            rss_in_kb: 8,
+            private_dirty_in_kb: 4,
+            vmflags: "rd wr".into(),
+            ..Default::default()
+        };
+        smap_entry.map_line.size_in_kb = 16;
+        let mut other = SmapEntry {
+            kernel_page_size_in_kb: 4,
+            rss_in_kb: 4,
+            private_dirty_in_kb: 4,
+            swap_in_kb: 12,
+            ..Default::default()
+        };
+        other.map_line.size_in_kb = 32;
+
+        smap_entry.absorb(&other);
+        assert_eq!(smap_entry.map_line.size_in_kb, 48);
+        assert_eq!(smap_entry.kernel_page_size_in_kb, 4);
+        assert_eq!(smap_entry.rss_in_kb, 12);
+        assert_eq!(smap_entry.private_dirty_in_kb, 8);
+        assert_eq!(smap_entry.swap_in_kb, 12);
+        assert_eq!(smap_entry.vmflags, "rd wr");
+    }
+
     #[test]
     fn test_parse_smaps() {
+        #[rustfmt::skip]
+        let mut konsole = create_smap_entry(
+            "0000560880413000", Perms::from("r--p"), "0000000000000000", "008:00008", 10813151, "/usr/bin/konsole",
+            180, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+            22, 0, "rd mr mw me dw sd");
+        konsole.other_fields = BTreeMap::from([
+            ("SomeUnknownFieldKB".to_string(), 23),
+            ("SomeUnknownField".to_string(), 24),
+        ]);
         let data = [
             (
-                vec![create_smap_entry(
-                    "0000560880413000", Perms::from("r--p"), "0000000000000000", "008:00008", 10813151, "/usr/bin/konsole",
-                    180, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
-                    22, 0, "rd mr mw me dw sd")],
+                vec![konsole],
                 concat!(
                     "560880413000-560880440000 r--p 00000000 08:08 10813151                   /usr/bin/konsole\n",
                     "Size:                180 kB\n",
@@ -983,4 +1105,61 @@ mod test {
             assert_eq!(expected_smap_entries, parsed.entries);
         }
     }
+
+    #[test]
+    fn test_summarize_mappings() {
+        let maps = concat!(
+            "5a1f2e0c0000-5a1f2e0c2000 r--p 00000000 fd:01 1835015                    /usr/bin/cat\n",
+            "5a1f2e0c2000-5a1f2e0c7000 r-xp 00002000 fd:01 1835015                    /usr/bin/cat\n",
+            "5a1f2f6d4000-5a1f2f6f5000 rw-p 00000000 00:00 0                          [heap]\n",
+            "7f9d6a400000-7f9d6a6eb000 r--p 00000000 fd:01 1837733                    /usr/lib/locale/C.utf8/LC_CTYPE\n",
+            "7ffc3f8df000-7ffc3f900000 rw-p 00000000 00:00 0                          [stack]\n",
+        );
+        let summary = summarize_mappings(maps);
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.file_backed_in_kb, 8 + 20 + 2988);
+
+        // The other lines of smaps are skipped
+        let smaps = concat!(
+            "5a1f2e0c0000-5a1f2e0c2000 r--p 00000000 fd:01 1835015                    /usr/bin/cat\n",
+            "Size:                  8 kB\n",
+            "Rss:                   8 kB\n",
+            "VmFlags: rd mr mw me dw sd \n",
+            "7ffc3f8df000-7ffc3f900000 rw-p 00000000 00:00 0                          [stack]\n",
+            "Size:                132 kB\n",
+        );
+        let summary = summarize_mappings(smaps);
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.file_backed_in_kb, 8);
+
+        assert_eq!(summarize_mappings(""), MappingSummary::default());
+    }
+
+    #[test]
+    fn test_parse_smaps_field_names() {
+        let smap_table = parse_smaps(concat!(
+            "71af50000000-71af50021000 rw-p 00000000 00:00 0 \n",
+            "Size:                132 kB\n",
+            "Rss:                 128 kB\n",
+            "NewKernelField:     1234 kB\n",
+            "NewKernelFlags:   ab cd\n",
+            "VmFlags: rd mr mw me sd \n",
+            "7ffc3f8df000-7ffc3f900000 rw-p 00000000 00:00 0                          [stack]\n",
+            "Size:                132 kB\n",
+            "Rss:                 108 kB\n",
+            "NewKernelField:  9998766 kB\n",
+            "Pss:                 108 kB\n",
+            "VmFlags: rd wr mr mw me gd ac\n",
+        ))
+        .unwrap();
+
+        assert_eq!(
+            smap_table.info.field_names,
+            ["Size", "Rss", "NewKernelField", "VmFlags", "Pss"]
+        );
+        assert_eq!(smap_table.entries[0].get_field("NewKernelField"), "1234");
+        assert_eq!(smap_table.info.get_total("NewKernelField"), 10000000);
+        assert_eq!(smap_table.info.get_width("NewKernelField"), 14);
+        assert_eq!(smap_table.info.get_width("NewKernelFlags"), 0);
+    }
 }
@@ -8,6 +8,42 @@ use clap::ArgMatches;
 use nix::errno::Errno;
 use std::{cell::RefCell, path::PathBuf, rc::Rc, str::FromStr};
 use uu_pgrep::process::{ProcessInformation, Teletype};
+#[cfg(unix)]
+use uucore::entries::{grp2gid, usr2uid};
+use uucore::error::{UResult, USimpleError};
+
+#[cfg(not(unix))]
+fn usr2uid(_name: &str) -> std::io::Result<u32> {
+    Err(std::io::ErrorKind::NotFound.into())
+}
+
+#[cfg(not(unix))]
+fn grp2gid(_name: &str) -> std::io::Result<u32> {
+    Err(std::io::ErrorKind::NotFound.into())
+}
+
+/// Names of the arguments which select processes, without any of them only the processes of the
+/// current terminal are shown.
+const SELECTION_ARGS: [&str; 12] = [
+    "A",
+    "a",
+    "d",
+    "user",
+    "User",
+    "group",
+    "Group",
+    "C",
+    "tty",
+    "ppid",
+    "bsd_all",
+    "bsd_no_tty",
+];
+
+pub(crate) fn has_selection(matches: &ArgMatches) -> bool {
+    SELECTION_ARGS
+        .iter()
+        .any(|id| matches.value_source(id) == Some(clap::parser::ValueSource::CommandLine))
+}
 
 // TODO: Temporary add to this file, this function will add to uucore.
 #[cfg(not(target_os = "redox"))]
@@ -72,6 +108,37 @@ pub(crate) fn process_collector(
     result
 }
 
+/// Filter for the BSD selection options
+///
+/// - `a` Lift the "only yourself" restriction, select the processes with a terminal.
+/// - `x` Lift the "must have a terminal" restriction, select the processes of the current user.
+/// - `ax` Select all processes.
+pub(crate) fn bsd_collector(
+    matches: &ArgMatches,
+    proc_snapshot: &[Rc<RefCell<ProcessInformation>>],
+) -> Vec<Rc<RefCell<ProcessInformation>>> {
+    let all_users = matches.get_flag("bsd_all");
+    let no_tty = matches.get_flag("bsd_no_tty");
+    if !all_users && !no_tty {
+        return Vec::new();
+    }
+
+    #[cfg(unix)]
+    let euid = uucore::process::geteuid();
+    #[cfg(not(unix))]
+    let euid = 0;
+
+    proc_snapshot
+        .iter()
+        .filter(|it| {
+            let mut proc = it.borrow_mut();
+            (all_users || proc.euid().is_ok_and(|id| id == euid))
+                && (no_tty || proc.tty() != Teletype::Unknown)
+        })
+        .cloned()
+        .collect()
+}
+
 /// Filter for session
 ///
 /// - `-d` Select all processes except session leaders.
@@ -107,3 +174,180 @@ pub(crate) fn session_collector(
 
     result
 }
+
+/// Splits list arguments, procps accepts both comma and blank separated lists.
+fn list_values<'a>(matches: &'a ArgMatches, id: &str) -> impl Iterator<Item = &'a str> {
+    matches
+        .get_many::<String>(id)
+        .unwrap_or_default()
+        .flat_map(|it| it.split_whitespace())
+}
+
+fn resolve_ids(
+    matches: &ArgMatches,
+    id: &str,
+    resolve: fn(&str) -> std::io::Result<u32>,
+    kind: &str,
+) -> UResult<Vec<u32>> {
+    list_values(matches, id)
+        .map(|it| {
+            it.parse::<u32>()
+                .or_else(|_| resolve(it))
+                .map_err(|_| USimpleError::new(1, format!("error: {kind} name does not exist")))
+        })
+        .collect()
+}
+
+/// Filter for users
+///
+/// - `-u` Select by effective user ID (EUID) or name.
+/// - `-U` Select by real user ID (RUID) or name.
+pub(crate) fn user_collector(
+    matches: &ArgMatches,
+    proc_snapshot: &[Rc<RefCell<ProcessInformation>>],
+) -> UResult<Vec<Rc<RefCell<ProcessInformation>>>> {
+    let euids = resolve_ids(matches, "user", usr2uid, "user")?;
+    let ruids = resolve_ids(matches, "User", usr2uid, "user")?;
+
+    Ok(proc_snapshot
+        .iter()
+        .filter(|it| {
+            let mut proc = it.borrow_mut();
+            proc.euid().is_ok_and(|id| euids.contains(&id))
+                || proc.uid().is_ok_and(|id| ruids.contains(&id))
+        })
+        .cloned()
+        .collect())
+}
+
+/// Filter for groups
+///
+/// - `-g` Select by session or by effective group name. The list is treated as a list of session
+///   IDs when it is completely numeric, like procps does.
+/// - `-G` Select by real group ID (RGID) or name.
+pub(crate) fn group_collector(
+    matches: &ArgMatches,
+    proc_snapshot: &[Rc<RefCell<ProcessInformation>>],
+) -> UResult<Vec<Rc<RefCell<ProcessInformation>>>> {
+    let by_session = list_values(matches, "group").all(|it| it.parse::<u64>().is_ok());
+    let (sids, egids) = if by_session {
+        let sids = list_values(matches, "group")
+            .map(|it| it.parse::<u64>().unwrap())
+            .collect::<Vec<_>>();
+        (sids, Vec::new())
+    } else {
+        (Vec::new(), resolve_ids(matches, "group", grp2gid, "group")?)
+    };
+    let rgids = resolve_ids(matches, "Group", grp2gid, "group")?;
+
+    Ok(proc_snapshot
+        .iter()
+        .filter(|it| {
+            let mut proc = it.borrow_mut();
+            proc.sid().is_ok_and(|id| sids.contains(&id))
+                || proc.egid().is_ok_and(|id| egids.contains(&id))
+                || proc.gid().is_ok_and(|id| rgids.contains(&id))
+        })
+        .cloned()
+        .collect())
+}
+
+/// Filter for command names
+///
+/// - `-C` Select by command name, i.e. the executable name shown by `comm`.
+pub(crate) fn command_collector(
+    matches: &ArgMatches,
+    proc_snapshot: &[Rc<RefCell<ProcessInformation>>],
+) -> Vec<Rc<RefCell<ProcessInformation>>> {
+    let names = list_values(matches, "C").collect::<Vec<_>>();
+    if names.is_empty() {
+        return Vec::new();
+    }
+
+    proc_snapshot
+        .iter()
+        .filter(|it| {
+            it.borrow_mut()
+                .name()
+                .is_ok_and(|name| names.contains(&name.as_str()))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Filter for parents
+///
+/// - `--ppid` Select by parent process ID, i.e. the children of the listed processes.
+pub(crate) fn parent_collector(
+    matches: &ArgMatches,
+    proc_snapshot: &[Rc<RefCell<ProcessInformation>>],
+) -> UResult<Vec<Rc<RefCell<ProcessInformation>>>> {
+    let ppids = list_values(matches, "ppid")
+        .map(|it| {
+            it.parse::<u64>()
+                .map_err(|_| USimpleError::new(1, "error: process ID list syntax error"))
+        })
+        .collect::<UResult<Vec<_>>>()?;
+    if ppids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(proc_snapshot
+        .iter()
+        .filter(|it| {
+            it.borrow_mut()
+                .ppid()
+                .is_ok_and(|ppid| ppids.contains(&ppid))
+        })
+        .cloned()
+        .collect())
+}
+
+/// Parses a tty of a `-t` list, accepting the forms shown by `ps` (`pts/3`, `tty1`, `?`) as well
+/// as full device paths. `-` and `?` select processes without a controlling terminal.
+fn parse_tty(value: &str) -> Option<Teletype> {
+    match value {
+        "-" | "?" => Some(Teletype::Unknown),
+        _ => Teletype::try_from(value.strip_prefix("/dev/").unwrap_or(value)).ok(),
+    }
+}
+
+/// Filter for terminals
+///
+/// - `-t` Select by controlling terminal.
+pub(crate) fn terminal_collector(
+    matches: &ArgMatches,
+    proc_snapshot: &[Rc<RefCell<ProcessInformation>>],
+) -> UResult<Vec<Rc<RefCell<ProcessInformation>>>> {
+    let ttys = list_values(matches, "tty")
+        .map(|it| {
+            parse_tty(it)
+                .ok_or_else(|| USimpleError::new(1, "error: TTY could not be found".to_string()))
+        })
+        .collect::<UResult<Vec<_>>>()?;
+    if ttys.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(proc_snapshot
+        .iter()
+        .filter(|it| ttys.contains(&it.borrow().tty()))
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tty() {
+        assert_eq!(parse_tty("?"), Some(Teletype::Unknown));
+        assert_eq!(parse_tty("-"), Some(Teletype::Unknown));
+        assert_eq!(parse_tty("pts/3"), Some(Teletype::Pts(3)));
+        assert_eq!(parse_tty("/dev/pts/3"), Some(Teletype::Pts(3)));
+        assert_eq!(parse_tty("tty1"), Some(Teletype::Tty(1)));
+        assert_eq!(parse_tty("ttyS0"), Some(Teletype::TtyS(0)));
+        assert_eq!(parse_tty("console"), None);
+    }
+}
@@ -0,0 +1,101 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::collections::HashMap;
+
+/// Identifies a row across runs: the PID, or the TID for thread rows.
+pub(crate) type Key = (usize, bool);
+
+/// Formatted rows along with the process they describe.
+pub(crate) type Rows = Vec<(Key, Vec<String>)>;
+
+/// Header of the column telling how a row changed.
+pub(crate) const HEADER: &str = "CHG";
+
+const NEW: &str = "+";
+const EXITED: &str = "-";
+const CHANGED: &str = "~";
+
+/// Rows of `current` which are new or differ from `previous`, followed by the rows of the
+/// processes which exited, each prefixed with the kind of change.
+pub(crate) fn changes(
+    previous: &[(Key, Vec<String>)],
+    current: &[(Key, Vec<String>)],
+) -> Vec<Vec<String>> {
+    let before = previous
+        .iter()
+        .map(|(key, row)| (key, row))
+        .collect::<HashMap<_, _>>();
+    let after = current
+        .iter()
+        .map(|(key, _)| key)
+        .collect::<std::collections::HashSet<_>>();
+
+    let prefixed = |marker: &str, row: &[String]| {
+        std::iter::once(marker.to_string())
+            .chain(row.iter().cloned())
+            .collect::<Vec<_>>()
+    };
+
+    let mut result = Vec::new();
+    for (key, row) in current {
+        match before.get(key) {
+            None => result.push(prefixed(NEW, row)),
+            Some(old) if *old != row => result.push(prefixed(CHANGED, row)),
+            Some(_) => {}
+        }
+    }
+    for (key, row) in previous {
+        if !after.contains(key) {
+            result.push(prefixed(EXITED, row));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|it| it.to_string()).collect()
+    }
+
+    #[test]
+    fn test_changes() {
+        let previous = vec![
+            ((1, false), row(&["1", "init"])),
+            ((2, false), row(&["2", "sleep"])),
+            ((3, false), row(&["3", "bash"])),
+        ];
+        let current = vec![
+            ((1, false), row(&["1", "init"])),
+            ((3, false), row(&["3", "vim"])),
+            ((4, false), row(&["4", "cat"])),
+        ];
+
+        assert_eq!(
+            changes(&previous, &current),
+            vec![
+                row(&["~", "3", "vim"]),
+                row(&["+", "4", "cat"]),
+                row(&["-", "2", "sleep"]),
+            ]
+        );
+        assert!(changes(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_threads_are_distinct_from_processes() {
+        let previous = vec![((5, false), row(&["5"]))];
+        let current = vec![((5, true), row(&["5"]))];
+
+        assert_eq!(
+            changes(&previous, &current),
+            vec![row(&["+", "5"]), row(&["-", "5"])]
+        );
+    }
+}
@@ -0,0 +1,228 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use uu_pgrep::process::ProcessInformation;
+
+/// Format codes whose value is decorated with the tree art.
+pub(crate) const COMMAND_CODES: [&str; 7] =
+    ["args", "cmd", "command", "comm", "ucmd", "ucomm", "fname"];
+
+/// How the line of a process is drawn in the forest, around its command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Tree {
+    /// The `\_` art before the command
+    pub(crate) prefix: String,
+    /// Number of identical sibling subtrees standing for the line, in compact mode
+    pub(crate) count: usize,
+    /// Identical children without children of their own, number and name, folded into the line
+    /// when they are the only children of the process
+    pub(crate) folded: Option<(usize, String)>,
+}
+
+impl Tree {
+    /// The command decorated with the tree art, `name---N*[child]` as pstree does for the
+    /// collapsed subtrees.
+    pub(crate) fn decorate(&self, command: &str) -> String {
+        let mut result = self.prefix.clone();
+        match self.count {
+            0 | 1 => result.push_str(command),
+            count => result.push_str(&format!("{count}*[{command}]")),
+        }
+        if let Some((count, name)) = &self.folded {
+            result.push_str(&format!("---{count}*[{name}]"));
+        }
+        result
+    }
+}
+
+type Children = HashMap<usize, Vec<Rc<RefCell<ProcessInformation>>>>;
+
+/// What makes two subtrees identical: the names of their processes, at the same places.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Shape {
+    name: String,
+    children: Vec<Shape>,
+}
+
+fn pid_of(proc: &Rc<RefCell<ProcessInformation>>) -> usize {
+    proc.borrow().pid
+}
+
+fn name_of(proc: &Rc<RefCell<ProcessInformation>>) -> String {
+    proc.borrow_mut().name().unwrap_or_default()
+}
+
+fn shape(proc: &Rc<RefCell<ProcessInformation>>, children: &Children) -> Shape {
+    let mut kids = children
+        .get(&pid_of(proc))
+        .into_iter()
+        .flatten()
+        .map(|kid| shape(kid, children))
+        .collect::<Vec<_>>();
+    // Siblings are in no particular order
+    kids.sort();
+    Shape {
+        name: name_of(proc),
+        children: kids,
+    }
+}
+
+/// Removes the descendants of a process which is collapsed into an identical sibling.
+fn discard(proc: &Rc<RefCell<ProcessInformation>>, children: &mut Children) {
+    for kid in children.remove(&pid_of(proc)).into_iter().flatten() {
+        discard(&kid, children);
+    }
+}
+
+/// Groups identical sibling subtrees when `compact`, each group is kept at the place of its
+/// first member.
+fn group(
+    kids: Vec<Rc<RefCell<ProcessInformation>>>,
+    children: &mut Children,
+    compact: bool,
+) -> Vec<(Rc<RefCell<ProcessInformation>>, usize)> {
+    if !compact {
+        return kids.into_iter().map(|kid| (kid, 1)).collect();
+    }
+    let mut groups: Vec<(Shape, Rc<RefCell<ProcessInformation>>, usize)> = Vec::new();
+    for kid in kids {
+        let shape = shape(&kid, children);
+        if let Some(group) = groups.iter_mut().find(|group| group.0 == shape) {
+            group.2 += 1;
+            discard(&kid, children);
+        } else {
+            groups.push((shape, kid, 1));
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, kid, count)| (kid, count))
+        .collect()
+}
+
+/// Reorders processes by ancestry and computes the `\_` tree art for each of them.
+///
+/// The relative order of the input is kept among siblings, processes whose parent is not part
+/// of the selection are treated as roots. When `compact`, identical sibling subtrees are
+/// collapsed into the line of the first of them.
+pub(crate) fn forest(
+    input: Vec<Rc<RefCell<ProcessInformation>>>,
+    compact: bool,
+) -> Vec<(Rc<RefCell<ProcessInformation>>, Tree)> {
+    let pids = input
+        .iter()
+        .map(|it| it.borrow().pid)
+        .collect::<std::collections::HashSet<_>>();
+
+    let mut roots = Vec::new();
+    let mut children: Children = HashMap::new();
+    for proc in input {
+        let (pid, ppid) = {
+            let mut proc = proc.borrow_mut();
+            (proc.pid, proc.ppid().unwrap_or_default() as usize)
+        };
+        // The idle task (pid 0) never shows up, so init and kthreadd are roots too.
+        if ppid != pid && pids.contains(&ppid) {
+            children.entry(ppid).or_default().push(proc);
+        } else {
+            roots.push(proc);
+        }
+    }
+
+    let roots = group(roots, &mut children, compact);
+    let mut result = Vec::new();
+    let mut walker = Walker {
+        children: &mut children,
+        compact,
+        indent: String::new(),
+        result: &mut result,
+    };
+    for (root, count) in roots {
+        walker.walk(root, count, None);
+    }
+
+    // Cycles cannot happen in a consistent snapshot, but keep every process in the output anyway.
+    let mut rest = children.into_values().flatten().collect::<Vec<_>>();
+    rest.sort_by_key(|it| it.borrow().pid);
+    result.extend(rest.into_iter().map(|it| (it, Tree::default())));
+
+    result
+}
+
+struct Walker<'a> {
+    children: &'a mut Children,
+    compact: bool,
+    indent: String,
+    result: &'a mut Vec<(Rc<RefCell<ProcessInformation>>, Tree)>,
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, proc: Rc<RefCell<ProcessInformation>>, count: usize, last: Option<bool>) {
+        let prefix = match last {
+            Some(_) => format!("{} \\_ ", self.indent),
+            None => String::new(),
+        };
+        let kids = self.children.remove(&pid_of(&proc)).unwrap_or_default();
+        let kids = group(kids, self.children, self.compact);
+
+        let folded = match kids.as_slice() {
+            [(kid, count)] if *count > 1 && !self.children.contains_key(&pid_of(kid)) => {
+                Some((*count, name_of(kid)))
+            }
+            _ => None,
+        };
+        let is_folded = folded.is_some();
+        self.result.push((
+            proc,
+            Tree {
+                prefix,
+                count,
+                folded,
+            },
+        ));
+        if is_folded {
+            return;
+        }
+
+        let len = self.indent.len();
+        match last {
+            Some(false) => self.indent.push_str(" |  "),
+            Some(true) => self.indent.push_str("    "),
+            None => {}
+        }
+        let total = kids.len();
+        for (i, (kid, count)) in kids.into_iter().enumerate() {
+            self.walk(kid, count, Some(i + 1 == total));
+        }
+        self.indent.truncate(len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decorate() {
+        let tree = |prefix: &str, count, folded: Option<(usize, &str)>| Tree {
+            prefix: prefix.into(),
+            count,
+            folded: folded.map(|(count, name)| (count, name.into())),
+        };
+
+        assert_eq!(Tree::default().decorate("init"), "init");
+        assert_eq!(tree(" \\_ ", 1, None).decorate("sshd"), " \\_ sshd");
+        assert_eq!(tree(" \\_ ", 4, None).decorate("agetty"), " \\_ 4*[agetty]");
+        assert_eq!(
+            tree("", 1, Some((8, "worker"))).decorate("nginx"),
+            "nginx---8*[worker]"
+        );
+        assert_eq!(
+            tree(" \\_ ", 2, Some((3, "sleep"))).decorate("sh"),
+            " \\_ 2*[sh]---3*[sleep]"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+/// Widths of the columns, computed from the values of the selected processes.
+///
+/// The widths only grow, so that the columns stay aligned across the reports of `--watch`.
+#[derive(Debug, Default)]
+pub(crate) struct Layout {
+    widths: Vec<usize>,
+    right_aligned: Vec<bool>,
+}
+
+impl Layout {
+    /// Widens the columns to fit the header and the rows. The alignment is decided on the first
+    /// rows: the numeric columns, such as PIDs, are right-aligned and the others left-aligned.
+    pub(crate) fn fit(&mut self, header: &[String], rows: &[Vec<String>]) {
+        if self.right_aligned.is_empty() && !rows.is_empty() {
+            self.right_aligned = (0..header.len())
+                .map(|column| {
+                    rows.iter()
+                        .filter_map(|row| row.get(column))
+                        .all(|value| value == "-" || value.parse::<f64>().is_ok())
+                })
+                .collect();
+        }
+
+        for row in std::iter::once(header).chain(rows.iter().map(Vec::as_slice)) {
+            if self.widths.len() < row.len() {
+                self.widths.resize(row.len(), 0);
+            }
+            for (width, value) in self.widths.iter_mut().zip(row) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+    }
+
+    /// Formats a row, the last column isn't padded unless it is right-aligned.
+    pub(crate) fn format(&self, row: &[String]) -> String {
+        let mut line = String::new();
+        for (column, value) in row.iter().enumerate() {
+            let width = self.widths.get(column).copied().unwrap_or_default();
+            let last = column == row.len() - 1;
+            line.push(' ');
+            if self.right_aligned.get(column).copied().unwrap_or_default() {
+                line.push_str(&format!("{value:>width$}"));
+            } else if last {
+                line.push_str(value);
+            } else {
+                line.push_str(&format!("{value:<width$}"));
+            }
+            if !last {
+                line.push(' ');
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[&str]) -> Vec<String> {
+        values.iter().map(|it| it.to_string()).collect()
+    }
+
+    #[test]
+    fn test_widths_from_values() {
+        let header = row(&["PID", "USER", "CMD"]);
+        let rows = [
+            row(&["1", "root", "init"]),
+            row(&["4194303", "a_long_user_name", "sleep 10"]),
+        ];
+        let mut layout = Layout::default();
+        layout.fit(&header, &rows);
+
+        assert_eq!(layout.format(&header), "     PID  USER              CMD");
+        assert_eq!(layout.format(&rows[0]), "       1  root              init");
+        assert_eq!(
+            layout.format(&rows[1]),
+            " 4194303  a_long_user_name  sleep 10"
+        );
+    }
+
+    #[test]
+    fn test_widths_only_grow() {
+        let header = row(&["PID", "CMD"]);
+        let mut layout = Layout::default();
+        layout.fit(&header, &[row(&["123456", "a"])]);
+        layout.fit(&header, &[row(&["7", "b"])]);
+        assert_eq!(layout.format(&row(&["7", "b"])), "      7  b");
+
+        // Blank values don't prevent the right alignment
+        let mut layout = Layout::default();
+        layout.fit(&header, &[row(&["-", "a"]), row(&["12", "b"])]);
+        assert_eq!(layout.format(&row(&["-", "a"])), "   -  a");
+    }
+}
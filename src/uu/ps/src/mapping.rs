@@ -26,6 +26,82 @@ pub(crate) fn default_codes() -> Vec<String> {
     ["pid", "tname", "time", "ucmd"].map(Into::into).to_vec()
 }
 
+/// Returns the codes of the full format (`-f`), or of the extra full format (`-F`).
+pub(crate) fn full_codes(extra: bool) -> Vec<String> {
+    let codes: &[&str] = if extra {
+        &[
+            "uid_hack", "pid", "ppid", "c", "sz", "rss", "psr", "stime", "tname", "time", "cmd",
+        ]
+    } else {
+        &[
+            "uid_hack", "pid", "ppid", "c", "stime", "tname", "time", "cmd",
+        ]
+    };
+    codes.iter().map(|it| it.to_string()).collect()
+}
+
+/// Returns the codes of the long format (`-l`), combined with the full formats when `full` is
+/// given.
+pub(crate) fn long_codes(full: Option<bool>) -> Vec<String> {
+    let mut codes = vec![
+        "f", "s", "uid", "pid", "ppid", "c", "opri", "ni", "addr", "sz", "wchan",
+    ];
+    match full {
+        None => codes.extend(["tname", "time", "ucmd"]),
+        Some(false) => codes.extend(["stime", "tname", "time", "cmd"]),
+        Some(true) => codes.extend(["rss", "psr", "stime", "tname", "time", "cmd"]),
+    }
+    if full.is_some() {
+        codes[2] = "uid_hack";
+    }
+    codes.into_iter().map(Into::into).collect()
+}
+
+/// Returns the codes of the BSD format, used when BSD selection options are given.
+pub(crate) fn bsd_codes() -> Vec<String> {
+    ["pid", "tname", "stat", "bsdtime", "args"]
+        .map(Into::into)
+        .to_vec()
+}
+
+/// Returns the codes of the BSD user oriented format (`u`).
+pub(crate) fn bsd_user_codes() -> Vec<String> {
+    [
+        "user",
+        "pid",
+        "%cpu",
+        "%mem",
+        "vsz",
+        "rss",
+        "tname",
+        "stat",
+        "start_time",
+        "bsdtime",
+        "args",
+    ]
+    .map(Into::into)
+    .to_vec()
+}
+
+/// Returns the codes of the BSD job control format (`j`).
+pub(crate) fn bsd_job_codes() -> Vec<String> {
+    [
+        "ppid", "pid", "pgid", "sid", "tname", "tpgid", "stat", "uid", "bsdtime", "args",
+    ]
+    .map(Into::into)
+    .to_vec()
+}
+
+/// Returns the codes of the BSD long format (`l`).
+pub(crate) fn bsd_long_codes() -> Vec<String> {
+    [
+        "f", "uid", "pid", "ppid", "pri", "ni", "vsz", "rss", "wchan", "stat", "tname", "bsdtime",
+        "args",
+    ]
+    .map(Into::into)
+    .to_vec()
+}
+
 /// Collect mapping from argument
 pub(crate) fn default_mapping() -> HashMap<String, String> {
     let mut mapping = HashMap::new();
@@ -176,5 +252,15 @@ pub(crate) fn default_mapping() -> HashMap<String, String> {
     append("wchars", "WCHARS");
     append("wops", "WOPS");
 
+    // Undocumented codes of the predefined formats
+    append("addr", "ADDR");
+    append("environ", "ENVIRONMENT");
+    append("opri", "PRI");
+    append("uid_hack", "UID");
+
+    // Extensions, summarizing /proc/<pid>/maps
+    append("mapped_kb", "MAPPED");
+    append("maps_count", "MAPS");
+
     mapping
 }
@@ -3,11 +3,20 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use std::cell::RefCell;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Mutex, OnceLock},
+};
 
+use chrono::{DateTime, Local};
 use uu_pgrep::process::{ProcessInformation, Teletype};
+use uu_pgrep::process_matcher::clock_ticks;
+use uu_pmap::smaps_format_parser::{summarize_mappings, MappingSummary};
+use uu_w::clock;
 #[cfg(unix)]
 use uucore::entries::{gid2grp, uid2usr};
+use uucore::error::{UResult, USimpleError};
 
 #[cfg(not(unix))]
 fn uid2usr(id: u32) -> Result<String, std::io::Error> {
@@ -19,19 +28,28 @@ fn gid2grp(id: u32) -> Result<String, std::io::Error> {
     Ok(id.to_string())
 }
 
+/// Formats the value of a column for a process.
+pub(crate) type Picker = Box<dyn Fn(RefCell<ProcessInformation>) -> String>;
+
+/// `date_format` replaces the default formats of the `start` and `lstart` columns. The codes
+/// which aren't implemented yet are rejected like the unknown ones.
 pub(crate) fn collect_pickers(
     code_order: &[String],
-) -> Vec<Box<dyn Fn(RefCell<ProcessInformation>) -> String>> {
+    date_format: Option<&str>,
+) -> UResult<Vec<Picker>> {
     let mut pickers = Vec::new();
+    let mappings = MappingsCache::default();
 
     for code in code_order {
         match code.as_str() {
             "pid" | "tgid" => pickers.push(helper(pid)),
+            "lwp" | "spid" | "tid" => pickers.push(helper(lwp)),
+            "nlwp" | "thcount" => pickers.push(helper(nlwp)),
             "ppid" => pickers.push(helper(ppid)),
             "uid" | "euid" => pickers.push(helper(euid)),
             "ruid" => pickers.push(helper(ruid)),
             "suid" => pickers.push(helper(suid)),
-            "user" | "euser" => pickers.push(helper(euser)),
+            "user" | "euser" | "uid_hack" => pickers.push(helper(euser)),
             "ruser" => pickers.push(helper(ruser)),
             "suser" => pickers.push(helper(suser)),
             "pgid" => pickers.push(helper(pgid)),
@@ -48,26 +66,85 @@ pub(crate) fn collect_pickers(
             "caught" => pickers.push(helper(caught)),
             "tname" | "tt" | "tty" => pickers.push(helper(tty)),
             "time" | "cputime" => pickers.push(helper(time)),
+            "bsdtime" => pickers.push(helper(bsdtime)),
+            "stat" => pickers.push(helper(stat)),
+            "s" | "state" => pickers.push(helper(state)),
+            "%cpu" | "pcpu" => pickers.push(helper(pcpu)),
+            "%mem" | "pmem" => pickers.push(helper(pmem)),
+            "vsz" | "vsize" => pickers.push(helper(vsz)),
+            "rss" | "rssize" => pickers.push(helper(rss)),
+            "tpgid" => pickers.push(helper(tpgid)),
+            "pri" => pickers.push(helper(pri)),
+            "opri" => pickers.push(helper(opri)),
+            "c" => pickers.push(helper(c)),
+            "sz" => pickers.push(helper(sz)),
+            "psr" => pickers.push(helper(psr)),
+            "addr" => pickers.push(helper(|_| "-".into())),
+            "ni" | "nice" => pickers.push(helper(ni)),
+            "wchan" => pickers.push(helper(wchan)),
+            "label" => pickers.push(helper(label)),
+            "environ" => pickers.push(helper(environ)),
+            "maps_count" => {
+                let mappings = mappings.clone();
+                pickers.push(helper(move |proc_info| maps_count(&mappings, proc_info)));
+            }
+            "mapped_kb" => {
+                let mappings = mappings.clone();
+                pickers.push(helper(move |proc_info| mapped_kb(&mappings, proc_info)));
+            }
+            "f" | "flag" | "flags" => pickers.push(helper(flags)),
+            "bsdstart" => pickers.push(helper(bsdstart)),
+            "start_time" | "stime" => pickers.push(helper(start_time)),
+            "start" | "lstart" if date_format.is_some() => {
+                let date_format = date_format.unwrap().to_owned();
+                pickers.push(helper(move |proc_info| {
+                    start_date(&proc_info).map_or_else(
+                        || "-".into(),
+                        |start| start.format(&date_format).to_string(),
+                    )
+                }));
+            }
+            "start" => pickers.push(helper(start)),
+            "lstart" => pickers.push(helper(lstart)),
+            "etime" => pickers.push(helper(etime)),
+            "etimes" => pickers.push(helper(etimes)),
             "ucmd" | "comm" => pickers.push(helper(ucmd)),
             "cmd" | "command" | "args" => pickers.push(helper(cmd)),
-            _ => {}
+            _ => {
+                return Err(USimpleError::new(
+                    1,
+                    format!("error: unknown user-defined format specifier \"{code}\""),
+                ));
+            }
         }
     }
 
-    pickers
+    Ok(pickers)
 }
 
 #[inline]
-fn helper(
-    f: impl Fn(RefCell<ProcessInformation>) -> String + 'static,
-) -> Box<dyn Fn(RefCell<ProcessInformation>) -> String> {
+fn helper(f: impl Fn(RefCell<ProcessInformation>) -> String + 'static) -> Picker {
     Box::new(f)
 }
 
 fn pid(proc_info: RefCell<ProcessInformation>) -> String {
+    // Threads report the process they belong to
+    let tgid = proc_info.borrow_mut().tgid();
+    tgid.map_or_else(|_| proc_info.borrow().pid.to_string(), |it| it.to_string())
+}
+
+fn lwp(proc_info: RefCell<ProcessInformation>) -> String {
     proc_info.borrow().pid.to_string()
 }
 
+fn nlwp(proc_info: RefCell<ProcessInformation>) -> String {
+    proc_info
+        .borrow_mut()
+        .num_threads()
+        .map(|it| it.to_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
+
 fn ppid(proc_info: RefCell<ProcessInformation>) -> String {
     proc_info.borrow_mut().ppid().unwrap().to_string()
 }
@@ -156,6 +233,12 @@ fn time(proc_info: RefCell<ProcessInformation>) -> String {
     format_time(cumulative_cpu_time)
 }
 
+fn bsdtime(proc_info: RefCell<ProcessInformation>) -> String {
+    let ticks = cpu_ticks(&proc_info);
+    let seconds = ticks / clock_ticks();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
 fn format_time(seconds: i64) -> String {
     let day = seconds / (3600 * 24);
     let hour = (seconds % (3600 * 24)) / 3600;
@@ -169,18 +252,383 @@ fn format_time(seconds: i64) -> String {
     }
 }
 
+fn page_size_kib() -> u64 {
+    static PAGE_SIZE: OnceLock<u64> = OnceLock::new();
+    // SAFETY: sysconf has no preconditions
+    *PAGE_SIZE.get_or_init(|| match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64 / 1024,
+        _ => 4,
+    })
+}
+
+/// Looks up a numeric value in a `/proc` file made of `key value` lines.
+fn proc_file_value(path: &str, key: &str) -> Option<u64> {
+    std::fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix(key))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn mem_total_kib() -> u64 {
+    static MEM_TOTAL: OnceLock<u64> = OnceLock::new();
+    *MEM_TOTAL.get_or_init(|| proc_file_value("/proc/meminfo", "MemTotal:").unwrap_or_default())
+}
+
+fn boot_time() -> u64 {
+    static BOOT_TIME: OnceLock<u64> = OnceLock::new();
+    *BOOT_TIME.get_or_init(|| proc_file_value("/proc/stat", "btime ").unwrap_or_default())
+}
+
+/// When the processes were read, the times of all the rows are relative to it.
+#[derive(Debug, Clone, Copy)]
+struct Moment {
+    uptime: f64,
+    now: DateTime<Local>,
+}
+
+impl Moment {
+    fn current() -> Self {
+        let uptime = std::fs::read_to_string("/proc/uptime")
+            .ok()
+            .and_then(|it| it.split_whitespace().next()?.parse().ok())
+            .unwrap_or_default();
+        Self {
+            uptime,
+            now: Local::now(),
+        }
+    }
+}
+
+static MOMENT: Mutex<Option<Moment>> = Mutex::new(None);
+
+/// Records the moment of a snapshot of the processes, right after reading them.
+pub(crate) fn capture_moment() {
+    *MOMENT.lock().unwrap() = Some(Moment::current());
+}
+
+fn moment() -> Moment {
+    MOMENT.lock().unwrap().unwrap_or_else(Moment::current)
+}
+
+fn uptime_seconds() -> f64 {
+    moment().uptime
+}
+
+fn stat_field(proc_info: &RefCell<ProcessInformation>, index: usize) -> Option<i64> {
+    proc_info.borrow_mut().stat().get(index)?.parse().ok()
+}
+
+/// User and system time of the process, in clock ticks
+fn cpu_ticks(proc_info: &RefCell<ProcessInformation>) -> u64 {
+    let utime = stat_field(proc_info, 13).unwrap_or_default();
+    let stime = stat_field(proc_info, 14).unwrap_or_default();
+    (utime + stime) as u64
+}
+
+fn start_date(proc_info: &RefCell<ProcessInformation>) -> Option<DateTime<Local>> {
+    let start = proc_info.borrow_mut().start_time().ok()? / clock_ticks();
+    let timestamp = (boot_time() + start) as i64;
+    Some(DateTime::from_timestamp(timestamp, 0)?.with_timezone(&Local))
+}
+
+fn state(proc_info: RefCell<ProcessInformation>) -> String {
+    proc_info
+        .borrow_mut()
+        .stat()
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| "?".into())
+}
+
+/// State followed by the BSD flags, see the PROCESS STATE CODES section of ps(1)
+fn stat(proc_info: RefCell<ProcessInformation>) -> String {
+    let mut result = state(proc_info.clone());
+
+    let nice = stat_field(&proc_info, 18).unwrap_or_default();
+    if nice < 0 {
+        result.push('<');
+    } else if nice > 0 {
+        result.push('N');
+    }
+
+    let locked = proc_info
+        .borrow_mut()
+        .status()
+        .get("VmLck")
+        .and_then(|it| it.split_whitespace().next()?.parse::<u64>().ok())
+        .unwrap_or_default();
+    if locked > 0 {
+        result.push('L');
+    }
+
+    let pid = proc_info.borrow().pid as i64;
+    if stat_field(&proc_info, 5) == Some(pid) {
+        result.push('s');
+    }
+    if proc_info.borrow_mut().num_threads().unwrap_or(1) > 1 {
+        result.push('l');
+    }
+    let tpgid = stat_field(&proc_info, 7);
+    if tpgid.is_some_and(|it| it > 0) && tpgid == stat_field(&proc_info, 4) {
+        result.push('+');
+    }
+
+    result
+}
+
+/// CPU time used divided by the time the process has been running, in percent
+fn cpu_usage(proc_info: &RefCell<ProcessInformation>) -> f64 {
+    let ticks = cpu_ticks(proc_info) as f64;
+    let start = proc_info.borrow_mut().start_time().unwrap_or_default() as f64;
+    let hertz = clock_ticks() as f64;
+
+    let elapsed = uptime_seconds() - start / hertz;
+    if elapsed <= 0.0 {
+        return 0.0;
+    }
+    ticks / hertz / elapsed * 100.0
+}
+
+fn pcpu(proc_info: RefCell<ProcessInformation>) -> String {
+    format!("{:.1}", cpu_usage(&proc_info).min(999.9))
+}
+
+fn c(proc_info: RefCell<ProcessInformation>) -> String {
+    (cpu_usage(&proc_info).min(99.0) as u64).to_string()
+}
+
+fn pmem(proc_info: RefCell<ProcessInformation>) -> String {
+    let rss = stat_field(&proc_info, 23).unwrap_or_default() as u64 * page_size_kib();
+    match mem_total_kib() {
+        0 => "0.0".into(),
+        total => format!("{:.1}", rss as f64 / total as f64 * 100.0),
+    }
+}
+
+fn vsz(proc_info: RefCell<ProcessInformation>) -> String {
+    (stat_field(&proc_info, 22).unwrap_or_default() / 1024).to_string()
+}
+
+fn rss(proc_info: RefCell<ProcessInformation>) -> String {
+    (stat_field(&proc_info, 23).unwrap_or_default() as u64 * page_size_kib()).to_string()
+}
+
+fn tpgid(proc_info: RefCell<ProcessInformation>) -> String {
+    stat_field(&proc_info, 7).unwrap_or(-1).to_string()
+}
+
+fn pri(proc_info: RefCell<ProcessInformation>) -> String {
+    stat_field(&proc_info, 17).unwrap_or_default().to_string()
+}
+
+fn opri(proc_info: RefCell<ProcessInformation>) -> String {
+    (60 + stat_field(&proc_info, 17).unwrap_or_default()).to_string()
+}
+
+fn sz(proc_info: RefCell<ProcessInformation>) -> String {
+    let vsz_kib = stat_field(&proc_info, 22).unwrap_or_default() as u64 / 1024;
+    (vsz_kib / page_size_kib()).to_string()
+}
+
+fn psr(proc_info: RefCell<ProcessInformation>) -> String {
+    stat_field(&proc_info, 38).unwrap_or_default().to_string()
+}
+
+fn ni(proc_info: RefCell<ProcessInformation>) -> String {
+    // Real time processes have no nice value
+    if stat_field(&proc_info, 40).unwrap_or_default() != 0 {
+        return "-".into();
+    }
+    stat_field(&proc_info, 18).unwrap_or_default().to_string()
+}
+
+fn wchan(proc_info: RefCell<ProcessInformation>) -> String {
+    let pid = proc_info.borrow().pid;
+    match std::fs::read_to_string(format!("/proc/{pid}/wchan")) {
+        Ok(wchan) if !wchan.is_empty() && wchan != "0" => wchan,
+        _ => "-".into(),
+    }
+}
+
+/// Mappings of the process of the row being picked, shared by the columns showing them so that
+/// the maps of each process are read once.
+type MappingsCache = Rc<RefCell<Option<(usize, Option<MappingSummary>)>>>;
+
+/// Mappings of the process, `None` when they cannot be read, most likely because the process
+/// belongs to another user.
+fn mappings(
+    cache: &MappingsCache,
+    proc_info: &RefCell<ProcessInformation>,
+) -> Option<MappingSummary> {
+    let pid = proc_info.borrow().pid;
+    let mut cache = cache.borrow_mut();
+    match *cache {
+        Some((cached_pid, summary)) if cached_pid == pid => summary,
+        _ => {
+            let summary = proc_info
+                .borrow()
+                .maps()
+                .ok()
+                .map(|content| summarize_mappings(&content));
+            *cache = Some((pid, summary));
+            summary
+        }
+    }
+}
+
+fn maps_count(cache: &MappingsCache, proc_info: RefCell<ProcessInformation>) -> String {
+    mappings(cache, &proc_info).map_or_else(|| "-".into(), |it| it.count.to_string())
+}
+
+/// Size of the files mapped by the process
+fn mapped_kb(cache: &MappingsCache, proc_info: RefCell<ProcessInformation>) -> String {
+    mappings(cache, &proc_info).map_or_else(|| "-".into(), |it| it.file_backed_in_kb.to_string())
+}
+
+/// Environment of the process separated by spaces, `None` when it cannot be read, most likely
+/// because the process belongs to another user.
+pub(crate) fn environment(proc_info: &RefCell<ProcessInformation>) -> Option<String> {
+    let pid = proc_info.borrow().pid;
+    let content = std::fs::read(format!("/proc/{pid}/environ")).ok()?;
+
+    let vars = content
+        .split(|it| *it == b'\0')
+        .filter(|it| !it.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>();
+    Some(vars.join(" "))
+}
+
+fn environ(proc_info: RefCell<ProcessInformation>) -> String {
+    match environment(&proc_info) {
+        Some(environ) if !environ.is_empty() => environ,
+        _ => "-".into(),
+    }
+}
+
+/// Security context, as set by SELinux or AppArmor
+fn label(proc_info: RefCell<ProcessInformation>) -> String {
+    let pid = proc_info.borrow().pid;
+    match std::fs::read_to_string(format!("/proc/{pid}/attr/current")) {
+        Ok(label) => {
+            let label = label.trim_end_matches(['\0', '\n']);
+            if label.is_empty() {
+                "-".into()
+            } else {
+                label.into()
+            }
+        }
+        Err(_) => "-".into(),
+    }
+}
+
+fn flags(proc_info: RefCell<ProcessInformation>) -> String {
+    // Only "forked but didn't exec" (1) and "used super-user privileges" (4) are shown
+    ((stat_field(&proc_info, 8).unwrap_or_default() >> 6) & 0x5).to_string()
+}
+
+fn bsdstart(proc_info: RefCell<ProcessInformation>) -> String {
+    let Some(start) = start_date(&proc_info) else {
+        return "-".into();
+    };
+
+    if moment().now.signed_duration_since(start).num_hours() < 24 {
+        start.format(" %H:%M").to_string()
+    } else {
+        start.format("%b %d").to_string()
+    }
+}
+
+fn start(proc_info: RefCell<ProcessInformation>) -> String {
+    let Some(start) = start_date(&proc_info) else {
+        return "-".into();
+    };
+
+    if moment().now.signed_duration_since(start).num_hours() < 24 {
+        start.format("%H:%M:%S").to_string()
+    } else {
+        start.format("%b %d").to_string()
+    }
+}
+
+fn lstart(proc_info: RefCell<ProcessInformation>) -> String {
+    // Resolving the style queries the locale, once is enough for all the rows
+    static STYLE: OnceLock<clock::Style> = OnceLock::new();
+    let style = STYLE.get_or_init(clock::Style::from_env);
+    start_date(&proc_info).map_or_else(|| "-".into(), |start| style.long(&start))
+}
+
+/// Seconds since the process started, from its start time in clock ticks since boot
+fn elapsed_seconds(proc_info: &RefCell<ProcessInformation>) -> Option<u64> {
+    let start = proc_info.borrow_mut().start_time().ok()? as f64 / clock_ticks() as f64;
+    Some((uptime_seconds() - start).max(0.0) as u64)
+}
+
+fn etime(proc_info: RefCell<ProcessInformation>) -> String {
+    elapsed_seconds(&proc_info).map_or_else(|| "-".into(), format_elapsed)
+}
+
+fn etimes(proc_info: RefCell<ProcessInformation>) -> String {
+    elapsed_seconds(&proc_info).map_or_else(|| "-".into(), |it| it.to_string())
+}
+
+/// Formats a duration as `[[dd-]hh:]mm:ss`
+fn format_elapsed(seconds: u64) -> String {
+    let days = seconds / (24 * 3600);
+    let hours = seconds % (24 * 3600) / 3600;
+    let minutes = seconds % 3600 / 60;
+    let seconds = seconds % 60;
+
+    let mut result = String::new();
+    if days > 0 {
+        result.push_str(&format!("{days}-"));
+    }
+    if days > 0 || hours > 0 {
+        result.push_str(&format!("{hours:02}:"));
+    }
+    result.push_str(&format!("{minutes:02}:{seconds:02}"));
+    result
+}
+
+fn start_time(proc_info: RefCell<ProcessInformation>) -> String {
+    let Some(start) = start_date(&proc_info) else {
+        return "-".into();
+    };
+
+    let now = moment().now;
+    if now.signed_duration_since(start).num_hours() < 24 {
+        start.format("%H:%M").to_string()
+    } else if now.format("%Y").to_string() == start.format("%Y").to_string() {
+        start.format("%b%d").to_string()
+    } else {
+        start.format("%Y").to_string()
+    }
+}
+
 fn cmd(proc_info: RefCell<ProcessInformation>) -> String {
     // Use command line if available, otherwise show process name in brackets (for kernel threads)
     let cmdline = proc_info.borrow().cmdline.clone();
     if !cmdline.is_empty() {
-        cmdline
+        printable(&cmdline)
     } else {
-        format!("[{}]", proc_info.borrow_mut().name().unwrap())
+        format!("[{}]", printable(&proc_info.borrow_mut().name().unwrap()))
     }
 }
 
 fn ucmd(proc_info: RefCell<ProcessInformation>) -> String {
-    proc_info.borrow_mut().name().unwrap()
+    printable(&proc_info.borrow_mut().name().unwrap())
+}
+
+/// Replaces the control characters, such as newlines, which would break the table.
+fn printable(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_control() { '?' } else { c })
+        .collect()
 }
 
 fn pending(proc_info: RefCell<ProcessInformation>) -> String {
@@ -237,3 +685,36 @@ fn test_time() {
     };
     assert_eq!(formatted, "01-10:17:37");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(0), "00:00");
+        assert_eq!(format_elapsed(59), "00:59");
+        assert_eq!(format_elapsed(3599), "59:59");
+        assert_eq!(format_elapsed(3600), "01:00:00");
+        assert_eq!(format_elapsed(86399), "23:59:59");
+        assert_eq!(format_elapsed(86400), "1-00:00:00");
+        assert_eq!(
+            format_elapsed(12 * 86400 + 3 * 3600 + 4 * 60 + 5),
+            "12-03:04:05"
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_moment() {
+        capture_moment();
+        let (uptime, now) = (uptime_seconds(), moment().now);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        // Every row is formatted against the same moment
+        assert_eq!(uptime_seconds(), uptime);
+        assert_eq!(moment().now, now);
+
+        capture_moment();
+        assert!(moment().now > now);
+    }
+}
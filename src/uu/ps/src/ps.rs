@@ -4,17 +4,24 @@
 // file that was distributed with this source code.
 
 mod collector;
+mod delta;
+mod forest;
+mod layout;
 mod mapping;
 mod parser;
 mod picker;
 mod sorting;
+mod threads;
 
 use clap::crate_version;
 use clap::{Arg, ArgAction, ArgMatches, Command};
-use mapping::{collect_code_mapping, default_codes, default_mapping};
+use mapping::{
+    bsd_codes, bsd_job_codes, bsd_long_codes, bsd_user_codes, collect_code_mapping, default_codes,
+    default_mapping, full_codes, long_codes,
+};
 use parser::{parser, OptionalKeyValue};
-use prettytable::{format::consts::FORMAT_CLEAN, Row, Table};
-use std::{cell::RefCell, rc::Rc};
+use std::io::Write;
+use std::{cell::RefCell, ffi::OsString, rc::Rc, thread::sleep, time::Duration};
 use uu_pgrep::process::walk_process;
 use uucore::{
     error::{UError, UResult, USimpleError},
@@ -26,71 +33,336 @@ const USAGE: &str = help_usage!("ps.md");
 
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
-    let matches = uu_app().try_get_matches_from(args)?;
+    let matches = uu_app().try_get_matches_from(translate_bsd_options(args))?;
+
+    let headers = Headers::from_matches(&matches);
+    let width = output_width(&matches);
+    let (header, rows) = collect_rows(&matches)?;
+    // Like procps, selecting no process is a failure, the header is still printed
+    if rows.is_empty() {
+        uucore::error::set_exit_code(1);
+    }
+    let mut layout = layout::Layout::default();
+    print_table(
+        &mut layout,
+        header.clone(),
+        rows.iter().map(|(_, row)| row.clone()).collect(),
+        headers,
+        width,
+    );
+
+    let Some(&interval) = matches.get_one::<Duration>("watch") else {
+        return Ok(());
+    };
+
+    let mut previous = rows;
+    // The changes have a column of their own
+    let mut layout = layout::Layout::default();
+    loop {
+        sleep(interval);
+
+        let (_, rows) = collect_rows(&matches)?;
+        let changes = delta::changes(&previous, &rows);
+        if !changes.is_empty() {
+            let header = std::iter::once(delta::HEADER.to_string()).chain(header.iter().cloned());
+            print_table(&mut layout, header.collect(), changes, headers, width);
+            std::io::stdout().flush()?;
+        }
+        previous = rows;
+    }
+}
 
+/// Selects the processes and formats them, returns the header and the rows along with the
+/// PID (or TID) they describe.
+fn collect_rows(matches: &ArgMatches) -> UResult<(Vec<String>, delta::Rows)> {
+    // Read every process at once, then select, sort and format them from this snapshot only
     let snapshot = walk_process()
         .map(|it| Rc::new(RefCell::new(it)))
         .collect::<Vec<_>>();
+    picker::capture_moment();
     let mut proc_infos = Vec::new();
 
-    proc_infos.extend(collector::basic_collector(&snapshot));
-    proc_infos.extend(collector::process_collector(&matches, &snapshot));
-    proc_infos.extend(collector::session_collector(&matches, &snapshot));
+    if !collector::has_selection(matches) {
+        proc_infos.extend(collector::basic_collector(&snapshot));
+    }
+    proc_infos.extend(collector::process_collector(matches, &snapshot));
+    proc_infos.extend(collector::bsd_collector(matches, &snapshot));
+    proc_infos.extend(collector::session_collector(matches, &snapshot));
+    proc_infos.extend(collector::user_collector(matches, &snapshot)?);
+    proc_infos.extend(collector::group_collector(matches, &snapshot)?);
+    proc_infos.extend(collector::command_collector(matches, &snapshot));
+    proc_infos.extend(collector::terminal_collector(matches, &snapshot)?);
+    proc_infos.extend(collector::parent_collector(matches, &snapshot)?);
 
-    proc_infos.dedup_by(|a, b| a.borrow().pid == b.borrow().pid);
+    sorting::sort(&mut proc_infos, matches)?;
 
-    sorting::sort(&mut proc_infos, &matches);
+    proc_infos.dedup_by(|a, b| a.borrow().pid == b.borrow().pid);
 
-    let arg_formats = collect_format(&matches);
-    let Ok(arg_formats) = arg_formats else {
-        return Err(arg_formats.err().unwrap());
+    let compact = matches.get_flag("compact-forest");
+    let proc_infos = if matches.get_flag("forest") || compact {
+        forest::forest(proc_infos, compact)
+    } else {
+        proc_infos
+            .into_iter()
+            .map(|it| (it, forest::Tree::default()))
+            .collect()
     };
 
+    let thread_mode = threads::ThreadMode::from_matches(matches);
+    let entries = threads::expand(proc_infos, thread_mode);
+
+    let arg_formats = collect_format(matches)?;
+
     // Collect codes with order
     let codes = if arg_formats.is_empty() {
-        default_codes()
+        thread_mode.default_codes(format_codes(matches).unwrap_or_else(default_codes))
     } else {
         arg_formats.iter().map(|it| it.key().to_owned()).collect()
     };
 
+    let codes = if matches.get_flag("context") {
+        std::iter::once("label".to_string()).chain(codes).collect()
+    } else {
+        codes
+    };
+
+    let date_format = matches.get_one::<String>("date-format").map(String::as_str);
+    if let Some(date_format) = date_format.filter(|it| !uu_w::clock::is_valid(it)) {
+        return Err(USimpleError::new(
+            1,
+            format!("invalid date format '{date_format}'"),
+        ));
+    }
+
     // Collect pickers ordered by codes
-    let pickers = picker::collect_pickers(&codes);
+    let pickers = picker::collect_pickers(&codes, date_format)?;
+
+    // BSD `e`, the environment follows the command line
+    let show_environ = matches.get_flag("bsd_environ");
 
     // Constructing table
     let mut rows = Vec::new();
-    for proc in proc_infos {
-        let picked = pickers
-            .iter()
-            .map(|picker| picker(Rc::unwrap_or_clone(proc.clone())));
-        rows.push(Row::from_iter(picked));
+    for entry in entries {
+        let picked = pickers.iter().zip(&codes).map(|(picker, code)| {
+            if threads::is_blank(&entry, thread_mode, code) {
+                return "-".into();
+            }
+            let mut value = picker(Rc::unwrap_or_clone(entry.info.clone()));
+            if show_environ && ["args", "cmd", "command"].contains(&code.as_str()) {
+                if let Some(environ) = picker::environment(&entry.info).filter(|it| !it.is_empty())
+                {
+                    value = format!("{value} {environ}");
+                }
+            }
+            if forest::COMMAND_CODES.contains(&code.as_str()) {
+                entry.tree.decorate(&value)
+            } else {
+                value
+            }
+        });
+        let key = (entry.info.borrow().pid, entry.is_thread);
+        rows.push((key, picked.collect()));
     }
 
     // Apply header mapping
+    let default_mapping = default_mapping();
     let code_mapping = if arg_formats.is_empty() {
-        let default_mapping = default_mapping();
-        default_codes();
         codes
             .into_iter()
             .map(|code| (code.clone(), default_mapping[&code].to_string()))
             .collect::<Vec<_>>()
     } else {
-        collect_code_mapping(&arg_formats)
+        let mut code_mapping = collect_code_mapping(&arg_formats);
+        if matches.get_flag("context") {
+            code_mapping.insert(0, ("label".into(), default_mapping["label"].clone()));
+        }
+        code_mapping
     };
 
     let header = code_mapping
-        .iter()
+        .into_iter()
         .map(|(_, header)| header)
-        .map(Into::into)
         .collect::<Vec<String>>();
 
-    // Apply header
-    let mut table = Table::from_iter([Row::from_iter(header)]);
-    table.set_format(*FORMAT_CLEAN);
-    table.extend(rows);
+    Ok((header, rows))
+}
+
+/// Width of the output lines, `None` when unlimited.
+///
+/// An explicit width, with `--cols` or `COLUMNS`, takes precedence over the width of the
+/// terminal. `-w` widens it to at least 132 columns and `-ww` removes the limit.
+fn output_width(matches: &ArgMatches) -> Option<usize> {
+    if matches.get_count("wide") >= 2 {
+        return None;
+    }
+
+    let width = matches
+        .get_one::<usize>("cols")
+        .copied()
+        .or_else(|| {
+            std::env::var("COLUMNS")
+                .ok()
+                .and_then(|it| it.parse().ok())
+                .filter(|it| *it > 0)
+        })
+        .or_else(|| terminal_size::terminal_size().map(|(width, _)| width.0 as usize))?;
+
+    if matches.get_count("wide") == 1 {
+        Some(width.max(132))
+    } else {
+        Some(width)
+    }
+}
+
+/// How often the header line is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Headers {
+    Never,
+    Once,
+    /// Once per screenful when writing to a terminal
+    Repeat,
+}
+
+impl Headers {
+    fn from_matches(matches: &ArgMatches) -> Self {
+        if matches.get_flag("no-headers") {
+            Self::Never
+        } else if matches.get_flag("headers") {
+            Self::Repeat
+        } else {
+            Self::Once
+        }
+    }
+}
+
+fn print_table(
+    layout: &mut layout::Layout,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    headers: Headers,
+    width: Option<usize>,
+) {
+    // The widths come from the values of all the rows, so that they stay aligned with the header
+    layout.fit(&header, &rows);
+    let lines = rows.iter().map(|row| layout.format(row));
+    let truncate = |line: &str| match width {
+        Some(width) => line.chars().take(width).collect(),
+        None => line.to_string(),
+    };
+    let header = truncate(&layout.format(&header));
 
-    print!("{table}");
+    let height = terminal_size::terminal_size().map_or(0, |(_, height)| height.0 as usize);
+    let mut output = String::new();
+    for (i, line) in lines.enumerate() {
+        let repeat = headers == Headers::Repeat && height > 1 && i % (height - 1) == 0;
+        if (i == 0 && headers != Headers::Never) || repeat {
+            output.push_str(&header);
+            output.push('\n');
+        }
+        output.push_str(&truncate(&line));
+        output.push('\n');
+    }
+    if output.is_empty() && headers != Headers::Never {
+        output.push_str(&header);
+        output.push('\n');
+    }
 
-    Ok(())
+    print!("{output}");
+}
+
+/// BSD style options and their long equivalents, options taking a value consume the next
+/// argument.
+const BSD_OPTIONS: [(char, &str, bool); 9] = [
+    ('a', "--bsd-all", false),
+    ('e', "--bsd-environ", false),
+    ('x', "--bsd-no-tty", false),
+    ('u', "--bsd-user-format", false),
+    ('j', "--bsd-job-format", false),
+    ('l', "--bsd-long-format", false),
+    ('f', "--forest", false),
+    ('w', "-w", false),
+    ('k', "--sort", true),
+];
+
+/// Translates BSD style options, which are given without a leading dash (e.g. `ps f`), into
+/// their long equivalents.
+fn translate_bsd_options(args: impl uucore::Args) -> Vec<OsString> {
+    let command = uu_app();
+    let takes_value = |arg: &str| {
+        command.get_arguments().any(|it| {
+            it.get_action().takes_values()
+                && (arg
+                    .strip_prefix("--")
+                    .is_some_and(|long| it.get_long() == Some(long))
+                    || arg.strip_prefix('-').and_then(|s| s.chars().last()) == it.get_short())
+        })
+    };
+    let bsd_option = |c: char| BSD_OPTIONS.iter().find(|(short, _, _)| *short == c);
+
+    let mut result: Vec<OsString> = Vec::new();
+    let mut expects_value = false;
+    let mut bsd_value_for: Option<&str> = None;
+    for (i, arg) in args.enumerate() {
+        if let Some(long) = bsd_value_for.take() {
+            let mut option = OsString::from(format!("{long}="));
+            option.push(arg);
+            result.push(option);
+            continue;
+        }
+
+        let bsd = arg.to_str().filter(|s| {
+            i > 0 && !expects_value && !s.is_empty() && s.chars().all(|c| bsd_option(c).is_some())
+        });
+        expects_value = arg
+            .to_str()
+            .is_some_and(|s| s.starts_with('-') && !s.contains('=') && takes_value(s));
+
+        let Some(bsd) = bsd else {
+            result.push(arg);
+            continue;
+        };
+        for c in bsd.chars() {
+            let (_, long, takes_value) = bsd_option(c).unwrap();
+            if *takes_value {
+                bsd_value_for = Some(long);
+            } else {
+                result.push(long.into());
+            }
+        }
+    }
+    // A trailing option without its value, let clap report it
+    if let Some(long) = bsd_value_for {
+        result.push(long.into());
+    }
+
+    result
+}
+
+/// Codes of the predefined output formats, `None` when no option selecting a format is given.
+fn format_codes(matches: &ArgMatches) -> Option<Vec<String>> {
+    let full = if matches.get_flag("F") {
+        Some(true)
+    } else if matches.get_flag("f") {
+        Some(false)
+    } else {
+        None
+    };
+
+    if matches.get_flag("l") {
+        Some(long_codes(full))
+    } else if let Some(extra) = full {
+        Some(full_codes(extra))
+    } else if matches.get_flag("bsd_user_format") {
+        Some(bsd_user_codes())
+    } else if matches.get_flag("bsd_job_format") {
+        Some(bsd_job_codes())
+    } else if matches.get_flag("bsd_long_format") {
+        Some(bsd_long_codes())
+    } else if matches.get_flag("bsd_all") || matches.get_flag("bsd_no_tty") {
+        Some(bsd_codes())
+    } else {
+        None
+    }
 }
 
 fn collect_format(
@@ -115,6 +387,16 @@ fn collect_format(
     Ok(collect)
 }
 
+/// Shorter intervals than a tenth of a second are raised to it, like `watch` does.
+fn parse_watch_interval(input: &str) -> Result<Duration, String> {
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| !seconds.is_nan())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds.max(0.1)).ok())
+        .ok_or_else(|| format!("invalid interval '{input}'"))
+}
+
 #[allow(clippy::cognitive_complexity)]
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
@@ -164,6 +446,175 @@ pub fn uu_app() -> Command {
             //     .help("processes without controlling ttys")
             //     .allow_hyphen_values(true),
         ])
+        .args([
+            Arg::new("user")
+                .short('u')
+                .long("user")
+                .value_name("userlist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("effective user id or name"),
+            Arg::new("User")
+                .short('U')
+                .long("User")
+                .value_name("userlist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("real user id or name"),
+            Arg::new("group")
+                .short('g')
+                .long("group")
+                .value_name("grplist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("session or effective group name"),
+            Arg::new("Group")
+                .short('G')
+                .long("Group")
+                .value_name("grplist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("real group id or name"),
+            Arg::new("C")
+                .short('C')
+                .value_name("cmdlist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("command name"),
+            Arg::new("tty")
+                .short('t')
+                .long("tty")
+                .value_name("ttylist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("terminal"),
+            Arg::new("ppid")
+                .long("ppid")
+                .value_name("pidlist")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .help("parent process id"),
+        ])
+        .args([
+            Arg::new("f")
+                .short('f')
+                .help("full-format listing")
+                .action(ArgAction::SetTrue),
+            Arg::new("F")
+                .short('F')
+                .help("extra full-format listing")
+                .action(ArgAction::SetTrue),
+            Arg::new("l")
+                .short('l')
+                .help("long format")
+                .action(ArgAction::SetTrue),
+        ])
+        .args([
+            Arg::new("L")
+                .short('L')
+                .help("show threads, with LWP and NLWP columns")
+                .action(ArgAction::SetTrue),
+            Arg::new("T")
+                .short('T')
+                .help("show threads, with SPID column")
+                .action(ArgAction::SetTrue),
+            Arg::new("m")
+                .short('m')
+                .help("show threads after processes")
+                .action(ArgAction::SetTrue),
+        ])
+        // BSD options, given without a dash and translated by `translate_bsd_options`
+        .args([
+            Arg::new("bsd_all")
+                .long("bsd-all")
+                .hide(true)
+                .action(ArgAction::SetTrue),
+            Arg::new("bsd_no_tty")
+                .long("bsd-no-tty")
+                .hide(true)
+                .action(ArgAction::SetTrue),
+            Arg::new("bsd_environ")
+                .long("bsd-environ")
+                .hide(true)
+                .action(ArgAction::SetTrue),
+            Arg::new("bsd_user_format")
+                .long("bsd-user-format")
+                .hide(true)
+                .overrides_with_all(["bsd_job_format", "bsd_long_format"])
+                .action(ArgAction::SetTrue),
+            Arg::new("bsd_job_format")
+                .long("bsd-job-format")
+                .hide(true)
+                .overrides_with_all(["bsd_user_format", "bsd_long_format"])
+                .action(ArgAction::SetTrue),
+            Arg::new("bsd_long_format")
+                .long("bsd-long-format")
+                .hide(true)
+                .overrides_with_all(["bsd_user_format", "bsd_job_format"])
+                .action(ArgAction::SetTrue),
+        ])
+        .arg(
+            Arg::new("wide")
+                .short('w')
+                .help("wide output, unlimited width when given twice")
+                .action(ArgAction::Count),
+        )
+        .arg(
+            Arg::new("cols")
+                .long("cols")
+                .visible_aliases(["columns", "width"])
+                .value_name("num")
+                .value_parser(clap::value_parser!(usize))
+                .help("set screen width"),
+        )
+        .arg(
+            Arg::new("context")
+                .short('Z')
+                .long("context")
+                .help("add a column of security data")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("forest")
+                .long("forest")
+                .help("ASCII art process tree")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("compact-forest")
+                .long("compact-forest")
+                .help("process tree collapsing identical sibling subtrees, as pstree does")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("spec")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .allow_hyphen_values(true)
+                .value_parser(sorting::parser)
+                .help("specify sort order"),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .value_name("interval")
+                .value_parser(parse_watch_interval)
+                .help("rerun every interval seconds, printing only the changed rows"),
+        )
+        .args([
+            Arg::new("no-headers")
+                .long("no-headers")
+                .visible_alias("no-heading")
+                .action(ArgAction::SetTrue)
+                .help("do not print header at all"),
+            Arg::new("headers")
+                .long("headers")
+                .action(ArgAction::SetTrue)
+                .overrides_with("no-headers")
+                .help("repeat header lines, one per page"),
+        ])
         .arg(
             Arg::new("format")
                 .short('o')
@@ -173,18 +624,14 @@ pub fn uu_app() -> Command {
                 .value_parser(parser)
                 .help("user-defined format"),
         )
+        .arg(
+            Arg::new("date-format")
+                .long("date-format")
+                .value_name("FORMAT")
+                .help("strftime(3) format of the start and lstart columns, in the TZ time zone"),
+        )
     // .args([
-    //     Arg::new("command").short('c').help("command name"),
-    //     Arg::new("GID")
-    //         .short('G')
-    //         .long("Group")
-    //         .help("real group id or name"),
-    //     Arg::new("group")
-    //         .short('g')
-    //         .long("group")
-    //         .help("session or effective group name"),
     //     Arg::new("PID").short('p').long("pid").help("process id"),
-    //     Arg::new("pPID").long("ppid").help("parent process id"),
     //     Arg::new("qPID")
     //         .short('q')
     //         .long("quick-pid")
@@ -193,14 +640,5 @@ pub fn uu_app() -> Command {
     //         .short('s')
     //         .long("sid")
     //         .help("session id"),
-    //     Arg::new("t").short('t').long("tty").help("terminal"),
-    //     Arg::new("eUID")
-    //         .short('u')
-    //         .long("user")
-    //         .help("effective user id or name"),
-    //     Arg::new("rUID")
-    //         .short('U')
-    //         .long("User")
-    //         .help("real user id or name"),
     // ])
 }
@@ -3,16 +3,179 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+use crate::mapping::default_mapping;
+use crate::picker::collect_pickers;
 use clap::ArgMatches;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 use uu_pgrep::process::ProcessInformation;
+use uucore::error::UResult;
 
-// TODO: Implementing sorting flags.
-pub(crate) fn sort(input: &mut [Rc<RefCell<ProcessInformation>>], _matches: &ArgMatches) {
-    sort_by_pid(input);
+/// A single key of a `--sort` specification such as `-%cpu` or `+pid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortKey {
+    pub code: String,
+    pub descending: bool,
+}
+
+// clap value parser wrapper
+pub(crate) fn parser(value: &str) -> Result<SortKey, String> {
+    let (code, descending) = if let Some(code) = value.strip_prefix('-') {
+        (code, true)
+    } else {
+        (value.strip_prefix('+').unwrap_or(value), false)
+    };
+
+    if !default_mapping().contains_key(code) {
+        return Err(format!("unknown sort specifier \"{code}\""));
+    }
+
+    Ok(SortKey {
+        code: code.into(),
+        descending,
+    })
+}
+
+pub(crate) fn sort(
+    input: &mut [Rc<RefCell<ProcessInformation>>],
+    matches: &ArgMatches,
+) -> UResult<()> {
+    let keys = matches
+        .get_many::<SortKey>("sort")
+        .unwrap_or_default()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if keys.is_empty() {
+        sort_by_pid(input);
+        Ok(())
+    } else {
+        sort_by_keys(input, &keys)
+    }
 }
 
 /// Sort by pid. (Default)
 fn sort_by_pid(input: &mut [Rc<RefCell<ProcessInformation>>]) {
-    input.sort_by(|a, b| a.borrow().pid.cmp(&b.borrow().pid));
+    input.sort_by_key(|it| it.borrow().pid);
+}
+
+/// Sort by the formatted values of the given keys, the first key has the highest priority.
+fn sort_by_keys(input: &mut [Rc<RefCell<ProcessInformation>>], keys: &[SortKey]) -> UResult<()> {
+    let codes = keys.iter().map(|key| key.code.clone()).collect::<Vec<_>>();
+    let pickers = collect_pickers(&codes, None)?;
+
+    // Keep the pid order for processes with equal keys
+    sort_by_pid(input);
+
+    let mut decorated = input
+        .iter()
+        .map(|proc| {
+            let values = pickers
+                .iter()
+                .map(|picker| picker(Rc::unwrap_or_clone(proc.clone())))
+                .collect::<Vec<_>>();
+            (values, proc.clone())
+        })
+        .collect::<Vec<_>>();
+
+    decorated.sort_by(|(a, _), (b, _)| {
+        keys.iter()
+            .zip(a.iter().zip(b))
+            .map(|(key, (a, b))| {
+                let ordering = compare_values(a, b);
+                if key.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    for (slot, (_, proc)) in input.iter_mut().zip(decorated) {
+        *slot = proc;
+    }
+    Ok(())
+}
+
+/// Numbers and durations are compared by value, everything else as text.
+fn compare_values(a: &str, b: &str) -> Ordering {
+    if let (Ok(a), Ok(b)) = (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        return a.total_cmp(&b);
+    }
+    match (parse_duration(a.trim()), parse_duration(b.trim())) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Seconds of a duration formatted as `[[dd-]hh:]mm:ss`, as in the `etime` and `time` columns.
+fn parse_duration(value: &str) -> Option<u64> {
+    let (days, clock) = match value.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, value),
+    };
+    let parts = clock
+        .split(':')
+        .map(|part| {
+            part.bytes()
+                .all(|it| it.is_ascii_digit())
+                .then(|| part.parse::<u64>().ok())
+                .flatten()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let seconds = match parts[..] {
+        [minutes, seconds] if days == 0 => minutes * 60 + seconds,
+        [hours, minutes, seconds] => (hours * 60 + minutes) * 60 + seconds,
+        _ => return None,
+    };
+    Some(days * 24 * 3600 + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort_key() {
+        assert_eq!(
+            parser("-%cpu").unwrap(),
+            SortKey {
+                code: "%cpu".into(),
+                descending: true
+            }
+        );
+        assert_eq!(
+            parser("+pid").unwrap(),
+            SortKey {
+                code: "pid".into(),
+                descending: false
+            }
+        );
+        assert!(!parser("ppid").unwrap().descending);
+        assert!(parser("-unknown").is_err());
+    }
+
+    #[test]
+    fn test_compare_values() {
+        assert_eq!(compare_values("9", "10"), Ordering::Less);
+        assert_eq!(compare_values("1.5", "1.25"), Ordering::Greater);
+        assert_eq!(compare_values("bash", "apt"), Ordering::Greater);
+        assert_eq!(compare_values("00:00:01", "00:00:10"), Ordering::Less);
+        // A longer elapsed time isn't always the greater text
+        assert_eq!(compare_values("59:59", "01:00:00"), Ordering::Less);
+        assert_eq!(compare_values("2-00:00:00", "23:59:59"), Ordering::Greater);
+        assert_eq!(compare_values("-", "00:01"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("00:05"), Some(5));
+        assert_eq!(parse_duration("01:02:03"), Some(3723));
+        assert_eq!(parse_duration("3-01:02:03"), Some(3 * 86400 + 3723));
+        assert_eq!(parse_duration("3-02:03"), None);
+        assert_eq!(parse_duration("bash"), None);
+        assert_eq!(parse_duration("+1:02"), None);
+        assert_eq!(parse_duration("-"), None);
+    }
 }
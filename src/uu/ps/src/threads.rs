@@ -0,0 +1,125 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::forest::Tree;
+use clap::ArgMatches;
+use std::{cell::RefCell, rc::Rc};
+use uu_pgrep::process::ProcessInformation;
+
+/// Format codes identifying a single thread.
+pub(crate) const THREAD_CODES: [&str; 3] = ["lwp", "spid", "tid"];
+
+/// Format codes left empty on the thread lines of `-m`, as they describe the whole process.
+const PROCESS_CODES: [&str; 12] = [
+    "pid", "tgid", "tname", "tt", "tty", "args", "cmd", "command", "comm", "ucmd", "ucomm", "fname",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ThreadMode {
+    /// One line per process
+    Off,
+    /// One line per thread, with `LWP` and `NLWP` columns (`-L`)
+    Lwp,
+    /// One line per thread, with a `SPID` column (`-T`)
+    Spid,
+    /// One line per process followed by a line per thread (`-m`)
+    After,
+}
+
+impl ThreadMode {
+    pub(crate) fn from_matches(matches: &ArgMatches) -> Self {
+        if matches.get_flag("L") {
+            Self::Lwp
+        } else if matches.get_flag("T") {
+            Self::Spid
+        } else if matches.get_flag("m") {
+            Self::After
+        } else {
+            Self::Off
+        }
+    }
+
+    /// Default codes when no format is given.
+    pub(crate) fn default_codes(self, codes: Vec<String>) -> Vec<String> {
+        let thread_codes: &[&str] = match self {
+            Self::Lwp => &["lwp", "nlwp"],
+            Self::Spid => &["spid"],
+            Self::Off | Self::After => return codes,
+        };
+
+        let mut codes = codes;
+        // After the parent when shown, like the full formats of procps do
+        let index = ["ppid", "pid"]
+            .iter()
+            .find_map(|code| codes.iter().position(|it| it == code))
+            .map_or(0, |i| i + 1);
+        codes.splice(index..index, thread_codes.iter().map(|it| it.to_string()));
+        codes
+    }
+}
+
+/// A line of the output, either describing a process or one of its threads.
+pub(crate) struct Entry {
+    pub(crate) info: Rc<RefCell<ProcessInformation>>,
+    pub(crate) tree: Tree,
+    pub(crate) is_thread: bool,
+}
+
+/// Expands the selected processes into their threads according to the mode.
+pub(crate) fn expand(
+    input: Vec<(Rc<RefCell<ProcessInformation>>, Tree)>,
+    mode: ThreadMode,
+) -> Vec<Entry> {
+    let mut result = Vec::new();
+
+    for (info, tree) in input {
+        if mode == ThreadMode::Off {
+            result.push(Entry {
+                info,
+                tree,
+                is_thread: false,
+            });
+            continue;
+        }
+
+        let threads = info.borrow_mut().threads();
+        if mode == ThreadMode::After {
+            result.push(Entry {
+                info,
+                tree: tree.clone(),
+                is_thread: false,
+            });
+        } else if threads.is_empty() {
+            // The process exited in between, keep what was already read about it
+            result.push(Entry {
+                info,
+                tree,
+                is_thread: true,
+            });
+            continue;
+        }
+
+        result.extend(threads.into_iter().map(|thread| Entry {
+            info: Rc::new(RefCell::new(thread)),
+            tree: tree.clone(),
+            is_thread: true,
+        }));
+    }
+
+    result
+}
+
+/// Whether the value of the code is replaced by `-` for the entry.
+pub(crate) fn is_blank(entry: &Entry, mode: ThreadMode, code: &str) -> bool {
+    if mode != ThreadMode::After {
+        return false;
+    }
+
+    if entry.is_thread {
+        PROCESS_CODES.contains(&code)
+    } else {
+        THREAD_CODES.contains(&code)
+    }
+}
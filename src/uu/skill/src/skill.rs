@@ -0,0 +1,199 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+// spell-checker:ignore (syscalls) tgkill
+
+use clap::{arg, crate_version, Command};
+#[cfg(unix)]
+use std::io::Error;
+use uu_snice::{collect_pids, expression_args, targets, SignalDisplay};
+#[cfg(unix)]
+use uucore::{
+    error::{set_exit_code, FromIo},
+    show,
+    signals::{signal_by_name_or_value, ALL_SIGNALS},
+};
+use uucore::{
+    error::{UResult, USimpleError},
+    format_usage, help_about, help_section, help_usage,
+};
+
+const ABOUT: &str = help_about!("skill.md");
+const USAGE: &str = help_usage!("skill.md");
+const AFTER_HELP: &str = help_section!("after help", "skill.md");
+
+/// Exit code when no process matches the expressions.
+const EXIT_NO_MATCH: i32 = 1;
+/// Exit code when some of the matched processes couldn't be signaled.
+#[cfg(unix)]
+const EXIT_SOME_FAILED: i32 = 2;
+
+#[uucore::main]
+pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    #[cfg(unix)]
+    let mut args = args.collect_ignore();
+    #[cfg(not(unix))]
+    let args = args.collect_ignore();
+    #[cfg(unix)]
+    handle_obsolete(&mut args);
+
+    let matches = uu_app().try_get_matches_from(&args)?;
+
+    let display = if matches.get_flag("table") {
+        Some(SignalDisplay::Table)
+    } else if matches.get_flag("list") {
+        Some(SignalDisplay::List)
+    } else {
+        None
+    };
+    #[cfg(unix)]
+    if let Some(display) = display {
+        println!("{}", display.display(&ALL_SIGNALS));
+        return Ok(());
+    }
+    #[cfg(not(unix))]
+    if display.is_some() {
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    let signal = {
+        let name = matches.get_one::<String>("signal").unwrap();
+        signal_by_name_or_value(name)
+            .ok_or_else(|| USimpleError::new(1, format!("unknown signal name {name}")))?
+            as i32
+    };
+
+    let Some(targets) = targets(&matches) else {
+        return Err(USimpleError::new(1, "no process selection criteria"));
+    };
+    let pids = collect_pids(&targets);
+    if pids.is_empty() {
+        return Err(USimpleError::new(
+            EXIT_NO_MATCH,
+            "no process matched the selection criteria",
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        let verbose = matches.get_flag("verbose");
+        for pid in pids {
+            if matches.get_flag("threads") {
+                signal_threads(pid, signal, verbose);
+            } else if let Err(e) = send(pid, None, signal) {
+                show!(Error::from_raw_os_error(e as i32)
+                    .map_err_context(|| format!("killing pid {pid} failed")));
+                set_exit_code(EXIT_SOME_FAILED);
+            } else if verbose {
+                println!("killed pid {pid}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the signal given as `-SIGNAL` with an option clap can parse.
+#[cfg(unix)]
+fn handle_obsolete(args: &mut [String]) {
+    // The signal can only be the first argument
+    if args.len() > 2 {
+        if let Some(signal) = args[1].strip_prefix('-') {
+            if signal_by_name_or_value(signal).is_some() {
+                args[1] = format!("--signal={signal}");
+            }
+        }
+    }
+}
+
+/// Sends `signal` to each thread of `pid` on its own, so that the handler runs in that thread
+/// rather than in whichever thread the kernel picks for the process.
+#[cfg(unix)]
+fn signal_threads(pid: u32, signal: i32, verbose: bool) {
+    use std::path::PathBuf;
+    use uu_pgrep::process::ProcessInformation;
+
+    let path = PathBuf::from(format!("/proc/{pid}"));
+    let Ok(mut process) = ProcessInformation::try_new(path) else {
+        show!(USimpleError::new(
+            EXIT_SOME_FAILED,
+            format!("killing pid {pid} failed: No such process"),
+        ));
+        return;
+    };
+
+    for tid in process.thread_ids().iter() {
+        let tid = *tid as u32;
+        if let Err(e) = send(pid, Some(tid), signal) {
+            show!(Error::from_raw_os_error(e as i32)
+                .map_err_context(|| format!("killing thread {tid} of pid {pid} failed")));
+            set_exit_code(EXIT_SOME_FAILED);
+        } else if verbose {
+            println!("killed thread {tid} of pid {pid}");
+        }
+    }
+}
+
+/// Sends `signal` to `pid`, or to its thread `tid` only through tgkill(2) when given.
+#[cfg(unix)]
+fn send(pid: u32, tid: Option<u32>, signal: i32) -> nix::Result<()> {
+    use nix::errno::Errno;
+
+    match tid {
+        #[cfg(target_os = "linux")]
+        Some(tid) => {
+            // SAFETY: tgkill has no preconditions, an invalid ID only fails with ESRCH
+            let result = unsafe { libc::syscall(libc::SYS_tgkill, pid, tid, signal) };
+            Errno::result(result).map(drop)
+        }
+        #[cfg(not(target_os = "linux"))]
+        Some(_) => Err(Errno::ENOSYS),
+        // SAFETY: kill has no preconditions, the signal 0 only checks that the process exists
+        None => Errno::result(unsafe { libc::kill(pid as libc::pid_t, signal) }).map(drop),
+    }
+}
+
+#[allow(clippy::cognitive_complexity)]
+pub fn uu_app() -> Command {
+    Command::new(uucore::util_name())
+        .version(crate_version!())
+        .about(ABOUT)
+        .override_usage(format_usage(USAGE))
+        .after_help(AFTER_HELP)
+        .infer_long_args(true)
+        .arg_required_else_help(true)
+        .args([
+            // arg!(-<signal>           "signal to send (either number or name)"),
+            arg!(-s --signal <signal>       "signal to send (either number or name)")
+                .default_value("TERM"),
+            arg!(-l --list                  "list all signal names"),
+            arg!(-L --table                 "list all signal names in a nice table"),
+            arg!(-v --verbose               "explain what is being done"),
+            arg!(--threads                  "signal each thread of the processes on its own"),
+        ])
+        .args(expression_args())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_obsolete() {
+        let mut args = ["skill", "-KILL", "-p", "1"].map(String::from);
+        handle_obsolete(&mut args);
+        assert_eq!(args[1], "--signal=KILL");
+
+        let mut args = ["skill", "-9", "-p", "1"].map(String::from);
+        handle_obsolete(&mut args);
+        assert_eq!(args[1], "--signal=9");
+
+        // Options aren't signals
+        let mut args = ["skill", "-v", "-p", "1"].map(String::from);
+        handle_obsolete(&mut args);
+        assert_eq!(args[1], "-v");
+    }
+}
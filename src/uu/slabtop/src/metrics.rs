@@ -0,0 +1,147 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::parse::SlabInfo;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use uucore_procps::prometheus::{escape, header};
+
+/// Metrics exported for each cache: name, help text and the `/proc/slabinfo` field.
+const METRICS: [(&str, &str, &str); 7] = [
+    (
+        "slab_active_objects",
+        "Number of objects in use in the slab cache.",
+        "active_objs",
+    ),
+    (
+        "slab_objects",
+        "Number of allocated objects in the slab cache.",
+        "num_objs",
+    ),
+    (
+        "slab_object_size_bytes",
+        "Size of the objects of the slab cache.",
+        "objsize",
+    ),
+    (
+        "slab_objects_per_slab",
+        "Number of objects in each slab of the slab cache.",
+        "objperslab",
+    ),
+    (
+        "slab_pages_per_slab",
+        "Number of pages of each slab of the slab cache.",
+        "pagesperslab",
+    ),
+    (
+        "slab_active_slabs",
+        "Number of slabs in use in the slab cache.",
+        "active_slabs",
+    ),
+    (
+        "slab_slabs",
+        "Number of allocated slabs in the slab cache.",
+        "num_slabs",
+    ),
+];
+
+/// Renders the slab data in the Prometheus text exposition format.
+pub(crate) fn render(info: &SlabInfo) -> String {
+    let mut output = String::new();
+
+    for (metric, help, field) in METRICS {
+        header(&mut output, metric, "gauge", help);
+        for name in info.names() {
+            if let Some(value) = info.fetch(name, field) {
+                let _ = writeln!(output, "{metric}{{cache=\"{}\"}} {value}", escape(name));
+            }
+        }
+    }
+
+    output
+}
+
+/// Answers a single HTTP request with the metrics, whatever the request is.
+pub(crate) fn serve_once(listener: &TcpListener, body: &str) -> io::Result<()> {
+    let (stream, _) = listener.accept()?;
+
+    // Read the request up to the empty line ending its headers
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && line != "\r\n" && line != "\n" {
+        line.clear();
+    }
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Accepts either an address or only a port, which is then served on the loopback interface.
+///
+/// `/proc/slabinfo` is only readable by root, so listening on other interfaces takes an explicit
+/// address such as `0.0.0.0:9100`.
+pub(crate) fn listen_address(value: &str) -> String {
+    if value.parse::<u16>().is_ok() {
+        format!("127.0.0.1:{value}")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_render() {
+        let test = include_str!("../../../../tests/fixtures/slabtop/data.txt");
+        let output = render(&SlabInfo::parse(test).unwrap());
+
+        assert!(output.contains("# TYPE slab_object_size_bytes gauge\n"));
+        assert!(output.contains("slab_object_size_bytes{cache=\"nf_conntrack_expect\"} 208\n"));
+        assert!(output.contains("slab_active_slabs{cache=\"dmaengine-unmap-2\"} 16389\n"));
+    }
+
+    #[test]
+    fn test_listen_address() {
+        assert_eq!(listen_address("9100"), "127.0.0.1:9100");
+        assert_eq!(listen_address("0.0.0.0:9100"), "0.0.0.0:9100");
+        assert_eq!(listen_address("127.0.0.1:9100"), "127.0.0.1:9100");
+    }
+
+    #[test]
+    fn test_serve_once() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream
+                .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+
+        serve_once(&listener, "slab_slabs{cache=\"x\"} 1\n").unwrap();
+        let response = client.join().unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 24\r\n"));
+        assert!(response.ends_with("\r\n\r\nslab_slabs{cache=\"x\"} 1\n"));
+    }
+}
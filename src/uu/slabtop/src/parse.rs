@@ -10,7 +10,7 @@ use std::{
 };
 
 #[derive(Debug, Default)]
-pub(crate) struct SlabInfo {
+pub struct SlabInfo {
     pub(crate) meta: Vec<String>,
     pub(crate) data: Vec<(String, Vec<u64>)>,
 }
@@ -47,90 +47,43 @@ impl SlabInfo {
         self.data.iter().map(|(k, _)| k).collect()
     }
 
+    /// Sorts the caches on the criterion `by`, in descending order unless `ascending_order`.
+    /// The caches which are equal on it are sorted by name, so that the order is deterministic.
     pub fn sort(mut self, by: char, ascending_order: bool) -> Self {
-        let mut sort = |by_meta: &str| {
-            if let Some(offset) = self.offset(by_meta) {
-                self.data.sort_by(|(_, data1), (_, data2)| {
-                    match (data1.get(offset), data2.get(offset)) {
-                        (Some(v1), Some(v2)) => {
-                            if ascending_order {
-                                v1.cmp(v2)
-                            } else {
-                                v2.cmp(v1)
-                            }
-                        }
-                        _ => Ordering::Equal,
-                    }
-                });
-            }
-        };
-
-        match by {
+        let key = match by {
             // <active_objs>
-            'a' => sort("active_objs"),
+            'a' => SortKey::Field(self.offset("active_objs")),
             // <objperslab>
-            'b' => sort("objperslab"),
+            'b' => SortKey::Field(self.offset("objperslab")),
             // <objsize> Maybe cache size I guess?
             // TODO: Check is <objsize>
-            'c' => sort("objsize"),
+            'c' => SortKey::Field(self.offset("objsize")),
             // <num_slabs>
-            'l' => sort("num_slabs"),
+            'l' => SortKey::Field(self.offset("num_slabs")),
             // <active_slabs>
-            'v' => sort("active_slabs"),
+            'v' => SortKey::Field(self.offset("active_slabs")),
             // name, sort by lexicographical order
-            'n' => self.data.sort_by(|(name1, _), (name2, _)| {
-                if ascending_order {
-                    name1.cmp(name2)
-                } else {
-                    name2.cmp(name1)
-                }
-            }),
+            'n' => SortKey::Name,
             // <pagesperslab>
-            'p' => sort("pagesperslab"),
+            'p' => SortKey::Field(self.offset("pagesperslab")),
             // <objsize>
-            's' => sort("objsize"),
+            's' => SortKey::Field(self.offset("objsize")),
             // sort by cache utilization
-            'u' => {
-                let offset_active_objs = self.offset("active_objs");
-                let offset_num_objs = self.offset("num_objs");
-
-                if let (Some(offset_active_objs), Some(offset_num_objs)) =
-                    (offset_active_objs, offset_num_objs)
-                {
-                    self.data.sort_by(|(_, data1), (_, data2)| {
-                        let cu = |active_jobs: Option<&u64>, num_jobs: Option<&u64>| match (
-                            active_jobs,
-                            num_jobs,
-                        ) {
-                            (Some(active_jobs), Some(num_jobs)) => {
-                                Some((*active_jobs as f64) / (*num_jobs as f64))
-                            }
-                            _ => None,
-                        };
-                        let cu1 = cu(data1.get(offset_active_objs), data1.get(offset_num_objs));
-                        let cu2 = cu(data2.get(offset_active_objs), data2.get(offset_num_objs));
-
-                        if let (Some(cu1), Some(cu2)) = (cu1, cu2) {
-                            let result = if ascending_order {
-                                cu1.partial_cmp(&cu2)
-                            } else {
-                                cu2.partial_cmp(&cu1)
-                            };
-                            match result {
-                                Some(ord) => ord,
-                                None => Ordering::Equal,
-                            }
-                        } else {
-                            Ordering::Equal
-                        }
-                    });
-                }
-            }
-
+            'u' => SortKey::Utilization(self.offset("active_objs"), self.offset("num_objs")),
             // <num_objs>
             // Default branch : `o`
-            _ => sort("num_objs"),
-        }
+            _ => SortKey::Field(self.offset("num_objs")),
+        };
+
+        self.data.sort_by(|cache1, cache2| {
+            let ordering = key.compare(cache1, cache2);
+            if ascending_order {
+                ordering
+            } else {
+                ordering.reverse()
+            }
+            .then_with(|| cache1.0.cmp(&cache2.0))
+        });
 
         self
     }
@@ -259,6 +212,32 @@ impl SlabInfo {
     }
 }
 
+/// Criterion of [`SlabInfo::sort`], the fields being given by their offset in the data.
+enum SortKey {
+    Field(Option<usize>),
+    Name,
+    Utilization(Option<usize>, Option<usize>),
+}
+
+impl SortKey {
+    fn compare(&self, cache1: &(String, Vec<u64>), cache2: &(String, Vec<u64>)) -> Ordering {
+        let field =
+            |data: &[u64], offset: Option<usize>| offset.and_then(|it| data.get(it).copied());
+        match *self {
+            Self::Field(offset) => field(&cache1.1, offset).cmp(&field(&cache2.1, offset)),
+            Self::Name => cache1.0.cmp(&cache2.0),
+            Self::Utilization(active_objs, num_objs) => {
+                let utilization =
+                    |data: &[u64]| match (field(data, active_objs), field(data, num_objs)) {
+                        (Some(active), Some(num)) if num > 0 => active as f64 / num as f64,
+                        _ => 0.0,
+                    };
+                utilization(&cache1.1).total_cmp(&utilization(&cache2.1))
+            }
+        }
+    }
+}
+
 pub(crate) fn parse_version(line: &str) -> Option<String> {
     line.replace(':', " ")
         .split_whitespace()
@@ -356,4 +335,31 @@ mod tests {
             16389
         );
     }
+
+    fn sorted_names(by: char, ascending_order: bool) -> Vec<String> {
+        let content = "slabinfo - version: 2.1\n\
+            # name <active_objs> <num_objs> <objsize> <objperslab> <pagesperslab> : tunables <limit> <batchcount> <sharedfactor> : slabdata <active_slabs> <num_slabs> <sharedavail>\n\
+            zeta 10 20 64 64 1 : tunables 0 0 0 : slabdata 1 1 0\n\
+            alpha 20 20 64 64 1 : tunables 0 0 0 : slabdata 1 1 0\n\
+            mid 5 40 128 32 1 : tunables 0 0 0 : slabdata 2 2 0\n\
+            beta 0 0 64 64 1 : tunables 0 0 0 : slabdata 0 0 0\n";
+        SlabInfo::parse(content)
+            .unwrap()
+            .sort(by, ascending_order)
+            .names()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(sorted_names('o', false), ["mid", "alpha", "zeta", "beta"]);
+        assert_eq!(sorted_names('o', true), ["beta", "alpha", "zeta", "mid"]);
+        // Equal sizes are sorted by name in both directions
+        assert_eq!(sorted_names('s', false), ["mid", "alpha", "beta", "zeta"]);
+        assert_eq!(sorted_names('s', true), ["alpha", "beta", "zeta", "mid"]);
+        assert_eq!(sorted_names('n', true), ["alpha", "beta", "mid", "zeta"]);
+        assert_eq!(sorted_names('u', false), ["alpha", "zeta", "mid", "beta"]);
+    }
 }
@@ -5,13 +5,18 @@
 
 use crate::parse::SlabInfo;
 use clap::{arg, crate_version, ArgAction, Command};
-use uucore::{error::UResult, format_usage, help_about, help_section, help_usage};
+use std::net::TcpListener;
+use uucore::{
+    error::{FromIo, UResult},
+    format_usage, help_about, help_section, help_usage,
+};
 
 const ABOUT: &str = help_about!("slabtop.md");
 const AFTER_HELP: &str = help_section!("after help", "slabtop.md");
 const USAGE: &str = help_usage!("slabtop.md");
 
-mod parse;
+mod metrics;
+pub mod parse;
 
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
@@ -23,7 +28,28 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         .unwrap_or(Some(&'o'))
         .unwrap_or(&'o');
 
-    let slabinfo = SlabInfo::new()?.sort(*sort_flag, false);
+    // Bind first, so that a busy port is reported before anything else
+    let listener = match matches.get_one::<String>("listen") {
+        Some(address) => {
+            let address = metrics::listen_address(address);
+            Some(
+                TcpListener::bind(&address)
+                    .map_err_context(|| format!("cannot listen on {address}"))?,
+            )
+        }
+        None => None,
+    };
+
+    let slabinfo = SlabInfo::new()?.sort(*sort_flag, matches.get_flag("reverse"));
+
+    if let Some(listener) = listener {
+        metrics::serve_once(&listener, &metrics::render(&slabinfo))?;
+        return Ok(());
+    }
+    if matches.get_flag("metrics") {
+        print!("{}", metrics::render(&slabinfo));
+        return Ok(());
+    }
 
     if matches.get_flag("once") {
         output_header(&slabinfo);
@@ -132,6 +158,10 @@ pub fn uu_app() -> Command {
             // arg!(-d --delay <secs>  "delay updates"),
             arg!(-o --once          "only display once, then exit").action(ArgAction::SetTrue),
             arg!(-s --sort  <char>  "specify sort criteria by character (see below)"),
+            arg!(--reverse          "sort in ascending order").action(ArgAction::SetTrue),
+            arg!(--metrics          "print the slab data as Prometheus metrics, then exit")
+                .action(ArgAction::SetTrue),
+            arg!(--listen   <address> "serve the metrics once over HTTP on [address:]port"),
         ])
         .after_help(AFTER_HELP)
 }
@@ -24,7 +24,7 @@ pub(crate) fn users() -> &'static Users {
 }
 
 #[derive(Debug)]
-pub(crate) enum SelectedTarget {
+pub enum SelectedTarget {
     Command(String),
     Pid(u32),
     Tty(Teletype),
@@ -33,7 +33,7 @@ pub(crate) enum SelectedTarget {
 
 #[allow(unused)]
 impl SelectedTarget {
-    pub(crate) fn to_pids(&self) -> Vec<u32> {
+    pub fn to_pids(&self) -> Vec<u32> {
         match self {
             Self::Command(cmd) => Self::from_cmd(cmd),
             Self::Pid(pid) => vec![*pid],
@@ -123,15 +123,10 @@ fn set_priority(pid: u32, prio: &Priority) -> Option<ActionResult> {
         let prio = unsafe { getpriority(PRIO_PROCESS, pid) };
         // prio == -1 might be error.
         if prio == -1 && Errno::last() != Errno::UnknownErrno {
-            // Must clear errno.
-            Errno::clear();
-
-            // I don't know but, just considering it just caused by permission.
+            // The only error left once the arguments are valid is a missing process.
             // https://manpages.debian.org/bookworm/manpages-dev/getpriority.2.en.html#ERRORS
-            return match Errno::last() {
-                Errno::ESRCH => Some(ActionResult::PermissionDenied),
-                _ => None,
-            };
+            Errno::clear();
+            return None;
         }
         prio
     };
@@ -149,8 +144,9 @@ fn set_priority(pid: u32, prio: &Priority) -> Option<ActionResult> {
     // https://manpages.debian.org/bookworm/manpages-dev/setpriority.2.en.html#ERRORS
     if result == -1 {
         match Errno::last() {
-            Errno::ESRCH => Some(ActionResult::PermissionDenied),
-            _ => None,
+            Errno::ESRCH => None,
+            // EPERM or EACCES
+            _ => Some(ActionResult::PermissionDenied),
         }
     } else {
         Some(ActionResult::Success)
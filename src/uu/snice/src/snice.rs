@@ -5,7 +5,8 @@
 
 use std::{collections::HashSet, path::PathBuf, str::FromStr};
 
-use action::{perform_action, process_snapshot, users, ActionResult, SelectedTarget};
+pub use action::SelectedTarget;
+use action::{perform_action, process_snapshot, users, ActionResult};
 use clap::{arg, crate_version, value_parser, Arg, ArgMatches, Command};
 use prettytable::{format::consts::FORMAT_CLEAN, row, Table};
 use priority::Priority;
@@ -14,25 +15,32 @@ use uu_pgrep::process::{ProcessInformation, Teletype};
 #[cfg(target_family = "unix")]
 use uucore::signals::ALL_SIGNALS;
 use uucore::{
-    error::{UResult, USimpleError},
-    format_usage, help_about, help_usage,
+    error::{set_exit_code, UResult, USimpleError},
+    format_usage, help_about, help_section, help_usage, show_error,
 };
 
 const ABOUT: &str = help_about!("snice.md");
 const USAGE: &str = help_usage!("snice.md");
+const AFTER_HELP: &str = help_section!("after help", "snice.md");
+
+/// Exit code when no process matches the expressions.
+const EXIT_NO_MATCH: i32 = 1;
+/// Exit code when the priority of some of the matched processes couldn't be changed.
+const EXIT_SOME_FAILED: i32 = 2;
 
 mod action;
 mod priority;
 
+/// How `-l` and `-L` list the signals, shared with `skill`.
 #[derive(Debug)]
-enum SignalDisplay {
+pub enum SignalDisplay {
     List,
     Table,
 }
 
 #[allow(unused)]
 impl SignalDisplay {
-    fn display(&self, signals: &[&str]) -> String {
+    pub fn display(&self, signals: &[&str]) -> String {
         match self {
             SignalDisplay::List => Self::list(signals),
             SignalDisplay::Table => Self::table(signals),
@@ -98,53 +106,54 @@ impl Settings {
 
         Ok(Self {
             display,
-            expressions: Self::targets(matches),
+            expressions: targets(matches),
             priority: expression,
             verbose: matches.get_flag("verbose"),
         })
     }
+}
 
-    fn targets(matches: &ArgMatches) -> Option<Vec<SelectedTarget>> {
-        let cmd = matches
-            .get_many::<String>("command")
-            .unwrap_or_default()
-            .map(Into::into)
-            .map(SelectedTarget::Command)
-            .collect::<Vec<_>>();
-
-        let pid = matches
-            .get_many::<u32>("pid")
-            .unwrap_or_default()
-            .map(Clone::clone)
-            .map(SelectedTarget::Pid)
-            .collect::<Vec<_>>();
-
-        let tty = matches
-            .get_many::<String>("tty")
-            .unwrap_or_default()
-            .flat_map(|it| Teletype::try_from(it.as_str()))
-            .map(SelectedTarget::Tty)
-            .collect::<Vec<_>>();
-
-        let user = matches
-            .get_many::<String>("user")
-            .unwrap_or_default()
-            .map(Into::into)
-            .map(SelectedTarget::User)
-            .collect::<Vec<_>>();
-
-        let collected = cmd
-            .into_iter()
-            .chain(pid)
-            .chain(tty)
-            .chain(user)
-            .collect::<Vec<_>>();
-
-        if collected.is_empty() {
-            None
-        } else {
-            Some(collected)
-        }
+/// The processes selected by the expression options of [expression_args], `None` without any.
+pub fn targets(matches: &ArgMatches) -> Option<Vec<SelectedTarget>> {
+    let cmd = matches
+        .get_many::<String>("command")
+        .unwrap_or_default()
+        .map(Into::into)
+        .map(SelectedTarget::Command)
+        .collect::<Vec<_>>();
+
+    let pid = matches
+        .get_many::<u32>("pid")
+        .unwrap_or_default()
+        .map(Clone::clone)
+        .map(SelectedTarget::Pid)
+        .collect::<Vec<_>>();
+
+    let tty = matches
+        .get_many::<String>("tty")
+        .unwrap_or_default()
+        .flat_map(|it| Teletype::try_from(it.as_str()))
+        .map(SelectedTarget::Tty)
+        .collect::<Vec<_>>();
+
+    let user = matches
+        .get_many::<String>("user")
+        .unwrap_or_default()
+        .map(Into::into)
+        .map(SelectedTarget::User)
+        .collect::<Vec<_>>();
+
+    let collected = cmd
+        .into_iter()
+        .chain(pid)
+        .chain(tty)
+        .chain(user)
+        .collect::<Vec<_>>();
+
+    if collected.is_empty() {
+        None
+    } else {
+        Some(collected)
     }
 }
 
@@ -178,13 +187,29 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         let results = perform_action(&pids, &settings.priority);
 
         if results.iter().all(|it| it.is_none()) || results.is_empty() {
-            return Err(USimpleError::new(1, "no process selection criteria"));
+            return Err(USimpleError::new(
+                EXIT_NO_MATCH,
+                "no process matched the selection criteria",
+            ));
         }
 
         if settings.verbose {
             let output = construct_verbose_result(&pids, &results).trim().to_owned();
             println!("{output}");
         }
+
+        let failed = failed_pids(&pids, &results);
+        for pid in &failed {
+            show_error!("failed to set the priority of process {pid}: Permission denied");
+        }
+        if !failed.is_empty() {
+            show_error!(
+                "{} of {} processes could not be changed",
+                failed.len(),
+                results.iter().flatten().count()
+            );
+            set_exit_code(EXIT_SOME_FAILED);
+        }
     }
 
     Ok(())
@@ -228,8 +253,17 @@ fn construct_verbose_result(pids: &[u32], action_results: &[Option<ActionResult>
     table.to_string()
 }
 
+/// Pids of the processes which exist but whose priority couldn't be changed.
+fn failed_pids(pids: &[u32], action_results: &[Option<ActionResult>]) -> Vec<u32> {
+    pids.iter()
+        .zip(action_results)
+        .filter(|(_, it)| matches!(it, Some(ActionResult::PermissionDenied)))
+        .map(|(pid, _)| *pid)
+        .collect()
+}
+
 /// Map and sort `SelectedTarget` to pids.
-fn collect_pids(targets: &[SelectedTarget]) -> Vec<u32> {
+pub fn collect_pids(targets: &[SelectedTarget]) -> Vec<u32> {
     let collected = targets
         .iter()
         .flat_map(SelectedTarget::to_pids)
@@ -246,6 +280,7 @@ pub fn uu_app() -> Command {
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
+        .after_help(AFTER_HELP)
         .infer_long_args(true)
         .arg_required_else_help(true)
         .arg(Arg::new("priority"))
@@ -258,13 +293,19 @@ pub fn uu_app() -> Command {
             // arg!(-n --"no-action"   "do not actually kill processes; just print what would happen"),
             arg!(-v --verbose               "explain what is being done"),
             // arg!(-w --warnings      "enable warnings (not implemented)"),
-            // Expressions
-            arg!(-c --command   <command>   ...   "expression is a command name"),
-            arg!(-p --pid       <pid>       ...   "expression is a process id number")
-                .value_parser(value_parser!(u32)),
-            arg!(-t --tty       <tty>       ...   "expression is a terminal"),
-            arg!(-u --user      <username>  ...   "expression is a username"),
         ])
+        .args(expression_args())
+}
+
+/// The options selecting the processes, shared with `skill`.
+pub fn expression_args() -> [Arg; 4] {
+    [
+        arg!(-c --command   <command>   ...   "expression is a command name"),
+        arg!(-p --pid       <pid>       ...   "expression is a process id number")
+            .value_parser(value_parser!(u32)),
+        arg!(-t --tty       <tty>       ...   "expression is a terminal"),
+        arg!(-u --user      <username>  ...   "expression is a username"),
+    ]
 }
 
 #[cfg(test)]
@@ -275,6 +316,18 @@ mod tests {
         "USR2", "PIPE", "ALRM", "TERM", "STKFLT", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU",
         "URG", "XCPU", "XFSZ", "VTALRM", "PROF", "WINCH", "POLL", "PWR", "SYS",
     ];
+    #[test]
+    fn test_failed_pids() {
+        let results = [
+            Some(ActionResult::Success),
+            None,
+            Some(ActionResult::PermissionDenied),
+            Some(ActionResult::PermissionDenied),
+        ];
+        assert_eq!(failed_pids(&[1, 2, 3, 4], &results), [3, 4]);
+        assert!(failed_pids(&[1], &[Some(ActionResult::Success)]).is_empty());
+    }
+
     #[test]
     fn test_signal_display_list() {
         let output = SignalDisplay::list(&ALL_SIGNALS);
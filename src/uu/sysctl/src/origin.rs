@@ -0,0 +1,209 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Where the values of the variables are configured, for `--origin`.
+//!
+//! The files are read as `sysctl --system` applies them at boot: the `*.conf` files of the
+//! sysctl.d directories sorted by name, a file masking the ones of the same name in the later
+//! directories, then `/etc/sysctl.conf`. The last assignment of a variable wins.
+
+use crate::linux::parse_assignment;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFIG_DIRS: [&str; 5] = [
+    "/etc/sysctl.d",
+    "/run/sysctl.d",
+    "/usr/local/lib/sysctl.d",
+    "/usr/lib/sysctl.d",
+    "/lib/sysctl.d",
+];
+
+const CONFIG_FILE: &str = "/etc/sysctl.conf";
+
+/// An assignment in a configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub value: String,
+    pub file: PathBuf,
+    /// Starting at 1
+    pub line: usize,
+}
+
+/// The assignments of the configuration files, in the order they are applied.
+#[derive(Debug, Default)]
+pub struct Configuration {
+    /// The variables may be globs, such as `net.ipv4.conf.*.rp_filter`
+    settings: Vec<(String, Setting)>,
+}
+
+impl Configuration {
+    /// The configuration of the system, the unreadable files being left out.
+    pub fn load() -> Self {
+        let dirs = CONFIG_DIRS.map(Path::new);
+        Self::load_from(&dirs, Path::new(CONFIG_FILE))
+    }
+
+    fn load_from(dirs: &[&Path], config_file: &Path) -> Self {
+        let mut files = BTreeMap::new();
+        for dir in dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                if Path::new(&name).extension().is_some_and(|it| it == "conf") {
+                    // The earlier directories take precedence
+                    files.entry(name).or_insert_with(|| entry.path());
+                }
+            }
+        }
+
+        let mut configuration = Self::default();
+        for file in files.values().map(PathBuf::as_path).chain([config_file]) {
+            if let Ok(content) = fs::read_to_string(file) {
+                configuration.parse(&content, file);
+            }
+        }
+        configuration
+    }
+
+    fn parse(&mut self, content: &str, file: &Path) {
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            // A leading dash only silences the errors of the assignment
+            let line = line.strip_prefix('-').unwrap_or(line);
+            if let (var, Some(value)) = parse_assignment(line) {
+                let setting = Setting {
+                    value: value.to_string(),
+                    file: file.to_path_buf(),
+                    line: i + 1,
+                };
+                self.settings.push((var, setting));
+            }
+        }
+    }
+
+    /// The assignment which sets `var` last.
+    pub fn lookup(&self, var: &str) -> Option<&Setting> {
+        self.settings
+            .iter()
+            .rev()
+            .find(|(pattern, _)| matches_glob(pattern, var))
+            .map(|(_, setting)| setting)
+    }
+
+    /// Where `var` is configured, and the configured value when the running one differs.
+    pub fn origin(&self, var: &str, value: &str) -> String {
+        match self.lookup(var) {
+            None => "not configured".to_string(),
+            Some(setting) => {
+                let location = format!("{}:{}", setting.file.display(), setting.line);
+                // The kernel separates the fields of multi-value variables with tabs
+                let normalize =
+                    |value: &str| value.split_whitespace().collect::<Vec<_>>().join(" ");
+                if normalize(&setting.value) == normalize(value) {
+                    location
+                } else {
+                    format!("{location}, differs from the configured {}", setting.value)
+                }
+            }
+        }
+    }
+}
+
+/// Whether `var` matches `pattern`, where `*` stands for any characters.
+fn matches_glob(pattern: &str, var: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == var,
+        Some((prefix, rest)) => {
+            let Some(var) = var.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=var.len())
+                .filter(|i| var.is_char_boundary(*i))
+                .any(|i| matches_glob(rest, &var[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("kernel.panic", "kernel.panic"));
+        assert!(!matches_glob("kernel.panic", "kernel.panic_on_oops"));
+        assert!(matches_glob(
+            "net.ipv4.conf.*.rp_filter",
+            "net.ipv4.conf.eth0.rp_filter"
+        ));
+        assert!(!matches_glob(
+            "net.ipv4.conf.*.rp_filter",
+            "net.ipv4.conf.eth0.forwarding"
+        ));
+        assert!(matches_glob("vm.*", "vm.swappiness"));
+    }
+
+    #[test]
+    fn test_load() {
+        let root = std::env::temp_dir().join(format!("uu_sysctl_origin_{}", std::process::id()));
+        let (etc, lib) = (root.join("etc"), root.join("lib"));
+        fs::create_dir_all(&etc).unwrap();
+        fs::create_dir_all(&lib).unwrap();
+        fs::write(
+            lib.join("10-defaults.conf"),
+            "# defaults\nkernel.panic = 10\nvm.swappiness=60\nnet.ipv4.conf.*.rp_filter = 2\n",
+        )
+        .unwrap();
+        // Masked by the file of the same name in the first directory
+        fs::write(lib.join("50-local.conf"), "vm.swappiness = 1\n").unwrap();
+        fs::write(etc.join("50-local.conf"), "; tuned\n-vm.swappiness = 10\n").unwrap();
+        fs::write(etc.join("notes.txt"), "kernel.panic = 99\n").unwrap();
+        let config_file = root.join("sysctl.conf");
+        fs::write(&config_file, "net/ipv4/conf/lo/rp_filter = 0\n").unwrap();
+
+        let configuration = Configuration::load_from(&[&etc, &lib], &config_file);
+        fs::remove_dir_all(&root).unwrap();
+
+        let defaults = lib.join("10-defaults.conf").display().to_string();
+        let local = etc.join("50-local.conf").display().to_string();
+        assert_eq!(
+            configuration.origin("kernel.panic", "10"),
+            format!("{defaults}:2")
+        );
+        assert_eq!(
+            configuration.origin("vm.swappiness", "60"),
+            format!("{local}:2, differs from the configured 10")
+        );
+        assert_eq!(
+            configuration.origin("net.ipv4.conf.eth0.rp_filter", "2"),
+            format!("{defaults}:4")
+        );
+        assert_eq!(
+            configuration.origin("net.ipv4.conf.lo.rp_filter", "0"),
+            format!("{}:1", config_file.display())
+        );
+        assert_eq!(
+            configuration.origin("kernel.ostype", "Linux"),
+            "not configured"
+        );
+    }
+
+    #[test]
+    fn test_multi_value() {
+        let mut configuration = Configuration::default();
+        configuration.parse("kernel.printk = 4 4 1 7\n", Path::new("printk.conf"));
+        assert_eq!(
+            configuration.origin("kernel.printk", "4\t4\t1\t7"),
+            "printk.conf:1"
+        );
+    }
+}
@@ -6,19 +6,164 @@
 use clap::{crate_version, Arg, ArgAction, Command};
 use std::env;
 use uucore::error::UResult;
-use uucore::{format_usage, help_about, help_usage};
+use uucore::{format_usage, help_about, help_section, help_usage};
 
 const ABOUT: &str = help_about!("sysctl.md");
 const USAGE: &str = help_usage!("sysctl.md");
+const AFTER_HELP: &str = help_section!("after help", "sysctl.md");
 
 #[cfg(target_os = "linux")]
 mod linux {
+    use nix::sched::{setns, CloneFlags};
+    use std::fs::File;
+    use std::io::{BufRead, IsTerminal, Write};
     use std::path::{Path, PathBuf};
-    use uucore::error::{FromIo, UIoError};
+    use uucore::error::{FromIo, UError, UResult, USimpleError};
     use walkdir::WalkDir;
 
     const PROC_SYS_ROOT: &str = "/proc/sys";
 
+    pub const DEFAULT_PROTECT_FILE: &str = "/etc/sysctl.protected";
+
+    /// Keys asking for confirmation when the protection file does not exist and there is a
+    /// terminal to ask on.
+    const DEFAULT_CONFIRM_KEYS: [&str; 6] = [
+        "kernel.kexec_load_disabled",
+        "kernel.modules_disabled",
+        "kernel.panic",
+        "kernel.panic_on_oops",
+        "vm.drop_caches",
+        "vm.panic_on_oom",
+    ];
+
+    /// Namespaces scoping variables under `/proc/sys`, which are resolved against the
+    /// namespaces of the reading process.
+    const NAMESPACES: [(&str, CloneFlags); 3] = [
+        ("net", CloneFlags::CLONE_NEWNET),
+        ("uts", CloneFlags::CLONE_NEWUTS),
+        ("ipc", CloneFlags::CLONE_NEWIPC),
+    ];
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Protection {
+        /// Ask before writing, never write without a terminal to ask on.
+        Confirm,
+        /// Never write without `--force`.
+        Deny,
+    }
+
+    /// Rules loaded from the protection file, one `deny <key>` or `confirm <key>` per line.
+    /// A key ending with `*` protects all keys starting with the given prefix.
+    #[derive(Debug, Default)]
+    pub struct ProtectedKeys {
+        rules: Vec<(String, Protection)>,
+    }
+
+    impl ProtectedKeys {
+        pub fn load(path: Option<&String>) -> UResult<Self> {
+            let content = match path {
+                Some(path) => std::fs::read_to_string(path)
+                    .map_err_context(|| format!("cannot read protection file '{path}'"))?,
+                None => match std::fs::read_to_string(DEFAULT_PROTECT_FILE) {
+                    Ok(content) => content,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        return Ok(Self::default_rules())
+                    }
+                    Err(e) => {
+                        return Err(e.map_err_context(|| {
+                            format!("cannot read protection file '{DEFAULT_PROTECT_FILE}'")
+                        }))
+                    }
+                },
+            };
+
+            Self::parse(&content).map_err(|e| USimpleError::new(1, e))
+        }
+
+        /// The built-in rules only ask, so that scripts can still write these keys.
+        fn default_rules() -> Self {
+            if !can_confirm() {
+                return Self::default();
+            }
+            Self {
+                rules: DEFAULT_CONFIRM_KEYS
+                    .iter()
+                    .map(|key| (key.to_string(), Protection::Confirm))
+                    .collect(),
+            }
+        }
+
+        pub fn parse(content: &str) -> Result<Self, String> {
+            let mut rules = Vec::new();
+            for (i, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                    continue;
+                }
+                let protection = match line.split_once(char::is_whitespace) {
+                    Some(("deny", key)) => (normalize_var(key.trim()), Protection::Deny),
+                    Some(("confirm", key)) => (normalize_var(key.trim()), Protection::Confirm),
+                    _ => return Err(format!("invalid protection rule at line {}", i + 1)),
+                };
+                rules.push(protection);
+            }
+            Ok(Self { rules })
+        }
+
+        /// Returns the strictest protection of all rules matching `var`.
+        pub fn lookup(&self, var: &str) -> Option<Protection> {
+            self.rules
+                .iter()
+                .filter(|(pattern, _)| match pattern.strip_suffix('*') {
+                    Some(prefix) => var.starts_with(prefix),
+                    None => pattern == var,
+                })
+                .map(|(_, protection)| *protection)
+                .max()
+        }
+    }
+
+    fn can_confirm() -> bool {
+        std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+    }
+
+    fn confirm_write(var: &str, value: &str) -> bool {
+        eprint!("sysctl: write protected key '{var}' = '{value}'? [y/N] ");
+        let _ = std::io::stderr().flush();
+
+        let mut answer = String::new();
+        if std::io::stdin().lock().read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim(), "y" | "Y" | "yes" | "Yes")
+    }
+
+    /// Joins the namespaces of `pid` which differ from ours, so that the variables read and
+    /// written afterwards are the ones seen by that process.
+    pub fn enter_namespaces_of(pid: u32) -> UResult<()> {
+        for (name, flag) in NAMESPACES {
+            let path = format!("/proc/{pid}/ns/{name}");
+            let target = std::fs::read_link(&path)
+                .map_err_context(|| format!("cannot read namespace of process {pid}"))?;
+            if std::fs::read_link(format!("/proc/self/ns/{name}")).ok() == Some(target) {
+                continue;
+            }
+
+            let file = File::open(&path)
+                .map_err_context(|| format!("cannot open namespace of process {pid}"))?;
+            setns(&file, flag).map_err(|e| {
+                USimpleError::new(
+                    1,
+                    format!(
+                        "cannot enter {name} namespace of process {pid}: {}",
+                        e.desc()
+                    ),
+                )
+            })?;
+        }
+        Ok(())
+    }
+
     pub fn get_all_sysctl_variables() -> Vec<String> {
         let mut ret = vec![];
         for entry in WalkDir::new(PROC_SYS_ROOT) {
@@ -50,10 +195,27 @@ mod linux {
         Path::new(PROC_SYS_ROOT).join(var.replace('.', "/"))
     }
 
+    /// Splits `var=value`, the whitespace around the name and the value being ignored as in
+    /// the configuration files, but not the one separating the fields of multi-value keys.
+    pub fn parse_assignment(var_or_assignment: &str) -> (String, Option<&str>) {
+        match var_or_assignment.split_once('=') {
+            Some((var, value)) => (
+                normalize_var(var.trim()),
+                Some(value.trim_matches([' ', '\t'])),
+            ),
+            None => (normalize_var(var_or_assignment.trim()), None),
+        }
+    }
+
+    /// Reads a value, only its final newline is removed so that fields separated by tabs, like
+    /// the ones of `kernel.printk`, are kept as is.
+    fn read_value(path: &Path) -> std::io::Result<String> {
+        let value = std::fs::read_to_string(path)?;
+        Ok(value.strip_suffix('\n').unwrap_or(&value).to_string())
+    }
+
     pub fn get_sysctl(var: &str) -> std::io::Result<String> {
-        Ok(std::fs::read_to_string(variable_path(var))?
-            .trim_end()
-            .to_string())
+        read_value(&variable_path(var))
     }
 
     pub fn set_sysctl(var: &str, value: &str) -> std::io::Result<()> {
@@ -63,33 +225,109 @@ mod linux {
     pub fn handle_one_arg(
         var_or_assignment: &str,
         quiet: bool,
-    ) -> Result<Option<(String, String)>, Box<UIoError>> {
-        let mut split = var_or_assignment.splitn(2, '=');
-        let var = normalize_var(split.next().expect("Split always returns at least 1 value"));
+        protected: &ProtectedKeys,
+        force: bool,
+    ) -> Result<Option<(String, String)>, Box<dyn UError>> {
+        let (var, value) = parse_assignment(var_or_assignment);
 
-        if let Some(value_to_set) = split.next() {
+        if let Some(value_to_set) = value {
+            match protected.lookup(&var) {
+                Some(_) if force => {}
+                Some(Protection::Deny) => {
+                    return Err(USimpleError::new(
+                        1,
+                        format!("refusing to write protected key '{var}', use --force to override"),
+                    ));
+                }
+                // Without a terminal to ask on, only --force writes them
+                Some(Protection::Confirm) if !can_confirm() => {
+                    return Err(USimpleError::new(
+                        1,
+                        format!(
+                            "refusing to write protected key '{var}' without confirmation, \
+                             use --force to override"
+                        ),
+                    ));
+                }
+                Some(Protection::Confirm) if !confirm_write(&var, value_to_set) => {
+                    return Ok(None);
+                }
+                _ => {}
+            }
             set_sysctl(&var, value_to_set)
-                .map_err(|e| e.map_err_context(|| format!("error writing key '{var}'")))?;
+                .map_err_context(|| format!("error writing key '{var}'"))?;
             if quiet {
                 Ok(None)
             } else {
                 Ok(Some((var, value_to_set.to_string())))
             }
         } else {
-            let value = get_sysctl(&var)
-                .map_err(|e| e.map_err_context(|| format!("error reading key '{var}'")))?;
+            let value =
+                get_sysctl(&var).map_err_context(|| format!("error reading key '{var}'"))?;
             Ok(Some((var, value)))
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_assignment() {
+            assert_eq!(
+                parse_assignment("kernel/ostype"),
+                ("kernel.ostype".into(), None)
+            );
+            assert_eq!(
+                parse_assignment("net.ipv4.ip_local_port_range = 32768 \t 60999 "),
+                (
+                    "net.ipv4.ip_local_port_range".into(),
+                    Some("32768 \t 60999")
+                )
+            );
+            assert_eq!(
+                parse_assignment("kernel.printk=4\t4\t1\t7"),
+                ("kernel.printk".into(), Some("4\t4\t1\t7"))
+            );
+            assert_eq!(
+                parse_assignment("kernel.domainname="),
+                ("kernel.domainname".into(), Some(""))
+            );
+        }
+
+        #[test]
+        fn test_multi_value_round_trip() {
+            let path = std::env::temp_dir().join(format!("uu_sysctl_test_{}", std::process::id()));
+            for value in ["4\t4\t1\t7", "32768\t60999", "a  b", "first\nsecond"] {
+                // The kernel terminates the values with a newline
+                std::fs::write(&path, format!("{value}\n")).unwrap();
+                let read = read_value(&path).unwrap();
+                std::fs::write(&path, &read).unwrap();
+                assert_eq!(read_value(&path).unwrap(), value);
+                assert_eq!(read, value);
+            }
+            std::fs::remove_file(path).unwrap();
+        }
+    }
 }
 #[cfg(target_os = "linux")]
 use linux::*;
 
+#[cfg(target_os = "linux")]
+mod origin;
+
 #[cfg(target_os = "linux")]
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
 
+    let force = matches.get_flag("force");
+    let configuration = matches.get_flag("origin").then(origin::Configuration::load);
+
+    if let Some(pid) = matches.get_one::<u32>("target-pid") {
+        enter_namespaces_of(*pid)?;
+    }
+
     let vars = if matches.get_flag("all") {
         get_all_sysctl_variables()
     } else if let Some(vars) = matches.get_many::<String>("variables") {
@@ -99,17 +337,37 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         return Ok(());
     };
 
+    // The protection file only matters to writes
+    let protected = if vars.iter().any(|it| it.contains('=')) {
+        ProtectedKeys::load(matches.get_one::<String>("protect-file"))?
+    } else {
+        ProtectedKeys::default()
+    };
+
     for var_or_assignment in vars {
-        match handle_one_arg(&var_or_assignment, matches.get_flag("quiet")) {
+        match handle_one_arg(
+            &var_or_assignment,
+            matches.get_flag("quiet"),
+            &protected,
+            force,
+        ) {
             Ok(None) => (),
             Ok(Some((var, value_to_print))) => {
+                // Reported after the first line of the variable
+                let mut origin = configuration
+                    .as_ref()
+                    .map(|it| format!("  # {}", it.origin(&var, &value_to_print)));
+                if matches.get_flag("names") {
+                    println!("{var}{}", origin.unwrap_or_default());
+                    continue;
+                }
+                // Values spanning several lines get a line of output each
                 for line in value_to_print.split('\n') {
-                    if matches.get_flag("names") {
-                        println!("{var}");
-                    } else if matches.get_flag("values") {
-                        println!("{line}");
+                    let origin = origin.take().unwrap_or_default();
+                    if matches.get_flag("values") {
+                        println!("{line}{origin}");
                     } else {
-                        println!("{var} = {line}");
+                        println!("{var} = {line}{origin}");
                     }
                 }
             }
@@ -140,6 +398,7 @@ pub fn uu_app() -> Command {
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
+        .after_help(AFTER_HELP)
         .infer_long_args(true)
         .arg(
             Arg::new("variables")
@@ -182,6 +441,31 @@ pub fn uu_app() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Do not print when setting variables"),
         )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .help("Write protected variables without asking"),
+        )
+        .arg(
+            Arg::new("protect-file")
+                .long("protect-file")
+                .value_name("FILE")
+                .help("Read the protected variables from FILE instead of /etc/sysctl.protected"),
+        )
+        .arg(
+            Arg::new("target-pid")
+                .long("target-pid")
+                .value_name("PID")
+                .value_parser(clap::value_parser!(u32))
+                .help("Use the network, UTS and IPC namespaces of process PID"),
+        )
+        .arg(
+            Arg::new("origin")
+                .long("origin")
+                .action(ArgAction::SetTrue)
+                .help("Show the configuration file setting each variable, and whether it differs"),
+        )
         .arg(
             Arg::new("noop_o")
                 .short('o')
@@ -1,3 +1,8 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
 fn main() {
     #[cfg(target_os = "linux")]
     pkg_config::find_library("libsystemd").unwrap();
@@ -47,6 +47,9 @@ pub(crate) fn fields() -> HashSet<String> {
                 ("PPID", "Parent Process Id"),
                 ("PR", "Priority"),
                 ("PSS", "Proportional Resident Memory, smaps (KiB)"),
+                ("RECVQ", "Bytes in the Receive Queues of the Sockets"),
+                ("SENDQ", "Bytes in the Send Queues of the Sockets"),
+                ("SOCK", "Open Sockets"),
             ]
             .iter()
             .map(|(key, value)| (key.to_string(), value.to_string()))
@@ -4,22 +4,22 @@
 // file that was distributed with this source code.
 
 use crate::picker::sysinfo;
-use bytesize::ByteSize;
-use uu_w::get_formatted_uptime_procps;
-use uucore::uptime::{get_formatted_loadavg, get_formatted_nusers, get_formatted_time};
+use uu_w::{clock, get_formatted_uptime_procps};
+use uucore::uptime::{get_formatted_loadavg, get_formatted_nusers};
+use uucore_procps::scale::{format_scaled, Scale};
 
-pub(crate) fn header(scale_summary_mem: Option<&String>) -> String {
+pub(crate) fn header(scale_summary_mem: Scale, per_cpu: bool) -> String {
     format!(
         "top - {time} {uptime}, {user}, {load_average}\n\
         {task}\n\
         {cpu}\n\
         {memory}",
-        time = get_formatted_time(),
+        time = clock::now(),
         uptime = uptime(),
         user = user(),
         load_average = load_average(),
         task = task(),
-        cpu = cpu(),
+        cpu = cpu(per_cpu),
         memory = memory(scale_summary_mem),
     )
 }
@@ -34,10 +34,6 @@ extern "C" {
     ) -> libc::c_int;
 }
 
-fn format_memory(memory_b: u64, unit: u64) -> f64 {
-    ByteSize::b(memory_b).0 as f64 / unit as f64
-}
-
 #[inline]
 pub fn uptime() -> String {
     get_formatted_uptime_procps().unwrap_or_default()
@@ -134,24 +130,66 @@ fn task() -> String {
 }
 
 #[cfg(target_os = "linux")]
-fn cpu() -> String {
-    let cpu_load = uu_vmstat::CpuLoad::current();
+static CPU_SAMPLE: std::sync::Mutex<Option<uu_vmstat::CpuStat>> = std::sync::Mutex::new(None);
 
-    format!(
-        "%Cpu(s):  {:.1} us, {:.1} sy, {:.1} ni, {:.1} id, {:.1} wa, {:.1} hi, {:.1} si, {:.1} st",
-        cpu_load.user,
-        cpu_load.system,
-        cpu_load.nice,
-        cpu_load.idle,
-        cpu_load.io_wait,
-        cpu_load.hardware_interrupt,
-        cpu_load.software_interrupt,
-        cpu_load.steal_time,
-    )
+/// Remembers the CPU counters, the next header reports the CPU usage since then.
+#[cfg(target_os = "linux")]
+pub(crate) fn sample_cpu() {
+    *CPU_SAMPLE.lock().unwrap() = uu_vmstat::CpuStat::current().ok();
+}
+
+#[cfg(target_os = "linux")]
+fn cpu(per_cpu: bool) -> String {
+    use uu_vmstat::{CpuLoad, CpuStat};
+
+    let format = |label: &str, cpu_load: CpuLoad| {
+        format!(
+            "{label}:  {:.1} us, {:.1} sy, {:.1} ni, {:.1} id, {:.1} wa, {:.1} hi, {:.1} si, {:.1} st",
+            cpu_load.user,
+            cpu_load.system,
+            cpu_load.nice,
+            cpu_load.idle,
+            cpu_load.io_wait,
+            cpu_load.hardware_interrupt,
+            cpu_load.software_interrupt,
+            cpu_load.steal_time,
+        )
+    };
+
+    let current = CpuStat::current().unwrap_or_default();
+    let earlier = CPU_SAMPLE.lock().unwrap().replace(current.clone());
+    let delta = match &earlier {
+        Some(earlier) => current.since(earlier),
+        None => current.clone(),
+    };
+
+    if !per_cpu {
+        return format("%Cpu(s)", CpuLoad::from_ticks(&delta.total));
+    }
+
+    // CPUs without a line in /proc/stat are offline, keep showing them so rows do not shift
+    let mut ids = uu_vmstat::present_cpus();
+    ids.extend(delta.cpus.keys());
+    ids.extend(earlier.iter().flat_map(|it| it.cpus.keys()));
+    ids.sort_unstable();
+    ids.dedup();
+
+    ids.into_iter()
+        .map(|id| {
+            let label = format!("%Cpu{id:<3}");
+            match delta.cpus.get(&id) {
+                Some(ticks) => format(&label, CpuLoad::from_ticks(ticks)),
+                // Came online during the interval, its usage shows from the next update
+                None if current.cpus.contains_key(&id) => format!("{label}:  online"),
+                None => format!("{label}:  offline"),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[cfg(target_os = "windows")]
-fn cpu() -> String {
+fn cpu(_per_cpu: bool) -> String {
     use libc::malloc;
     use windows_sys::Wdk::System::SystemInformation::NtQuerySystemInformation;
 
@@ -216,36 +254,25 @@ fn cpu() -> String {
 
 //TODO: Implement for macos
 #[cfg(target_os = "macos")]
-fn cpu() -> String {
+fn cpu(_per_cpu: bool) -> String {
     "TODO".into()
 }
 
-fn memory(scale_summary_mem: Option<&String>) -> String {
+fn memory(scale: Scale) -> String {
     let binding = sysinfo().read().unwrap();
-    let (unit, unit_name) = match scale_summary_mem {
-        Some(scale) => match scale.as_str() {
-            "k" => (bytesize::KIB, "KiB"),
-            "m" => (bytesize::MIB, "MiB"),
-            "g" => (bytesize::GIB, "GiB"),
-            "t" => (bytesize::TIB, "TiB"),
-            "p" => (bytesize::PIB, "PiB"),
-            "e" => (1_152_921_504_606_846_976, "EiB"),
-            _ => (bytesize::MIB, "MiB"),
-        },
-        None => (bytesize::MIB, "MiB"),
-    };
+    let format_memory = |bytes: u64| format_scaled(bytes, scale, 8, false);
 
     format!(
-        "{unit_name} Mem : {:8.1} total, {:8.1} free, {:8.1} used, {:8.1} buff/cache\n\
-        {unit_name} Swap: {:8.1} total, {:8.1} free, {:8.1} used, {:8.1} avail Mem",
-        format_memory(binding.total_memory(), unit),
-        format_memory(binding.free_memory(), unit),
-        format_memory(binding.used_memory(), unit),
-        format_memory(binding.available_memory() - binding.free_memory(), unit),
-        format_memory(binding.total_swap(), unit),
-        format_memory(binding.free_swap(), unit),
-        format_memory(binding.used_swap(), unit),
-        format_memory(binding.available_memory(), unit),
-        unit_name = unit_name
+        "{unit_name} Mem : {} total, {} free, {} used, {} buff/cache\n\
+        {unit_name} Swap: {} total, {} free, {} used, {} avail Mem",
+        format_memory(binding.total_memory()),
+        format_memory(binding.free_memory()),
+        format_memory(binding.used_memory()),
+        format_memory(binding.available_memory() - binding.free_memory()),
+        format_memory(binding.total_swap()),
+        format_memory(binding.free_swap()),
+        format_memory(binding.used_swap()),
+        format_memory(binding.available_memory()),
+        unit_name = scale.name()
     )
 }
@@ -0,0 +1,256 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::Settings;
+use crossterm::event::KeyCode;
+
+/// What a key does in interactive mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    Help,
+    Quit,
+    Refresh,
+    TogglePerCpu,
+    ToggleUsersWindow,
+    SortLeft,
+    SortRight,
+    CycleSummaryScale,
+    CycleTaskScale,
+    ToggleCommandLine,
+    ToggleIdle,
+    ToggleCumulative,
+}
+
+pub(crate) struct Binding {
+    pub(crate) keys: &'static [char],
+    pub(crate) action: Action,
+    pub(crate) description: &'static str,
+    /// Current value of the setting changed by the key, for toggles
+    pub(crate) value: Option<fn(&Settings) -> String>,
+}
+
+/// Every key of interactive mode, the help screen is generated from it.
+pub(crate) const BINDINGS: &[Binding] = &[
+    Binding {
+        keys: &['h', '?'],
+        action: Action::Help,
+        description: "Show this help",
+        value: None,
+    },
+    Binding {
+        keys: &[' ', '\n'],
+        action: Action::Refresh,
+        description: "Update the display",
+        value: None,
+    },
+    Binding {
+        keys: &['1'],
+        action: Action::TogglePerCpu,
+        description: "Toggle a line per CPU in the summary area",
+        value: Some(|settings| on_off(settings.per_cpu)),
+    },
+    Binding {
+        keys: &['A'],
+        action: Action::ToggleUsersWindow,
+        description: "Toggle the per-user summary window",
+        value: Some(|settings| on_off(settings.users_window)),
+    },
+    Binding {
+        keys: &['<'],
+        action: Action::SortLeft,
+        description: "Sort the per-user window on the column to the left",
+        value: Some(|settings| settings.users_sort.name().into()),
+    },
+    Binding {
+        keys: &['>'],
+        action: Action::SortRight,
+        description: "Sort the per-user window on the column to the right",
+        value: None,
+    },
+    Binding {
+        keys: &['E'],
+        action: Action::CycleSummaryScale,
+        description: "Cycle the memory unit of the summary area",
+        value: Some(|settings| settings.scale_summary_mem.name().into()),
+    },
+    Binding {
+        keys: &['e'],
+        action: Action::CycleTaskScale,
+        description: "Cycle the memory unit of the task area",
+        value: Some(|settings| settings.scale_task_mem.name().into()),
+    },
+    Binding {
+        keys: &['c'],
+        action: Action::ToggleCommandLine,
+        description: "Toggle the command line or the program name of the tasks",
+        value: Some(|settings| on_off(settings.full_command)),
+    },
+    Binding {
+        keys: &['i'],
+        action: Action::ToggleIdle,
+        description: "Toggle the display of the idle tasks",
+        value: Some(|settings| on_off(settings.show_idle)),
+    },
+    Binding {
+        keys: &['S'],
+        action: Action::ToggleCumulative,
+        description: "Toggle the cumulative mode, adding the time of dead children",
+        value: Some(|settings| on_off(settings.cumulative)),
+    },
+    Binding {
+        keys: &['q'],
+        action: Action::Quit,
+        description: "Quit",
+        value: None,
+    },
+];
+
+/// Footer of the help screen, any other key leaves it.
+pub(crate) const HELP_FOOTER: &str = "Type 'q' or <Esc> to continue, <Space> or 'b' to page";
+
+pub(crate) fn action(key: char) -> Option<Action> {
+    BINDINGS
+        .iter()
+        .find(|binding| binding.keys.contains(&key))
+        .map(|binding| binding.action)
+}
+
+/// The character standing for a key in [`BINDINGS`].
+pub(crate) fn key_char(code: KeyCode) -> Option<char> {
+    match code {
+        KeyCode::Char(c) => Some(c),
+        KeyCode::Enter => Some('\n'),
+        KeyCode::Esc => Some('\x1b'),
+        _ => None,
+    }
+}
+
+fn key_name(key: char) -> String {
+    match key {
+        ' ' => "Space".into(),
+        '\n' => "Enter".into(),
+        '\x1b' => "Esc".into(),
+        c => c.into(),
+    }
+}
+
+fn on_off(value: bool) -> String {
+    if value { "on" } else { "off" }.into()
+}
+
+fn on_off_title(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+/// Lines of the help screen, listing the bindings along with the current value of the toggles.
+pub(crate) fn help(settings: &Settings) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "Help for Interactive Commands - top {}",
+            clap::crate_version!()
+        ),
+        format!(
+            "Window 1:Def: Cumulative mode {}.  System: Delay {:.1} secs; Secure mode {}.",
+            on_off_title(settings.cumulative),
+            crate::tui::DELAY.as_secs_f64(),
+            on_off_title(settings.secure),
+        ),
+        String::new(),
+    ];
+
+    for binding in BINDINGS {
+        let keys = binding
+            .keys
+            .iter()
+            .map(|key| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut line = format!("  {keys:<12} {}", binding.description);
+        if let Some(value) = binding.value {
+            line.push_str(&format!(" [{}]", value(settings)));
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uucore_procps::scale::Scale;
+
+    fn settings() -> Settings {
+        Settings {
+            filter: None,
+            width: None,
+            scale_summary_mem: Scale::Mib,
+            scale_task_mem: Scale::Kib,
+            per_cpu: false,
+            users_window: false,
+            users_sort: Default::default(),
+            full_command: false,
+            show_idle: true,
+            cumulative: false,
+            secure: false,
+            net: false,
+        }
+    }
+
+    #[test]
+    fn test_keys_are_unique() {
+        let mut keys = BINDINGS
+            .iter()
+            .flat_map(|binding| binding.keys)
+            .collect::<Vec<_>>();
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+    }
+
+    #[test]
+    fn test_action() {
+        assert_eq!(action('?'), Some(Action::Help));
+        assert_eq!(action('\n'), Some(Action::Refresh));
+        assert_eq!(action('1'), Some(Action::TogglePerCpu));
+        assert_eq!(action('A'), Some(Action::ToggleUsersWindow));
+        assert_eq!(action('>'), Some(Action::SortRight));
+        assert_eq!(action('E'), Some(Action::CycleSummaryScale));
+        assert_eq!(action('e'), Some(Action::CycleTaskScale));
+        assert_eq!(action('c'), Some(Action::ToggleCommandLine));
+        assert_eq!(action('i'), Some(Action::ToggleIdle));
+        assert_eq!(action('S'), Some(Action::ToggleCumulative));
+        assert_eq!(action('Z'), None);
+    }
+
+    #[test]
+    fn test_help() {
+        let mut settings = settings();
+        let lines = help(&settings);
+        assert_eq!(lines.len(), BINDINGS.len() + 3);
+        assert_eq!(
+            lines[1],
+            "Window 1:Def: Cumulative mode Off.  System: Delay 3.0 secs; Secure mode Off."
+        );
+        assert!(lines.contains(&"  h,?          Show this help".to_string()));
+        assert!(lines.contains(&"  Space,Enter  Update the display".to_string()));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("  1 ") && line.ends_with("[off]")));
+
+        settings.per_cpu = true;
+        assert!(help(&settings)
+            .iter()
+            .any(|line| line.starts_with("  1 ") && line.ends_with("[on]")));
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("  < ") && line.ends_with("[%CPU]")));
+    }
+}
@@ -0,0 +1,352 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Network activity of the tasks, for the columns of `--net`.
+//!
+//! The kernel doesn't account the network traffic per process. Instead, the sockets of the
+//! system are dumped once per update through the sock_diag netlink interface, then matched to
+//! the tasks by the inodes of the sockets among their file descriptors. The bytes waiting in
+//! the queues of these sockets estimate how busy the task is on the network.
+
+// spell-checker:ignore (netlink) nlmsghdr rqueue wqueue rqlen sockaddr
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Bytes waiting in the queues of a socket, or of all the sockets of a task.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Queues {
+    /// Received, not read yet by the task
+    pub(crate) recv: u64,
+    /// Written by the task, not sent yet
+    pub(crate) send: u64,
+}
+
+/// The sockets of a task.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Usage {
+    /// Sockets among the file descriptors, including the ones unknown to sock_diag
+    pub(crate) sockets: usize,
+    pub(crate) queues: Queues,
+}
+
+/// The sockets of the system at one update, with the usage of the tasks computed on demand.
+#[derive(Debug, Default)]
+pub(crate) struct Sockets {
+    queues: HashMap<u64, Queues>,
+    /// Every column asks for the usage, the file descriptors are only listed once
+    usage: RefCell<HashMap<u32, Option<Usage>>>,
+}
+
+impl Sockets {
+    /// The sockets of the system, none when sock_diag is not available.
+    pub(crate) fn current() -> Self {
+        Self {
+            queues: dump(),
+            usage: RefCell::default(),
+        }
+    }
+
+    /// The sockets of a task, unknown when its file descriptors can't be read.
+    pub(crate) fn usage(&self, pid: u32) -> Option<Usage> {
+        *self
+            .usage
+            .borrow_mut()
+            .entry(pid)
+            .or_insert_with(|| self.read_usage(&Path::new("/proc").join(pid.to_string())))
+    }
+
+    fn read_usage(&self, proc_dir: &Path) -> Option<Usage> {
+        let mut usage = Usage::default();
+        for entry in fs::read_dir(proc_dir.join("fd")).ok()?.flatten() {
+            let Some(inode) = fs::read_link(entry.path())
+                .ok()
+                .and_then(|target| socket_inode(target.to_str()?))
+            else {
+                continue;
+            };
+            usage.sockets += 1;
+            if let Some(queues) = self.queues.get(&inode) {
+                usage.queues.recv += queues.recv;
+                usage.queues.send += queues.send;
+            }
+        }
+        Some(usage)
+    }
+}
+
+/// The inode of the targets of the links to sockets in `/proc/<pid>/fd`, as in `socket:[1234]`.
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn dump() -> HashMap<u64, Queues> {
+    sock_diag::dump()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn dump() -> HashMap<u64, Queues> {
+    HashMap::new()
+}
+
+#[cfg(target_os = "linux")]
+mod sock_diag {
+    //! The requests and replies of sock_diag(7), which libc doesn't define.
+
+    use super::Queues;
+    use std::collections::HashMap;
+    use std::io;
+    use std::mem::size_of;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    const SOCK_DIAG_BY_FAMILY: u16 = 20;
+    const UDIAG_SHOW_RQLEN: u32 = 0x10;
+    const UNIX_DIAG_RQLEN: u16 = 4;
+    /// Sockets in every state
+    const ALL_STATES: u32 = u32::MAX;
+
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    struct InetDiagSockId {
+        sport: u16,
+        dport: u16,
+        src: [u32; 4],
+        dst: [u32; 4],
+        interface: u32,
+        cookie: [u32; 2],
+    }
+
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    struct InetDiagReqV2 {
+        family: u8,
+        protocol: u8,
+        ext: u8,
+        pad: u8,
+        states: u32,
+        id: InetDiagSockId,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct InetDiagMsg {
+        family: u8,
+        state: u8,
+        timer: u8,
+        retrans: u8,
+        id: InetDiagSockId,
+        expires: u32,
+        rqueue: u32,
+        wqueue: u32,
+        uid: u32,
+        inode: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    struct UnixDiagReq {
+        family: u8,
+        protocol: u8,
+        pad: u16,
+        states: u32,
+        inode: u32,
+        show: u32,
+        cookie: [u32; 2],
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct UnixDiagMsg {
+        family: u8,
+        kind: u8,
+        state: u8,
+        pad: u8,
+        inode: u32,
+        cookie: [u32; 2],
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct RtAttr {
+        len: u16,
+        kind: u16,
+    }
+
+    #[repr(C)]
+    struct Request<T> {
+        header: libc::nlmsghdr,
+        body: T,
+    }
+
+    /// Netlink messages and attributes are aligned on 4 bytes.
+    fn align(len: usize) -> usize {
+        (len + 3) & !3
+    }
+
+    /// Reads a `T` at the start of `bytes`, none when they are too short.
+    fn read<T: Copy>(bytes: &[u8]) -> Option<T> {
+        // SAFETY: the structures are plain integers, valid for any bytes
+        (bytes.len() >= size_of::<T>())
+            .then(|| unsafe { std::ptr::read_unaligned(bytes.as_ptr().cast::<T>()) })
+    }
+
+    /// The queues of the TCP, UDP and Unix sockets of the system, by inode.
+    ///
+    /// The families whose diag module is missing from the kernel are skipped.
+    pub(super) fn dump() -> HashMap<u64, Queues> {
+        let mut queues = HashMap::new();
+
+        for family in [libc::AF_INET, libc::AF_INET6] {
+            for protocol in [libc::IPPROTO_TCP, libc::IPPROTO_UDP] {
+                let request = InetDiagReqV2 {
+                    family: family as u8,
+                    protocol: protocol as u8,
+                    states: ALL_STATES,
+                    ..Default::default()
+                };
+                for message in messages(request).unwrap_or_default() {
+                    if let Some(msg) = read::<InetDiagMsg>(&message) {
+                        let (recv, send) = (msg.rqueue.into(), msg.wqueue.into());
+                        queues.insert(msg.inode.into(), Queues { recv, send });
+                    }
+                }
+            }
+        }
+
+        let request = UnixDiagReq {
+            family: libc::AF_UNIX as u8,
+            states: ALL_STATES,
+            show: UDIAG_SHOW_RQLEN,
+            ..Default::default()
+        };
+        for message in messages(request).unwrap_or_default() {
+            let Some(msg) = read::<UnixDiagMsg>(&message) else {
+                continue;
+            };
+            let mut attributes = &message[align(size_of::<UnixDiagMsg>()).min(message.len())..];
+            while let Some(attribute) = read::<RtAttr>(attributes) {
+                let len = usize::from(attribute.len).clamp(size_of::<RtAttr>(), attributes.len());
+                if attribute.kind == UNIX_DIAG_RQLEN {
+                    let payload = &attributes[size_of::<RtAttr>()..len];
+                    if let Some([recv, send]) = read::<[u32; 2]>(payload) {
+                        let (recv, send) = (recv.into(), send.into());
+                        queues.insert(msg.inode.into(), Queues { recv, send });
+                    }
+                }
+                attributes = &attributes[align(len).min(attributes.len())..];
+            }
+        }
+
+        queues
+    }
+
+    /// Sends a dump request, then collects the payloads of the replies until the end of the dump.
+    fn messages<T>(body: T) -> io::Result<Vec<Vec<u8>>> {
+        // SAFETY: plain system calls, the descriptor is owned right away
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_SOCK_DIAG,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let request = Request {
+            header: libc::nlmsghdr {
+                nlmsg_len: size_of::<Request<T>>() as u32,
+                nlmsg_type: SOCK_DIAG_BY_FAMILY,
+                nlmsg_flags: (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16,
+                nlmsg_seq: 1,
+                nlmsg_pid: 0,
+            },
+            body,
+        };
+        // SAFETY: the kernel is the destination when the address is zeroed
+        let sent = unsafe {
+            let mut address: libc::sockaddr_nl = std::mem::zeroed();
+            address.nl_family = libc::AF_NETLINK as u16;
+            libc::sendto(
+                fd.as_raw_fd(),
+                (&raw const request).cast(),
+                size_of::<Request<T>>(),
+                0,
+                (&raw const address).cast(),
+                size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut messages = Vec::new();
+        let mut buffer = vec![0u8; 32 * 1024];
+        loop {
+            // SAFETY: the kernel writes at most the length of the buffer
+            let received =
+                unsafe { libc::recv(fd.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0) };
+            if received < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut replies = &buffer[..received as usize];
+            while let Some(header) = read::<libc::nlmsghdr>(replies) {
+                let len =
+                    (header.nlmsg_len as usize).clamp(size_of::<libc::nlmsghdr>(), replies.len());
+                let payload = &replies[align(size_of::<libc::nlmsghdr>())..len];
+                match i32::from(header.nlmsg_type) {
+                    libc::NLMSG_DONE => return Ok(messages),
+                    libc::NLMSG_ERROR => {
+                        let errno = read::<i32>(payload).unwrap_or(-libc::EIO);
+                        return Err(io::Error::from_raw_os_error(-errno));
+                    }
+                    _ => messages.push(payload.to_vec()),
+                }
+                replies = &replies[align(len).min(replies.len())..];
+            }
+            if received == 0 {
+                return Ok(messages);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_inode() {
+        assert_eq!(socket_inode("socket:[1234]"), Some(1234));
+        assert_eq!(socket_inode("pipe:[1234]"), None);
+        assert_eq!(socket_inode("/dev/null"), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_usage() {
+        use std::io::Write;
+        use std::os::unix::net::UnixStream;
+
+        let before = Sockets::current().usage(std::process::id()).unwrap();
+        let (mut writer, _reader) = UnixStream::pair().unwrap();
+        writer.write_all(b"hello").unwrap();
+
+        let after = Sockets::current().usage(std::process::id()).unwrap();
+        // Other tests may open sockets in the meantime
+        assert!(after.sockets >= before.sockets + 2, "{before:?} {after:?}");
+        assert!(after.queues.recv >= 5, "{after:?}");
+    }
+}
@@ -3,6 +3,7 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+use crate::Settings;
 use std::{
     ffi::OsString,
     fs::File,
@@ -11,7 +12,8 @@ use std::{
     str::FromStr,
     sync::{OnceLock, RwLock},
 };
-use sysinfo::{Pid, System, Users};
+use sysinfo::{Pid, ProcessStatus, System, Users};
+use uucore_procps::scale::{format_scaled, Scale};
 
 static SYSINFO: OnceLock<RwLock<System>> = OnceLock::new();
 
@@ -19,20 +21,37 @@ pub fn sysinfo() -> &'static RwLock<System> {
     SYSINFO.get_or_init(|| RwLock::new(System::new_all()))
 }
 
-pub(crate) fn pickers(fields: &[String]) -> Vec<Box<dyn Fn(u32) -> String>> {
+/// Width of the memory columns of the task area.
+const MEMORY_WIDTH: usize = 7;
+
+pub(crate) fn pickers(fields: &[String], settings: &Settings) -> Vec<Box<dyn Fn(u32) -> String>> {
+    let scale = settings.scale_task_mem;
+    let (cumulative, full_command) = (settings.cumulative, settings.full_command);
+    // The sockets of the system are dumped once for all the tasks
+    #[cfg(feature = "net")]
+    let sockets = settings
+        .net
+        .then(|| std::rc::Rc::new(crate::net::Sockets::current()));
     fields
         .iter()
         .map(|field| match field.as_str() {
             "PID" => helper(pid),
             "USER" => helper(user),
             "PR" => helper(pr),
-            "RES" => helper(res),
-            "SHR" => helper(shr),
+            "VIRT" => helper(move |pid| virt(pid, scale)),
+            "RES" => helper(move |pid| res(pid, scale)),
+            "SHR" => helper(move |pid| shr(pid, scale)),
             "S" => helper(s),
             "%CPU" => helper(cpu),
-            "TIME+" => helper(time_plus),
+            "TIME+" => helper(move |pid| time_plus(pid, cumulative)),
             "%MEM" => helper(mem),
-            "COMMAND" => helper(command),
+            "COMMAND" => helper(move |pid| command(pid, full_command)),
+            #[cfg(feature = "net")]
+            "SOCK" | "RECVQ" | "SENDQ" if sockets.is_some() => {
+                let sockets = sockets.clone().unwrap();
+                let field = field.clone();
+                helper(move |pid| net(&sockets, pid, &field))
+            }
             _ => helper(todo),
         })
         .collect()
@@ -43,6 +62,29 @@ fn helper(f: impl Fn(u32) -> String + 'static) -> Box<dyn Fn(u32) -> String> {
     Box::new(f)
 }
 
+/// Whether the task neither used any CPU since the last update nor is running.
+pub(crate) fn is_idle(pid: u32) -> bool {
+    let binding = sysinfo().read().unwrap();
+    binding
+        .process(Pid::from_u32(pid))
+        .is_none_or(|proc| proc.cpu_usage() == 0.0 && proc.status() != ProcessStatus::Run)
+}
+
+/// The sockets of the task, or the bytes queued in them.
+#[cfg(feature = "net")]
+fn net(sockets: &crate::net::Sockets, pid: u32, field: &str) -> String {
+    let Some(usage) = sockets.usage(pid) else {
+        return "-".into();
+    };
+
+    match field {
+        "SOCK" => usage.sockets,
+        "RECVQ" => usage.queues.recv as usize,
+        _ => usage.queues.send as usize,
+    }
+    .to_string()
+}
+
 fn todo(_pid: u32) -> String {
     "TODO".into()
 }
@@ -99,11 +141,40 @@ fn pr(_pid: u32) -> String {
     "0".into()
 }
 
-fn res(_pid: u32) -> String {
-    "TODO".into()
+fn virt(pid: u32, scale: Scale) -> String {
+    let binding = sysinfo().read().unwrap();
+    let bytes = binding
+        .process(Pid::from_u32(pid))
+        .map_or(0, |proc| proc.virtual_memory());
+
+    format_scaled(bytes, scale, MEMORY_WIDTH, true)
+}
+
+fn res(pid: u32, scale: Scale) -> String {
+    let binding = sysinfo().read().unwrap();
+    let bytes = binding
+        .process(Pid::from_u32(pid))
+        .map_or(0, |proc| proc.memory());
+
+    format_scaled(bytes, scale, MEMORY_WIDTH, true)
+}
+
+#[cfg(target_os = "linux")]
+fn shr(pid: u32, scale: Scale) -> String {
+    // The third field of statm is the number of resident shared pages
+    let pages = std::fs::read_to_string(format!("/proc/{pid}/statm"))
+        .ok()
+        .and_then(|statm| statm.split_whitespace().nth(2)?.parse::<u64>().ok())
+        .unwrap_or_default();
+    // SAFETY: sysconf has no preconditions, and the page size is always known
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+
+    format_scaled(pages * page_size, scale, MEMORY_WIDTH, true)
 }
 
-fn shr(_pid: u32) -> String {
+// TODO: Implement this function for other platforms
+#[cfg(not(target_os = "linux"))]
+fn shr(_pid: u32, _scale: Scale) -> String {
     "TODO".into()
 }
 
@@ -122,22 +193,45 @@ fn s(pid: u32) -> String {
         .to_string()
 }
 
-fn time_plus(pid: u32) -> String {
+/// CPU time of the task as minutes, seconds and hundredths, along with the time of its dead
+/// children in cumulative mode.
+#[cfg(target_os = "linux")]
+fn time_plus(pid: u32, cumulative: bool) -> String {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap_or_default();
+    // The fields following the command name, which may contain spaces, starting at the state
+    let fields = stat
+        .rsplit_once(')')
+        .map(|(_, fields)| fields.split_whitespace().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let field = |index: usize| {
+        fields
+            .get(index)
+            .and_then(|it| it.parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+    // utime, stime, cutime and cstime
+    let mut ticks = field(11) + field(12);
+    if cumulative {
+        ticks += field(13) + field(14);
+    }
+    format_time_plus(ticks * 100 / uu_pgrep::process_matcher::clock_ticks())
+}
+
+// TODO: Implement the CPU time for other platforms
+#[cfg(not(target_os = "linux"))]
+fn time_plus(pid: u32, _cumulative: bool) -> String {
     let binding = sysinfo().read().unwrap();
     let Some(proc) = binding.process(Pid::from_u32(pid)) else {
         return "0:00.00".into();
     };
 
-    let (hour, min, sec) = {
-        let total = proc.run_time();
-        let hour = total / 3600;
-        let minute = (total % 3600) / 60;
-        let second = total % 60;
-
-        (hour, minute, second)
-    };
+    format_time_plus(proc.run_time() * 100)
+}
 
-    format!("{hour}:{min:0>2}.{sec:0>2}")
+fn format_time_plus(hundredths: u64) -> String {
+    let minutes = hundredths / 6000;
+    let seconds = hundredths / 100 % 60;
+    format!("{minutes}:{seconds:0>2}.{:0>2}", hundredths % 100)
 }
 
 fn mem(pid: u32) -> String {
@@ -152,7 +246,7 @@ fn mem(pid: u32) -> String {
     )
 }
 
-fn command(pid: u32) -> String {
+fn command(pid: u32, full_command: bool) -> String {
     let f = |cmd: &[OsString]| -> String {
         let binding = cmd
             .iter()
@@ -192,9 +286,25 @@ fn command(pid: u32) -> String {
         return "?".into();
     };
 
+    if full_command && !proc.cmd().is_empty() {
+        return f(proc.cmd());
+    }
+
     proc.exe()
         .and_then(|it| it.iter().next_back())
         .map(|it| it.to_str().unwrap())
         .unwrap_or(&f(proc.cmd()))
         .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_time_plus() {
+        assert_eq!(format_time_plus(0), "0:00.00");
+        assert_eq!(format_time_plus(1234), "0:12.34");
+        assert_eq!(format_time_plus(6000 * 75 + 501), "75:05.01");
+    }
+}
@@ -8,19 +8,29 @@ use header::header;
 use picker::pickers;
 use picker::sysinfo;
 use prettytable::{format::consts::FORMAT_CLEAN, Row, Table};
+use std::io::IsTerminal;
 use std::{thread::sleep, time::Duration};
 use sysinfo::{Pid, Users};
 use uucore::{
     error::{UResult, USimpleError},
     format_usage, help_about, help_usage,
 };
+use uucore_procps::scale::Scale;
 
 const ABOUT: &str = help_about!("top.md");
 const USAGE: &str = help_usage!("top.md");
 
+/// Largest width of the lines, as in procps.
+const MAX_WIDTH: usize = 512;
+
 mod field;
 pub mod header;
+mod keys;
+#[cfg(feature = "net")]
+mod net;
 mod picker;
+pub mod tui;
+mod users;
 
 #[allow(unused)]
 #[derive(Debug)]
@@ -30,22 +40,111 @@ enum Filter {
     EUser(String),
 }
 
+/// A value of `-p`: a PID, or `-` for the PIDs read from the standard input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PidArg {
+    Pid(u32),
+    Stdin,
+}
+
+fn parse_pid_arg(input: &str) -> Result<PidArg, String> {
+    match input {
+        "-" => Ok(PidArg::Stdin),
+        _ => input
+            .parse()
+            .map(PidArg::Pid)
+            .map_err(|_| format!("invalid PID '{input}'")),
+    }
+}
+
+/// The PIDs of a list such as the output of `pgrep`, separated by whitespace or commas.
+fn parse_pid_list(input: &str) -> UResult<Vec<u32>> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|it| !it.is_empty())
+        .map(|it| {
+            it.parse().map_err(|_| {
+                USimpleError::new(1, format!("invalid PID '{it}' in the standard input"))
+            })
+        })
+        .collect()
+}
+
+/// The PIDs given to `-p`, the standard input being read at most once.
+fn pid_list(args: &[PidArg]) -> UResult<Vec<u32>> {
+    let mut pids = Vec::new();
+    let mut stdin_read = false;
+    for arg in args {
+        match arg {
+            PidArg::Pid(pid) => pids.push(*pid),
+            PidArg::Stdin if !stdin_read => {
+                stdin_read = true;
+                let input = std::io::read_to_string(std::io::stdin())?;
+                pids.extend(parse_pid_list(&input)?);
+            }
+            PidArg::Stdin => {}
+        }
+    }
+    Ok(pids)
+}
+
 #[derive(Debug)]
 struct Settings {
     // batch:bool
     filter: Option<Filter>,
     width: Option<usize>,
+    scale_summary_mem: Scale,
+    scale_task_mem: Scale,
+    per_cpu: bool,
+    /// Shows the per-user summary instead of the task list
+    users_window: bool,
+    users_sort: users::UserColumn,
+    /// Shows the command lines of the tasks rather than their names
+    full_command: bool,
+    /// Shows the tasks which didn't use any CPU since the last update
+    show_idle: bool,
+    /// Includes the CPU time of the dead children of the tasks
+    cumulative: bool,
+    /// Only shown on the help screen, as top has none of the commands secure mode restricts yet,
+    /// such as killing or renicing tasks
+    secure: bool,
+    /// Adds the columns of the sockets of the tasks, with the `net` feature
+    net: bool,
 }
 
 impl Settings {
     fn new(matches: &ArgMatches) -> Self {
-        let width = matches.get_one::<usize>("width").cloned();
+        // Without a value, -w extends the width as much as possible
+        let width = matches.get_one::<u64>("width").map(|width| match *width {
+            0 => max_width(),
+            width => width as usize,
+        });
 
         Self {
             width,
             filter: None,
+            scale_summary_mem: Self::scale(matches, "scale-summary-mem"),
+            scale_task_mem: Self::scale(matches, "scale-task-mem"),
+            per_cpu: matches.get_flag("single-cpu-toggle"),
+            users_window: false,
+            users_sort: Default::default(),
+            full_command: matches.get_flag("cmdline-toggle"),
+            show_idle: !matches.get_flag("idle-toggle"),
+            cumulative: matches.get_flag("accum-time-toggle"),
+            secure: matches.get_flag("secure-mode"),
+            net: cfg!(feature = "net") && matches.get_flag("net"),
         }
     }
+
+    fn scale(matches: &ArgMatches, id: &str) -> Scale {
+        matches
+            .get_one::<String>(id)
+            .and_then(|it| Scale::from_suffix(it))
+            .unwrap_or(match id {
+                "scale-task-mem" => Scale::Kib,
+                _ => Scale::Mib,
+            })
+    }
 }
 
 #[uucore::main]
@@ -55,15 +154,24 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     // Must refresh twice.
     // https://docs.rs/sysinfo/0.31.2/sysinfo/struct.System.html#method.refresh_cpu_usage
     picker::sysinfo().write().unwrap().refresh_all();
+    #[cfg(target_os = "linux")]
+    header::sample_cpu();
     sleep(Duration::from_millis(200));
     picker::sysinfo().write().unwrap().refresh_all();
 
     let settings = Settings::new(&matches);
 
+    let pid_args = matches
+        .get_many::<PidArg>("pid")
+        .map(|args| args.copied().collect::<Vec<_>>());
+    let pids_from_stdin = pid_args
+        .as_ref()
+        .is_some_and(|args| args.contains(&PidArg::Stdin));
+
     let settings = {
-        let filter = matches
-            .get_many::<u32>("pid")
-            .map(|pidlist| Filter::Pid(pidlist.cloned().collect()))
+        let filter = pid_args
+            .map(|args| pid_list(&args).map(Filter::Pid))
+            .transpose()?
             .or_else(|| {
                 matches
                     .get_one::<String>("filter-any-user")
@@ -85,10 +193,29 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         Settings { filter, ..settings }
     };
 
-    let fields = selected_fields();
-    let collected = collect(&settings, &fields);
+    let mut settings = settings;
+    // Once the PIDs are read from a pipe, the keys are read from the terminal instead
+    let keyboard = std::io::stdin().is_terminal()
+        || (pids_from_stdin && std::fs::File::open("/dev/tty").is_ok());
+    if keyboard && std::io::stdout().is_terminal() {
+        return tui::run(&mut settings);
+    }
+
+    print!("{}", frame(&settings));
+
+    Ok(())
+}
+
+/// Renders the summary area followed by the task area, or the per-user window.
+fn frame(settings: &Settings) -> String {
+    let table = if settings.users_window {
+        let mut summaries = users::summarize(&filtered_pids(settings));
+        users::sort(&mut summaries, settings.users_sort);
+        users::table(&summaries)
+    } else {
+        let fields = selected_fields(settings);
+        let collected = collect(settings, &fields);
 
-    let table = {
         let mut table = Table::new();
 
         table.set_format(*FORMAT_CLEAN);
@@ -99,29 +226,27 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         table
     };
 
-    println!("{}", header(matches.get_one::<String>("scale-summary-mem")));
-    println!("\n");
-
-    let cutter = {
-        #[inline]
-        fn f(f: impl Fn(&str) -> String + 'static) -> Box<dyn Fn(&str) -> String> {
-            Box::new(f)
-        }
+    let mut output = header(settings.scale_summary_mem, settings.per_cpu);
+    output.push_str("\n\n\n");
 
-        if let Some(width) = settings.width {
-            f(move |line: &str| apply_width(line, width))
-        } else {
-            f(|line: &str| line.to_string())
+    for line in table.to_string().lines() {
+        match settings.width {
+            Some(width) => output.push_str(&apply_width(line, width)),
+            None => output.push_str(line),
         }
-    };
+        output.push('\n');
+    }
 
-    table
-        .to_string()
-        .lines()
-        .map(cutter)
-        .for_each(|it| println!("{it}"));
+    output
+}
 
-    Ok(())
+/// Width used by -w without a value, from the COLUMNS variable as in procps.
+fn max_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|it| it.parse().ok())
+        .filter(|it| (1..=MAX_WIDTH).contains(it))
+        .unwrap_or(MAX_WIDTH)
 }
 
 fn try_into_uid<T>(input: T) -> UResult<String>
@@ -160,18 +285,35 @@ where
 }
 
 // TODO: Implement fields selecting
-fn selected_fields() -> Vec<String> {
-    vec![
+fn selected_fields(settings: &Settings) -> Vec<String> {
+    let mut fields = vec![
         "PID", "USER", "PR", "NI", "VIRT", "RES", "SHR", "S", "%CPU", "%MEM", "TIME+", "COMMAND",
-    ]
-    .into_iter()
-    .map(Into::into)
-    .collect()
+    ];
+    if settings.net {
+        fields.splice(
+            fields.len() - 1..fields.len() - 1,
+            ["SOCK", "RECVQ", "SENDQ"],
+        );
+    }
+    fields.into_iter().map(Into::into).collect()
 }
 
 fn collect(settings: &Settings, fields: &[String]) -> Vec<Vec<String>> {
-    let pickers = pickers(fields);
+    let pickers = pickers(fields, settings);
 
+    filtered_pids(settings)
+        .into_iter()
+        .map(|it| {
+            pickers
+                .iter()
+                .map(move |picker| picker(it))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Tasks matching the filter of `Settings`
+fn filtered_pids(settings: &Settings) -> Vec<u32> {
     let pids = sysinfo()
         .read()
         .unwrap()
@@ -184,12 +326,7 @@ fn collect(settings: &Settings, fields: &[String]) -> Vec<Vec<String>> {
 
     pids.into_iter()
         .filter(|pid| filter(*pid))
-        .map(|it| {
-            pickers
-                .iter()
-                .map(move |picker| picker(it))
-                .collect::<Vec<_>>()
-        })
+        .filter(|pid| settings.show_idle || !picker::is_idle(*pid))
         .collect()
 }
 
@@ -249,32 +386,40 @@ fn construct_filter(settings: &Settings) -> Box<dyn Fn(u32) -> bool> {
 
 #[allow(clippy::cognitive_complexity)]
 pub fn uu_app() -> Command {
-    Command::new(uucore::util_name())
+    let command = Command::new(uucore::util_name())
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
         .infer_long_args(true)
         .args([
             // arg!(-b  --"batch-mode"                         "run in non-interactive batch mode"),
-            // arg!(-c  --"cmdline-toggle"                     "reverse last remembered 'c' state"),
+            arg!(-c  --"cmdline-toggle"                     "reverse last remembered 'c' state"),
             // arg!(-d  --delay                <SECS>          "iterative delay as SECS [.TENTHS]"),
             arg!(-E  --"scale-summary-mem"  <SCALE>         "set mem as: k,m,g,t,p,e for SCALE"),
-            // arg!(-e  --"scale-task-mem"     <SCALE>         "set mem with: k,m,g,t,p for SCALE"),
+            arg!(-e  --"scale-task-mem"     <SCALE>         "set mem with: k,m,g,t,p for SCALE"),
             // arg!(-H  --"threads-show"                       "show tasks plus all their threads"),
-            // arg!(-i  --"idle-toggle"                        "reverse last remembered 'i' state"),
+            arg!(-i  --"idle-toggle"                        "reverse last remembered 'i' state"),
             // arg!(-n  --iterations           <NUMBER>        "exit on maximum iterations NUMBER"),
             arg!(-O  --"list-fields"                        "output all field names, then exit"),
             // arg!(-o  --"sort-override"      <FIELD>         "force sorting on this named FIELD"),
-            arg!(-p  --pid                  <PIDLIST>       "monitor only the tasks in PIDLIST")
+            arg!(-p  --pid                  <PIDLIST>       "monitor only the tasks in PIDLIST, - for stdin")
                 .action(ArgAction::Append)
-                .value_parser(value_parser!(u32))
+                .value_parser(parse_pid_arg)
                 .value_delimiter(','),
-            // arg!(-S  --"accum-time-toggle"                  "reverse last remembered 'S' state"),
-            // arg!(-s  --"secure-mode"                        "run with secure mode restrictions"),
+            arg!(-S  --"accum-time-toggle"                  "reverse last remembered 'S' state"),
+            arg!(-s  --"secure-mode"                        "secure mode, which restricts nothing yet"),
             arg!(-U  --"filter-any-user"    <USER>          "show only processes owned by USER"),
             arg!(-u  --"filter-only-euser"  <EUSER>         "show only processes owned by USER"),
-            arg!(-w  --width                <COLUMNS>       "change print width [,use COLUMNS]"),
-            // arg!(-1  --single-cpu-toggle         "reverse last remembered '1' state"),
+            arg!(-w  --width                [COLUMNS]       "change print width [,use COLUMNS]")
+                .value_parser(value_parser!(u64).range(..=MAX_WIDTH as u64))
+                .num_args(0..=1)
+                .default_missing_value("0"),
+            arg!(-'1'  --"single-cpu-toggle"                "show a line per CPU"),
         ])
-        .group(ArgGroup::new("filter").args(["pid", "filter-any-user", "filter-only-euser"]))
+        .group(ArgGroup::new("filter").args(["pid", "filter-any-user", "filter-only-euser"]));
+
+    #[cfg(feature = "net")]
+    let command = command.arg(arg!(--net "show the sockets of the tasks and their queued bytes"));
+
+    command
 }
@@ -0,0 +1,329 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! The interactive display of top, whose terminal handling is shared with hugetop.
+
+use crate::keys::{self, Action};
+use crate::{frame, picker, Settings};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use std::io::{stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use uucore::error::UResult;
+use uucore_procps::scale::Scale;
+
+/// Delay between two updates of the display.
+pub const DELAY: Duration = Duration::from_secs(3);
+
+/// How often the signals are checked while waiting for a key.
+const SIGNAL_CHECK: Duration = Duration::from_millis(50);
+
+/// Set by SIGINT and SIGTERM to quit.
+static QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Set by SIGTSTP to suspend top, once the terminal is restored.
+static SUSPEND_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Set by SIGCONT to set up the terminal again, whoever stopped top.
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+/// What the signals received since the last check ask of the event loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Quit,
+    Suspend,
+    Resume,
+}
+
+fn take_request() -> Option<Request> {
+    if QUIT_REQUESTED.swap(false, Ordering::Relaxed) {
+        Some(Request::Quit)
+    } else if SUSPEND_REQUESTED.swap(false, Ordering::Relaxed) {
+        Some(Request::Suspend)
+    } else if RESUMED.swap(false, Ordering::Relaxed) {
+        Some(Request::Resume)
+    } else {
+        None
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn record_signal(signal: nix::libc::c_int) {
+    use nix::libc::{SIGCONT, SIGTSTP};
+    match signal {
+        SIGTSTP => SUSPEND_REQUESTED.store(true, Ordering::Relaxed),
+        SIGCONT => RESUMED.store(true, Ordering::Relaxed),
+        _ => QUIT_REQUESTED.store(true, Ordering::Relaxed),
+    }
+}
+
+/// Leaves the terminal to the event loop on the signals which would otherwise stop or kill top
+/// with the terminal still in raw mode.
+#[cfg(unix)]
+pub fn install_handlers() -> UResult<()> {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+    let action = SigAction::new(
+        SigHandler::Handler(record_signal),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    for signal in [
+        Signal::SIGINT,
+        Signal::SIGTERM,
+        Signal::SIGTSTP,
+        Signal::SIGCONT,
+    ] {
+        // SAFETY: the handler only stores to atomics.
+        unsafe { sigaction(signal, &action) }.map_err(std::io::Error::from)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn install_handlers() -> UResult<()> {
+    Ok(())
+}
+
+/// Stops top until it is continued, with the terminal as it was before top started.
+#[cfg(unix)]
+pub fn suspend() -> UResult<()> {
+    use nix::sys::signal::{raise, Signal};
+
+    leave();
+    raise(Signal::SIGSTOP).map_err(std::io::Error::from)?;
+    enter()?;
+    // Continuing top delivers SIGCONT, which is handled here already
+    RESUMED.store(false, Ordering::Relaxed);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn suspend() -> UResult<()> {
+    Ok(())
+}
+
+/// Switches to raw mode on the alternate screen.
+pub fn enter() -> UResult<()> {
+    terminal::enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen, Hide)?;
+    Ok(())
+}
+
+/// Restores the terminal as `enter` found it.
+pub fn leave() {
+    let _ = execute!(stdout(), Show, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Runs top interactively until it is asked to quit, restoring the terminal afterwards.
+pub(crate) fn run(settings: &mut Settings) -> UResult<()> {
+    install_handlers()?;
+    enter()?;
+
+    let result = event_loop(settings);
+
+    leave();
+    result
+}
+
+/// What interrupted the wait for a key.
+pub enum Wake {
+    Timeout,
+    Event,
+    Request(Request),
+}
+
+/// Waits up to `timeout` for an event, checking the signals in the meantime since polling the
+/// terminal carries on when it is interrupted.
+pub fn wait(timeout: Duration) -> UResult<Wake> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(request) = take_request() {
+            return Ok(Wake::Request(request));
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Ok(Wake::Timeout);
+        }
+        if event::poll(left.min(SIGNAL_CHECK))? {
+            return Ok(Wake::Event);
+        }
+    }
+}
+
+fn event_loop(settings: &mut Settings) -> UResult<()> {
+    // Page of the help screen when it is shown
+    let mut help_page: Option<usize> = None;
+    // The first frame uses the data sampled at startup
+    let mut refresh = false;
+
+    loop {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+
+        let lines = match help_page {
+            Some(page) => {
+                let (lines, page) = help_screen(settings, page, height);
+                help_page = Some(page);
+                lines
+            }
+            None => {
+                if refresh {
+                    picker::sysinfo().write().unwrap().refresh_all();
+                }
+                refresh = true;
+                frame(settings).lines().map(String::from).collect()
+            }
+        };
+        draw(&lines, width, height)?;
+
+        match wait(DELAY)? {
+            Wake::Timeout => continue,
+            Wake::Event => {}
+            Wake::Request(Request::Quit) => return Ok(()),
+            Wake::Request(Request::Suspend) => {
+                suspend()?;
+                continue;
+            }
+            // The terminal may have been reset while top was stopped, and raw mode is only set
+            // up again once it has been left
+            Wake::Request(Request::Resume) => {
+                leave();
+                enter()?;
+                continue;
+            }
+        }
+        // Other events, such as resizing, redraw the screen
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        // Raw mode turns off the signals of these keys
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('c') => return Ok(()),
+                KeyCode::Char('z') => {
+                    suspend()?;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        let Some(key) = keys::key_char(key.code) else {
+            continue;
+        };
+
+        if let Some(page) = help_page {
+            help_page = match key {
+                ' ' => Some(page + 1),
+                'b' => Some(page.saturating_sub(1)),
+                _ => None,
+            };
+            continue;
+        }
+
+        match keys::action(key) {
+            Some(Action::Help) => help_page = Some(0),
+            Some(Action::Quit) => return Ok(()),
+            Some(Action::TogglePerCpu) => settings.per_cpu = !settings.per_cpu,
+            Some(Action::ToggleUsersWindow) => settings.users_window = !settings.users_window,
+            Some(Action::SortLeft) => settings.users_sort = settings.users_sort.shift(false),
+            Some(Action::SortRight) => settings.users_sort = settings.users_sort.shift(true),
+            Some(Action::CycleSummaryScale) => {
+                settings.scale_summary_mem = settings.scale_summary_mem.cycle(Scale::Eib);
+            }
+            // As in procps, the task area doesn't go as far as EiB
+            Some(Action::CycleTaskScale) => {
+                settings.scale_task_mem = settings.scale_task_mem.cycle(Scale::Pib);
+            }
+            Some(Action::ToggleCommandLine) => settings.full_command = !settings.full_command,
+            Some(Action::ToggleIdle) => settings.show_idle = !settings.show_idle,
+            Some(Action::ToggleCumulative) => settings.cumulative = !settings.cumulative,
+            Some(Action::Refresh) | None => {}
+        }
+    }
+}
+
+/// Lines of the given page of the help screen, the page is clamped to the last one.
+fn help_screen(settings: &Settings, page: usize, height: usize) -> (Vec<String>, usize) {
+    let help = keys::help(settings);
+    // Keep two lines for the footer
+    let per_page = height.saturating_sub(2).max(1);
+    let pages = help.len().div_ceil(per_page);
+    let page = page.min(pages.saturating_sub(1));
+
+    let mut lines = help
+        .into_iter()
+        .skip(page * per_page)
+        .take(per_page)
+        .collect::<Vec<_>>();
+    lines.push(String::new());
+    if pages > 1 {
+        lines.push(format!("{} (page {}/{pages})", keys::HELP_FOOTER, page + 1));
+    } else {
+        lines.push(keys::HELP_FOOTER.into());
+    }
+
+    (lines, page)
+}
+
+fn draw(lines: &[String], width: usize, height: usize) -> UResult<()> {
+    let mut stdout = stdout().lock();
+    execute!(stdout, MoveTo(0, 0), Clear(ClearType::All))?;
+
+    let lines = lines
+        .iter()
+        .take(height)
+        .map(|line| line.chars().take(width).collect::<String>())
+        .collect::<Vec<_>>();
+    // Raw mode does not translate newlines
+    write!(stdout, "{}", lines.join("\r\n"))?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_help_screen_paging() {
+        let settings = Settings {
+            filter: None,
+            width: None,
+            scale_summary_mem: Scale::Mib,
+            scale_task_mem: Scale::Kib,
+            per_cpu: false,
+            users_window: false,
+            users_sort: Default::default(),
+            full_command: false,
+            show_idle: true,
+            cumulative: false,
+            secure: false,
+            net: false,
+        };
+        let help = keys::help(&settings);
+
+        let (lines, page) = help_screen(&settings, 0, 100);
+        assert_eq!(page, 0);
+        assert_eq!(lines.len(), help.len() + 2);
+        assert_eq!(lines.last().unwrap(), keys::HELP_FOOTER);
+
+        // Two lines of help per page, the page is clamped to the last one
+        let (lines, page) = help_screen(&settings, 100, 4);
+        let pages = help.len().div_ceil(2);
+        assert_eq!(page, pages - 1);
+        assert_eq!(lines[0], help[(pages - 1) * 2]);
+        assert!(lines
+            .last()
+            .unwrap()
+            .ends_with(&format!("(page {pages}/{pages})")));
+    }
+}
@@ -0,0 +1,177 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::picker::sysinfo;
+use prettytable::{format::consts::FORMAT_CLEAN, Row, Table};
+use std::collections::BTreeMap;
+use sysinfo::{Pid, Users};
+
+/// Columns of the per-user window, which can all be sorted on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UserColumn {
+    User,
+    Tasks,
+    #[default]
+    Cpu,
+    Res,
+}
+
+impl UserColumn {
+    const ALL: [Self; 4] = [Self::User, Self::Tasks, Self::Cpu, Self::Res];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::User => "USER",
+            Self::Tasks => "TASKS",
+            Self::Cpu => "%CPU",
+            Self::Res => "RES",
+        }
+    }
+
+    /// The column on the left, or on the right, wrapping around.
+    pub(crate) fn shift(self, right: bool) -> Self {
+        let index = Self::ALL.iter().position(|it| *it == self).unwrap();
+        let len = Self::ALL.len();
+        Self::ALL[if right {
+            (index + 1) % len
+        } else {
+            (index + len - 1) % len
+        }]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UserSummary {
+    pub(crate) user: String,
+    pub(crate) tasks: usize,
+    pub(crate) cpu: f32,
+    /// Resident memory in KiB
+    pub(crate) res: u64,
+}
+
+/// Adds up the `(user, %CPU, resident KiB)` of every task by user.
+pub(crate) fn aggregate(tasks: impl IntoIterator<Item = (String, f32, u64)>) -> Vec<UserSummary> {
+    let mut users = BTreeMap::<String, UserSummary>::new();
+
+    for (user, cpu, res) in tasks {
+        let summary = users.entry(user.clone()).or_insert(UserSummary {
+            user,
+            tasks: 0,
+            cpu: 0.0,
+            res: 0,
+        });
+        summary.tasks += 1;
+        summary.cpu += cpu;
+        summary.res += res;
+    }
+
+    users.into_values().collect()
+}
+
+/// Sorts the users on the column, the largest values first except for the names.
+pub(crate) fn sort(summaries: &mut [UserSummary], column: UserColumn) {
+    match column {
+        UserColumn::User => summaries.sort_by(|a, b| a.user.cmp(&b.user)),
+        UserColumn::Tasks => summaries.sort_by_key(|it| std::cmp::Reverse(it.tasks)),
+        UserColumn::Cpu => summaries.sort_by(|a, b| b.cpu.total_cmp(&a.cpu)),
+        UserColumn::Res => summaries.sort_by_key(|it| std::cmp::Reverse(it.res)),
+    }
+}
+
+/// Summaries of the users owning the given tasks.
+pub(crate) fn summarize(pids: &[u32]) -> Vec<UserSummary> {
+    let binding = sysinfo().read().unwrap();
+    let users = Users::new_with_refreshed_list();
+
+    aggregate(pids.iter().filter_map(|pid| {
+        let proc = binding.process(Pid::from_u32(*pid))?;
+        let user = match proc.user_id() {
+            Some(uid) => users
+                .get_user_by_id(uid)
+                .map_or_else(|| uid.to_string(), |it| it.name().to_string()),
+            None => "?".into(),
+        };
+        Some((user, proc.cpu_usage(), proc.memory() / 1024))
+    }))
+}
+
+pub(crate) fn table(summaries: &[UserSummary]) -> Table {
+    let mut table = Table::new();
+    table.set_format(*FORMAT_CLEAN);
+
+    table.add_row(Row::from_iter(UserColumn::ALL.map(UserColumn::name)));
+    table.extend(summaries.iter().map(|summary| {
+        Row::from_iter([
+            summary.user.clone(),
+            summary.tasks.to_string(),
+            format!("{:.1}", summary.cpu),
+            summary.res.to_string(),
+        ])
+    }));
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_and_sort() {
+        let mut summaries = aggregate([
+            ("root".to_string(), 1.5, 100),
+            ("alice".to_string(), 20.0, 50),
+            ("root".to_string(), 2.5, 300),
+            ("bob".to_string(), 0.0, 1000),
+        ]);
+        assert_eq!(
+            summaries,
+            [
+                UserSummary {
+                    user: "alice".into(),
+                    tasks: 1,
+                    cpu: 20.0,
+                    res: 50
+                },
+                UserSummary {
+                    user: "bob".into(),
+                    tasks: 1,
+                    cpu: 0.0,
+                    res: 1000
+                },
+                UserSummary {
+                    user: "root".into(),
+                    tasks: 2,
+                    cpu: 4.0,
+                    res: 400
+                },
+            ]
+        );
+
+        let users = |summaries: &[UserSummary]| {
+            summaries
+                .iter()
+                .map(|it| it.user.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        sort(&mut summaries, UserColumn::Cpu);
+        assert_eq!(users(&summaries), "alice,root,bob");
+        sort(&mut summaries, UserColumn::Res);
+        assert_eq!(users(&summaries), "bob,root,alice");
+        sort(&mut summaries, UserColumn::Tasks);
+        assert_eq!(users(&summaries)[..4], *"root");
+        sort(&mut summaries, UserColumn::User);
+        assert_eq!(users(&summaries), "alice,bob,root");
+    }
+
+    #[test]
+    fn test_shift() {
+        assert_eq!(UserColumn::Cpu.shift(true), UserColumn::Res);
+        assert_eq!(UserColumn::Res.shift(true), UserColumn::User);
+        assert_eq!(UserColumn::User.shift(false), UserColumn::Res);
+        assert_eq!(UserColumn::Cpu.shift(false), UserColumn::Tasks);
+    }
+}
@@ -0,0 +1,216 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::ProcData;
+use std::collections::HashMap;
+use std::fmt::Write;
+use uucore_procps::prometheus::header;
+
+/// Columns of the `cpu` lines of `/proc/stat`.
+const CPU_MODES: [&str; 10] = [
+    "user",
+    "nice",
+    "system",
+    "idle",
+    "iowait",
+    "irq",
+    "softirq",
+    "steal",
+    "guest",
+    "guest_nice",
+];
+
+/// Single value lines of `/proc/stat`: key, metric name, type and help text.
+const STAT_METRICS: [(&str, &str, &str, &str); 7] = [
+    (
+        "intr",
+        "vmstat_interrupts_total",
+        "counter",
+        "Interrupts serviced since boot.",
+    ),
+    (
+        "ctxt",
+        "vmstat_context_switches_total",
+        "counter",
+        "Context switches since boot.",
+    ),
+    (
+        "btime",
+        "vmstat_boot_time_seconds",
+        "gauge",
+        "Boot time, in seconds since the epoch.",
+    ),
+    (
+        "processes",
+        "vmstat_forks_total",
+        "counter",
+        "Processes and threads created since boot.",
+    ),
+    (
+        "procs_running",
+        "vmstat_procs_running",
+        "gauge",
+        "Processes in a runnable state.",
+    ),
+    (
+        "procs_blocked",
+        "vmstat_procs_blocked",
+        "gauge",
+        "Processes blocked waiting for I/O.",
+    ),
+    (
+        "softirq",
+        "vmstat_softirqs_total",
+        "counter",
+        "Softirqs serviced since boot.",
+    ),
+];
+
+/// Renders every sampled counter in the Prometheus text exposition format.
+pub(crate) fn render(proc_data: &ProcData) -> String {
+    let mut output = String::new();
+
+    header(
+        &mut output,
+        "vmstat_uptime_seconds",
+        "gauge",
+        "Time since boot.",
+    );
+    let _ = writeln!(output, "vmstat_uptime_seconds {}", proc_data.uptime.0);
+    header(
+        &mut output,
+        "vmstat_idle_seconds_total",
+        "counter",
+        "Time spent idle by all the CPUs since boot.",
+    );
+    let _ = writeln!(output, "vmstat_idle_seconds_total {}", proc_data.uptime.1);
+
+    header(
+        &mut output,
+        "vmstat_cpu_ticks_total",
+        "counter",
+        "Clock ticks spent by the CPUs in each mode.",
+    );
+    for (key, values) in sorted(&proc_data.stat) {
+        let Some(cpu) = key.strip_prefix("cpu") else {
+            continue;
+        };
+        let cpu = if cpu.is_empty() { "all" } else { cpu };
+        for (mode, value) in CPU_MODES.iter().zip(values.split_whitespace()) {
+            let _ = writeln!(
+                output,
+                "vmstat_cpu_ticks_total{{cpu=\"{cpu}\",mode=\"{mode}\"}} {value}"
+            );
+        }
+    }
+
+    for (key, metric, kind, help) in STAT_METRICS {
+        // Only the total is kept for the lines with a column per source
+        let Some(value) = proc_data
+            .stat
+            .get(key)
+            .and_then(|it| it.split_whitespace().next())
+        else {
+            continue;
+        };
+        header(&mut output, metric, kind, help);
+        let _ = writeln!(output, "{metric} {value}");
+    }
+
+    let (bytes, pages): (Vec<_>, Vec<_>) = sorted(&proc_data.meminfo)
+        .into_iter()
+        .partition(|(_, value)| value.ends_with("kB"));
+    header(
+        &mut output,
+        "vmstat_memory_bytes",
+        "gauge",
+        "Memory statistics from /proc/meminfo.",
+    );
+    for (key, value) in bytes {
+        if let Some(kib) = value.split_whitespace().next().and_then(number) {
+            let _ = writeln!(
+                output,
+                "vmstat_memory_bytes{{field=\"{key}\"}} {}",
+                kib * 1024
+            );
+        }
+    }
+    header(
+        &mut output,
+        "vmstat_memory_pages",
+        "gauge",
+        "Memory statistics from /proc/meminfo counted in pages.",
+    );
+    for (key, value) in pages {
+        if let Some(value) = number(value) {
+            let _ = writeln!(output, "vmstat_memory_pages{{field=\"{key}\"}} {value}");
+        }
+    }
+
+    // Both gauges and counters live in /proc/vmstat
+    header(
+        &mut output,
+        "vmstat_vm_events",
+        "untyped",
+        "Virtual memory statistics from /proc/vmstat.",
+    );
+    for (key, value) in sorted(&proc_data.vmstat) {
+        if let Some(value) = number(value) {
+            let _ = writeln!(output, "vmstat_vm_events{{counter=\"{key}\"}} {value}");
+        }
+    }
+
+    output
+}
+
+fn sorted(map: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+fn number(value: &str) -> Option<u64> {
+    value.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let proc_data = ProcData::from_snapshot(
+            "# vmstat snapshot v1\n\
+             [uptime]\n\
+             100.5 300.25\n\
+             [stat]\n\
+             cpu 10 1 5 80 4 0 0 0 0 0\n\
+             cpu0 10 1 5 80 4 0 0 0 0 0\n\
+             ctxt 1234\n\
+             intr 99 1 2 3\n\
+             btime 1700000000\n\
+             [meminfo]\n\
+             MemTotal 2048 kB\n\
+             HugePages_Total 4\n\
+             [vmstat]\n\
+             pgfault 42\n\
+             pgpgin 7\n",
+        )
+        .unwrap();
+
+        let output = render(&proc_data);
+        assert!(output.contains("vmstat_uptime_seconds 100.5\n"));
+        assert!(output.contains("vmstat_idle_seconds_total 300.25\n"));
+        assert!(output.contains("vmstat_cpu_ticks_total{cpu=\"all\",mode=\"user\"} 10\n"));
+        assert!(output.contains("vmstat_cpu_ticks_total{cpu=\"0\",mode=\"iowait\"} 4\n"));
+        assert!(output.contains("# TYPE vmstat_context_switches_total counter\n"));
+        assert!(output.contains("vmstat_context_switches_total 1234\n"));
+        assert!(output.contains("vmstat_interrupts_total 99\n"));
+        assert!(output.contains("vmstat_memory_bytes{field=\"MemTotal\"} 2097152\n"));
+        assert!(output.contains("vmstat_memory_pages{field=\"HugePages_Total\"} 4\n"));
+        assert!(output.contains("vmstat_vm_events{counter=\"pgfault\"} 42\n"));
+        assert!(!output.contains("vmstat_procs_running"));
+    }
+}
@@ -10,6 +10,11 @@ use std::collections::HashMap;
 pub fn parse_proc_file(path: &str) -> HashMap<String, String> {
     let file = std::fs::File::open(std::path::Path::new(path)).unwrap();
     let content = std::io::read_to_string(file).unwrap();
+    parse_proc_content(&content)
+}
+
+#[cfg(target_os = "linux")]
+pub fn parse_proc_content(content: &str) -> HashMap<String, String> {
     let mut map: HashMap<String, String> = HashMap::new();
 
     for line in content.lines() {
@@ -56,13 +61,94 @@ impl ProcData {
     fn get_uptime() -> (f64, f64) {
         let file = std::fs::File::open(std::path::Path::new("/proc/uptime")).unwrap();
         let content = std::io::read_to_string(file).unwrap();
+        Self::parse_uptime(&content).unwrap()
+    }
+
+    fn parse_uptime(content: &str) -> Option<(f64, f64)> {
         let mut parts = content.split_whitespace();
-        let uptime = parts.next().unwrap().parse::<f64>().unwrap();
-        let idle_time = parts.next().unwrap().parse::<f64>().unwrap();
-        (uptime, idle_time)
+        let uptime = parts.next()?.parse::<f64>().ok()?;
+        let idle_time = parts.next()?.parse::<f64>().ok()?;
+        Some((uptime, idle_time))
+    }
+
+    /// Boot time in seconds since the epoch, as reported by the `btime` line of `/proc/stat`.
+    pub fn boot_time(&self) -> Option<u64> {
+        self.stat.get("btime")?.trim().parse().ok()
+    }
+
+    // Serializes all raw counters into a plain text snapshot which can be loaded again with
+    // `from_snapshot`, e.g. on another day or after a reboot.
+    pub fn to_snapshot(&self) -> String {
+        let mut output = format!("{SNAPSHOT_MAGIC}\n");
+        output.push_str(&format!("[uptime]\n{} {}\n", self.uptime.0, self.uptime.1));
+        for (section, map) in [
+            ("stat", &self.stat),
+            ("meminfo", &self.meminfo),
+            ("vmstat", &self.vmstat),
+        ] {
+            output.push_str(&format!("[{section}]\n"));
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                output.push_str(&format!("{key} {}\n", map[key]));
+            }
+        }
+        output
+    }
+
+    pub fn from_snapshot(content: &str) -> Result<Self, String> {
+        let mut lines = content.lines();
+        if lines.next() != Some(SNAPSHOT_MAGIC) {
+            return Err("not a vmstat snapshot".into());
+        }
+
+        let mut sections: HashMap<&str, String> = HashMap::new();
+        let mut current = None;
+        for line in lines {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = Some(name);
+                sections.entry(name).or_default();
+            } else if let Some(name) = current {
+                let section = sections.get_mut(name).unwrap();
+                section.push_str(line);
+                section.push('\n');
+            } else {
+                return Err(format!("unexpected line '{line}'"));
+            }
+        }
+
+        let mut take = |name: &str| {
+            sections
+                .remove(name)
+                .ok_or_else(|| format!("missing section [{name}]"))
+        };
+        let uptime = Self::parse_uptime(&take("uptime")?).ok_or("invalid section [uptime]")?;
+        let stat = parse_proc_content(&take("stat")?);
+        let meminfo = parse_proc_content(&take("meminfo")?);
+        let vmstat = parse_proc_content(&take("vmstat")?);
+
+        for (section, map, key) in [
+            ("stat", &stat, "cpu"),
+            ("meminfo", &meminfo, "MemTotal"),
+            ("vmstat", &vmstat, "pgpgin"),
+        ] {
+            if !map.contains_key(key) {
+                return Err(format!("missing '{key}' in section [{section}]"));
+            }
+        }
+
+        Ok(Self {
+            uptime,
+            stat,
+            meminfo,
+            vmstat,
+        })
     }
 }
 
+#[cfg(target_os = "linux")]
+const SNAPSHOT_MAGIC: &str = "# vmstat snapshot v1";
+
 #[cfg(target_os = "linux")]
 pub struct CpuLoad {
     pub user: f64,
@@ -103,6 +189,49 @@ impl CpuLoad {
         let steal_time = load[7].parse::<f64>().unwrap_or_default(); // since 2.6.11
         let guest = load[8].parse::<f64>().unwrap_or_default(); // since 2.6.24
         let guest_nice = load[9].parse::<f64>().unwrap_or_default(); // since 2.6.33
+        Self::from_values([
+            user,
+            nice,
+            system,
+            idle,
+            io_wait,
+            hardware_interrupt,
+            software_interrupt,
+            steal_time,
+            guest,
+            guest_nice,
+        ])
+    }
+
+    /// Percentages of the ticks spent in each state, an interval without any tick is reported
+    /// as fully idle.
+    pub fn from_ticks(ticks: &CpuTicks) -> Self {
+        if ticks.total() == 0 {
+            let mut values = [0.0; 10];
+            values[3] = 1.0;
+            return Self::from_values(values);
+        }
+
+        Self::from_values(
+            [
+                ticks.user,
+                ticks.nice,
+                ticks.system,
+                ticks.idle,
+                ticks.io_wait,
+                ticks.hardware_interrupt,
+                ticks.software_interrupt,
+                ticks.steal_time,
+                ticks.guest,
+                ticks.guest_nice,
+            ]
+            .map(|it| it as f64),
+        )
+    }
+
+    fn from_values(values: [f64; 10]) -> Self {
+        let [user, nice, system, idle, io_wait, hardware_interrupt, software_interrupt, steal_time, guest, guest_nice] =
+            values;
         let total = user
             + nice
             + system
@@ -128,6 +257,174 @@ impl CpuLoad {
     }
 }
 
+/// Tick counters of a `cpu` line of `/proc/stat`
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTicks {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub io_wait: u64,
+    pub hardware_interrupt: u64,
+    pub software_interrupt: u64,
+    pub steal_time: u64,
+    pub guest: u64,
+    pub guest_nice: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl CpuTicks {
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut values = s.split_whitespace().map(|it| it.parse::<u64>().ok());
+        let mut next = || values.next().flatten().unwrap_or_default();
+        let ticks = Self {
+            user: next(),
+            nice: next(),
+            system: next(),
+            idle: next(),
+            io_wait: next(),
+            hardware_interrupt: next(),
+            software_interrupt: next(),
+            steal_time: next(),
+            guest: next(),
+            guest_nice: next(),
+        };
+        (!s.trim().is_empty()).then_some(ticks)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.io_wait
+            + self.hardware_interrupt
+            + self.software_interrupt
+            + self.steal_time
+            + self.guest
+            + self.guest_nice
+    }
+
+    /// Ticks spent since `earlier`, counters which went backwards count as zero.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            user: self.user.saturating_sub(earlier.user),
+            nice: self.nice.saturating_sub(earlier.nice),
+            system: self.system.saturating_sub(earlier.system),
+            idle: self.idle.saturating_sub(earlier.idle),
+            io_wait: self.io_wait.saturating_sub(earlier.io_wait),
+            hardware_interrupt: self
+                .hardware_interrupt
+                .saturating_sub(earlier.hardware_interrupt),
+            software_interrupt: self
+                .software_interrupt
+                .saturating_sub(earlier.software_interrupt),
+            steal_time: self.steal_time.saturating_sub(earlier.steal_time),
+            guest: self.guest.saturating_sub(earlier.guest),
+            guest_nice: self.guest_nice.saturating_sub(earlier.guest_nice),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::ops::Add for CpuTicks {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            user: self.user + other.user,
+            nice: self.nice + other.nice,
+            system: self.system + other.system,
+            idle: self.idle + other.idle,
+            io_wait: self.io_wait + other.io_wait,
+            hardware_interrupt: self.hardware_interrupt + other.hardware_interrupt,
+            software_interrupt: self.software_interrupt + other.software_interrupt,
+            steal_time: self.steal_time + other.steal_time,
+            guest: self.guest + other.guest,
+            guest_nice: self.guest_nice + other.guest_nice,
+        }
+    }
+}
+
+/// Tick counters of all the CPUs in `/proc/stat`, offline CPUs have no `cpuN` line.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuStat {
+    pub total: CpuTicks,
+    pub cpus: std::collections::BTreeMap<usize, CpuTicks>,
+}
+
+#[cfg(target_os = "linux")]
+impl CpuStat {
+    pub fn current() -> std::io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string("/proc/stat")?))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut stat = Self::default();
+        for line in content.lines() {
+            let Some((name, values)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(id) = name.strip_prefix("cpu") else {
+                continue;
+            };
+            let Some(ticks) = CpuTicks::parse(values) else {
+                continue;
+            };
+            if id.is_empty() {
+                stat.total = ticks;
+            } else if let Ok(id) = id.parse::<usize>() {
+                stat.cpus.insert(id, ticks);
+            }
+        }
+        stat
+    }
+
+    /// Ticks spent since `earlier`.
+    ///
+    /// Only the CPUs online in both samples are counted: the ones which went offline in between
+    /// took their ticks out of the aggregate line, and the ones which came online only have their
+    /// counters since boot.
+    pub fn since(&self, earlier: &Self) -> Self {
+        let cpus = self
+            .cpus
+            .iter()
+            .filter_map(|(id, ticks)| Some((*id, ticks.since(earlier.cpus.get(id)?))))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        let total = if self.cpus.is_empty() || earlier.cpus.is_empty() {
+            self.total.since(&earlier.total)
+        } else {
+            cpus.values()
+                .copied()
+                .fold(CpuTicks::default(), |sum, it| sum + it)
+        };
+        Self { total, cpus }
+    }
+}
+
+/// CPUs which can be brought online, from `/sys/devices/system/cpu/present`
+#[cfg(target_os = "linux")]
+pub fn present_cpus() -> Vec<usize> {
+    std::fs::read_to_string("/sys/devices/system/cpu/present")
+        .map(|it| parse_cpu_list(&it))
+        .unwrap_or_default()
+}
+
+/// Parses a kernel CPU list such as `0-3,8,10-11`.
+#[cfg(target_os = "linux")]
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+            None => range.parse().ok().map(|it| it..=it),
+        })
+        .flatten()
+        .collect()
+}
+
 #[cfg(target_os = "linux")]
 pub struct Meminfo {
     pub mem_total: bytesize::ByteSize,
@@ -149,20 +446,23 @@ impl Meminfo {
     }
 
     pub fn from_proc_map(proc_map: &HashMap<String, String>) -> Self {
-        use std::str::FromStr;
-
-        let mem_total = bytesize::ByteSize::from_str(proc_map.get("MemTotal").unwrap()).unwrap();
-        let mem_free = bytesize::ByteSize::from_str(proc_map.get("MemFree").unwrap()).unwrap();
-        let mem_available =
-            bytesize::ByteSize::from_str(proc_map.get("MemAvailable").unwrap()).unwrap();
-        let buffers = bytesize::ByteSize::from_str(proc_map.get("Buffers").unwrap()).unwrap();
-        let cached = bytesize::ByteSize::from_str(proc_map.get("Cached").unwrap()).unwrap();
-        let swap_cached =
-            bytesize::ByteSize::from_str(proc_map.get("SwapCached").unwrap()).unwrap();
-        let active = bytesize::ByteSize::from_str(proc_map.get("Active").unwrap()).unwrap();
-        let inactive = bytesize::ByteSize::from_str(proc_map.get("Inactive").unwrap()).unwrap();
-        let swap_total = bytesize::ByteSize::from_str(proc_map.get("SwapTotal").unwrap()).unwrap();
-        let swap_free = bytesize::ByteSize::from_str(proc_map.get("SwapFree").unwrap()).unwrap();
+        // The values are in KiB even though the kernel labels them kB
+        let kib = |key: &str| {
+            let value = proc_map.get(key).unwrap();
+            let value = value.trim().trim_end_matches("kB").trim_end();
+            bytesize::ByteSize::kib(value.parse().unwrap())
+        };
+
+        let mem_total = kib("MemTotal");
+        let mem_free = kib("MemFree");
+        let mem_available = kib("MemAvailable");
+        let buffers = kib("Buffers");
+        let cached = kib("Cached");
+        let swap_cached = kib("SwapCached");
+        let active = kib("Active");
+        let inactive = kib("Inactive");
+        let swap_total = kib("SwapTotal");
+        let swap_free = kib("SwapFree");
         Self {
             mem_total,
             mem_free,
@@ -177,3 +477,344 @@ impl Meminfo {
         }
     }
 }
+
+/// A memory zone from `/proc/zoneinfo`, all values are in pages.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Zone {
+    pub node: u32,
+    pub name: String,
+    pub free: u64,
+    pub min: u64,
+    pub low: u64,
+    pub high: u64,
+    pub spanned: u64,
+    pub present: u64,
+    pub managed: u64,
+    pub protection: Vec<u64>,
+}
+
+#[cfg(target_os = "linux")]
+impl Zone {
+    /// Pages kept back from userspace allocations, see `calculate_totalreserve_pages()` in the
+    /// kernel sources.
+    pub fn reserved(&self) -> u64 {
+        let protection = self.protection.iter().max().copied().unwrap_or_default();
+        (protection + self.high).min(self.managed)
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Zoneinfo {
+    pub zones: Vec<Zone>,
+}
+
+#[cfg(target_os = "linux")]
+impl Zoneinfo {
+    pub fn current() -> Result<Self, std::io::Error> {
+        Self::parse(&std::fs::read_to_string("/proc/zoneinfo")?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, std::io::Error> {
+        let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidData);
+        let number = |s: &str| s.trim().parse::<u64>().map_err(|_| invalid());
+
+        let mut zones: Vec<Zone> = Vec::new();
+        for line in content.lines() {
+            // e.g. "Node 0, zone   Normal"
+            if let Some(header) = line.strip_prefix("Node ") {
+                let (node, name) = header.split_once(", zone").ok_or_else(invalid)?;
+                zones.push(Zone {
+                    node: node.trim().parse().map_err(|_| invalid())?,
+                    name: name.trim().to_string(),
+                    ..Default::default()
+                });
+                continue;
+            }
+            let Some(zone) = zones.last_mut() else {
+                continue;
+            };
+
+            let line = line.trim_start();
+            if let Some(list) = line.strip_prefix("protection:") {
+                zone.protection = list
+                    .trim()
+                    .trim_matches(|c| c == '(' || c == ')')
+                    .split(',')
+                    .map(number)
+                    .collect::<Result<_, _>>()?;
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let field = match (words.next(), words.next()) {
+                (Some("pages"), Some("free")) => &mut zone.free,
+                (Some("min"), _) => &mut zone.min,
+                (Some("low"), _) => &mut zone.low,
+                (Some("high"), _) => &mut zone.high,
+                (Some("spanned"), _) => &mut zone.spanned,
+                (Some("present"), _) => &mut zone.present,
+                (Some("managed"), _) => &mut zone.managed,
+                _ => continue,
+            };
+            *field = number(line.split_whitespace().last().ok_or_else(invalid)?)?;
+        }
+
+        Ok(Self { zones })
+    }
+
+    /// Sum of the low watermarks of all zones, in pages.
+    pub fn low_watermark(&self) -> u64 {
+        self.zones.iter().map(|zone| zone.low).sum()
+    }
+
+    /// Sum of the reserved pages of all zones, see [`Zone::reserved`].
+    pub fn reserved(&self) -> u64 {
+        self.zones.iter().map(Zone::reserved).sum()
+    }
+}
+
+/// Increase of a counter between two readings, along with whether it went backwards.
+///
+/// A counter still fitting in 32 bits which went backwards wrapped around, as the counters of
+/// `/proc/vmstat` do on 32-bit kernels. Larger ones cannot wrap in practice, so they were reset
+/// and counted from 0 since.
+pub fn counter_delta(now: u64, before: u64) -> (u64, bool) {
+    if now >= before {
+        (now - before, false)
+    } else if before <= u32::MAX.into() {
+        (now + (u64::from(u32::MAX) - before) + 1, true)
+    } else {
+        (now, true)
+    }
+}
+
+/// I/O counters of a block device from `/proc/diskstats`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DiskStat {
+    pub major: u32,
+    pub minor: u32,
+    pub name: String,
+    pub reads: u64,
+    pub reads_merged: u64,
+    pub read_sectors: u64,
+    pub read_ms: u64,
+    pub writes: u64,
+    pub writes_merged: u64,
+    pub write_sectors: u64,
+    pub write_ms: u64,
+    pub io_in_progress: u64,
+    pub io_ms: u64,
+}
+
+#[cfg(target_os = "linux")]
+impl DiskStat {
+    pub fn current() -> Result<Vec<Self>, std::io::Error> {
+        Self::parse(&std::fs::read_to_string("/proc/diskstats")?)
+    }
+
+    pub fn parse(content: &str) -> Result<Vec<Self>, std::io::Error> {
+        let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidData);
+
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut words = line.split_whitespace();
+                let major = words.next().and_then(|it| it.parse().ok());
+                let minor = words.next().and_then(|it| it.parse().ok());
+                let name = words.next();
+                // Later kernels append discard and flush counters, which aren't reported
+                let mut next = || {
+                    words
+                        .next()
+                        .and_then(|it| it.parse::<u64>().ok())
+                        .ok_or_else(invalid)
+                };
+                Ok(Self {
+                    major: major.ok_or_else(invalid)?,
+                    minor: minor.ok_or_else(invalid)?,
+                    name: name.ok_or_else(invalid)?.to_string(),
+                    reads: next()?,
+                    reads_merged: next()?,
+                    read_sectors: next()?,
+                    read_ms: next()?,
+                    writes: next()?,
+                    writes_merged: next()?,
+                    write_sectors: next()?,
+                    write_ms: next()?,
+                    io_in_progress: next()?,
+                    io_ms: next()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the device is a whole disk rather than a partition, the disks being the entries of
+    /// `/sys/block`.
+    pub fn is_disk(&self) -> bool {
+        std::path::Path::new("/sys/block")
+            .join(self.name.replace('/', "!"))
+            .exists()
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_stat_hotplug() {
+        let before = CpuStat::parse(
+            "cpu  300 0 30 600 0 0 0 0 0 0\n\
+             cpu0 100 0 10 200 0 0 0 0 0 0\n\
+             cpu1 100 0 10 200 0 0 0 0 0 0\n\
+             cpu2 100 0 10 200 0 0 0 0 0 0\n\
+             intr 1 2 3\n",
+        );
+        // cpu1 went offline and cpu3 came online, cpu2 counters went backwards
+        let after = CpuStat::parse(
+            "cpu  290 0 30 700 0 0 0 0 0 0\n\
+             cpu0 150 0 10 250 0 0 0 0 0 0\n\
+             cpu2 90 0 10 200 0 0 0 0 0 0\n\
+             cpu3 50 0 10 50 0 0 0 0 0 0\n",
+        );
+
+        let delta = after.since(&before);
+        assert_eq!(delta.cpus.keys().copied().collect::<Vec<_>>(), [0, 2]);
+        // The sum of cpu0 and cpu2, although the aggregate user ticks went backwards
+        assert_eq!(delta.total.user, 50);
+        assert_eq!(delta.total.idle, 50);
+        assert_eq!(delta.cpus[&0].total(), 100);
+        assert_eq!(delta.cpus[&2].total(), 0);
+
+        let load = CpuLoad::from_ticks(&delta.cpus[&2]);
+        assert_eq!(load.idle, 100.0);
+        assert_eq!(load.user, 0.0);
+        let load = CpuLoad::from_ticks(&delta.cpus[&0]);
+        assert_eq!(load.user, 50.0);
+    }
+
+    #[test]
+    fn test_counter_delta() {
+        assert_eq!(counter_delta(150, 100), (50, false));
+        assert_eq!(counter_delta(100, 100), (0, false));
+        // Wrapped around at 2^32
+        assert_eq!(counter_delta(10, u64::from(u32::MAX) - 9), (20, true));
+        assert_eq!(counter_delta(0, u64::from(u32::MAX)), (1, true));
+        // Reset
+        assert_eq!(counter_delta(10, 1 << 40), (10, true));
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("0"), [0]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    const ZONEINFO: &str = "Node 0, zone      DMA
+  per-node stats
+      nr_inactive_anon 41787
+  pages free     3840
+        boost    0
+        min      42
+        low      52
+        high     62
+        spanned  4095
+        present  3998
+        managed  3840
+        cma      0
+        protection: (0, 3024, 5988, 5988, 5988)
+      nr_free_pages 3840
+Node 0, zone   Normal
+  pages free     16236
+        boost    0
+        min      8340
+        low      10425
+        high     12510
+        spanned  786432
+        present  786432
+        managed  758676
+        protection: (0, 0, 0, 0, 0)
+Node 1, zone  Movable
+  pages free     0
+        min      32
+        low      32
+        high     32
+        spanned  0
+        present  0
+        managed  0
+        protection: (0, 0, 0, 0, 0)
+";
+
+    #[test]
+    fn test_parse_zoneinfo() {
+        let zoneinfo = Zoneinfo::parse(ZONEINFO).unwrap();
+        assert_eq!(zoneinfo.zones.len(), 3);
+
+        let dma = &zoneinfo.zones[0];
+        assert_eq!(dma.node, 0);
+        assert_eq!(dma.name, "DMA");
+        assert_eq!(dma.free, 3840);
+        assert_eq!((dma.min, dma.low, dma.high), (42, 52, 62));
+        assert_eq!((dma.spanned, dma.present, dma.managed), (4095, 3998, 3840));
+        assert_eq!(dma.protection, vec![0, 3024, 5988, 5988, 5988]);
+
+        assert_eq!(zoneinfo.zones[2].node, 1);
+        assert_eq!(zoneinfo.zones[2].name, "Movable");
+    }
+
+    #[test]
+    fn test_zoneinfo_totals() {
+        let zoneinfo = Zoneinfo::parse(ZONEINFO).unwrap();
+        assert_eq!(zoneinfo.low_watermark(), 52 + 10425 + 32);
+        // DMA is capped by its managed pages, Movable has no managed pages at all
+        assert_eq!(zoneinfo.reserved(), 3840 + 12510);
+    }
+
+    #[test]
+    fn test_parse_invalid_zoneinfo() {
+        assert!(Zoneinfo::parse("Node x, zone DMA").is_err());
+        assert!(Zoneinfo::parse("Node 0, zone DMA\n  pages free abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_diskstats() {
+        let disks = DiskStat::parse(
+            "   8       0 sda 101419 3476 6327106 107146 120931 73733 5823930 448923 0 170360 589164 0 0 0 0 913 33094\n\
+             \x20  8       1 sda1 512 0 4096 20 0 0 0 0 0 40 20\n",
+        )
+        .unwrap();
+        assert_eq!(disks.len(), 2);
+        assert_eq!(
+            disks[0],
+            DiskStat {
+                major: 8,
+                minor: 0,
+                name: "sda".into(),
+                reads: 101419,
+                reads_merged: 3476,
+                read_sectors: 6327106,
+                read_ms: 107146,
+                writes: 120931,
+                writes_merged: 73733,
+                write_sectors: 5823930,
+                write_ms: 448923,
+                io_in_progress: 0,
+                io_ms: 170360,
+            }
+        );
+        assert_eq!(disks[1].name, "sda1");
+        assert_eq!(disks[1].io_ms, 40);
+    }
+
+    #[test]
+    fn test_parse_invalid_diskstats() {
+        assert!(DiskStat::parse("8 0 sda 1 2 3").is_err());
+        assert!(DiskStat::parse("8 0 sda 1 2 3 4 5 6 7 8 x 10").is_err());
+    }
+}
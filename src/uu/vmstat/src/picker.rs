@@ -4,7 +4,7 @@
 // file that was distributed with this source code.
 
 #[cfg(target_os = "linux")]
-use crate::{CpuLoad, Meminfo, ProcData};
+use crate::{counter_delta, CpuLoad, CpuTicks, Meminfo, ProcData};
 #[cfg(target_os = "linux")]
 use clap::ArgMatches;
 
@@ -14,9 +14,28 @@ pub type Picker = (
     Box<dyn Fn(&ProcData, Option<&ProcData>, &ArgMatches, &mut Vec<String>, &mut usize)>,
 );
 
+/// The pickers of the columns, in wide mode with -w or when the wide columns fit in the terminal.
 #[cfg(target_os = "linux")]
 pub fn get_pickers(matches: &ArgMatches) -> Vec<Picker> {
-    let wide = matches.get_flag("wide");
+    let wide_pickers = build_pickers(matches, true);
+    let terminal_width = terminal_size::terminal_size().map(|size| size.0 .0 as usize);
+    if matches.get_flag("wide")
+        || terminal_width.is_some_and(|width| width >= line_width(&wide_pickers))
+    {
+        wide_pickers
+    } else {
+        build_pickers(matches, false)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn line_width(pickers: &[Picker]) -> usize {
+    let titles: usize = pickers.iter().map(|picker| picker.0 .1.len()).sum();
+    titles + pickers.len().saturating_sub(1)
+}
+
+#[cfg(target_os = "linux")]
+fn build_pickers(matches: &ArgMatches, wide: bool) -> Vec<Picker> {
     let mut pickers = vec![
         concat_helper(
             if wide {
@@ -24,12 +43,15 @@ pub fn get_pickers(matches: &ArgMatches) -> Vec<Picker> {
             } else {
                 ("procs".into(), " r  b".into())
             },
-            get_process_info,
+            move |proc_data, _, _| get_process_info(proc_data, wide),
         ),
         concat_helper(
             if wide {
                 (
-                    "-----------------------memory----------------------".into(),
+                    memory_title(
+                        "-----------------------memory----------------------",
+                        matches,
+                    ),
                     if matches.get_flag("active") {
                         "        swpd         free        inact       active".into()
                     } else {
@@ -38,7 +60,7 @@ pub fn get_pickers(matches: &ArgMatches) -> Vec<Picker> {
                 )
             } else {
                 (
-                    "-----------memory----------".into(),
+                    memory_title("-----------memory----------", matches),
                     if matches.get_flag("active") {
                         "  swpd   free  inact active".into()
                     } else {
@@ -46,7 +68,7 @@ pub fn get_pickers(matches: &ArgMatches) -> Vec<Picker> {
                     },
                 )
             },
-            get_memory_info,
+            move |proc_data, _, matches| get_memory_info(proc_data, matches, wide),
         ),
         concat_helper(("---swap--".into(), "  si   so".into()), get_swap_info),
         concat_helper(("-----io----".into(), "   bi    bo".into()), get_io_info),
@@ -60,24 +82,42 @@ pub fn get_pickers(matches: &ArgMatches) -> Vec<Picker> {
             } else {
                 ("-------cpu-------".into(), "us sy id wa st gu".into())
             },
-            get_cpu_info,
+            move |proc_data, proc_data_before, _| get_cpu_info(proc_data, proc_data_before, wide),
         ),
     ];
     if matches.get_flag("timestamp") {
-        pickers.push(concat_helper(
-            (
-                "-----timestamp-----".into(),
-                format!("{:>19}", uucore::custom_tz_fmt::custom_time_format("%Z")),
-            ),
-            get_timestamp,
-        ));
+        pickers.push(concat_helper(timestamp_title(), get_timestamp));
     }
 
     pickers
 }
 
+/// The title of the memory section, naming the unit of -S when one is given.
+#[cfg(target_os = "linux")]
+fn memory_title(title: &str, matches: &ArgMatches) -> String {
+    match matches.get_one::<String>("unit") {
+        Some(unit) => format!(
+            "{:-^width$}",
+            format!("memory ({})", unit_name(unit)),
+            width = title.len()
+        ),
+        None => title.into(),
+    }
+}
+
 #[cfg(target_os = "linux")]
-fn with_unit(x: u64, arg: &ArgMatches) -> u64 {
+pub(crate) fn unit_name(unit: &str) -> &'static str {
+    match unit {
+        "k" => "kB",
+        "K" => "KiB",
+        "m" => "MB",
+        "M" => "MiB",
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn with_unit(x: u64, arg: &ArgMatches) -> u64 {
     if let Some(unit) = arg.get_one::<String>("unit") {
         return match unit.as_str() {
             "k" => x / bytesize::KB,
@@ -130,78 +170,161 @@ macro_rules! diff {
     };
 }
 
+/// Increase of a counter since the previous report, or its value for the first one.
 #[cfg(target_os = "linux")]
-fn get_process_info(
+macro_rules! counter {
+    ($now:expr, $before:expr, $($property:tt)*) => {
+        if let Some(before) = &$before {
+            counter_delta($now.$($property)*, before.$($property)*).0
+        } else {
+            $now.$($property)*
+        }
+    };
+}
+
+/// The counters of the report, by the title of their column.
+#[cfg(target_os = "linux")]
+fn counters(proc_data: &ProcData) -> [(&'static str, u64); 6] {
+    let vmstat = |name: &str| {
+        proc_data
+            .vmstat
+            .get(name)
+            .and_then(|it| it.parse().ok())
+            .unwrap_or_default()
+    };
+    let stat = |name: &str| {
+        proc_data
+            .stat
+            .get(name)
+            .and_then(|it| it.split_whitespace().next())
+            .and_then(|it| it.parse().ok())
+            .unwrap_or_default()
+    };
+    [
+        ("si", vmstat("pswpin")),
+        ("so", vmstat("pswpout")),
+        ("bi", vmstat("pgpgin")),
+        ("bo", vmstat("pgpgout")),
+        ("in", stat("intr")),
+        ("cs", stat("ctxt")),
+    ]
+}
+
+/// Titles of the columns whose counter wrapped around, or was reset, since the previous report.
+#[cfg(target_os = "linux")]
+pub(crate) fn wrapped_counters(
     proc_data: &ProcData,
-    _proc_data_before: Option<&ProcData>,
-    matches: &ArgMatches,
-) -> Vec<(usize, String)> {
+    proc_data_before: &ProcData,
+) -> Vec<&'static str> {
+    counters(proc_data)
+        .into_iter()
+        .zip(counters(proc_data_before))
+        .filter(|((_, now), (_, before))| counter_delta(*now, *before).1)
+        .map(|((title, _), _)| title)
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn get_process_info(proc_data: &ProcData, wide: bool) -> Vec<(usize, String)> {
     let runnable = proc_data.stat.get("procs_running").unwrap();
     let blocked = proc_data.stat.get("procs_blocked").unwrap();
-    let len = if matches.get_flag("wide") { 4 } else { 2 };
+    let len = if wide { 4 } else { 2 };
 
     vec![(len, runnable.to_string()), (len, blocked.to_string())]
 }
 
 #[cfg(target_os = "linux")]
-fn get_memory_info(
-    proc_data: &ProcData,
-    _proc_data_before: Option<&ProcData>,
-    matches: &ArgMatches,
-) -> Vec<(usize, String)> {
-    let len = if matches.get_flag("wide") { 12 } else { 6 };
+fn get_memory_info(proc_data: &ProcData, matches: &ArgMatches, wide: bool) -> Vec<(usize, String)> {
+    let len = if wide { 12 } else { 6 };
     let memory_info = Meminfo::from_proc_map(&proc_data.meminfo);
 
-    let swap_used = with_unit(
-        (memory_info.swap_total - memory_info.swap_free).as_u64(),
-        matches,
-    );
-    let free = with_unit(memory_info.mem_free.as_u64(), matches);
-
-    if matches.get_flag("active") {
-        let inactive = with_unit(memory_info.inactive.as_u64(), matches);
-        let active = with_unit(memory_info.active.as_u64(), matches);
-        return vec![
-            (len, format!("{swap_used}")),
-            (len, format!("{free}")),
-            (len, format!("{inactive}")),
-            (len, format!("{active}")),
-        ];
-    }
+    let values = if matches.get_flag("active") {
+        [memory_info.inactive, memory_info.active]
+    } else {
+        [memory_info.buffers, memory_info.cached]
+    };
+    [
+        memory_info.swap_total - memory_info.swap_free,
+        memory_info.mem_free,
+    ]
+    .into_iter()
+    .chain(values)
+    .map(|it| {
+        (
+            len,
+            fit_column(with_unit(it.as_u64(), matches), len, matches),
+        )
+    })
+    .collect()
+}
 
-    let buffer = with_unit(memory_info.buffers.as_u64(), matches);
-    let cache = with_unit(memory_info.cached.as_u64(), matches);
+/// Formats `value`, in the unit of -S, so that it fits in `len` columns, the values which are
+/// too large being shown in the next larger unit they fit in along with its suffix.
+#[cfg(target_os = "linux")]
+fn fit_column(value: u64, len: usize, arg: &ArgMatches) -> String {
+    let unit = arg.get_one::<String>("unit").map_or("K", |it| it.as_str());
+    let factor = if unit.chars().all(char::is_lowercase) {
+        1000
+    } else {
+        1024
+    };
+    let larger = ["m", "g", "t", "p", "e"];
+    let larger = match unit.to_lowercase().as_str() {
+        "m" => &larger[1..],
+        _ => &larger[..],
+    };
 
-    vec![
-        (len, format!("{swap_used}")),
-        (len, format!("{free}")),
-        (len, format!("{buffer}")),
-        (len, format!("{cache}")),
-    ]
+    let formatted = value.to_string();
+    if formatted.len() <= len {
+        return formatted;
+    }
+    let mut value = value;
+    for suffix in larger {
+        value = value.div_ceil(factor);
+        let formatted = format!("{value}{suffix}");
+        if formatted.len() <= len {
+            return formatted;
+        }
+    }
+    formatted
 }
 
 #[cfg(target_os = "linux")]
 fn get_swap_info(
     proc_data: &ProcData,
     proc_data_before: Option<&ProcData>,
-    _matches: &ArgMatches,
+    matches: &ArgMatches,
 ) -> Vec<(usize, String)> {
     let period = diff!(proc_data, proc_data_before, uptime.0);
-    let swap_in = diff!(
+    let swap_in = counter!(
         proc_data,
         proc_data_before,
         vmstat.get("pswpin").unwrap().parse::<u64>().unwrap()
     );
-    let swap_out = diff!(
+    let swap_out = counter!(
         proc_data,
         proc_data_before,
         vmstat.get("pswpout").unwrap().parse::<u64>().unwrap()
     );
 
-    vec![
-        (4, format!("{:.0}", swap_in as f64 / period)),
-        (4, format!("{:.0}", swap_out as f64 / period)),
-    ]
+    // The counters are in pages, the amounts are shown in the unit of the memory columns
+    [swap_in, swap_out]
+        .map(|pages| {
+            let bytes = (pages * page_size()) as f64 / period;
+            (4, with_unit(bytes as u64, matches).to_string())
+        })
+        .into()
+}
+
+#[cfg(target_os = "linux")]
+fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if page_size > 0 {
+        page_size as u64
+    } else {
+        4096
+    }
 }
 
 #[cfg(target_os = "linux")]
@@ -211,12 +334,12 @@ fn get_io_info(
     _matches: &ArgMatches,
 ) -> Vec<(usize, String)> {
     let period = diff!(proc_data, proc_data_before, uptime.0);
-    let read_bytes = diff!(
+    let read_bytes = counter!(
         proc_data,
         proc_data_before,
         vmstat.get("pgpgin").unwrap().parse::<u64>().unwrap()
     );
-    let write_bytes = diff!(
+    let write_bytes = counter!(
         proc_data,
         proc_data_before,
         vmstat.get("pgpgout").unwrap().parse::<u64>().unwrap()
@@ -236,7 +359,7 @@ fn get_system_info(
 ) -> Vec<(usize, String)> {
     let period = diff!(proc_data, proc_data_before, uptime.0);
 
-    let interrupts = diff!(
+    let interrupts = counter!(
         proc_data,
         proc_data_before,
         stat.get("intr")
@@ -244,13 +367,13 @@ fn get_system_info(
             .split_whitespace()
             .next()
             .unwrap()
-            .parse::<i64>()
+            .parse::<u64>()
             .unwrap()
     );
-    let context_switches = diff!(
+    let context_switches = counter!(
         proc_data,
         proc_data_before,
-        stat.get("ctxt").unwrap().parse::<i64>().unwrap()
+        stat.get("ctxt").unwrap().parse::<u64>().unwrap()
     );
 
     vec![
@@ -262,12 +385,17 @@ fn get_system_info(
 #[cfg(target_os = "linux")]
 fn get_cpu_info(
     proc_data: &ProcData,
-    _proc_data_before: Option<&ProcData>,
-    matches: &ArgMatches,
+    proc_data_before: Option<&ProcData>,
+    wide: bool,
 ) -> Vec<(usize, String)> {
-    let len = if matches.get_flag("wide") { 3 } else { 2 };
+    let len = if wide { 3 } else { 2 };
 
-    let cpu_load = CpuLoad::from_proc_map(&proc_data.stat);
+    // The first report is the average since boot, the next ones cover the last interval
+    let ticks = |data: &ProcData| data.stat.get("cpu").and_then(|it| CpuTicks::parse(it));
+    let cpu_load = match (ticks(proc_data), proc_data_before.and_then(ticks)) {
+        (Some(now), Some(before)) => CpuLoad::from_ticks(&now.since(&before)),
+        _ => CpuLoad::from_proc_map(&proc_data.stat),
+    };
 
     vec![
         (len, format!("{:.0}", cpu_load.user)),
@@ -279,14 +407,81 @@ fn get_cpu_info(
     ]
 }
 
+/// The titles of the timestamp column of -t, the second one being the time zone.
+#[cfg(target_os = "linux")]
+pub(crate) fn timestamp_title() -> (String, String) {
+    (
+        "-----timestamp-----".into(),
+        format!("{:>19}", uucore::custom_tz_fmt::custom_time_format("%Z")),
+    )
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
 #[cfg(target_os = "linux")]
 fn get_timestamp(
     _proc_data: &ProcData,
     _proc_data_before: Option<&ProcData>,
     _matches: &ArgMatches,
 ) -> Vec<(usize, String)> {
-    vec![(
-        10,
-        chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-    )]
+    vec![(19, timestamp())]
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_fit_column() {
+        let matches = crate::uu_app().get_matches_from(["vmstat"]);
+        assert_eq!(fit_column(123_456, 6, &matches), "123456");
+        // 1.5 TiB of memory in KiB
+        assert_eq!(fit_column(1_610_612_736, 6, &matches), "1536g");
+        assert_eq!(fit_column(1_610_612_736, 12, &matches), "1610612736");
+
+        let matches = crate::uu_app().get_matches_from(["vmstat", "-S", "m"]);
+        assert_eq!(fit_column(12_345_678, 6, &matches), "12346g");
+    }
+
+    #[test]
+    fn test_cpu_info_since_last_report() {
+        let data = |cpu: &str| ProcData {
+            uptime: (0.0, 0.0),
+            stat: HashMap::from([("cpu".to_string(), cpu.to_string())]),
+            meminfo: HashMap::new(),
+            vmstat: HashMap::new(),
+        };
+        let before = data("100 0 100 800 0 0 0 0 0 0");
+        let now = data("150 0 150 800 0 0 0 0 0 0");
+
+        let since_boot = get_cpu_info(&now, None, false);
+        assert_eq!(since_boot[0].1, "14");
+        let interval = get_cpu_info(&now, Some(&before), false);
+        assert_eq!(interval[0].1, "50");
+        assert_eq!(interval[1].1, "50");
+        assert_eq!(interval[2].1, "0");
+    }
+
+    #[test]
+    fn test_memory_title() {
+        let matches = crate::uu_app().get_matches_from(["vmstat"]);
+        let title = "-----------memory----------";
+        assert_eq!(memory_title(title, &matches), title);
+
+        let matches = crate::uu_app().get_matches_from(["vmstat", "-S", "M"]);
+        assert_eq!(memory_title(title, &matches), "-------memory (MiB)--------");
+    }
+
+    #[test]
+    fn test_line_width() {
+        let matches = crate::uu_app().get_matches_from(["vmstat"]);
+        let pickers = build_pickers(&matches, false);
+        assert_eq!(line_width(&pickers), 83);
+        assert!(line_width(&build_pickers(&matches, true)) > 83);
+    }
 }
@@ -0,0 +1,139 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::picker::with_unit;
+use crate::{CpuTicks, Meminfo, ProcData};
+use clap::ArgMatches;
+use std::fmt::Write;
+
+/// Renders the table of memory totals and event counters of `-s`.
+pub(crate) fn render(proc_data: &ProcData, matches: &ArgMatches) -> String {
+    let mut output = String::new();
+    let unit = matches
+        .get_one::<String>("unit")
+        .map_or("K", |it| it.as_str());
+
+    let memory = Meminfo::from_proc_map(&proc_data.meminfo);
+    let swap_used = memory.swap_total - memory.swap_free;
+    for (value, name) in [
+        (memory.mem_total, "total memory"),
+        (memory.mem_total - memory.mem_available, "used memory"),
+        (memory.active, "active memory"),
+        (memory.inactive, "inactive memory"),
+        (memory.mem_free, "free memory"),
+        (memory.buffers, "buffer memory"),
+        (memory.cached, "swap cache"),
+        (memory.swap_total, "total swap"),
+        (swap_used, "used swap"),
+        (memory.swap_free, "free swap"),
+    ] {
+        let _ = writeln!(
+            output,
+            "{:>13} {unit} {name}",
+            with_unit(value.as_u64(), matches)
+        );
+    }
+
+    let ticks = proc_data
+        .stat
+        .get("cpu")
+        .and_then(|it| CpuTicks::parse(it))
+        .unwrap_or_default();
+    let stat = |key: &str| {
+        proc_data
+            .stat
+            .get(key)
+            .and_then(|it| it.split_whitespace().next())
+            .and_then(|it| it.parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+    let vmstat = |key: &str| {
+        proc_data
+            .vmstat
+            .get(key)
+            .and_then(|it| it.trim().parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+    for (value, name) in [
+        (ticks.user, "non-nice user cpu ticks"),
+        (ticks.nice, "nice user cpu ticks"),
+        (ticks.system, "system cpu ticks"),
+        (ticks.idle, "idle cpu ticks"),
+        (ticks.io_wait, "IO-wait cpu ticks"),
+        (ticks.hardware_interrupt, "IRQ cpu ticks"),
+        (ticks.software_interrupt, "softirq cpu ticks"),
+        (ticks.steal_time, "stolen cpu ticks"),
+        (ticks.guest, "non-nice guest cpu ticks"),
+        (ticks.guest_nice, "nice guest cpu ticks"),
+        (vmstat("pgpgin"), "pages paged in"),
+        (vmstat("pgpgout"), "pages paged out"),
+        (vmstat("pswpin"), "pages swapped in"),
+        (vmstat("pswpout"), "pages swapped out"),
+        (stat("intr"), "interrupts"),
+        (stat("ctxt"), "CPU context switches"),
+        (stat("btime"), "boot time"),
+        (stat("processes"), "forks"),
+    ] {
+        let _ = writeln!(output, "{value:>13} {name}");
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let proc_data = ProcData::from_snapshot(
+            "# vmstat snapshot v1\n\
+             [uptime]\n\
+             100.5 300.25\n\
+             [stat]\n\
+             cpu 10 1 5 80 4 2 3 0 6 0\n\
+             ctxt 1234\n\
+             intr 99 1 2 3\n\
+             btime 1700000000\n\
+             processes 321\n\
+             [meminfo]\n\
+             MemTotal 2048 kB\n\
+             MemFree 512 kB\n\
+             MemAvailable 1024 kB\n\
+             Buffers 64 kB\n\
+             Cached 128 kB\n\
+             SwapCached 0 kB\n\
+             Active 256 kB\n\
+             Inactive 32 kB\n\
+             SwapTotal 4096 kB\n\
+             SwapFree 1024 kB\n\
+             [vmstat]\n\
+             pgpgin 7\n\
+             pgpgout 8\n\
+             pswpin 1\n\
+             pswpout 2\n",
+        )
+        .unwrap();
+
+        let matches = crate::uu_app().get_matches_from(["vmstat", "-s"]);
+        let output = render(&proc_data, &matches);
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 28);
+        assert_eq!(lines[0], "         2048 K total memory");
+        assert_eq!(lines[1], "         1024 K used memory");
+        assert_eq!(lines[8], "         3072 K used swap");
+        assert_eq!(lines[10], "           10 non-nice user cpu ticks");
+        assert_eq!(lines[18], "            6 non-nice guest cpu ticks");
+        assert_eq!(lines[20], "            7 pages paged in");
+        assert!(output.contains("           99 interrupts\n"));
+        assert!(output.contains("         1234 CPU context switches\n"));
+        assert!(output.contains("   1700000000 boot time\n"));
+        assert!(output.ends_with("          321 forks\n"));
+
+        let matches = crate::uu_app().get_matches_from(["vmstat", "-s", "-S", "M"]);
+        let output = render(&proc_data, &matches);
+        assert!(output.starts_with("            2 M total memory\n"));
+    }
+}
@@ -3,18 +3,24 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#[cfg(target_os = "linux")]
+mod metrics;
 mod parser;
 mod picker;
+#[cfg(target_os = "linux")]
+mod stats;
 
 #[cfg(target_os = "linux")]
-use crate::picker::{get_pickers, Picker};
+use crate::picker::{get_pickers, timestamp, timestamp_title, wrapped_counters, Picker};
 use clap::value_parser;
 #[allow(unused_imports)]
 use clap::{arg, crate_version, ArgMatches, Command};
 #[allow(unused_imports)]
 pub use parser::*;
 #[allow(unused_imports)]
-use uucore::error::{UResult, USimpleError};
+use uucore::display::Quotable;
+#[allow(unused_imports)]
+use uucore::error::{FromIo, UResult, USimpleError};
 use uucore::{format_usage, help_about, help_usage};
 
 const ABOUT: &str = help_about!("vmstat.md");
@@ -36,8 +42,29 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             }
         }
 
+        if matches.get_flag("forks") {
+            return print_forks(&ProcData::new());
+        }
+        if matches.get_flag("stats") {
+            print!("{}", stats::render(&ProcData::new(), &matches));
+            return Ok(());
+        }
+        if matches.get_flag("metrics") {
+            print!("{}", metrics::render(&ProcData::new()));
+            return Ok(());
+        }
+        if let Some(path) = matches.get_one::<String>("snapshot") {
+            return write_snapshot(path);
+        }
+        if let Some(mut paths) = matches.get_many::<String>("diff") {
+            let before = paths.next().unwrap();
+            let after = paths.next().unwrap();
+            return print_snapshot_diff(before, after, &matches);
+        }
+
         let one_header = matches.get_flag("one-header");
         let no_first = matches.get_flag("no-first");
+        let timestamps = matches.get_flag("timestamp");
 
         let delay = matches.get_one::<u64>("delay");
         let count = matches.get_one::<u64>("count");
@@ -47,6 +74,16 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             1
         });
 
+        if matches.get_flag("slabs") {
+            return print_slabs(delay, count);
+        }
+        if matches.get_flag("disk") {
+            return print_disk_stats(one_header, timestamps, delay, count);
+        }
+        if let Some(partition) = matches.get_one::<String>("partition") {
+            return print_partition_stats(partition, timestamps, delay, count);
+        }
+
         let pickers = get_pickers(&matches);
         let mut proc_data = ProcData::new();
 
@@ -76,6 +113,204 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     Ok(())
 }
 
+#[cfg(target_os = "linux")]
+fn write_snapshot(path: &str) -> UResult<()> {
+    std::fs::write(path, ProcData::new().to_snapshot())
+        .map_err_context(|| format!("cannot write snapshot {}", path.quote()))
+}
+
+#[cfg(target_os = "linux")]
+fn read_snapshot(path: &str) -> UResult<ProcData> {
+    let content = std::fs::read_to_string(path)
+        .map_err_context(|| format!("cannot read snapshot {}", path.quote()))?;
+    ProcData::from_snapshot(&content)
+        .map_err(|e| USimpleError::new(1, format!("invalid snapshot {}: {e}", path.quote())))
+}
+
+#[cfg(target_os = "linux")]
+fn print_snapshot_diff(before: &str, after: &str, matches: &ArgMatches) -> UResult<()> {
+    let mut before = read_snapshot(before)?;
+    let mut after = read_snapshot(after)?;
+
+    // btime may drift by a second between reads because of clock adjustments.
+    let same_boot = match (before.boot_time(), after.boot_time()) {
+        (Some(a), Some(b)) => a.abs_diff(b) <= 1,
+        _ => false,
+    };
+
+    if same_boot && after.uptime.0 < before.uptime.0
+        || !same_boot && before.boot_time() > after.boot_time()
+    {
+        std::mem::swap(&mut before, &mut after);
+    }
+    if !same_boot {
+        // Counters are reset on reboot, so the only meaningful figures are the averages since
+        // boot of the most recent snapshot.
+        uucore::show_warning!(
+            "snapshots were taken in different boots, showing averages since the latest boot"
+        );
+    }
+
+    let pickers = get_pickers(matches);
+    print_header(&pickers);
+    print_data(&pickers, &after, same_boot.then_some(&before), matches);
+
+    Ok(())
+}
+
+/// Prints the number of forks since boot, threads included since they are cloned processes.
+#[cfg(target_os = "linux")]
+fn print_forks(proc_data: &ProcData) -> UResult<()> {
+    let forks = proc_data
+        .stat
+        .get("processes")
+        .and_then(|it| it.trim().parse::<u64>().ok())
+        .ok_or_else(|| USimpleError::new(1, "cannot read the number of forks in /proc/stat"))?;
+    println!("{forks:>13} forks");
+    Ok(())
+}
+
+/// Calls `report` with the index of each report, waiting `delay` seconds in between.
+#[cfg(target_os = "linux")]
+fn repeat_reports(
+    delay: u64,
+    count: Option<u64>,
+    mut report: impl FnMut(u64) -> UResult<()>,
+) -> UResult<()> {
+    let mut report_count = 0;
+    loop {
+        report(report_count)?;
+        report_count += 1;
+        if count.is_some_and(|count| report_count >= count) {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_secs(delay));
+    }
+}
+
+/// Prints the caches of `/proc/slabinfo`, which can only be read by root.
+#[cfg(target_os = "linux")]
+fn print_slabs(delay: u64, count: Option<u64>) -> UResult<()> {
+    repeat_reports(delay, count, |_| {
+        let slabinfo = uu_slabtop::parse::SlabInfo::new()
+            .map_err_context(|| "cannot read /proc/slabinfo".into())?;
+        println!(
+            "{:<24} {:>6} {:>6} {:>6} {:>6}",
+            "Cache", "Num", "Total", "Size", "Pages"
+        );
+        for name in slabinfo.names() {
+            let field = |meta| slabinfo.fetch(name, meta).unwrap_or_default();
+            println!(
+                "{:<24.24} {:>6} {:>6} {:>6} {:>6}",
+                name,
+                field("active_objs"),
+                field("num_objs"),
+                field("objsize"),
+                field("objperslab"),
+            );
+        }
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_disk_stats() -> UResult<Vec<DiskStat>> {
+    DiskStat::current().map_err_context(|| "cannot read /proc/diskstats".into())
+}
+
+/// Appends the timestamp column of -t to `line`.
+#[cfg(target_os = "linux")]
+fn with_timestamp(line: String, timestamp: Option<&str>) -> String {
+    match timestamp {
+        Some(timestamp) => format!("{line} {timestamp}"),
+        None => line,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn print_disk_stats(
+    one_header: bool,
+    timestamps: bool,
+    delay: u64,
+    count: Option<u64>,
+) -> UResult<()> {
+    let titles = timestamps.then(timestamp_title);
+    repeat_reports(delay, count, |report| {
+        let disks = read_disk_stats()?;
+        if report == 0 || !one_header {
+            println!(
+                "{}",
+                with_timestamp(
+                    "disk- ------------reads------------ ------------writes----------- -----IO------".into(),
+                    titles.as_ref().map(|it| it.0.as_str()),
+                )
+            );
+            println!(
+                "{}",
+                with_timestamp(
+                    "       total merged sectors      ms  total merged sectors      ms    cur    sec".into(),
+                    titles.as_ref().map(|it| it.1.as_str()),
+                )
+            );
+        }
+        let timestamp = timestamps.then(timestamp);
+        for disk in disks.iter().filter(|it| it.is_disk()) {
+            let line = format!(
+                "{:<5} {:>6} {:>6} {:>7} {:>7} {:>6} {:>6} {:>7} {:>7} {:>6} {:>6}",
+                disk.name,
+                disk.reads,
+                disk.reads_merged,
+                disk.read_sectors,
+                disk.read_ms,
+                disk.writes,
+                disk.writes_merged,
+                disk.write_sectors,
+                disk.write_ms,
+                disk.io_in_progress,
+                disk.io_ms / 1000,
+            );
+            println!("{}", with_timestamp(line, timestamp.as_deref()));
+        }
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn print_partition_stats(
+    partition: &str,
+    timestamps: bool,
+    delay: u64,
+    count: Option<u64>,
+) -> UResult<()> {
+    let name = partition.strip_prefix("/dev/").unwrap_or(partition);
+    let find = |disks: Vec<DiskStat>| {
+        disks.into_iter().find(|it| it.name == name).ok_or_else(|| {
+            USimpleError::new(1, format!("partition {} not found", partition.quote()))
+        })
+    };
+    // Validate the name before printing anything
+    find(read_disk_stats()?)?;
+
+    let header = format!(
+        "{name:<10} {:>10} {:>16} {:>10} {:>16}",
+        "reads", "read sectors", "writes", "requested writes"
+    );
+    let title = timestamps.then(|| timestamp_title().1);
+    println!("{}", with_timestamp(header, title.as_deref()));
+    repeat_reports(delay, count, |_| {
+        let stat = find(read_disk_stats()?)?;
+        let line = format!(
+            "{:<10} {:>10} {:>16} {:>10} {:>16}",
+            "", stat.reads, stat.read_sectors, stat.writes, stat.write_sectors
+        );
+        println!(
+            "{}",
+            with_timestamp(line, timestamps.then(timestamp).as_deref())
+        );
+        Ok(())
+    })
+}
+
 #[cfg(target_os = "linux")]
 fn print_header(pickers: &[Picker]) {
     let mut section: Vec<&str> = vec![];
@@ -107,7 +342,14 @@ fn print_data(
             &mut data_len_excess,
         );
     });
-    println!("{}", data.join(" "));
+    let mut line = data.join(" ");
+    if let Some(before) = proc_data_before.filter(|_| matches.get_flag("mark-wraps")) {
+        let wrapped = wrapped_counters(proc_data, before);
+        if !wrapped.is_empty() {
+            line.push_str(&format!(" wrapped: {}", wrapped.join(",")));
+        }
+    }
+    println!("{line}");
 }
 
 #[allow(clippy::cognitive_complexity)]
@@ -125,16 +367,29 @@ pub fn uu_app() -> Command {
                 .required(false)
                 .value_parser(value_parser!(u64)),
             arg!(-a --active "Display active and inactive memory"),
-            // arg!(-f --forks "switch displays the number of forks since boot"),
-            // arg!(-m --slabs "Display slabinfo"),
+            arg!(-f --forks "Display the number of forks since boot")
+                .conflicts_with_all(["stats", "disk", "partition", "snapshot", "diff", "metrics"]),
+            arg!(-m --slabs "Display slabinfo")
+                .conflicts_with_all(["forks", "stats", "disk", "partition", "snapshot", "diff", "metrics"]),
             arg!(-n --"one-header" "Display the header only once rather than periodically"),
-            // arg!(-s --stats "Displays a table of various event counters and memory statistics"),
-            // arg!(-d --disk "Report disk statistics"),
+            arg!(-s --stats "Displays a table of various event counters and memory statistics")
+                .conflicts_with_all(["disk", "partition", "snapshot", "diff", "metrics"]),
+            arg!(-d --disk "Report disk statistics")
+                .conflicts_with_all(["snapshot", "diff", "metrics"]),
             // arg!(-D --"disk-sum" "Report some summary statistics about disk activity"),
-            // arg!(-p --partition <device> "Detailed statistics about partition"),
+            arg!(-p --partition <device> "Detailed statistics about partition")
+                .conflicts_with_all(["disk", "snapshot", "diff", "metrics"]),
             arg!(-S --unit <character> "Switches outputs between 1000 (k), 1024 (K), 1000000 (m), or 1048576 (M) bytes"),
             arg!(-t --timestamp "Append timestamp to each line"),
             arg!(-w --wide "Wide output mode"),
             arg!(-y --"no-first" "Omits first report with statistics since system boot"),
+            arg!(--"mark-wraps" "Mark the reports in which a counter wrapped around, with the columns it affects"),
+            arg!(--snapshot <file> "Write all raw counters to a file for a later --diff"),
+            arg!(--diff <file> "Report statistics for the interval between two snapshots")
+                .num_args(2)
+                .value_names(["file1", "file2"])
+                .conflicts_with("snapshot"),
+            arg!(--metrics "Print all raw counters in the Prometheus text format")
+                .conflicts_with_all(["snapshot", "diff"]),
         ])
 }
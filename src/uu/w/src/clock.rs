@@ -0,0 +1,301 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Formatting of wall-clock times, shared by the utilities showing them: the time in the header
+//! of `top`, `hugetop` and `w`, the LOGIN@ column of `w` and the lstart column of `ps`.
+//!
+//! The format follows the `TIME_STYLE` environment variable like in GNU `ls`, the `locale` style
+//! using the 12 or 24 hour clock of the locale. Without it, the fixed formats of procps are kept.
+
+// spell-checker:ignore (locales) POSIX langinfo
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeZone};
+use std::env;
+use std::ffi::CStr;
+use std::fmt::Display;
+
+/// The time conventions of the locale set by `LC_ALL`, `LC_TIME` or `LANG`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    posix: bool,
+    twelve_hour: bool,
+}
+
+impl Locale {
+    /// Falls back to the POSIX locale when the one of the environment isn't installed.
+    pub fn from_env() -> Self {
+        // SAFETY: the strings returned by setlocale and nl_langinfo are copied before the next
+        // call, and the locale of the caller is restored before returning
+        unsafe {
+            let previous = libc::setlocale(libc::LC_TIME, std::ptr::null());
+            let previous = (!previous.is_null()).then(|| CStr::from_ptr(previous).to_owned());
+
+            let name = libc::setlocale(libc::LC_TIME, c"".as_ptr());
+            let locale = if name.is_null() {
+                Self {
+                    posix: true,
+                    twelve_hour: false,
+                }
+            } else {
+                let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+                let time_format = CStr::from_ptr(libc::nl_langinfo(libc::T_FMT));
+                Self::new(&name, &time_format.to_string_lossy())
+            };
+
+            if let Some(previous) = previous {
+                libc::setlocale(libc::LC_TIME, previous.as_ptr());
+            }
+            locale
+        }
+    }
+
+    /// From the name of the locale and its `T_FMT`, the format of the time of day.
+    fn new(name: &str, time_format: &str) -> Self {
+        // Strip the codeset and the modifier, as in `en_US.UTF-8@euro`
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        Self {
+            posix: name == "C" || name == "POSIX",
+            twelve_hour: ["%p", "%P", "%r", "%I", "%l"]
+                .iter()
+                .any(|it| time_format.contains(it)),
+        }
+    }
+}
+
+/// How times are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Style {
+    /// The fixed formats of procps, when `TIME_STYLE` is unset or unknown
+    Procps,
+    /// The formats of procps, with the clock of the locale
+    Locale { twelve_hour: bool },
+    /// `2024-03-09 14:05:09.123456789 +0100`
+    FullIso,
+    /// `2024-03-09 14:05`
+    LongIso,
+    /// `14:05:09` for the clock, `03-09 14:05` for a login of today, `2024-03-09` before
+    Iso,
+    /// `+FORMAT` in strftime(3) syntax, a second format after a newline applies to the logins
+    /// older than today
+    Format(String, Option<String>),
+}
+
+impl Style {
+    /// The style set by `TIME_STYLE`, the locale is only looked up when the style needs it.
+    pub fn from_env() -> Self {
+        match env::var("TIME_STYLE") {
+            Ok(time_style) => Self::new(&time_style, &Locale::from_env()),
+            Err(_) => Self::Procps,
+        }
+    }
+
+    /// Unknown styles and invalid formats fall back to the formats of procps, as the time is
+    /// never worth failing for.
+    pub fn new(time_style: &str, locale: &Locale) -> Self {
+        let default = Self::Procps;
+
+        // `posix-` styles only apply outside of the POSIX locale
+        let time_style = match time_style.strip_prefix("posix-") {
+            Some(_) if locale.posix => return default,
+            Some(time_style) => time_style,
+            None => time_style,
+        };
+
+        match time_style {
+            "locale" => Self::Locale {
+                twelve_hour: locale.twelve_hour,
+            },
+            "full-iso" => Self::FullIso,
+            "long-iso" => Self::LongIso,
+            "iso" => Self::Iso,
+            _ => match time_style.strip_prefix('+') {
+                Some(format) => {
+                    let (recent, older) = match format.split_once('\n') {
+                        Some((recent, older)) => (recent, Some(older)),
+                        None => (format, None),
+                    };
+                    if [Some(recent), older].into_iter().flatten().all(is_valid) {
+                        Self::Format(recent.into(), older.map(Into::into))
+                    } else {
+                        default
+                    }
+                }
+                None => default,
+            },
+        }
+    }
+
+    /// The time of day, for headers.
+    pub fn clock<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let format = match self {
+            Self::Procps | Self::Locale { twelve_hour: false } | Self::Iso => "%H:%M:%S",
+            Self::Locale { twelve_hour: true } => "%l:%M:%S %p",
+            Self::FullIso => FULL_ISO,
+            Self::LongIso => LONG_ISO,
+            Self::Format(format, _) => format,
+        };
+        time.format(format).to_string()
+    }
+
+    /// The time of a login, with its day instead when it is not today.
+    pub fn login<Tz: TimeZone>(&self, time: &DateTime<Tz>, now: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let today = time.date_naive() == now.date_naive();
+        let format = match self {
+            Self::Procps | Self::Locale { .. } if !today => "%a%d",
+            Self::Procps | Self::Locale { twelve_hour: false } => "%H:%M",
+            Self::Locale { twelve_hour: true } => "%I:%M%p",
+            Self::FullIso => FULL_ISO,
+            Self::LongIso => LONG_ISO,
+            Self::Iso if today => "%m-%d %H:%M",
+            Self::Iso => "%Y-%m-%d",
+            Self::Format(_, Some(older)) if !today => older,
+            Self::Format(recent, _) => recent,
+        };
+        time.format(format).to_string()
+    }
+
+    /// The full date and time, such as the start of a process.
+    pub fn long<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let format = match self {
+            Self::Procps | Self::Locale { twelve_hour: false } => "%a %b %e %H:%M:%S %Y",
+            Self::Locale { twelve_hour: true } => "%a %b %e %I:%M:%S %p %Y",
+            Self::FullIso => FULL_ISO,
+            Self::LongIso => LONG_ISO,
+            Self::Iso => "%Y-%m-%d %H:%M:%S",
+            Self::Format(format, _) => format,
+        };
+        time.format(format).to_string()
+    }
+}
+
+const FULL_ISO: &str = "%Y-%m-%d %H:%M:%S%.9f %z";
+const LONG_ISO: &str = "%Y-%m-%d %H:%M";
+
+/// Whether chrono can format dates with the strftime(3) format, formatting panics otherwise.
+pub fn is_valid(format: &str) -> bool {
+    StrftimeItems::new(format).all(|item| item != Item::Error)
+}
+
+/// The current time of day in the style of the environment, for headers.
+pub fn now() -> String {
+    Style::from_env().clock(&Local::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, TimeDelta};
+
+    fn time() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-03-09T14:05:09.5+01:00").unwrap()
+    }
+
+    fn style(time_style: &str) -> Style {
+        Style::new(time_style, &Locale::new("en_GB.UTF-8", "%T"))
+    }
+
+    #[test]
+    fn test_locale() {
+        let posix = Locale::new("C", "%H:%M:%S");
+        assert!(posix.posix && !posix.twelve_hour);
+        let us = Locale::new("en_US.UTF-8", "%r");
+        assert!(!us.posix && us.twelve_hour);
+        assert!(Locale::new("ko_KR.UTF-8", "%p %I시 %M분 %S초").twelve_hour);
+        assert!(!Locale::new("de_DE.UTF-8@euro", "%T").twelve_hour);
+        assert!(Locale::new("POSIX", "%H:%M:%S").posix);
+    }
+
+    #[test]
+    fn test_new() {
+        let us = Locale::new("en_US.UTF-8", "%r");
+        assert_eq!(
+            Style::new("locale", &us),
+            Style::Locale { twelve_hour: true }
+        );
+        assert_eq!(style("locale"), Style::Locale { twelve_hour: false });
+        assert_eq!(style("long-iso"), Style::LongIso);
+        assert_eq!(style("posix-iso"), Style::Iso);
+        assert_eq!(
+            Style::new("posix-iso", &Locale::new("POSIX", "%H:%M:%S")),
+            Style::Procps
+        );
+        assert_eq!(
+            style("+%H\n%d"),
+            Style::Format("%H".into(), Some("%d".into()))
+        );
+        assert_eq!(style("+%Q"), Style::Procps);
+        assert_eq!(style("unknown"), Style::Procps);
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid("%Y-%m-%dT%H:%M:%S%:z"));
+        assert!(is_valid("%s"));
+        assert!(is_valid("started at %T"));
+        assert!(!is_valid("%Q"));
+        assert!(!is_valid("%"));
+    }
+
+    #[test]
+    fn test_clock() {
+        let time = time();
+        assert_eq!(Style::Procps.clock(&time), "14:05:09");
+        assert_eq!(style("locale").clock(&time), "14:05:09");
+        assert_eq!(
+            Style::Locale { twelve_hour: true }.clock(&time),
+            " 2:05:09 PM"
+        );
+        assert_eq!(
+            style("full-iso").clock(&time),
+            "2024-03-09 14:05:09.500000000 +0100"
+        );
+        assert_eq!(style("long-iso").clock(&time), "2024-03-09 14:05");
+        assert_eq!(style("iso").clock(&time), "14:05:09");
+        assert_eq!(style("+%s").clock(&time), "1709989509");
+    }
+
+    #[test]
+    fn test_login() {
+        let time = time();
+        let later = time + TimeDelta::hours(2);
+        let tomorrow = time + TimeDelta::days(1);
+        assert_eq!(Style::Procps.login(&time, &later), "14:05");
+        assert_eq!(Style::Procps.login(&time, &tomorrow), "Sat09");
+        assert_eq!(style("locale").login(&time, &later), "14:05");
+        assert_eq!(style("locale").login(&time, &tomorrow), "Sat09");
+        assert_eq!(
+            Style::Locale { twelve_hour: true }.login(&time, &later),
+            "02:05PM"
+        );
+        assert_eq!(style("iso").login(&time, &later), "03-09 14:05");
+        assert_eq!(style("iso").login(&time, &tomorrow), "2024-03-09");
+        assert_eq!(style("+%H\n%d").login(&time, &later), "14");
+        assert_eq!(style("+%H\n%d").login(&time, &tomorrow), "09");
+        assert_eq!(style("+%H").login(&time, &tomorrow), "14");
+    }
+
+    #[test]
+    fn test_long() {
+        let time = time();
+        assert_eq!(Style::Procps.long(&time), "Sat Mar  9 14:05:09 2024");
+        assert_eq!(style("locale").long(&time), "Sat Mar  9 14:05:09 2024");
+        assert_eq!(
+            Style::Locale { twelve_hour: true }.long(&time),
+            "Sat Mar  9 02:05:09 PM 2024"
+        );
+        assert_eq!(style("iso").long(&time), "2024-03-09 14:05:09");
+        assert_eq!(style("long-iso").long(&time), "2024-03-09 14:05");
+    }
+}
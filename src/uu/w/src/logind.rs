@@ -0,0 +1,499 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Graphical sessions of systemd-logind, which have no controlling terminal and often no utmp
+//! record either, so that `w` would not list the users logged in on a desktop otherwise.
+//!
+//! The sessions are queried through sd-login, the API of libsystemd for them, which is loaded at
+//! runtime so that `w` builds and runs without systemd. Without the library, the state logind
+//! keeps in `/run/systemd/sessions/<id>` as `KEY=value` lines is read instead.
+
+// spell-checker:ignore (names) kwin mutter weston wayfire niri labwc Hyprland Xwayland
+// spell-checker:ignore (libc) dlopen dlsym
+
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::ptr;
+use std::sync::OnceLock;
+use uucore::entries::uid2usr;
+
+pub(crate) const SESSIONS_DIR: &str = "/run/systemd/sessions";
+
+type StringGetter = unsafe extern "C" fn(*const c_char, *mut *mut c_char) -> c_int;
+type SessionsGetter = unsafe extern "C" fn(*mut *mut *mut c_char) -> c_int;
+type UidGetter = unsafe extern "C" fn(*const c_char, *mut libc::uid_t) -> c_int;
+type LeaderGetter = unsafe extern "C" fn(*const c_char, *mut libc::pid_t) -> c_int;
+type StartTimeGetter = unsafe extern "C" fn(*const c_char, *mut u64) -> c_int;
+
+/// The functions of sd-login used here, resolved from libsystemd.
+struct SdLogin {
+    get_sessions: SessionsGetter,
+    get_uid: UidGetter,
+    get_type: StringGetter,
+    get_class: StringGetter,
+    get_tty: StringGetter,
+    get_display: StringGetter,
+    get_seat: StringGetter,
+    get_remote_host: StringGetter,
+    /// Only systemd 254 and newer have these, the fields are unknown otherwise
+    get_leader: Option<LeaderGetter>,
+    get_start_time: Option<StartTimeGetter>,
+}
+
+impl SdLogin {
+    /// `None` when libsystemd can't be loaded, as on systems without systemd or static builds.
+    fn get() -> Option<&'static Self> {
+        static SD_LOGIN: OnceLock<Option<SdLogin>> = OnceLock::new();
+        SD_LOGIN.get_or_init(Self::load).as_ref()
+    }
+
+    fn load() -> Option<Self> {
+        // SAFETY: the library is never closed, and each symbol is cast to the signature
+        // documented in sd-login(3)
+        unsafe {
+            let library = libc::dlopen(
+                c"libsystemd.so.0".as_ptr(),
+                libc::RTLD_NOW | libc::RTLD_LOCAL,
+            );
+            if library.is_null() {
+                return None;
+            }
+            let symbol = |name: &CStr| {
+                let symbol = libc::dlsym(library, name.as_ptr());
+                (!symbol.is_null()).then_some(symbol)
+            };
+            let function = |name: &CStr| {
+                symbol(name).map(|it| std::mem::transmute::<*mut c_void, StringGetter>(it))
+            };
+
+            Some(Self {
+                get_sessions: std::mem::transmute::<*mut c_void, SessionsGetter>(symbol(
+                    c"sd_get_sessions",
+                )?),
+                get_uid: std::mem::transmute::<*mut c_void, UidGetter>(symbol(
+                    c"sd_session_get_uid",
+                )?),
+                get_type: function(c"sd_session_get_type")?,
+                get_class: function(c"sd_session_get_class")?,
+                get_tty: function(c"sd_session_get_tty")?,
+                get_display: function(c"sd_session_get_display")?,
+                get_seat: function(c"sd_session_get_seat")?,
+                get_remote_host: function(c"sd_session_get_remote_host")?,
+                get_leader: symbol(c"sd_session_get_leader")
+                    .map(|it| std::mem::transmute::<*mut c_void, LeaderGetter>(it)),
+                get_start_time: symbol(c"sd_session_get_start_time")
+                    .map(|it| std::mem::transmute::<*mut c_void, StartTimeGetter>(it)),
+            })
+        }
+    }
+
+    fn sessions(&self) -> Vec<Session> {
+        let mut ids: *mut *mut c_char = ptr::null_mut();
+        // SAFETY: on success sd_get_sessions stores an array of as many strings as it returns,
+        // all allocated with malloc and freed here
+        unsafe {
+            let count = (self.get_sessions)(&mut ids);
+            if count < 0 || ids.is_null() {
+                return Vec::new();
+            }
+            let sessions = (0..count as usize)
+                .filter_map(|i| {
+                    let id = *ids.add(i);
+                    let session = self.session(CStr::from_ptr(id));
+                    libc::free(id.cast());
+                    session
+                })
+                .collect();
+            libc::free(ids.cast());
+            sessions
+        }
+    }
+
+    fn session(&self, id: &CStr) -> Option<Session> {
+        let mut uid = 0;
+        // SAFETY: the ID comes from sd_get_sessions, and the UID is only written to
+        let uid = (unsafe { (self.get_uid)(id.as_ptr(), &mut uid) } >= 0).then_some(uid);
+
+        Some(Session {
+            id: id.to_str().ok()?.into(),
+            user: uid2usr(uid?).ok()?,
+            uid,
+            kind: string(self.get_type, id).unwrap_or_else(|| "unspecified".into()),
+            class: string(self.get_class, id).unwrap_or_else(|| "user".into()),
+            tty: string(self.get_tty, id),
+            display: string(self.get_display, id),
+            seat: string(self.get_seat, id),
+            remote_host: string(self.get_remote_host, id),
+            leader: self
+                .get_leader
+                .and_then(|getter| value(getter, id))
+                .filter(|&pid| pid > 0),
+            realtime: self.get_start_time.and_then(|getter| value(getter, id)),
+        })
+    }
+}
+
+/// Calls a string getter of sd-login, `None` when the session has no such field.
+fn string(getter: StringGetter, id: &CStr) -> Option<String> {
+    let mut value: *mut c_char = ptr::null_mut();
+    // SAFETY: on success the getter stores a string allocated with malloc, freed here
+    unsafe {
+        if getter(id.as_ptr(), &mut value) < 0 || value.is_null() {
+            return None;
+        }
+        let string = CStr::from_ptr(value).to_string_lossy().into_owned();
+        libc::free(value.cast());
+        Some(string).filter(|it| !it.is_empty())
+    }
+}
+
+/// Calls a getter of sd-login setting a plain value.
+fn value<T: Default>(
+    getter: unsafe extern "C" fn(*const c_char, *mut T) -> c_int,
+    id: &CStr,
+) -> Option<T> {
+    let mut value = T::default();
+    // SAFETY: the getter only writes the value
+    (unsafe { getter(id.as_ptr(), &mut value) } >= 0).then_some(value)
+}
+
+/// Display servers and compositors, the processes shown as WHAT for a graphical session.
+const COMPOSITORS: &[&str] = &[
+    "gnome-shell",
+    "kwin_wayland",
+    "kwin_x11",
+    "mutter",
+    "sway",
+    "Hyprland",
+    "weston",
+    "wayfire",
+    "labwc",
+    "niri",
+    "river",
+    "cosmic-comp",
+    "Xwayland",
+    "Xorg",
+];
+
+/// A session of logind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Session {
+    pub(crate) id: String,
+    pub(crate) user: String,
+    pub(crate) uid: Option<u32>,
+    /// `tty`, `x11`, `wayland`, `mir` or `unspecified`
+    kind: String,
+    /// `user` for the sessions of people, `greeter` for the login screen
+    class: String,
+    pub(crate) tty: Option<String>,
+    display: Option<String>,
+    seat: Option<String>,
+    pub(crate) remote_host: Option<String>,
+    pub(crate) leader: Option<i32>,
+    /// Microseconds since the epoch
+    realtime: Option<u64>,
+}
+
+impl Session {
+    fn parse(id: &str, content: &str) -> Option<Self> {
+        let fields = content
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .collect::<HashMap<_, _>>();
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .filter(|value| !value.is_empty())
+                .map(|value| value.to_string())
+        };
+
+        Some(Self {
+            id: id.into(),
+            user: field("USER")?,
+            uid: field("UID").and_then(|uid| uid.parse().ok()),
+            kind: field("TYPE").unwrap_or_else(|| "unspecified".into()),
+            class: field("CLASS").unwrap_or_else(|| "user".into()),
+            tty: field("TTY"),
+            display: field("DISPLAY"),
+            seat: field("SEAT"),
+            remote_host: field("REMOTE_HOST"),
+            leader: field("LEADER").and_then(|pid| pid.parse().ok()),
+            realtime: field("REALTIME").and_then(|usec| usec.parse().ok()),
+        })
+    }
+
+    /// Whether the session is a desktop of a user, rather than a terminal or a login screen.
+    pub(crate) fn is_graphical(&self) -> bool {
+        matches!(self.kind.as_str(), "x11" | "wayland" | "mir") && self.class == "user"
+    }
+
+    /// What stands for the terminal of the session: its X display such as `:0`, its seat, or
+    /// the session itself.
+    pub(crate) fn terminal(&self) -> String {
+        self.display
+            .clone()
+            .or_else(|| self.seat.clone())
+            .unwrap_or_else(|| format!("session-{}", self.id))
+    }
+
+    pub(crate) fn login_time(&self) -> Option<DateTime<Local>> {
+        DateTime::from_timestamp_micros(self.realtime?.try_into().ok()?)
+            .map(|time| time.with_timezone(&Local))
+    }
+
+    /// The processes in the scope of the session, which logind names `session-<id>.scope`.
+    pub(crate) fn members<'a>(&self, processes: &'a [Process]) -> Vec<&'a Process> {
+        let scope = format!("session-{}.scope", self.id);
+        processes
+            .iter()
+            .filter(|process| process.cgroup.rsplit('/').next() == Some(scope.as_str()))
+            .collect()
+    }
+
+    /// The compositor of the session, preferably in its scope. Desktops such as GNOME start
+    /// it as a service of the user instead, outside of the scope.
+    pub(crate) fn compositor(&self, processes: &[Process]) -> Option<i32> {
+        let is_compositor = |process: &&Process| COMPOSITORS.contains(&process.name.as_str());
+        self.members(processes)
+            .into_iter()
+            .find(is_compositor)
+            .or_else(|| {
+                processes
+                    .iter()
+                    .filter(|process| Some(process.uid) == self.uid)
+                    .filter(is_compositor)
+                    .min_by_key(|process| process.pid)
+            })
+            .map(|process| process.pid)
+    }
+}
+
+/// The sessions of logind, sorted by their ID, none when logind isn't running.
+pub(crate) fn sessions() -> Vec<Session> {
+    let mut sessions = match SdLogin::get() {
+        Some(sd_login) => sd_login.sessions(),
+        None => read_sessions(Path::new(SESSIONS_DIR)),
+    };
+    sessions.sort_by(|a, b| natural_key(&a.id).cmp(&natural_key(&b.id)));
+    sessions
+}
+
+/// The sessions from the state files of logind, when libsystemd isn't available.
+fn read_sessions(dir: &Path) -> Vec<Session> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().into_string().ok()?;
+            // Skip the `.ref` pipes and temporary files
+            if id.contains('.') {
+                return None;
+            }
+            Session::parse(&id, &fs::read_to_string(entry.path()).ok()?)
+        })
+        .collect()
+}
+
+/// Orders `2` before `10`, and the numbers before the other IDs like `c1`.
+fn natural_key(id: &str) -> (u64, &str) {
+    (id.parse().unwrap_or(u64::MAX), id)
+}
+
+/// A process, with what is needed to find the ones of a session.
+#[derive(Debug)]
+pub(crate) struct Process {
+    pub(crate) pid: i32,
+    uid: u32,
+    name: String,
+    /// The path in the unified cgroup hierarchy
+    cgroup: String,
+}
+
+pub(crate) fn processes(proc_root: &Path) -> Vec<Process> {
+    let Ok(entries) = fs::read_dir(proc_root) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let path = entry.path();
+            let cgroup = fs::read_to_string(path.join("cgroup")).ok()?;
+            Some(Process {
+                pid,
+                uid: entry.metadata().ok()?.uid(),
+                name: fs::read_to_string(path.join("comm"))
+                    .ok()?
+                    .trim_end()
+                    .into(),
+                cgroup: cgroup
+                    .lines()
+                    .find_map(|line| line.strip_prefix("0::"))
+                    .unwrap_or_default()
+                    .into(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wayland(id: &str) -> Session {
+        Session {
+            id: id.into(),
+            user: "alice".into(),
+            uid: Some(1000),
+            kind: "wayland".into(),
+            class: "user".into(),
+            tty: None,
+            display: None,
+            seat: Some("seat0".into()),
+            remote_host: None,
+            leader: Some(1500),
+            realtime: Some(1_700_000_000_000_000),
+        }
+    }
+
+    const WAYLAND: &str = "\
+# This is private data. Do not parse.
+UID=1000
+USER=alice
+ACTIVE=1
+TYPE=wayland
+CLASS=user
+SCOPE=session-2.scope
+SEAT=seat0
+LEADER=1500
+REALTIME=1700000000000000
+";
+
+    fn write_process(proc_root: &Path, pid: i32, name: &str, cgroup: &str) {
+        let dir = proc_root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("comm"), format!("{name}\n")).unwrap();
+        fs::write(dir.join("cgroup"), format!("0::{cgroup}\n")).unwrap();
+    }
+
+    #[test]
+    fn test_session() {
+        let session = wayland("2");
+        assert!(session.is_graphical());
+        assert_eq!(session.terminal(), "seat0");
+        assert_eq!(session.login_time().unwrap().timestamp(), 1_700_000_000);
+
+        let x11 = Session {
+            kind: "x11".into(),
+            display: Some(":0".into()),
+            ..wayland("3")
+        };
+        assert_eq!(x11.terminal(), ":0");
+
+        let greeter = Session {
+            class: "greeter".into(),
+            ..wayland("c1")
+        };
+        assert!(!greeter.is_graphical());
+        let tty = Session {
+            kind: "tty".into(),
+            tty: Some("tty3".into()),
+            ..wayland("4")
+        };
+        assert!(!tty.is_graphical());
+
+        let unseated = Session {
+            seat: None,
+            ..wayland("5")
+        };
+        assert_eq!(unseated.terminal(), "session-5");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Session::parse("2", WAYLAND), Some(wayland("2")));
+
+        let x11 = WAYLAND.replace("TYPE=wayland", "TYPE=x11") + "DISPLAY=:0\n";
+        assert_eq!(Session::parse("3", &x11).unwrap().terminal(), ":0");
+        assert_eq!(Session::parse("5", "UID=1000\n"), None);
+    }
+
+    #[test]
+    fn test_read_sessions() {
+        let dir = std::env::temp_dir().join(format!("uu_w_sessions_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("2"), WAYLAND).unwrap();
+        fs::write(dir.join("2.ref"), "").unwrap();
+        let sessions = read_sessions(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sessions, [wayland("2")]);
+    }
+
+    #[test]
+    fn test_natural_key() {
+        let mut ids = ["c1", "10", "2"];
+        ids.sort_by_key(|id| natural_key(id));
+        assert_eq!(ids, ["2", "10", "c1"]);
+    }
+
+    #[test]
+    fn test_members_and_compositor() {
+        let proc_root = std::env::temp_dir().join(format!("uu_w_logind_{}", std::process::id()));
+        let scope = "/user.slice/user-1000.slice/session-2.scope";
+        write_process(&proc_root, 1500, "gdm-wayland-ses", scope);
+        write_process(&proc_root, 1510, "gnome-session-b", scope);
+        write_process(
+            &proc_root,
+            1520,
+            "bash",
+            "/user.slice/user-1000.slice/session-20.scope",
+        );
+        // Started as a service of the user, outside of the scope
+        write_process(
+            &proc_root,
+            1600,
+            "gnome-shell",
+            "/user.slice/user-1000.slice/user@1000.service/app.slice/gnome-shell.service",
+        );
+
+        let processes = processes(&proc_root);
+        // The processes belong to the user running the tests
+        let uid = fs::metadata(proc_root.join("1600")).unwrap().uid();
+        fs::remove_dir_all(&proc_root).unwrap();
+
+        let session = Session {
+            uid: Some(uid),
+            ..wayland("2")
+        };
+        let mut members = session
+            .members(&processes)
+            .iter()
+            .map(|it| it.pid)
+            .collect::<Vec<_>>();
+        members.sort_unstable();
+        assert_eq!(members, [1500, 1510]);
+        assert_eq!(session.compositor(&processes), Some(1600));
+
+        let stranger = Session {
+            uid: Some(uid + 1),
+            ..session
+        };
+        assert_eq!(stranger.compositor(&processes), None);
+    }
+
+    #[test]
+    fn test_sessions() {
+        // Whatever the sessions of the machine running the tests are, they are sorted
+        let ids = sessions().into_iter().map(|it| it.id).collect::<Vec<_>>();
+        assert!(ids.is_sorted_by_key(|id| natural_key(id)));
+    }
+}
@@ -0,0 +1,149 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::net::IpAddr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Longest time a reverse lookup may take before the address is shown as is.
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Buffer size for the host names returned by `getnameinfo`, `NI_MAXHOST` in glibc.
+const MAX_HOST: usize = 1025;
+
+/// Turns the addresses of the FROM field into host names, each address being looked up once.
+pub(crate) struct Resolver {
+    enabled: bool,
+    cache: HashMap<IpAddr, Option<String>>,
+}
+
+impl Resolver {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Name of `host` if it is an address with a reverse DNS record, `host` itself otherwise.
+    pub(crate) fn resolve(&mut self, host: &str) -> String {
+        let Ok(addr) = host.parse::<IpAddr>() else {
+            return host.into();
+        };
+        if !self.enabled {
+            return host.into();
+        }
+
+        let name = match self.cache.get(&addr) {
+            Some(name) => name.clone(),
+            None => {
+                let name = match lookup_with_timeout(addr) {
+                    Some(name) => name,
+                    None => {
+                        // A resolver which does not answer in time is unlikely to answer for
+                        // the next addresses either, don't make the listing wait for each of them
+                        self.enabled = false;
+                        None
+                    }
+                };
+                self.cache.insert(addr, name.clone());
+                name
+            }
+        };
+
+        name.unwrap_or_else(|| host.into())
+    }
+}
+
+/// Runs the lookup in a thread that is left behind if it takes too long, as `getnameinfo` has
+/// no timeout of its own. Returns `None` on timeout.
+fn lookup_with_timeout(addr: IpAddr) -> Option<Option<String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(reverse_lookup(addr));
+    });
+    receiver.recv_timeout(TIMEOUT).ok()
+}
+
+fn reverse_lookup(addr: IpAddr) -> Option<String> {
+    let mut host = [0 as libc::c_char; MAX_HOST];
+
+    let ret = match addr {
+        IpAddr::V4(addr) => {
+            // SAFETY: all-zero is a valid sockaddr_in
+            let mut sockaddr: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+            sockaddr.sin_family = libc::AF_INET as libc::sa_family_t;
+            sockaddr.sin_addr.s_addr = u32::from_ne_bytes(addr.octets());
+            // SAFETY: the address and the buffer are valid for the given lengths
+            unsafe {
+                libc::getnameinfo(
+                    (&raw const sockaddr).cast(),
+                    size_of::<libc::sockaddr_in>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+        }
+        IpAddr::V6(addr) => {
+            // SAFETY: all-zero is a valid sockaddr_in6
+            let mut sockaddr: libc::sockaddr_in6 = unsafe { std::mem::zeroed() };
+            sockaddr.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sockaddr.sin6_addr.s6_addr = addr.octets();
+            // SAFETY: the address and the buffer are valid for the given lengths
+            unsafe {
+                libc::getnameinfo(
+                    (&raw const sockaddr).cast(),
+                    size_of::<libc::sockaddr_in6>() as libc::socklen_t,
+                    host.as_mut_ptr(),
+                    host.len() as libc::socklen_t,
+                    std::ptr::null_mut(),
+                    0,
+                    libc::NI_NAMEREQD,
+                )
+            }
+        }
+    };
+    if ret != 0 {
+        return None;
+    }
+
+    // SAFETY: getnameinfo wrote a nul-terminated string on success
+    let name = unsafe { CStr::from_ptr(host.as_ptr()) };
+    name.to_str().ok().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut resolver = Resolver::new(true);
+        // Host names and X displays are kept as recorded
+        assert_eq!(resolver.resolve("example.org"), "example.org");
+        assert_eq!(resolver.resolve(":0"), ":0");
+        assert_eq!(resolver.resolve(""), "");
+
+        let mut resolver = Resolver::new(false);
+        assert_eq!(resolver.resolve("127.0.0.1"), "127.0.0.1");
+        assert_eq!(resolver.resolve("::1"), "::1");
+        assert!(resolver.cache.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_is_cached() {
+        let mut resolver = Resolver::new(true);
+        let name = resolver.resolve("127.0.0.1");
+        assert_eq!(resolver.cache.len(), 1);
+        assert_eq!(resolver.resolve("127.0.0.1"), name);
+        assert_eq!(resolver.cache.len(), 1);
+    }
+}
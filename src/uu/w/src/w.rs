@@ -4,32 +4,43 @@
 // file that was distributed with this source code.
 
 #[cfg(target_os = "linux")]
-use chrono::{Datelike, Local};
+use chrono::Local;
 use clap::crate_version;
 use clap::{Arg, ArgAction, Command};
 #[cfg(target_os = "linux")]
-use libc::{sysconf, _SC_CLK_TCK};
-#[cfg(target_os = "linux")]
-use std::{collections::HashMap, fs, path::Path, time::SystemTime};
-use std::{process, time::Duration};
-use uucore::uptime::{
-    get_formatted_loadavg, get_formatted_nusers, get_formatted_time, get_uptime, UptimeError,
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    time::SystemTime,
 };
+use std::{process, time::Duration};
+#[cfg(target_os = "linux")]
+use uu_pgrep::process_matcher::clock_ticks;
+use uucore::uptime::{get_formatted_loadavg, get_formatted_nusers, get_uptime, UptimeError};
 #[cfg(target_os = "linux")]
 use uucore::utmpx::Utmpx;
 use uucore::{error::UResult, format_usage, help_about, help_usage};
 
+pub mod clock;
+#[cfg(target_os = "linux")]
+mod logind;
+mod resolve;
+
 const ABOUT: &str = help_about!("w.md");
 const USAGE: &str = help_usage!("w.md");
 
 struct UserInfo {
     user: String,
     terminal: String,
+    from: String,
     login_time: String,
     idle_time: Duration, // for better compatibility with old-style outputs
     jcpu: String,
     pcpu: String,
     command: String,
+    /// The process shown as WHAT, the compositor for graphical sessions
+    pid: i32,
 }
 
 #[cfg(target_os = "linux")]
@@ -67,11 +78,6 @@ fn fetch_terminal_number(pid: i32) -> Result<u64, std::io::Error> {
     Ok(stat[6].parse().unwrap_or_default())
 }
 
-#[cfg(target_os = "linux")]
-fn get_clock_tick() -> i64 {
-    unsafe { sysconf(_SC_CLK_TCK) }
-}
-
 #[cfg(target_os = "linux")]
 fn fetch_pcpu_time(pid: i32) -> Result<f64, std::io::Error> {
     let stat_path = Path::new("/proc").join(pid.to_string()).join("stat");
@@ -83,7 +89,7 @@ fn fetch_pcpu_time(pid: i32) -> Result<f64, std::io::Error> {
     let utime: f64 = stat[13].parse().unwrap_or_default();
     let stime: f64 = stat[14].parse().unwrap_or_default();
     // Divide by clock tick to get actual time
-    Ok((utime + stime) / get_clock_tick() as f64)
+    Ok((utime + stime) / clock_ticks() as f64)
 }
 
 #[cfg(target_os = "linux")]
@@ -133,15 +139,10 @@ fn format_time(time: String) -> Result<String, chrono::format::ParseError> {
     if let Some(time_offset) = t.rfind(':') {
         t = t.drain(..time_offset).collect();
     }
-    // If login time day is not current day, format like Sat16, or Fri06
-    let current_dt = Local::now().fixed_offset();
     let dt = chrono::DateTime::parse_from_str(&t, "%Y-%m-%d %H:%M:%S%.f %:z")?;
+    let now = Local::now().with_timezone(dt.offset());
 
-    if current_dt.day() == dt.day() {
-        Ok(dt.format("%H:%M").to_string())
-    } else {
-        Ok(dt.format("%a%d").to_string())
-    }
+    Ok(clock::Style::from_env().login(&dt, &now))
 }
 
 #[cfg(target_os = "linux")]
@@ -169,19 +170,86 @@ fn fetch_user_info() -> Result<Vec<UserInfo>, std::io::Error> {
             let user_info = UserInfo {
                 user: entry.user(),
                 terminal: entry.tty_device(),
+                from: entry.host(),
                 login_time: format_time(entry.login_time().to_string()).unwrap_or_default(),
                 idle_time: fetch_idle_time(entry.tty_device())?,
                 jcpu: format!("{jcpu:.2}"),
                 pcpu: fetch_pcpu_time(entry.pid()).unwrap_or_default().to_string(),
                 command: fetch_cmdline(entry.pid()).unwrap_or_default(),
+                pid: entry.pid(),
             };
             user_info_list.push(user_info);
         }
     }
 
+    user_info_list.extend(fetch_graphical_sessions(&user_info_list));
     Ok(user_info_list)
 }
 
+/// The graphical sessions of logind missing from utmp, with their compositor as WHAT.
+#[cfg(target_os = "linux")]
+fn fetch_graphical_sessions(listed: &[UserInfo]) -> Vec<UserInfo> {
+    let sessions = logind::sessions()
+        .into_iter()
+        .filter(logind::Session::is_graphical)
+        .collect::<Vec<_>>();
+    if sessions.is_empty() {
+        return Vec::new();
+    }
+
+    let listed_pids = listed.iter().map(|it| it.pid).collect::<HashSet<_>>();
+    let processes = logind::processes(Path::new("/proc"));
+    let mut user_info_list = Vec::new();
+    for session in sessions {
+        let terminal = session.terminal();
+        // Display managers may record the session in utmp, with its display as the terminal
+        if session.leader.is_some_and(|pid| listed_pids.contains(&pid))
+            || listed
+                .iter()
+                .any(|it| it.terminal == terminal || Some(&it.terminal) == session.tty.as_ref())
+        {
+            continue;
+        }
+
+        let what = session.compositor(&processes).or(session.leader);
+        let jcpu: f64 = session
+            .members(&processes)
+            .iter()
+            .map(|it| fetch_pcpu_time(it.pid).unwrap_or_default())
+            .sum();
+        let now = Local::now();
+        user_info_list.push(UserInfo {
+            user: session.user.clone(),
+            terminal,
+            from: session.remote_host.clone().unwrap_or_default(),
+            login_time: session
+                .login_time()
+                .map(|time| clock::Style::from_env().login(&time, &now))
+                .unwrap_or_default(),
+            // The activity on a desktop isn't known without a terminal
+            idle_time: Duration::ZERO,
+            jcpu: format!("{jcpu:.2}"),
+            pcpu: what
+                .and_then(|pid| fetch_pcpu_time(pid).ok())
+                .unwrap_or_default()
+                .to_string(),
+            command: what
+                .and_then(|pid| fetch_cmdline(pid).ok())
+                .map(|cmdline| {
+                    cmdline
+                        .split('\0')
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .trim_end()
+                        .into()
+                })
+                .unwrap_or_default(),
+            pid: what.unwrap_or_default(),
+        });
+    }
+    user_info_list
+}
+
 #[cfg(target_os = "linux")]
 fn get_uptime_container() -> UResult<i64> {
     let proc_file = fs::read_to_string("/proc/1/stat")?;
@@ -190,7 +258,7 @@ fn get_uptime_container() -> UResult<i64> {
     let start_time: i64 = start_time_str
         .parse()
         .map_err(|_| UptimeError::SystemUptime)?;
-    Ok(get_uptime(None)? + start_time / get_clock_tick())
+    Ok(get_uptime(None)? + start_time / clock_ticks() as i64)
 }
 
 pub fn format_uptime_procps(up_secs: i64) -> UResult<String> {
@@ -228,7 +296,7 @@ fn get_formatted_uptime_container_procps() -> UResult<String> {
 
 #[allow(unused_variables)]
 fn print_uptime(from_container: bool) {
-    print!(" {} ", get_formatted_time());
+    print!(" {} ", clock::now());
     #[cfg(target_os = "linux")]
     let uptime = if from_container {
         get_formatted_uptime_container_procps()
@@ -259,6 +327,10 @@ fn truncate_username(user: &str) -> String {
     user.chars().take(8).collect::<String>()
 }
 
+fn truncate_host(host: &str) -> String {
+    host.chars().take(16).collect::<String>()
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
@@ -267,24 +339,38 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let short = matches.get_flag("short");
     let old_style = matches.get_flag("old-style");
     let container = matches.get_flag("container");
+    let show_from = matches.get_flag("from");
+    let mut resolver =
+        resolve::Resolver::new(!matches.get_flag("no-resolve") && !matches.get_flag("ip-addr"));
 
     match fetch_user_info() {
         Ok(user_info) => {
             if !no_header {
                 print_uptime(container);
+                let from = if show_from {
+                    format!("{:<17}", "FROM")
+                } else {
+                    String::new()
+                };
                 if short {
-                    println!("{:<9}{:<9}{:<7}{:<}", "USER", "TTY", "IDLE", "WHAT");
+                    println!("{:<9}{:<9}{from}{:<7}{:<}", "USER", "TTY", "IDLE", "WHAT");
                 } else {
                     println!(
-                        "{:<9}{:<10}{:<9}{:<6} {:<7}{:<6}{:<}",
+                        "{:<9}{:<10}{from}{:<9}{:<6} {:<7}{:<6}{:<}",
                         "USER", "TTY", "LOGIN@", "IDLE", "JCPU", "PCPU", "WHAT"
                     );
                 }
             }
             for user in user_info {
+                let from = if show_from {
+                    let host = resolver.resolve(&user.from);
+                    format!("{:<17}", truncate_host(&host))
+                } else {
+                    String::new()
+                };
                 if short {
                     println!(
-                        "{:<9}{:<9}{:<7}{:<}",
+                        "{:<9}{:<9}{from}{:<7}{:<}",
                         truncate_username(&user.user),
                         user.terminal,
                         format_time_elapsed(user.idle_time, old_style).unwrap_or_default(),
@@ -292,7 +378,7 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
                     );
                 } else {
                     println!(
-                        "{:<9}{:<10}{:<9}{:<6} {:<7}{:<6}{:<}",
+                        "{:<9}{:<10}{from}{:<9}{:<6} {:<7}{:<6}{:<}",
                         truncate_username(&user.user),
                         user.terminal,
                         user.login_time,
@@ -375,6 +461,12 @@ pub fn uu_app() -> Command {
                 .help("display IP address instead of hostname (if possible)")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("no-resolve")
+                .long("no-resolve")
+                .help("do not look up the hostname of remote addresses")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("pids")
                 .short('p')
@@ -389,9 +481,10 @@ pub fn uu_app() -> Command {
 mod tests {
     use crate::{
         fetch_cmdline, fetch_pcpu_time, fetch_terminal_number, format_time, format_time_elapsed,
-        format_uptime_procps, get_clock_tick,
+        format_uptime_procps,
     };
     use std::{fs, path::Path, process, time::Duration};
+    use uu_pgrep::process_matcher::clock_ticks;
 
     #[test]
     fn test_format_time() {
@@ -457,7 +550,7 @@ mod tests {
         let stime: f64 = stat[14].parse().unwrap();
         assert_eq!(
             fetch_pcpu_time(pid).unwrap(),
-            (utime + stime) / get_clock_tick() as f64
+            (utime + stime) / clock_ticks() as f64
         )
     }
 
@@ -0,0 +1,351 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! The `--json-path` mode: the output of the command is parsed as JSON, and only the value at
+//! the path is shown, one line per scalar inside it, with the change of the numbers since the
+//! previous run.
+
+use serde_json::{Deserializer, Value};
+use std::fmt::Write;
+
+/// The member or item of the value a step of a path leads to.
+fn get<'a>(value: &'a Value, segment: &Segment) -> Option<&'a Value> {
+    match (value, segment) {
+        (Value::Object(members), Segment::Key(key)) => members.get(key),
+        (Value::Array(items), Segment::Key(key)) => items.get(key.parse::<usize>().ok()?),
+        (Value::Array(items), Segment::Index(index)) => {
+            let index = if *index < 0 {
+                items.len().checked_sub(index.unsigned_abs() as usize)?
+            } else {
+                *index as usize
+            };
+            items.get(index)
+        }
+        _ => None,
+    }
+}
+
+/// The scalars and the empty containers inside the value, after their path from it.
+fn leaves(value: &Value) -> Vec<(String, &Value)> {
+    let mut leaves = Vec::new();
+    collect_leaves(value, String::new(), &mut leaves);
+    leaves
+}
+
+fn collect_leaves<'a>(value: &'a Value, path: String, leaves: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Array(items) if !items.is_empty() => {
+            for (index, item) in items.iter().enumerate() {
+                collect_leaves(item, format!("{path}[{index}]"), leaves);
+            }
+        }
+        Value::Object(members) if !members.is_empty() => {
+            for (key, member) in members {
+                let is_identifier = !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+                let path = if is_identifier {
+                    format!("{path}.{key}")
+                } else {
+                    format!("{path}[{}]", Value::from(key.as_str()))
+                };
+                collect_leaves(member, path, leaves);
+            }
+        }
+        _ => leaves.push((path, value)),
+    }
+}
+
+/// A scalar as a person reads it: strings without their quotes, and with the control
+/// characters escaped so they can't alter the screen. The numbers keep all their digits.
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(string) => string
+            .chars()
+            .map(|c| {
+                if c.is_control() {
+                    c.escape_default().to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect(),
+        Value::Array(_) => "[]".into(),
+        Value::Object(_) => "{}".into(),
+        scalar => scalar.to_string(),
+    }
+}
+
+/// A step of a path into a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// A member of an object, or an item of an array when it is a number
+    Key(String),
+    /// An item of an array, from the end when negative
+    Index(i64),
+}
+
+/// Where the shown value is in the output of the command, as in `.items[0].count`, or
+/// `items.0.count` without the jq syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Path(Vec<Segment>);
+
+impl Path {
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        let error = |position: usize, message: &str| format!("{message} at offset {position}");
+        let mut segments = Vec::new();
+        // A leading dot is optional, alone it selects the whole output
+        let mut position = usize::from(expression.starts_with('.'));
+        while let Some(c) = expression[position..].chars().next() {
+            let rest = &expression[position..];
+            match c {
+                '[' => {
+                    position += 1;
+                    let rest = &rest[1..];
+                    if rest.starts_with('"') {
+                        let mut key = Deserializer::from_str(rest).into_iter::<String>();
+                        match key.next() {
+                            Some(Ok(key)) => segments.push(Segment::Key(key)),
+                            _ => return Err(error(position, "invalid key")),
+                        }
+                        position += key.byte_offset();
+                    } else {
+                        let len = rest.find(']').unwrap_or(rest.len());
+                        let index = rest[..len]
+                            .trim()
+                            .parse()
+                            .map_err(|_| error(position, "invalid index"))?;
+                        segments.push(Segment::Index(index));
+                        position += len;
+                    }
+                    if !expression[position..].starts_with(']') {
+                        return Err(error(position, "expected ']'"));
+                    }
+                    position += 1;
+                }
+                '.' if segments.is_empty() => return Err(error(position, "empty key")),
+                _ => {
+                    let start = position + usize::from(c == '.');
+                    let rest = &expression[start..];
+                    let len = rest.find(['.', '[']).unwrap_or(rest.len());
+                    position = start + len;
+                    if len == 0 {
+                        // A dot before brackets, as in `.items.[0]`
+                        if rest.starts_with('[') {
+                            continue;
+                        }
+                        return Err(error(position, "empty key"));
+                    }
+                    segments.push(Segment::Key(rest[..len].into()));
+                }
+            }
+        }
+        Ok(Self(segments))
+    }
+
+    fn select<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.0
+            .iter()
+            .try_fold(value, |value, segment| get(value, segment))
+    }
+}
+
+/// The scalars of the value at the path in the output of a run, or why there are none.
+pub(crate) type Selection = Result<Vec<(String, Value)>, String>;
+
+pub(crate) fn select(output: &[u8], path: &Path) -> Selection {
+    let output = std::str::from_utf8(output).map_err(|_| "output is not UTF-8".to_string())?;
+    let value = serde_json::from_str(output).map_err(|error| format!("invalid JSON: {error}"))?;
+    let value = path
+        .select(&value)
+        .ok_or_else(|| "no value at the path".to_string())?;
+    Ok(leaves(value)
+        .into_iter()
+        .map(|(path, value)| (path, value.clone()))
+        .collect())
+}
+
+/// One line per scalar, after its path inside the selected value. The values which changed
+/// since the previous run are highlighted, with the difference for the numbers.
+pub(crate) fn render(current: &Selection, previous: Option<&Selection>, highlight: bool) -> String {
+    let current = match current {
+        Ok(current) => current,
+        Err(error) => return format!("watch: {error}\n"),
+    };
+    let previous = previous.and_then(|previous| previous.as_ref().ok());
+
+    let mut output = String::new();
+    for (path, value) in current {
+        if !path.is_empty() {
+            let _ = write!(output, "{path}: ");
+        }
+        let before = previous.map(|previous| {
+            previous
+                .iter()
+                .find(|(previous_path, _)| previous_path == path)
+                .map(|(_, value)| value)
+        });
+        let shown = display(value);
+        match before {
+            Some(before) if before != Some(value) => {
+                if highlight {
+                    let _ = write!(output, "\x1b[7m{shown}\x1b[0m");
+                } else {
+                    output.push_str(&shown);
+                }
+                if let (Some(Value::Number(before)), Value::Number(after)) = (before, value) {
+                    let _ = write!(
+                        output,
+                        "  ({})",
+                        change(&before.to_string(), &after.to_string())
+                    );
+                }
+            }
+            _ => output.push_str(&shown),
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// The difference between two numbers, and relative to the first one when it isn't zero.
+fn change(before: &str, after: &str) -> String {
+    let (Ok(from), Ok(to)) = (before.parse::<f64>(), after.parse::<f64>()) else {
+        return String::new();
+    };
+    // As many decimals as the numbers, to avoid the rounding errors of the subtraction
+    let decimals = |number: &str| {
+        if number.contains(['e', 'E']) {
+            None
+        } else {
+            Some(
+                number
+                    .split_once('.')
+                    .map_or(0, |(_, decimals)| decimals.len()),
+            )
+        }
+    };
+    let delta = to - from;
+    let mut change = match (decimals(before), decimals(after)) {
+        (Some(a), Some(b)) => format!("{delta:+.*}", a.max(b)),
+        _ => format!("{delta:+}"),
+    };
+    if from != 0.0 {
+        let _ = write!(change, ", {:+.1}%", delta / from.abs() * 100.0);
+    }
+    change
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(output: &str, path: &str) -> Selection {
+        select(output.as_bytes(), &Path::parse(path).unwrap())
+    }
+
+    #[test]
+    fn test_numbers_kept_as_written() {
+        let value = serde_json::from_str(r#"{"a": [1.50, -2.5e3, 12345678901234567890123]}"#);
+        let leaves = leaves(&value.unwrap())
+            .into_iter()
+            .map(|(path, value)| (path, display(value)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            leaves,
+            [
+                (".a[0]".to_string(), "1.50".to_string()),
+                (".a[1]".to_string(), "-2.5e+3".to_string()),
+                (".a[2]".to_string(), "12345678901234567890123".to_string()),
+            ]
+        );
+        for invalid in ["01", "1.", "+1", "{\"a\": 1,}", "\"\\ud83d\""] {
+            assert!(selection(invalid, ".").is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_path() {
+        let key = |key: &str| Segment::Key(key.into());
+        assert_eq!(Path::parse("."), Ok(Path(vec![])));
+        assert_eq!(Path::parse(""), Ok(Path(vec![])));
+        assert_eq!(
+            Path::parse(".items[0].count"),
+            Ok(Path(vec![key("items"), Segment::Index(0), key("count")]))
+        );
+        assert_eq!(
+            Path::parse("items.0.count"),
+            Ok(Path(vec![key("items"), key("0"), key("count")]))
+        );
+        assert_eq!(
+            Path::parse(r#"["a.b"][-1]"#),
+            Ok(Path(vec![key("a.b"), Segment::Index(-1)]))
+        );
+        assert!(Path::parse("..a").is_err());
+        assert!(Path::parse("a[x]").is_err());
+        assert!(Path::parse("a[0").is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let output = r#"{"items": [{"n": 1}, {"n": 2, "tags": ["x"], "empty": {}}]}"#;
+        let number = |n: &str| Value::Number(n.parse().unwrap());
+        assert_eq!(
+            selection(output, ".items[0].n"),
+            Ok(vec![(String::new(), number("1"))])
+        );
+        assert_eq!(
+            selection(output, "items.-1"),
+            Err("no value at the path".into())
+        );
+        assert_eq!(
+            selection(output, ".items[-1]"),
+            Ok(vec![
+                (".n".into(), number("2")),
+                (".tags[0]".into(), Value::String("x".into())),
+                (".empty".into(), Value::Object(Default::default())),
+            ])
+        );
+        assert!(selection("not json", ".")
+            .unwrap_err()
+            .starts_with("invalid JSON"));
+    }
+
+    #[test]
+    fn test_render() {
+        let before = selection(r#"{"a": 10, "b": "x", "c": 0}"#, ".");
+        let after = selection(r#"{"a": 12.5, "b": "y", "c": 3}"#, ".");
+        assert_eq!(render(&before, None, true), ".a: 10\n.b: x\n.c: 0\n");
+        assert_eq!(
+            render(&before, Some(&before), true),
+            ".a: 10\n.b: x\n.c: 0\n"
+        );
+        assert_eq!(
+            render(&after, Some(&before), false),
+            ".a: 12.5  (+2.5, +25.0%)\n.b: y\n.c: 3  (+3)\n"
+        );
+        assert_eq!(
+            render(&after, Some(&before), true),
+            ".a: \x1b[7m12.5\x1b[0m  (+2.5, +25.0%)\n.b: \x1b[7my\x1b[0m\n.c: \x1b[7m3\x1b[0m  (+3)\n"
+        );
+        let error = Err("no value at the path".into());
+        assert_eq!(
+            render(&error, Some(&before), true),
+            "watch: no value at the path\n"
+        );
+        assert_eq!(
+            render(&before, Some(&error), true),
+            ".a: 10\n.b: x\n.c: 0\n"
+        );
+    }
+
+    #[test]
+    fn test_change() {
+        assert_eq!(change("0.1", "0.3"), "+0.2, +200.0%");
+        assert_eq!(change("-4", "-5"), "-1, -25.0%");
+        assert_eq!(change("1e3", "1.5e3"), "+500, +50.0%");
+    }
+}
@@ -0,0 +1,103 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Removes the escape sequences that move the cursor or otherwise alter the screen, such as
+/// cursor positioning, erasing, scrolling regions or window titles.
+///
+/// SGR sequences (colors and styles) are kept, so the output of the watched command can still
+/// be displayed as it would be in a terminal, just without redrawing over the rest of the screen.
+pub(crate) fn sanitize(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+
+    while i < input.len() {
+        if input[i] != ESC {
+            output.push(input[i]);
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+        match input.get(i) {
+            // CSI: parameter and intermediate bytes followed by a single final byte
+            Some(b'[') => {
+                i += 1;
+                while i < input.len() && !(0x40..=0x7e).contains(&input[i]) {
+                    i += 1;
+                }
+                if input.get(i) == Some(&b'm') {
+                    output.extend_from_slice(&input[start..=i]);
+                }
+                i += 1;
+            }
+            // OSC, DCS, SOS, PM and APC strings, terminated by BEL or ST (ESC \)
+            Some(b']' | b'P' | b'X' | b'^' | b'_') => {
+                i += 1;
+                while i < input.len() {
+                    if input[i] == BEL {
+                        i += 1;
+                        break;
+                    }
+                    if input[i] == ESC && input.get(i + 1) == Some(&b'\\') {
+                        i += 2;
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+            // Any other escape sequence: optional intermediate bytes then a final byte
+            Some(_) => {
+                while i < input.len() && (0x20..=0x2f).contains(&input[i]) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            None => {}
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(sanitize(b"hello\nworld\n"), b"hello\nworld\n");
+    }
+
+    #[test]
+    fn test_keeps_colors() {
+        let input = b"\x1b[1;31mred\x1b[0m";
+        assert_eq!(sanitize(input), input);
+    }
+
+    #[test]
+    fn test_removes_cursor_movement() {
+        assert_eq!(sanitize(b"\x1b[H\x1b[2Jtop\x1b[5;10Hx\x1b[?25l"), b"topx");
+        assert_eq!(sanitize(b"a\x1b7b\x1b8c\x1bMd"), b"abcd");
+        assert_eq!(sanitize(b"\x1b(Bcharset"), b"charset");
+    }
+
+    #[test]
+    fn test_removes_strings() {
+        assert_eq!(sanitize(b"\x1b]0;title\x07text"), b"text");
+        assert_eq!(sanitize(b"\x1b]0;title\x1b\\text"), b"text");
+        assert_eq!(sanitize(b"\x1bPdata\x1b\\text"), b"text");
+    }
+
+    #[test]
+    fn test_truncated_sequences() {
+        assert_eq!(sanitize(b"text\x1b"), b"text");
+        assert_eq!(sanitize(b"text\x1b[1;2"), b"text");
+        assert_eq!(sanitize(b"text\x1b]0;title"), b"text");
+    }
+}
@@ -4,16 +4,20 @@
 // file that was distributed with this source code.
 
 use clap::crate_version;
-use clap::{Arg, Command};
-use std::io::{Error, ErrorKind};
+use clap::{Arg, ArgAction, Command};
+use std::io::{Error, ErrorKind, IsTerminal, Write};
 use std::num::ParseIntError;
-use std::process::{Command as SystemCommand, Stdio};
+use std::process::{Command as SystemCommand, ExitStatus, Stdio};
 use std::thread::sleep;
-use std::time::Duration;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use std::time::{Duration, Instant};
+use uucore::{error::UResult, format_usage, help_about, help_section, help_usage};
+
+mod json;
+mod sanitize;
 
 const ABOUT: &str = help_about!("watch.md");
 const USAGE: &str = help_usage!("watch.md");
+const AFTER_HELP: &str = help_section!("after help", "watch.md");
 
 fn parse_interval(input: &str) -> Result<Duration, ParseIntError> {
     // Find index where to split string into seconds and nanos
@@ -57,6 +61,46 @@ fn parse_interval(input: &str) -> Result<Duration, ParseIntError> {
     Ok(std::cmp::max(duration, Duration::from_millis(100)))
 }
 
+/// The exit code of a run for `WATCH_PREV_EXIT`, 128 plus the signal when it was killed as in
+/// the shells.
+fn exit_code(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return (128 + signal).to_string();
+    }
+    status.code().unwrap_or_default().to_string()
+}
+
+/// Exit status and runtime of the last run of the command, for the header.
+fn format_status(code: Option<i32>, runtime: Duration) -> String {
+    let runtime = runtime.as_secs_f64();
+    match code {
+        Some(code) => format!("exit {code} in {runtime:.2}s"),
+        // The command was killed by a signal
+        None => format!("killed in {runtime:.2}s"),
+    }
+}
+
+fn write_header(
+    stdout: &mut impl Write,
+    interval: Duration,
+    command: &str,
+    code: Option<i32>,
+    runtime: Duration,
+) -> std::io::Result<()> {
+    let status = format_status(code, runtime);
+    let status = if code == Some(0) {
+        status
+    } else {
+        format!("\x1b[1;31m{status}\x1b[0m")
+    };
+    writeln!(
+        stdout,
+        "Every {:.1}s: {command}    {status}\n",
+        interval.as_secs_f64()
+    )
+}
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches = uu_app().try_get_matches_from(args)?;
@@ -71,13 +115,31 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             Err(_) => {
                 return Err(Box::from(Error::new(
                     ErrorKind::InvalidInput,
-                    format!("watch: failed to parse argument: '{input}': Invalid argument"),
+                    format!("failed to parse argument: '{input}': Invalid argument"),
+                )));
+            }
+        },
+    };
+    let json_path = match matches.get_one::<String>("json-path") {
+        None => None,
+        Some(expression) => match json::Path::parse(expression) {
+            Ok(path) => Some(path),
+            Err(error) => {
+                return Err(Box::from(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid JSON path: '{expression}': {error}"),
                 )));
             }
         },
     };
+    let raw = matches.get_flag("raw");
+    let is_terminal = std::io::stdout().is_terminal();
+    // The header is only meaningful on screen, piped output is kept as the command wrote it
+    let show_title = !matches.get_flag("no-title") && is_terminal;
 
-    loop {
+    let mut previous_exit = None;
+    let mut previous_selection = None;
+    for iteration in 1u64.. {
         #[cfg(windows)]
         let mut command =
             SystemCommand::new(std::env::var_os("COMSPEC").unwrap_or_else(|| "cmd.exe".into()));
@@ -89,12 +151,46 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         #[cfg(not(windows))]
         command.arg("-c");
 
+        // Lets the watched command know where it stands, to print its own deltas for example
+        command
+            .env("WATCH_ITERATION", iteration.to_string())
+            .env("WATCH_INTERVAL", interval.as_secs_f64().to_string());
+        if let Some(code) = previous_exit {
+            command.env("WATCH_PREV_EXIT", exit_code(code));
+        }
+
+        let start = Instant::now();
         let output = command
             .arg(command_to_watch)
-            .stdout(Stdio::inherit())
+            .stdout(Stdio::piped())
             .stderr(Stdio::inherit())
             .output()?;
 
+        let runtime = start.elapsed();
+        previous_exit = Some(output.status);
+
+        let mut stdout = std::io::stdout().lock();
+        if show_title {
+            write_header(
+                &mut stdout,
+                interval,
+                command_to_watch,
+                output.status.code(),
+                runtime,
+            )?;
+        }
+        if let Some(path) = &json_path {
+            let selection = json::select(&output.stdout, path);
+            let rendered = json::render(&selection, previous_selection.as_ref(), is_terminal);
+            stdout.write_all(rendered.as_bytes())?;
+            previous_selection = Some(selection);
+        } else if raw {
+            stdout.write_all(&output.stdout)?;
+        } else {
+            stdout.write_all(&sanitize::sanitize(&output.stdout))?;
+        }
+        stdout.flush()?;
+
         if !output.status.success() {
             eprintln!("watch: command failed: {:?}", output.status);
             break;
@@ -111,6 +207,7 @@ pub fn uu_app() -> Command {
         .version(crate_version!())
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
+        .after_help(AFTER_HELP)
         .infer_long_args(true)
         .arg(
             Arg::new("command")
@@ -186,6 +283,7 @@ pub fn uu_app() -> Command {
             Arg::new("no-title")
                 .short('t')
                 .long("no-title")
+                .action(ArgAction::SetTrue)
                 .help("Turn off header"),
         )
         .arg(
@@ -194,6 +292,20 @@ pub fn uu_app() -> Command {
                 .long("no-wrap")
                 .help("Turn off line wrapping"),
         )
+        .arg(
+            Arg::new("raw")
+                .long("raw")
+                .action(ArgAction::SetTrue)
+                .help("Pass cursor movement sequences through unchanged"),
+        )
+        .arg(
+            Arg::new("json-path")
+                .long("json-path")
+                .value_name("PATH")
+                .help(
+                "Show only the value at PATH in the JSON output, with the changes of its numbers",
+            ),
+        )
         .arg(
             Arg::new("exec")
                 .short('x')
@@ -202,6 +314,73 @@ pub fn uu_app() -> Command {
         )
 }
 
+#[cfg(test)]
+mod header_tests {
+    use super::*;
+
+    #[test]
+    fn test_format_status() {
+        assert_eq!(
+            format_status(Some(0), Duration::from_millis(1234)),
+            "exit 0 in 1.23s"
+        );
+        assert_eq!(
+            format_status(Some(3), Duration::from_millis(5)),
+            "exit 3 in 0.01s"
+        );
+        assert_eq!(
+            format_status(None, Duration::from_secs(2)),
+            "killed in 2.00s"
+        );
+    }
+
+    #[test]
+    fn test_header_is_colored_on_failure() {
+        let mut out = Vec::new();
+        write_header(
+            &mut out,
+            Duration::from_secs(2),
+            "true",
+            Some(0),
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Every 2.0s: true    exit 0 in 0.00s\n\n"
+        );
+
+        let mut out = Vec::new();
+        write_header(
+            &mut out,
+            Duration::from_secs(2),
+            "false",
+            Some(1),
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Every 2.0s: false    \x1b[1;31mexit 1 in 0.00s\x1b[0m\n\n"
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod environment_tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(exit_code(ExitStatus::from_raw(0)), "0");
+        assert_eq!(exit_code(ExitStatus::from_raw(3 << 8)), "3");
+        // Killed by SIGKILL
+        assert_eq!(exit_code(ExitStatus::from_raw(9)), "137");
+    }
+}
+
 #[cfg(test)]
 mod parse_interval_tests {
     use super::*;
@@ -0,0 +1,9 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Code shared between the utilities which doesn't belong to any of them.
+
+pub mod prometheus;
+pub mod scale;
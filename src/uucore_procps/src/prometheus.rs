@@ -0,0 +1,43 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Helpers for the metrics the tools expose in the Prometheus text exposition format.
+
+use std::fmt::Write;
+
+/// Writes the `HELP` and `TYPE` lines introducing a metric.
+pub fn header(output: &mut String, metric: &str, kind: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {metric} {help}");
+    let _ = writeln!(output, "# TYPE {metric} {kind}");
+}
+
+/// Escapes a label value, see the Prometheus text format specification.
+pub fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let mut output = String::new();
+        header(&mut output, "slab_objects", "gauge", "Number of objects.");
+        assert_eq!(
+            output,
+            "# HELP slab_objects Number of objects.\n# TYPE slab_objects gauge\n"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
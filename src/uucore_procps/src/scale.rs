@@ -0,0 +1,161 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Memory amounts shown in a unit of choice, falling back to larger units for the values too
+//! large for their column, as in the memory columns of top.
+
+/// Unit in which the memory is shown, as in the summary area or in the task area of top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scale {
+    Kib,
+    Mib,
+    Gib,
+    Tib,
+    Pib,
+    Eib,
+}
+
+impl Scale {
+    pub const ALL: [Self; 6] = [
+        Self::Kib,
+        Self::Mib,
+        Self::Gib,
+        Self::Tib,
+        Self::Pib,
+        Self::Eib,
+    ];
+
+    /// Parses the `k,m,g,t,p,e` suffixes of -E and -e.
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|it| it.suffix() == suffix)
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Kib => "k",
+            Self::Mib => "m",
+            Self::Gib => "g",
+            Self::Tib => "t",
+            Self::Pib => "p",
+            Self::Eib => "e",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kib => "KiB",
+            Self::Mib => "MiB",
+            Self::Gib => "GiB",
+            Self::Tib => "TiB",
+            Self::Pib => "PiB",
+            Self::Eib => "EiB",
+        }
+    }
+
+    /// Bytes in one unit.
+    pub fn factor(self) -> u64 {
+        match self {
+            Self::Kib => bytesize::KIB,
+            Self::Mib => bytesize::MIB,
+            Self::Gib => bytesize::GIB,
+            Self::Tib => bytesize::TIB,
+            Self::Pib => bytesize::PIB,
+            Self::Eib => bytesize::PIB * 1024,
+        }
+    }
+
+    /// The next larger unit, wrapping around after `last`.
+    pub fn cycle(self, last: Self) -> Self {
+        if self >= last {
+            Self::Kib
+        } else {
+            Self::ALL[self as usize + 1]
+        }
+    }
+}
+
+/// Formats `bytes` at `scale`, right-aligned in `width` columns. Values which don't fit are shown
+/// in the next larger unit that they fit in, along with its suffix, so that the columns keep their
+/// width whatever the magnitude of the values. `suffixed` tells whether the suffix is also shown
+/// at `scale` itself, the amounts of KiB being shown without decimals nor suffix in any case.
+pub fn format_scaled(bytes: u64, scale: Scale, width: usize, suffixed: bool) -> String {
+    let mut candidate = String::new();
+    for unit in Scale::ALL.into_iter().filter(|it| *it >= scale) {
+        let value = bytes as f64 / unit.factor() as f64;
+        let suffix = if unit != scale || suffixed && unit != Scale::Kib {
+            unit.suffix()
+        } else {
+            ""
+        };
+
+        let candidates = if unit == scale && unit == Scale::Kib {
+            vec![format!("{value:.0}{suffix}")]
+        } else {
+            vec![format!("{value:.1}{suffix}"), format!("{value:.0}{suffix}")]
+        };
+        for it in candidates {
+            candidate = it;
+            if candidate.len() <= width {
+                return format!("{candidate:>width$}");
+            }
+        }
+    }
+
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    const GIB: u64 = 1024 * MIB;
+
+    #[test]
+    fn test_from_suffix() {
+        assert_eq!(Scale::from_suffix("k"), Some(Scale::Kib));
+        assert_eq!(Scale::from_suffix("e"), Some(Scale::Eib));
+        assert_eq!(Scale::from_suffix("x"), None);
+    }
+
+    #[test]
+    fn test_cycle() {
+        assert_eq!(Scale::Kib.cycle(Scale::Pib), Scale::Mib);
+        assert_eq!(Scale::Pib.cycle(Scale::Pib), Scale::Kib);
+        assert_eq!(Scale::Pib.cycle(Scale::Eib), Scale::Eib);
+        assert_eq!(Scale::Eib.cycle(Scale::Eib), Scale::Kib);
+    }
+
+    #[test]
+    fn test_format_task_columns() {
+        assert_eq!(format_scaled(1536 * KIB, Scale::Kib, 7, true), "   1536");
+        assert_eq!(format_scaled(1536 * KIB, Scale::Mib, 7, true), "   1.5m");
+        assert_eq!(format_scaled(1536 * MIB, Scale::Gib, 7, true), "   1.5g");
+        assert_eq!(format_scaled(123_456 * KIB, Scale::Mib, 7, true), " 120.6m");
+        // Too large for the column at the requested unit
+        assert_eq!(
+            format_scaled(12_345_678 * KIB, Scale::Kib, 7, true),
+            " 12056m"
+        );
+        assert_eq!(format_scaled(99_999 * MIB, Scale::Mib, 7, true), " 99999m");
+        assert_eq!(
+            format_scaled(9_999_999 * MIB, Scale::Mib, 7, true),
+            "9765.6g"
+        );
+        assert_eq!(format_scaled(0, Scale::Gib, 7, true), "   0.0g");
+    }
+
+    #[test]
+    fn test_format_summary() {
+        assert_eq!(
+            format_scaled(15_900 * MIB, Scale::Mib, 8, false),
+            " 15900.0"
+        );
+        assert_eq!(format_scaled(16 * GIB, Scale::Kib, 8, false), "16777216");
+        assert_eq!(format_scaled(16 * GIB, Scale::Gib, 8, false), "    16.0");
+        assert_eq!(format_scaled(1024 * GIB, Scale::Kib, 8, false), "1048576m");
+    }
+}
@@ -83,6 +83,72 @@ fn test_total() {
             .unwrap()
             .starts_with("Total:"));
     }
+
+    // The first three columns of Mem and Swap add up
+    let result = new_ucmd!().args(&["-t", "-k"]).succeeds();
+    let rows = result
+        .stdout_str()
+        .lines()
+        .skip(1)
+        .map(|line| {
+            line.split_whitespace()
+                .skip(1)
+                .take(3)
+                .map(|value| value.parse::<u64>().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let [mem, swap, total] = &rows[..] else {
+        panic!("{rows:?}");
+    };
+    for ((mem, swap), total) in mem.iter().zip(swap).zip(total) {
+        assert_eq!(mem + swap, *total);
+    }
+}
+
+#[test]
+fn test_no_swap() {
+    let result = new_ucmd!().args(&["--no-swap", "-t"]).succeeds();
+    let lines = result.stdout_str().lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("Mem:"));
+    assert!(lines[2].starts_with("Total:"));
+
+    new_ucmd!()
+        .args(&["--no-swap", "--line"])
+        .fails()
+        .code_is(1);
+    new_ucmd!()
+        .arg("--no-swap=never")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid value 'never'");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_no_swap_auto() {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap();
+    let has_swap = !meminfo
+        .lines()
+        .any(|line| line.starts_with("SwapTotal:") && line.ends_with(" 0 kB"));
+
+    let result = new_ucmd!().arg("--no-swap=auto").succeeds();
+    assert_eq!(result.stdout_str().contains("Swap:"), has_swap);
+}
+
+#[test]
+fn test_human() {
+    for (args, value) in [
+        (&["-h"][..], r"(0B|[0-9.]{1,3}[KMGTP]i)"),
+        (&["--human", "--si", "--total"], r"(0B|[0-9.]{1,3}[KMGTP])"),
+    ] {
+        let output = new_ucmd!().args(args).succeeds().stdout_move_str();
+        let row = Regex::new(&format!(r"^[A-Z][a-z]+: +{value}( +{value})+$")).unwrap();
+        for line in output.lines().skip(1) {
+            assert!(row.is_match(line), "{line}");
+        }
+    }
 }
 
 #[test]
@@ -154,6 +220,70 @@ fn test_committed() {
     }
 }
 
+#[test]
+fn test_rows() {
+    let output = new_ucmd!()
+        .args(&["--rows", "mem"])
+        .succeeds()
+        .stdout_move_str();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("total"));
+    assert!(lines[1].starts_with("Mem:"));
+
+    // In the given order, repeating the option adds rows
+    let output = new_ucmd!()
+        .args(&["--rows", "total,swap", "--rows=mem", "--wide"])
+        .succeeds()
+        .stdout_move_str();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].contains("buffers"));
+    assert!(lines[1].starts_with("Total:"));
+    assert!(lines[2].starts_with("Swap:"));
+    assert!(lines[3].starts_with("Mem:"));
+
+    new_ucmd!()
+        .args(&["--rows", "mem,buffers"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid value 'buffers'");
+}
+
+#[test]
+fn test_old_format_is_ignored() {
+    let output = new_ucmd!().arg("-o").succeeds().stdout_move_str();
+    assert_default_format(&output);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_explain() {
+    let result = new_ucmd!().arg("--explain").succeeds();
+    let output = result.stdout_str();
+    assert_default_format(output);
+
+    let lines: Vec<&str> = output.lines().skip(4).collect();
+    if lines[0].starts_with("Available: cannot explain") {
+        return;
+    }
+
+    let value = |line: &str| -> u64 {
+        line[3..]
+            .split_whitespace()
+            .find_map(|word| word.parse().ok())
+            .unwrap()
+    };
+    assert_eq!(lines.len(), 6);
+    assert!(lines[1].starts_with("   free"));
+    assert!(lines[5].starts_with(" = available"));
+
+    // The reserved memory can exceed the free memory, the estimate doesn't go below zero
+    let estimate =
+        (value(lines[1]) + value(lines[3]) + value(lines[4])).saturating_sub(value(lines[2]));
+    assert_eq!(estimate, value(lines[5]));
+}
+
 #[test]
 fn test_seconds_zero() {
     for arg in ["-s", "--seconds"] {
@@ -166,6 +296,29 @@ fn test_seconds_zero() {
     }
 }
 
+#[test]
+fn test_seconds_not_positive() {
+    for seconds in ["-s=-1", "-s=nan", "-s=inf"] {
+        new_ucmd!()
+            .arg(seconds)
+            .fails()
+            .code_is(1)
+            .stderr_only("free: seconds argument must be greater than 0\n");
+    }
+}
+
+#[test]
+fn test_seconds_fraction() {
+    let start = std::time::Instant::now();
+    let output = new_ucmd!()
+        .args(&["-s", "0.2", "-c", "3"])
+        .succeeds()
+        .stdout_move_str();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(400));
+    // Three tables separated by blank lines
+    assert_eq!(output.lines().count(), 3 * 3 + 2);
+}
+
 #[test]
 fn test_unit() {
     fn extract_total(re: &Regex, output: &str) -> u64 {
@@ -187,6 +340,8 @@ fn test_unit() {
     let base: u64 = 1024;
     let base_si: u64 = 1000;
     for (args, divisor) in vec![
+        (vec!["-b"], 1),
+        (vec!["--bytes"], 1),
         (vec!["--kilo"], base_si),
         (vec!["--mega"], base_si.pow(2)),
         (vec!["--giga"], base_si.pow(3)),
@@ -218,6 +373,25 @@ fn test_unit() {
     }
 }
 
+#[test]
+fn test_unit_conflicts() {
+    for args in [
+        ["-b", "--kilo"],
+        ["-k", "-m"],
+        ["--mega", "--gibi"],
+        ["--tera", "--pebi"],
+    ] {
+        new_ucmd!()
+            .args(&args)
+            .fails()
+            .code_is(1)
+            .stderr_contains("cannot be used with");
+    }
+
+    // Repeating the same unit is fine
+    new_ucmd!().args(&["-m", "--mebi"]).succeeds();
+}
+
 fn assert_default_format(s: &str) {
     let header_pattern = r"^ {15}total {8}used {8}free {6}shared {2}buff/cache {3}available$";
     let mem_pattern = r"^Mem:( +\d+){6}$";
@@ -0,0 +1,81 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use uutests::new_ucmd;
+use uutests::util::TestScenario;
+use uutests::util_name;
+
+#[test]
+fn test_invalid_arg() {
+    new_ucmd!().arg("--definitely-invalid").fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_metrics() {
+    if !std::path::Path::new("/sys/kernel/mm/hugepages").exists() {
+        return;
+    }
+
+    let result = new_ucmd!().arg("--metrics").succeeds();
+    let stdout = result.stdout_str();
+
+    for metric in ["hugepages_total", "hugepages_free", "hugepages_reserved"] {
+        assert!(stdout.contains(&format!("# TYPE {metric} gauge\n")));
+    }
+    assert!(stdout.contains("# TYPE hugepages_process_private_bytes gauge\n"));
+    assert!(!stdout.contains("PID"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cgroups() {
+    if !std::path::Path::new("/sys/kernel/mm/hugepages").exists() {
+        return;
+    }
+
+    let result = new_ucmd!().arg("--cgroups").run();
+    if result.succeeded() {
+        let stdout = result.stdout_str();
+        assert!(stdout.starts_with("top - "));
+        assert!(stdout.contains("\nSize     Usage        Limit        Cgroup"));
+        assert!(!stdout.contains("PID"));
+    } else {
+        result
+            .code_is(1)
+            .stderr_contains("the hugetlb cgroup controller is not available");
+    }
+}
+
+#[test]
+fn test_cgroups_conflicts_with_metrics() {
+    new_ucmd!()
+        .args(&["--cgroups", "--metrics"])
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_interactive_indicator() {
+    if !std::path::Path::new("/sys/kernel/mm/hugepages").exists() {
+        return;
+    }
+
+    // Scroll down and quit
+    new_ucmd!()
+        .terminal_simulation(true)
+        .pipe_in("jq")
+        .succeeds()
+        .stdout_contains("PID      Private")
+        .stdout_contains("showing ");
+
+    new_ucmd!()
+        .arg("--once")
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("PID      Private")
+        .stdout_does_not_contain("showing");
+}
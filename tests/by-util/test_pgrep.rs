@@ -133,6 +133,65 @@ fn test_older_non_matching_pattern() {
         .no_stdout();
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_newer() {
+    use std::process::Command;
+
+    let mut child = Command::new("sleep").arg("4321.8").spawn().unwrap();
+    // Nothing started in the future, but the child started after the tests
+    new_ucmd!()
+        .args(&["--newer", "0", "-f", "^sleep 4321.8$"])
+        .fails()
+        .code_is(1);
+    new_ucmd!()
+        .args(&["-N"])
+        .arg(format!("pid:{}", std::process::id()))
+        .args(&["-f", "^sleep 4321.8$"])
+        .succeeds()
+        .stdout_is(format!("{}\n", child.id()));
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    new_ucmd!()
+        .args(&["--older", "pid:999999999"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("no process with PID 999999999");
+    new_ucmd!()
+        .args(&["--newer", "pid:x"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid value 'pid:x'");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ns() {
+    use std::process::Command;
+
+    let mut child = Command::new("sleep").arg("4322.3").spawn().unwrap();
+    new_ucmd!()
+        .arg("--ns")
+        .arg(child.id().to_string())
+        .args(&["--nslist", "net,pid", "-f", "^sleep 4322.3$"])
+        .succeeds()
+        .stdout_is(format!("{}\n", child.id()));
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    new_ucmd!()
+        .args(&["--ns", "999999999"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("no process with PID 999999999");
+    new_ucmd!()
+        .args(&["--ns", "1", "--nslist", "bogus"])
+        .fails()
+        .stderr_contains("invalid value 'bogus'");
+    new_ucmd!().args(&["--nslist", "net", "sh"]).fails();
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_full() {
@@ -225,6 +284,38 @@ fn test_list_full() {
     }
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_listing_and_delimiter() {
+    use std::process::Command;
+
+    let mut first = Command::new("sleep").arg("4324.1").spawn().unwrap();
+    let mut second = Command::new("sleep").arg("4324.1").spawn().unwrap();
+    let (first_pid, second_pid) = (first.id(), second.id());
+    let pattern = "^sleep 4324.1$";
+
+    new_ucmd!()
+        .args(&["-d", " ", "-f", pattern])
+        .succeeds()
+        .stdout_is(format!("{first_pid} {second_pid}\n"));
+    new_ucmd!()
+        .args(&["-l", "-f", pattern])
+        .succeeds()
+        .stdout_is(format!("{first_pid} sleep\n{second_pid} sleep\n"));
+    // The full command line wins over the name
+    new_ucmd!()
+        .args(&["-l", "-a", "-d", ",", "-f", pattern])
+        .succeeds()
+        .stdout_is(format!(
+            "{first_pid} sleep 4324.1,{second_pid} sleep 4324.1\n"
+        ));
+
+    for child in [&mut first, &mut second] {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_list_full_process_with_empty_cmdline() {
@@ -259,6 +350,34 @@ fn test_count_with_non_matching_pattern() {
         .no_stderr();
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_count_children() {
+    use std::process::Command;
+
+    let mut children = [(); 2].map(|_| Command::new("sleep").arg("4325.1").spawn().unwrap());
+    let pattern = "^sleep 4325.1$";
+
+    new_ucmd!()
+        .args(&["-c", "-f", pattern])
+        .succeeds()
+        .stdout_is("2\n");
+    // The count replaces the listing
+    new_ucmd!()
+        .args(&["-c", "-l", "-d", ",", "-f", pattern])
+        .succeeds()
+        .stdout_is("2\n");
+    new_ucmd!()
+        .args(&["-c", "-n", "-f", pattern])
+        .succeeds()
+        .stdout_is("1\n");
+
+    for child in &mut children {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_terminal() {
@@ -686,6 +805,40 @@ fn test_ignore_ancestors() {
         .stdout_does_not_match(&Regex::new("(?m)^1$").unwrap());
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_exclude_descendants() {
+    use std::process::Command;
+    use std::{thread::sleep, time::Duration};
+
+    // The shell waits for its child rather than executing it, as a command follows
+    let mut wrapper = Command::new("sh")
+        .args(["-c", "sleep 4322.1; exit"])
+        .spawn()
+        .unwrap();
+    sleep(Duration::from_millis(100));
+    let pattern = r"^(sh -c )?sleep 4322\.1";
+
+    let all = new_ucmd!()
+        .args(&["-f", pattern])
+        .succeeds()
+        .stdout_move_str();
+    let pids = all
+        .lines()
+        .map(|it| it.parse::<i32>().unwrap())
+        .collect::<Vec<_>>();
+    new_ucmd!()
+        .args(&["--exclude-descendants", "-f", pattern])
+        .succeeds()
+        .stdout_is(format!("{}\n", wrapper.id()));
+
+    for pid in &pids {
+        unsafe { uucore::libc::kill(*pid, uucore::libc::SIGKILL) };
+    }
+    wrapper.wait().unwrap();
+    assert_eq!(pids.len(), 2, "{all}");
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_env_nonexistent() {
@@ -720,3 +873,38 @@ fn test_env_multiple_filters() {
     // Multiple filters use OR logic
     new_ucmd!().arg("--env=PATH,NONEXISTENT").succeeds();
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_until_count() {
+    let mut p = new_ucmd!()
+        .args(&[
+            "--until-count",
+            ">=1",
+            "--poll",
+            "0.1",
+            "-f",
+            "^sleep 4322.5$",
+        ])
+        .run_no_wait();
+    p.make_assertion_with_delay(300).is_alive();
+
+    let mut child = Command::new("sleep").arg("4322.5").spawn().unwrap();
+    let result = p.wait().unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    result.success().stdout_is(format!("{}\n", child.id()));
+
+    // No match is fine when waiting for none
+    new_ucmd!()
+        .args(&["--until-count", "==0", "NONEXISTENT"])
+        .succeeds()
+        .no_output();
+
+    new_ucmd!()
+        .args(&["--until-count", "~1", "sh"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid count condition");
+}
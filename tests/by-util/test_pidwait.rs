@@ -46,3 +46,89 @@ fn test_too_many_patterns() {
         .no_stdout()
         .stderr_contains("only one pattern can be provided");
 }
+
+#[cfg(target_os = "linux")]
+fn pidfile_of(child: &std::process::Child) -> tempfile::NamedTempFile {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), format!("{}\n", child.id())).unwrap();
+    file
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_exit_status() {
+    // The test harness doesn't reap the child until the end, so its exit status stays readable.
+    let mut child = std::process::Command::new("sh")
+        .args(["-c", "sleep 0.3; exit 3"])
+        .spawn()
+        .unwrap();
+    let pidfile = pidfile_of(&child);
+
+    new_ucmd!()
+        .arg("--verbose")
+        .arg("--pidfile")
+        .arg(pidfile.path())
+        .succeeds()
+        .stdout_is(format!("sh (pid {}) exited with status 3\n", child.id()));
+
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_killed_by_signal() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let pidfile = pidfile_of(&child);
+    let pid = child.id().to_string();
+
+    let killer = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        std::process::Command::new("kill")
+            .args(["-KILL", &pid])
+            .status()
+            .unwrap();
+    });
+
+    new_ucmd!()
+        .arg("--verbose")
+        .arg("--pidfile")
+        .arg(pidfile.path())
+        .fails()
+        .code_is(4)
+        .stdout_contains("was killed by signal 9 (SIGKILL)");
+
+    killer.join().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_timeout() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let pidfile = pidfile_of(&child);
+
+    new_ucmd!()
+        .args(&["--verbose", "--timeout", "0.2", "--pidfile"])
+        .arg(pidfile.path())
+        .fails()
+        .code_is(3)
+        .stdout_contains("is still running");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_invalid_timeout() {
+    new_ucmd!()
+        .args(&["--timeout=-1", "sh"])
+        .fails()
+        .code_is(2)
+        .stderr_contains("invalid timeout '-1'");
+}
@@ -74,3 +74,114 @@ fn test_too_long_pattern() {
         .code_is(1)
         .stderr_contains("pattern that searches for process name longer than 15 characters will result in zero matches");
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_max_count() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+    use std::{thread::sleep, time::Duration};
+
+    let mut children = (0..3)
+        .map(|_| {
+            let child = Command::new("sleep").arg("4321.5").spawn().unwrap();
+            // Make sure the start times differ
+            sleep(Duration::from_millis(20));
+            child
+        })
+        .collect::<Vec<_>>();
+
+    new_ucmd!()
+        .args(&["--max-count", "2", "-o", "-c", "-f", "^sleep 4321.5$"])
+        .succeeds()
+        .stdout_is("2\n");
+
+    // The two oldest ones got signaled
+    assert_eq!(
+        children[0].wait().unwrap().signal(),
+        Some(uucore::libc::SIGTERM)
+    );
+    assert_eq!(
+        children[1].wait().unwrap().signal(),
+        Some(uucore::libc::SIGTERM)
+    );
+    assert!(children[2].try_wait().unwrap().is_none());
+
+    new_ucmd!()
+        .args(&["--max-count", "1", "-n", "-c", "-f", "^sleep 4321.5$"])
+        .succeeds()
+        .stdout_is("1\n");
+    assert_eq!(
+        children[2].wait().unwrap().signal(),
+        Some(uucore::libc::SIGTERM)
+    );
+
+    new_ucmd!()
+        .args(&["--max-count", "0", "sleep"])
+        .fails()
+        .code_is(1);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_queue() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    let mut child = Command::new("sleep").arg("4321.6").spawn().unwrap();
+    new_ucmd!()
+        .args(&["-USR1", "--queue", "-7", "-f", "^sleep 4321.6$"])
+        .succeeds();
+    assert_eq!(child.wait().unwrap().signal(), Some(uucore::libc::SIGUSR1));
+
+    new_ucmd!()
+        .args(&["-q", "seven", "sleep"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid value 'seven'");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_user() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+    use uucore::entries::uid2usr;
+    use uucore::process::getuid;
+
+    let mut child = Command::new("sleep").arg("4321.7").spawn().unwrap();
+    // Nobody has this ID, the process survives
+    new_ucmd!()
+        .args(&["-u", "4000000", "-f", "^sleep 4321.7$"])
+        .fails()
+        .code_is(1);
+
+    let user = uid2usr(getuid()).unwrap();
+    new_ucmd!()
+        .args(&["-u", &format!("4000000,{user}"), "-f", "^sleep 4321.7$"])
+        .succeeds();
+    assert_eq!(child.wait().unwrap().signal(), Some(uucore::libc::SIGTERM));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_cgroup() {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::Command;
+
+    // The child stays in the cgroup of the tests
+    let cgroup = std::fs::read_to_string("/proc/self/cgroup").unwrap();
+    let Some(cgroup) = cgroup.lines().find_map(|line| line.strip_prefix("0::")) else {
+        // No unified hierarchy
+        return;
+    };
+    let mut child = Command::new("sleep").arg("4323.1").spawn().unwrap();
+    new_ucmd!()
+        .args(&["--cgroup", "/nonexisting.slice", "-f", "^sleep 4323.1$"])
+        .fails()
+        .code_is(1);
+    new_ucmd!()
+        .args(&["--cgroup", cgroup, "-f", "^sleep 4323.1$"])
+        .succeeds();
+    assert_eq!(child.wait().unwrap().signal(), Some(uucore::libc::SIGTERM));
+}
@@ -92,6 +92,48 @@ fn test_read_rc_from() {
     }
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_read_rc_from_missing_file() {
+    new_ucmd!()
+        .args(&["-C", "no_such_rc_file", "1"])
+        .fails()
+        .code_is(1)
+        .stderr_is("pmap: couldn't read the rc file\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_rc_selects_fields() {
+    let mut child = spawn_sleep("10");
+    let pid = child.id().to_string();
+    let ts = TestScenario::new(util_name!());
+
+    ts.ucmd().args(&["-N", "pmap_rc_file_name"]).succeeds();
+    let rc = ts.fixtures.read("pmap_rc_file_name");
+    let rc = ["Perm", "Rss", "Mapping", "ShowPath"]
+        .iter()
+        .fold(rc, |rc, field| {
+            rc.replace(&format!("#{field}\n"), &format!("{field}\n"))
+        });
+    ts.fixtures.write("pmap_rc_file_name", &rc);
+
+    let result = ts
+        .ucmd()
+        .args(&["-C", "pmap_rc_file_name", &pid])
+        .succeeds()
+        .stdout_move_str();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let mut lines = result.lines().skip(1);
+    let header = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+    assert_eq!(header, ["Address", "Perm", "Rss", "Mapping"]);
+    // The paths are shown in full
+    assert!(result.contains(" /"), "{result}");
+    assert!(!result.contains(" sleep\n"), "{result}");
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_existing_pid() {
@@ -126,6 +168,41 @@ fn test_multiple_existing_pids() {
     assert_format(pid, &right.join("\n"), false, false);
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_multiple_processes() {
+    let mut children = ["10", "11", "12"].map(spawn_sleep);
+    let pids = children.each_ref().map(|child| child.id().to_string());
+
+    let result = new_ucmd!()
+        .args(&pids)
+        .arg(NON_EXISTING_PID)
+        .fails()
+        .code_is(42)
+        .stdout_str()
+        .to_string();
+    for child in &mut children {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    // One header per process, in the order of the arguments
+    let re = Regex::new(r"^[1-9]\d*:").unwrap();
+    let headers = result
+        .lines()
+        .filter(|line| re.is_match(line))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        headers,
+        [
+            format!("{}:   sleep 10", pids[0]),
+            format!("{}:   sleep 11", pids[1]),
+            format!("{}:   sleep 12", pids[2]),
+        ]
+    );
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_non_existing_and_existing_pid() {
@@ -269,6 +346,30 @@ fn test_quiet() {
     }
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_quiet_only_lists_mappings() {
+    let mut child = spawn_sleep("10");
+    let pid = child.id().to_string();
+    let re = Regex::new(r"^[0-9a-f]{16} ").unwrap();
+
+    for format in [None, Some("-x"), Some("-X"), Some("-d")] {
+        let mut cmd = new_ucmd!();
+        cmd.arg("-q").args(format.as_slice()).arg(&pid);
+        let result = cmd.succeeds().stdout_move_str();
+
+        // Only the line of the process is left besides the mappings
+        let mut lines = result.lines();
+        assert_eq!(lines.next().unwrap(), format!("{pid}:   sleep 10"));
+        for line in lines {
+            assert!(re.is_match(line), "unexpected line {line:?} for {format:?}");
+        }
+    }
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_showpath() {
@@ -341,11 +442,259 @@ fn _test_multiple_formats(pid: u32, arg: &str, quiet: bool, show_path: bool) {
     assert_device_format(pid, &result, quiet, show_path);
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_extended_totals() {
+    let mut child = spawn_sleep("10");
+
+    let result = new_ucmd!()
+        .arg("-x")
+        .arg(child.id().to_string())
+        .succeeds()
+        .stdout_move_str();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let lines = result.lines().collect::<Vec<_>>();
+    let mut sums = [0u64; 3];
+    for line in &lines[2..lines.len() - 2] {
+        let columns = line.split_whitespace().collect::<Vec<_>>();
+        for (sum, value) in sums.iter_mut().zip(&columns[1..4]) {
+            *sum += value.parse::<u64>().unwrap();
+        }
+    }
+
+    let totals = lines[lines.len() - 1]
+        .split_whitespace()
+        .skip(2)
+        .map(|it| it.parse::<u64>().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(totals, sums);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_device_totals() {
+    let mut child = spawn_sleep("10");
+
+    let result = new_ucmd!()
+        .arg("-d")
+        .arg(child.id().to_string())
+        .succeeds()
+        .stdout_move_str();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let lines = result.lines().collect::<Vec<_>>();
+    let (mut mapped, mut writeable_private, mut shared) = (0, 0, 0);
+    for line in &lines[2..lines.len() - 1] {
+        let columns = line.split_whitespace().collect::<Vec<_>>();
+        let size = columns[1].parse::<u64>().unwrap();
+        let mode = columns[2].as_bytes();
+        mapped += size;
+        if mode[3] == b's' {
+            shared += size;
+        } else if mode[1] == b'w' {
+            writeable_private += size;
+        }
+    }
+
+    assert_eq!(
+        lines[lines.len() - 1],
+        format!(
+            "mapped: {mapped}K    writeable/private: {writeable_private}K    shared: {shared}K"
+        )
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_range() {
+    let mut child = spawn_sleep("10");
+    let pid = child.id().to_string();
+
+    let output = new_ucmd!().arg(&pid).succeeds().stdout_move_str();
+    let lines = output.lines().collect::<Vec<_>>();
+    let address = |line: &str| line.split_whitespace().next().unwrap().to_string();
+    let (second, third) = (address(lines[2]), address(lines[3]));
+
+    // A single address selects the mapping containing it
+    new_ucmd!()
+        .args(&["-A", &second, &pid])
+        .succeeds()
+        .stdout_is(format!(
+            "{}\n{}\n total {:>16}K\n",
+            lines[0],
+            lines[2],
+            lines[2]
+                .split_whitespace()
+                .nth(1)
+                .unwrap()
+                .trim_end_matches('K')
+        ));
+
+    // The totals only cover the mappings in range
+    let output = new_ucmd!()
+        .args(&["-x", "-A", &format!("{second},{third}"), &pid])
+        .succeeds()
+        .stdout_move_str();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 6);
+    assert!(lines[2].starts_with(&second));
+    assert!(lines[3].starts_with(&third));
+
+    new_ucmd!()
+        .args(&["-A", "0x1,zz", &pid])
+        .fails()
+        .code_is(1)
+        .stderr_is("pmap: invalid address 'zz'\n");
+    new_ucmd!()
+        .args(&["-A", "2,1", &pid])
+        .fails()
+        .code_is(1)
+        .stderr_contains("greater than the high one");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_collapse_anon() {
+    let mut child = spawn_sleep("10");
+    let pid = child.id().to_string();
+    let re = Regex::new(r"\[ anon \] \(([0-9]+) mappings\)$").unwrap();
+
+    for arg in [None, Some("--extended"), Some("--device")] {
+        let run = |collapse: bool| {
+            let mut ucmd = new_ucmd!();
+            ucmd.args(&arg.into_iter().collect::<Vec<_>>());
+            if collapse {
+                ucmd.arg("--collapse-anon");
+            }
+            ucmd.arg(&pid).succeeds().stdout_move_str()
+        };
+        let expanded = run(false);
+        let collapsed = run(true);
+
+        // The footer is unchanged, only the rows are merged
+        assert_eq!(expanded.lines().last(), collapsed.lines().last());
+        assert!(collapsed.lines().count() <= expanded.lines().count());
+
+        let merged = collapsed
+            .lines()
+            .filter_map(|line| re.captures(line))
+            .map(|captures| captures[1].parse::<usize>().unwrap() - 1)
+            .sum::<usize>();
+        assert_eq!(collapsed.lines().count() + merged, expanded.lines().count());
+    }
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
 #[test]
 fn test_invalid_arg() {
     new_ucmd!().arg("--definitely-invalid").fails().code_is(1);
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_json() {
+    let mut child = spawn_sleep("10");
+    let pid = child.id().to_string();
+
+    let default = new_ucmd!().arg("-q").arg(&pid).succeeds().stdout_move_str();
+    let result = new_ucmd!()
+        .arg("--json")
+        .arg(&pid)
+        .succeeds()
+        .stdout_move_str();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let prefix = format!("{{\"pid\":{pid},\"cmdline\":\"sleep 10\",\"mappings\":[");
+    assert!(result.starts_with(&prefix), "{result}");
+    assert!(result.ends_with("]}\n"));
+    assert_eq!(result.lines().count(), 1);
+
+    let re = Regex::new(
+        r#"\{"address":"[0-9a-f]{16}","size_kb":[0-9]+,"rss_kb":[0-9]+,"pss_kb":[0-9]+,"flags":"[r-][w-][x-][sp]","path":"[^"]*"\}"#,
+    )
+    .unwrap();
+    assert_eq!(re.find_iter(&result).count(), default.lines().count() - 1);
+    assert!(result.contains("/sleep\"}"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_separator() {
+    let mut child = spawn_sleep("10");
+    let pid = child.id().to_string();
+
+    let default = new_ucmd!().arg("-q").arg(&pid).succeeds().stdout_move_str();
+    let result = new_ucmd!()
+        .args(&["--separator", ";", &pid])
+        .succeeds()
+        .stdout_move_str();
+    let quiet = new_ucmd!()
+        .args(&["-q", "--separator", ";", &pid])
+        .succeeds()
+        .stdout_move_str();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let mut lines = result.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "PID;Address;Kbytes;RSS;PSS;Mode;Mapping"
+    );
+    let rows = lines.collect::<Vec<_>>();
+    assert_eq!(rows.len(), default.lines().count() - 1);
+    for row in &rows {
+        let fields = row.splitn(7, ';').collect::<Vec<_>>();
+        assert_eq!(fields.len(), 7, "{row}");
+        assert_eq!(fields[0], pid);
+        assert!(fields[2..5].iter().all(|it| it.parse::<u64>().is_ok()));
+    }
+    // Only the header is left out, the RSS and PSS might have changed in the meantime
+    let addresses = |rows: &mut dyn Iterator<Item = &str>| {
+        rows.map(|row| row.split(';').nth(1).unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        addresses(&mut quiet.lines()),
+        addresses(&mut rows.iter().copied())
+    );
+}
+
+#[test]
+fn test_json_conflicts_with_other_formats() {
+    for arg in ["-x", "-d", "--separator=,"] {
+        new_ucmd!()
+            .args(&["--json", arg, "1"])
+            .fails()
+            .code_is(1)
+            .stderr_contains("cannot be used with");
+    }
+}
+
+/// Spawns `sleep`, waiting for the exec so that its mappings no longer are those of the tests.
+#[cfg(target_os = "linux")]
+fn spawn_sleep(seconds: &str) -> process::Child {
+    use std::time::{Duration, Instant};
+
+    let mut child = process::Command::new("sleep").arg(seconds).spawn().unwrap();
+    let cmdline = format!("/proc/{}/cmdline", child.id());
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !std::fs::read(&cmdline).is_ok_and(|it| it.starts_with(b"sleep\0")) {
+        assert!(child.try_wait().unwrap().is_none(), "sleep exited early");
+        assert!(Instant::now() < deadline, "sleep didn't start in time");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    child
+}
+
 // Ensure `s` has the following format:
 //
 // 1234:   /some/path
@@ -608,3 +957,47 @@ fn assert_device_format(pid: u32, s: &str, quiet: bool, show_path: bool) {
         );
     }
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_command() {
+    let result = new_ucmd!()
+        .args(&["-q", "--command", "sleep 0.1; exit 3"])
+        .fails();
+    result.code_is(3);
+    let stdout = result.stdout_str();
+
+    // Both the shell and the process it forked are reported when they exit
+    assert!(stdout.contains(":   sh -c sleep 0.1; exit 3\n"), "{stdout}");
+    assert!(stdout.contains(":   sleep 0.1\n"), "{stdout}");
+    assert!(stdout.lines().any(|line| line.ends_with(" sleep")));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_command_report_on_sigusr1() {
+    let mut ucmd = new_ucmd!();
+    let child = ucmd.args(&["-q", "--command", "sleep 1"]).run_no_wait();
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    std::process::Command::new("kill")
+        .args(["-USR1", &child.id().to_string()])
+        .status()
+        .unwrap();
+
+    let result = child.wait().unwrap();
+    result.success();
+    let reports = result
+        .stdout_str()
+        .lines()
+        .filter(|line| line.ends_with(":   sleep 1"))
+        .count();
+    assert_eq!(reports, 2);
+}
+
+#[test]
+fn test_command_conflicts_with_pid() {
+    new_ucmd!()
+        .args(&["--command", "true", "1"])
+        .fails()
+        .code_is(1);
+}
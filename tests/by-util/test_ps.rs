@@ -3,6 +3,9 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#[cfg(target_os = "linux")]
+use uutests::util::CmdResult;
+
 use uutests::new_ucmd;
 use uutests::util::TestScenario;
 use uutests::util_name;
@@ -33,6 +36,15 @@ fn test_code_mapping() {
 
     new_ucmd!().args(&["-o", "ccmd=CCMD"]).fails().code_is(1);
 
+    // Known to procps, but not implemented yet
+    for args in [["-o", "cgroup"], ["--sort", "cgroup"]] {
+        new_ucmd!()
+            .args(&args)
+            .fails()
+            .code_is(1)
+            .stderr_is("ps: error: unknown user-defined format specifier \"cgroup\"\n");
+    }
+
     new_ucmd!()
         .args(&["-o", "cmd=CMD1", "-o", "cmd=CMD2"])
         .succeeds()
@@ -57,3 +69,694 @@ fn test_code_mapping() {
         .stdout_contains("CMD1")
         .stdout_contains("CMD2");
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_forest() {
+    for args in [vec!["-A", "--forest"], vec!["-A", "f"]] {
+        new_ucmd!().args(&args).succeeds().stdout_contains(" \\_ ");
+    }
+
+    // Option values are not mistaken for BSD options
+    new_ucmd!()
+        .args(&["-o", "f"])
+        .succeeds()
+        .stdout_contains("F");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_select_by_user() {
+    let euid = uucore::process::geteuid().to_string();
+    let user = uucore::entries::uid2usr(uucore::process::geteuid()).unwrap();
+
+    for arg in ["-u", "--user", "-U", "--User"] {
+        for value in [&euid, &user] {
+            let result = new_ucmd!()
+                .args(&[arg, value, "-o", "euid,ruid"])
+                .succeeds();
+            // header and at least ps itself
+            assert!(result.stdout_str().lines().count() >= 2);
+        }
+    }
+
+    new_ucmd!()
+        .args(&["-u", "definitely-not-a-user"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("user name does not exist");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_select_by_group() {
+    let egid = uucore::process::getegid();
+    let group = uucore::entries::gid2grp(egid).unwrap();
+
+    for arg in ["-g", "--group", "-G", "--Group"] {
+        let result = new_ucmd!()
+            .args(&[arg, &group, "-o", "egid,rgid"])
+            .succeeds();
+        assert!(result.stdout_str().lines().count() >= 2);
+    }
+
+    // numeric lists select by session with -g
+    let sid = unsafe { libc::getsid(0) }.to_string();
+    let result = new_ucmd!().args(&["-g", &sid, "-o", "sid"]).succeeds();
+    assert!(result
+        .stdout_str()
+        .lines()
+        .skip(1)
+        .all(|line| line.trim() == sid));
+
+    new_ucmd!()
+        .args(&["-G", "definitely-not-a-group"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("group name does not exist");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_select_by_command() {
+    // The utility under test runs inside the multicall binary
+    let result = new_ucmd!()
+        .args(&["-C", "procps,definitely-not-a-command", "-o", "comm"])
+        .succeeds();
+    let lines = result.stdout_str().lines().skip(1).collect::<Vec<_>>();
+    assert!(!lines.is_empty());
+    assert!(lines.iter().all(|line| line.trim() == "procps"));
+
+    let result = new_ucmd!()
+        .args(&["-C", "definitely-not-a-command"])
+        .fails_with_code(1);
+    assert_eq!(result.stdout_str().lines().count(), 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_select_by_tty() {
+    use std::os::unix::process::CommandExt;
+
+    // Without a controlling terminal, whatever the tests run in
+    let mut command = std::process::Command::new("sleep");
+    command.arg("4326.1");
+    // SAFETY: setsid is async-signal-safe
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().unwrap();
+
+    for arg in ["-t", "--tty"] {
+        for tty in ["?", "-"] {
+            let result = new_ucmd!()
+                .args(&[arg, tty, "--no-headers", "-o", "pid,tty"])
+                .succeeds();
+            let rows = result
+                .stdout_str()
+                .lines()
+                .map(|line| line.split_whitespace().collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            assert!(rows.iter().all(|row| row[1] == "?"), "{rows:?}");
+            assert!(
+                rows.iter().any(|row| row[0] == child.id().to_string()),
+                "{rows:?}"
+            );
+        }
+
+        // Nothing runs on these terminals
+        let result = new_ucmd!()
+            .args(&[arg, "pts/999,tty999"])
+            .fails_with_code(1);
+        assert_eq!(result.stdout_str().lines().count(), 1);
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    new_ucmd!()
+        .args(&["-t", "definitely-not-a-tty"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("TTY could not be found");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_sort() {
+    for args in [
+        vec!["-A", "--sort=-pid", "-o", "pid"],
+        vec!["-A", "k", "-pid", "-o", "pid"],
+    ] {
+        let result = new_ucmd!().args(&args).succeeds();
+        let pids = result
+            .stdout_str()
+            .lines()
+            .skip(1)
+            .map(|line| line.trim().parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(pids.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    new_ucmd!()
+        .args(&["-A", "--sort=+ppid,-pid", "-o", "ppid,pid"])
+        .succeeds();
+    new_ucmd!().arg("--sort=-foo").fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_threads() {
+    new_ucmd!()
+        .arg("-L")
+        .succeeds()
+        .stdout_contains("LWP")
+        .stdout_contains("NLWP");
+    new_ucmd!().arg("-T").succeeds().stdout_contains("SPID");
+
+    // -m lists the threads, with a dash in place of the process columns, under each process
+    let result = new_ucmd!().args(&["-m", "-o", "pid,tid,comm"]).succeeds();
+    let mut lines = result.stdout_str().lines().skip(1);
+    let process = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+    assert_eq!(process[1], "-");
+    let thread = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+    assert_eq!(thread[0], "-");
+    assert_eq!(thread[1], process[0]);
+    assert_eq!(thread[2], "-");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_bsd_options() {
+    new_ucmd!()
+        .arg("aux")
+        .succeeds()
+        .stdout_contains("USER")
+        .stdout_contains("%CPU")
+        .stdout_contains("STAT")
+        .stdout_contains("START");
+
+    new_ucmd!()
+        .arg("axjf")
+        .succeeds()
+        .stdout_contains("TPGID")
+        .stdout_contains(" \\_ ");
+
+    new_ucmd!().arg("axl").succeeds().stdout_contains("WCHAN");
+
+    // `ax` selects every process, like `-A`
+    let count = |args: &[&str]| {
+        new_ucmd!()
+            .args(args)
+            .args(&["-o", "pid"])
+            .succeeds()
+            .stdout_str()
+            .lines()
+            .count()
+    };
+    let all = count(&["-A"]);
+    assert!(count(&["ax"]).abs_diff(all) <= 2);
+    assert!(count(&["x"]) <= all);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_watch() {
+    let mut p = new_ucmd!()
+        .args(&["-A", "-o", "pid,comm", "--watch", "0.2"])
+        .run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_contains("PID");
+
+    for interval in ["soon", "inf", "NaN", "1e300"] {
+        new_ucmd!()
+            .args(&["--watch", interval])
+            .fails()
+            .code_is(1)
+            .stderr_contains("invalid value");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_standard_formats() {
+    let header = |args: &[&str]| {
+        let result = new_ucmd!().args(args).succeeds();
+        result.stdout_str().lines().next().unwrap().to_string()
+    };
+    let columns = |header: String| {
+        header
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        columns(header(&["-f"])),
+        ["UID", "PID", "PPID", "C", "STIME", "TTY", "TIME", "CMD"]
+    );
+    assert_eq!(
+        columns(header(&["-F"])),
+        ["UID", "PID", "PPID", "C", "SZ", "RSS", "PSR", "STIME", "TTY", "TIME", "CMD"]
+    );
+    assert_eq!(
+        columns(header(&["-l"])),
+        [
+            "F", "S", "UID", "PID", "PPID", "C", "PRI", "NI", "ADDR", "SZ", "WCHAN", "TTY", "TIME",
+            "CMD"
+        ]
+    );
+    assert!(columns(header(&["-lf"])).join(" ").contains("WCHAN STIME"));
+    assert!(columns(header(&["-fL"]))
+        .join(" ")
+        .contains("PPID LWP NLWP C"));
+
+    // An explicit format wins over the predefined ones
+    assert_eq!(columns(header(&["-f", "-o", "pid"])), ["PID"]);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_context() {
+    for arg in ["-Z", "--context"] {
+        let result = new_ucmd!().arg(arg).succeeds();
+        let header = result.stdout_str().lines().next().unwrap();
+        assert_eq!(header.split_whitespace().next(), Some("LABEL"));
+    }
+
+    new_ucmd!()
+        .args(&["-o", "pid,label"])
+        .succeeds()
+        .stdout_contains("LABEL");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_mappings() {
+    let result = new_ucmd!()
+        .args(&["-C", "procps", "-o", "pid,maps_count,mapped_kb"])
+        .succeeds();
+    let mut lines = result.stdout_str().lines();
+    let header = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+    assert_eq!(header, ["PID", "MAPS", "MAPPED"]);
+
+    // At least the executable itself is mapped
+    let row = lines.next().unwrap().split_whitespace().collect::<Vec<_>>();
+    assert!(row[1].parse::<u64>().unwrap() > 0, "{row:?}");
+    assert!(row[2].parse::<u64>().unwrap() > 0, "{row:?}");
+
+    new_ucmd!()
+        .args(&["-A", "--sort=-maps_count", "-o", "pid,maps_count"])
+        .succeeds();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_environment() {
+    new_ucmd!()
+        .args(&["e", "-C", "procps", "-o", "args"])
+        .env("PS_TEST_VARIABLE", "42")
+        .succeeds()
+        .stdout_contains(" PS_TEST_VARIABLE=42");
+
+    new_ucmd!()
+        .args(&["-C", "procps", "-o", "pid,environ"])
+        .env("PS_TEST_VARIABLE", "42")
+        .succeeds()
+        .stdout_contains("ENVIRONMENT")
+        .stdout_contains("PS_TEST_VARIABLE=42");
+
+    new_ucmd!()
+        .args(&["-C", "procps", "-o", "args"])
+        .env("PS_TEST_VARIABLE", "42")
+        .succeeds()
+        .stdout_does_not_contain("PS_TEST_VARIABLE");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_select_by_ppid() {
+    let result = new_ucmd!()
+        .args(&["--ppid", "1,2", "-o", "ppid"])
+        .succeeds();
+    let ppids = result.stdout_str().lines().skip(1).collect::<Vec<_>>();
+    assert!(!ppids.is_empty());
+    assert!(ppids.iter().all(|it| ["1", "2"].contains(&it.trim())));
+
+    new_ucmd!()
+        .args(&["--ppid", "init"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("process ID list syntax error");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_headers() {
+    let result = new_ucmd!().args(&["-A", "--no-headers"]).succeeds();
+    assert!(!result.stdout_str().contains("CMD"));
+    assert!(!result.stdout_str().is_empty());
+
+    // Not a terminal, so the header is printed only once
+    let result = new_ucmd!().args(&["-A", "--headers"]).succeeds();
+    assert_eq!(result.stdout_str().matches("CMD").count(), 1);
+
+    // The simulated terminal has 30 rows, so a header every 29 rows
+    let result = new_ucmd!()
+        .args(&["-A", "--headers", "-o", "pid"])
+        .terminal_simulation(true)
+        .succeeds();
+    let lines = result.stdout_str().lines().collect::<Vec<_>>();
+    let headers = lines.iter().filter(|line| line.trim() == "PID").count();
+    assert_eq!(headers, (lines.len() - headers).div_ceil(29).max(1));
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(line.trim() == "PID", i % 30 == 0, "{line}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_long_cmdline() {
+    // A single argument is limited to 128 KiB, make several of them
+    let argument = "x".repeat(100_000);
+    let mut child = std::process::Command::new("sh")
+        .args([
+            "-c",
+            "sleep 10; :",
+            "long_cmdline",
+            &argument,
+            &argument,
+            &argument,
+        ])
+        .spawn()
+        .unwrap();
+
+    let result = new_ucmd!()
+        .args(&["-ww", "--no-headers", "-o", "args", "--ppid"])
+        .arg(std::process::id().to_string())
+        .succeeds();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let expected = format!("sh -c sleep 10; : long_cmdline {argument} {argument} {argument}");
+    assert!(result
+        .stdout_str()
+        .lines()
+        .any(|line| line.trim() == expected));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cmdline_control_characters() {
+    let mut child = std::process::Command::new("sh")
+        .args([
+            "-c",
+            "sleep 10; :",
+            "control_characters",
+            "first\nsecond\tthird",
+        ])
+        .spawn()
+        .unwrap();
+
+    let result = new_ucmd!()
+        .args(&["--no-headers", "-o", "args", "--ppid"])
+        .arg(std::process::id().to_string())
+        .succeeds();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(result
+        .stdout_str()
+        .lines()
+        .any(|line| line.trim() == "sh -c sleep 10; : control_characters first?second?third"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_width() {
+    let widths = |result: &CmdResult| {
+        result
+            .stdout_str()
+            .lines()
+            .map(|line| line.trim_end_matches('\r').chars().count())
+            .collect::<Vec<_>>()
+    };
+
+    // Not a terminal, so unlimited by default
+    let result = new_ucmd!().args(&["-A", "-o", "pid,args"]).succeeds();
+    assert!(widths(&result).iter().any(|width| *width > 20));
+
+    for option in ["--cols", "--columns", "--width"] {
+        let result = new_ucmd!()
+            .args(&["-A", "-o", "pid,args", option, "20"])
+            .succeeds();
+        assert!(widths(&result).iter().all(|width| *width <= 20));
+    }
+
+    let result = new_ucmd!()
+        .args(&["-A", "-o", "pid,args"])
+        .env("COLUMNS", "20")
+        .succeeds();
+    assert!(widths(&result).iter().all(|width| *width <= 20));
+
+    let result = new_ucmd!()
+        .args(&["-A", "-o", "pid,args", "-w", "--cols", "20"])
+        .succeeds();
+    assert!(widths(&result).iter().all(|width| *width <= 132));
+    assert!(widths(&result).iter().any(|width| *width > 20));
+
+    let result = new_ucmd!()
+        .args(&["-A", "-o", "pid,args", "-ww", "--cols", "20"])
+        .succeeds();
+    assert!(widths(&result).iter().any(|width| *width > 20));
+
+    // The simulated terminal is 80 columns wide
+    let result = new_ucmd!()
+        .args(&["-A", "-o", "pid,args"])
+        .terminal_simulation(true)
+        .succeeds();
+    assert!(widths(&result).iter().all(|width| *width <= 80));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_elapsed_and_start_time() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+
+    let result = new_ucmd!()
+        .args(&[
+            "--no-headers",
+            "-o",
+            "pid,etimes,etime,start,lstart",
+            "--ppid",
+        ])
+        .arg(std::process::id().to_string())
+        .succeeds();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    let line = result
+        .stdout_str()
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some(&child.id().to_string()))
+        .unwrap()
+        .to_string();
+    let columns = line.split_whitespace().collect::<Vec<_>>();
+
+    // Just started
+    let elapsed = columns[1].parse::<u64>().unwrap();
+    assert!(elapsed < 10);
+    // Read at a slightly different time
+    let seconds = columns[2]
+        .strip_prefix("00:")
+        .unwrap()
+        .parse::<u64>()
+        .unwrap();
+    assert!(seconds.abs_diff(elapsed) <= 1);
+    // HH:MM:SS
+    assert_eq!(columns[3].len(), 8);
+    assert_eq!(columns[3].matches(':').count(), 2);
+    // Weekday, month, day, time and year
+    assert_eq!(columns.len(), 9);
+    assert_eq!(columns[7], columns[3]);
+
+    new_ucmd!()
+        .args(&["-A", "-o", "etime,etimes,start,lstart"])
+        .succeeds()
+        .stdout_contains("ELAPSED")
+        .stdout_contains("STARTED");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_date_format() {
+    let format = "%Y-%m-%dT%H:%M:%S%:z";
+    let utc = new_ucmd!()
+        .env("TZ", "UTC")
+        .args(&[
+            "--no-headers",
+            "--date-format",
+            format,
+            "-o",
+            "lstart,start",
+            "--ppid",
+            "0",
+        ])
+        .succeeds()
+        .stdout_move_str();
+    let columns = utc
+        .lines()
+        .next()
+        .unwrap()
+        .split_whitespace()
+        .collect::<Vec<_>>();
+    assert_eq!(columns.len(), 2);
+    assert_eq!(columns[0], columns[1]);
+    assert!(columns[0].ends_with("+00:00"), "{utc}");
+
+    // The same instant in another time zone
+    let shifted = new_ucmd!()
+        .env("TZ", "UTC-09:00")
+        .args(&[
+            "--no-headers",
+            "--date-format",
+            "%s %:z",
+            "-o",
+            "lstart",
+            "--ppid",
+            "0",
+        ])
+        .succeeds()
+        .stdout_move_str();
+    let utc_seconds = new_ucmd!()
+        .env("TZ", "UTC")
+        .args(&[
+            "--no-headers",
+            "--date-format",
+            "%s",
+            "-o",
+            "lstart",
+            "--ppid",
+            "0",
+        ])
+        .succeeds()
+        .stdout_move_str();
+    assert_eq!(
+        shifted
+            .lines()
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<_>>(),
+        [utc_seconds.lines().next().unwrap().trim(), "+09:00"]
+    );
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_time_style() {
+    let lstart = |time_style: &str| {
+        new_ucmd!()
+            .env("TZ", "UTC")
+            .env("TIME_STYLE", time_style)
+            .args(&["--no-headers", "-o", "lstart", "--ppid", "0"])
+            .succeeds()
+            .stdout_move_str()
+            .lines()
+            .next()
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+
+    let seconds = new_ucmd!()
+        .env("TZ", "UTC")
+        .args(&["--no-headers", "--date-format", "%s", "-o", "lstart"])
+        .args(&["--ppid", "0"])
+        .succeeds()
+        .stdout_move_str();
+    assert_eq!(lstart("+%s"), seconds.lines().next().unwrap().trim());
+
+    let long_iso = lstart("long-iso");
+    assert!(
+        regex::Regex::new(r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}$")
+            .unwrap()
+            .is_match(&long_iso),
+        "{long_iso}"
+    );
+    // Unknown styles keep the default format
+    assert_eq!(lstart("unknown"), lstart("locale"));
+}
+
+#[test]
+fn test_invalid_date_format() {
+    new_ucmd!()
+        .args(&["--date-format", "%Q", "-o", "lstart"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid date format '%Q'");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_column_widths_from_values() {
+    let result = new_ucmd!()
+        .args(&["-e", "-o", "pid,user,comm", "--no-headers"])
+        .succeeds();
+    let lines = result.stdout_str().lines().collect::<Vec<_>>();
+
+    // The PIDs are right-aligned on the widest one
+    let pid_width = lines
+        .iter()
+        .map(|line| line.split_whitespace().next().unwrap().len())
+        .max()
+        .unwrap();
+    for line in &lines {
+        let pid = line.split_whitespace().next().unwrap();
+        assert_eq!(line.find(pid).unwrap() + pid.len(), pid_width + 1);
+        assert!(!line.ends_with(' '));
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_compact_forest() {
+    let mut shell = std::process::Command::new("sh")
+        .args(["-c", "sh -c 'sleep 10 & sleep 10 & sleep 10 & wait' & wait"])
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let pid = shell.id();
+    let inner = std::fs::read_to_string(format!("/proc/{pid}/task/{pid}/children")).unwrap();
+    let pids = format!("{pid},{}", inner.trim());
+
+    // The inner shell along with its children
+    let args = ["--ppid", &pids, "-o", "comm"];
+    new_ucmd!()
+        .args(&args)
+        .arg("--compact-forest")
+        .succeeds()
+        .stdout_is(" COMMAND\n sh---3*[sleep]\n");
+    new_ucmd!()
+        .args(&args)
+        .arg("--forest")
+        .succeeds()
+        .stdout_is(" COMMAND\n sh\n  \\_ sleep\n  \\_ sleep\n  \\_ sleep\n");
+    // Without their parent, the identical roots are collapsed as well
+    new_ucmd!()
+        .args(&["--ppid", inner.trim(), "-o", "comm", "--compact-forest"])
+        .succeeds()
+        .stdout_is(" COMMAND\n 3*[sleep]\n");
+
+    shell.kill().unwrap();
+    shell.wait().unwrap();
+}
@@ -0,0 +1,102 @@
+// This file is part of the uutils procps package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+#[cfg(target_os = "linux")]
+use std::{os::unix::process::ExitStatusExt, process};
+use uutests::new_ucmd;
+use uutests::util::TestScenario;
+use uutests::util_name;
+
+#[test]
+fn test_no_args() {
+    new_ucmd!().fails().code_is(1);
+}
+
+#[test]
+fn test_no_process_selected() {
+    new_ucmd!()
+        .arg("-u=invalid_user")
+        .fails()
+        .code_is(1)
+        .stderr_contains("no process matched the selection criteria");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_list() {
+    new_ucmd!()
+        .arg("-l")
+        .succeeds()
+        .stdout_contains("HUP INT QUIT");
+    new_ucmd!()
+        .arg("-L")
+        .succeeds()
+        .stdout_contains(" 1 HUP      2 INT");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_invalid_signal() {
+    new_ucmd!()
+        .args(&["--signal", "FOO", "-p", "1"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("unknown signal name FOO");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_signal_process() {
+    for args in [&["-KILL"][..], &["-s", "9"], &["-9", "--threads"]] {
+        let mut child = process::Command::new("sleep")
+            .arg("4326.2")
+            .spawn()
+            .unwrap();
+        let pid = child.id().to_string();
+        new_ucmd!()
+            .args(args)
+            .args(&["-p", &pid])
+            .succeeds()
+            .no_output();
+        assert_eq!(child.wait().unwrap().signal(), Some(9), "{args:?}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_some_targets_failed_as_non_root() {
+    if uucore::process::geteuid() == 0 {
+        print!("Test skipped; requires non-root user");
+        return;
+    }
+
+    // Signaling init requires privileges, the signal 0 only checks for it
+    let pid = process::id().to_string();
+    new_ucmd!()
+        .args(&["-0", "-v", "-p", "1", "-p", &pid])
+        .fails()
+        .code_is(2)
+        .stdout_is(format!("killed pid {pid}\n"))
+        .stderr_contains("killing pid 1 failed: Permission denied");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_threads() {
+    // The tests run in threads of their own, which the signal 0 only checks for
+    let pid = process::id();
+    // SAFETY: gettid has no preconditions
+    let tid = unsafe { libc::gettid() };
+    new_ucmd!()
+        .args(&["-0", "--threads", "-v", "-p", &pid.to_string()])
+        .succeeds()
+        .stdout_contains(format!("killed thread {pid} of pid {pid}\n"))
+        .stdout_contains(format!("killed thread {tid} of pid {pid}\n"));
+
+    new_ucmd!()
+        .args(&["-0", "-v", "-p", &pid.to_string()])
+        .succeeds()
+        .stdout_is(format!("killed pid {pid}\n"));
+}
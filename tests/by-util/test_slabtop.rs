@@ -74,3 +74,40 @@ fn test_once_as_root() {
         }
     }
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_reverse_as_root() {
+    let ts = TestScenario::new(util_name!());
+
+    if let Ok(result) = run_ucmd_as_root(&ts, &["--once", "--sort=n", "--reverse"]) {
+        let names = result
+            .success()
+            .stdout_str()
+            .lines()
+            .skip_while(|line| !line.trim_start().starts_with("OBJS"))
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().last())
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert!(names.is_sorted());
+    } else {
+        print!("Test skipped; requires root user");
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_metrics_as_root() {
+    let ts = TestScenario::new(util_name!());
+
+    if let Ok(result) = run_ucmd_as_root(&ts, &["--metrics"]) {
+        result
+            .success()
+            .stdout_contains("# TYPE slab_objects gauge")
+            .stdout_contains("slab_object_size_bytes{cache=\"")
+            .stdout_does_not_contain("Active / Total Objects");
+    } else {
+        print!("Test skipped; requires root user");
+    }
+}
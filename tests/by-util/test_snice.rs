@@ -3,6 +3,8 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#[cfg(target_os = "linux")]
+use std::process;
 use uutests::new_ucmd;
 use uutests::util::TestScenario;
 use uutests::util_name;
@@ -16,3 +18,46 @@ fn test_no_args() {
 fn test_no_process_selected() {
     new_ucmd!().arg("-u=invalid_user").fails().code_is(1);
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_all_targets_changed() {
+    let mut child = process::Command::new("sleep").arg("10").spawn().unwrap();
+
+    new_ucmd!()
+        .args(&["+1", "-p", &child.id().to_string()])
+        .succeeds()
+        .no_stderr();
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_some_targets_failed_as_non_root() {
+    if uucore::process::geteuid() == 0 {
+        print!("Test skipped; requires non-root user");
+        return;
+    }
+
+    let mut children =
+        ["10", "11"].map(|seconds| process::Command::new("sleep").arg(seconds).spawn().unwrap());
+    let [reniced, other] = children.each_ref().map(|child| child.id().to_string());
+    new_ucmd!().args(&["10", "-p", &reniced]).succeeds();
+
+    // Going back to a lower niceness requires privileges
+    new_ucmd!()
+        .args(&["0", "-p", &reniced, "-p", &other])
+        .fails()
+        .code_is(2)
+        .stderr_contains(format!(
+            "failed to set the priority of process {reniced}: Permission denied"
+        ))
+        .stderr_contains("1 of 2 processes could not be changed");
+
+    for child in &mut children {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -48,6 +48,20 @@ mod linux {
             .stdout_is("kernel.ostype\nfs.overflowuid\n");
     }
 
+    #[test]
+    fn test_get_multi_value() {
+        let printk = std::fs::read_to_string("/proc/sys/kernel/printk").unwrap();
+        assert!(printk.contains('\t'));
+        new_ucmd!()
+            .arg("kernel.printk")
+            .succeeds()
+            .stdout_is(format!("kernel.printk = {printk}"));
+        new_ucmd!()
+            .args(&["-n", "net.ipv4.ip_local_port_range"])
+            .succeeds()
+            .stdout_is(std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range").unwrap());
+    }
+
     #[test]
     fn test_continues_on_error() {
         new_ucmd!()
@@ -69,6 +83,122 @@ mod linux {
             .stdout_is("kernel.ostype = Linux\n")
             .stderr_is("");
     }
+
+    #[test]
+    fn test_protected_key_is_denied() {
+        let ts = TestScenario::new(util_name!());
+        for rule in ["deny kernel.ostype", "# comment\n\ndeny kernel.*"] {
+            ts.fixtures.write("protected", rule);
+            ts.ucmd()
+                .arg("--protect-file=protected")
+                .arg("kernel.ostype=foo")
+                .arg("fs.overflowuid")
+                .fails()
+                .stdout_is("fs.overflowuid = 65534\n")
+                .stderr_is(
+                    "sysctl: refusing to write protected key 'kernel.ostype', \
+                     use --force to override\n",
+                );
+        }
+    }
+
+    #[test]
+    fn test_protected_key_reading_is_allowed() {
+        let ts = TestScenario::new(util_name!());
+        ts.fixtures
+            .write("protected", "deny kernel.ostype\nconfirm fs.*\n");
+        ts.ucmd()
+            .arg("--protect-file=protected")
+            .arg("kernel.ostype")
+            .arg("fs.overflowuid")
+            .succeeds()
+            .stdout_is("kernel.ostype = Linux\nfs.overflowuid = 65534\n");
+
+        // Not even read without a write
+        ts.ucmd()
+            .arg("--protect-file=nonexisting")
+            .arg("kernel.ostype")
+            .succeeds()
+            .stdout_is("kernel.ostype = Linux\n");
+    }
+
+    #[test]
+    fn test_confirmed_key_without_terminal() {
+        let ts = TestScenario::new(util_name!());
+        ts.fixtures.write("protected", "confirm kernel.*\n");
+        ts.ucmd()
+            .arg("--protect-file=protected")
+            .arg("kernel.ostype=foo")
+            .fails()
+            .code_is(1)
+            .stderr_is(
+                "sysctl: refusing to write protected key 'kernel.ostype' without confirmation, \
+                 use --force to override\n",
+            );
+    }
+
+    #[test]
+    fn test_default_confirmed_key_without_terminal() {
+        // The built-in keys are only confirmed on a terminal, 0 is rejected by the kernel
+        new_ucmd!()
+            .arg("vm.drop_caches=0")
+            .fails()
+            .code_is(1)
+            .stderr_contains("error writing key 'vm.drop_caches'");
+    }
+
+    #[test]
+    fn test_invalid_protect_file() {
+        let ts = TestScenario::new(util_name!());
+        ts.fixtures
+            .write("protected", "deny kernel.ostype\nprotect fs.*\n");
+        ts.ucmd()
+            .arg("--protect-file=protected")
+            .arg("kernel.ostype=foo")
+            .fails()
+            .code_is(1)
+            .stderr_is("sysctl: invalid protection rule at line 2\n");
+
+        ts.ucmd()
+            .arg("--protect-file=nonexisting")
+            .arg("kernel.ostype=foo")
+            .fails()
+            .code_is(1)
+            .stderr_contains("cannot read protection file 'nonexisting'");
+    }
+
+    #[test]
+    fn test_target_pid() {
+        // Sharing our namespaces, nothing needs to be joined
+        new_ucmd!()
+            .arg(format!("--target-pid={}", std::process::id()))
+            .arg("kernel.hostname")
+            .arg("kernel.ostype")
+            .succeeds()
+            .stdout_contains("kernel.hostname = ")
+            .stdout_contains("kernel.ostype = Linux\n");
+
+        new_ucmd!()
+            .arg("--target-pid=999999999")
+            .arg("kernel.ostype")
+            .fails()
+            .code_is(1)
+            .no_stdout()
+            .stderr_contains("cannot read namespace of process 999999999");
+    }
+
+    #[test]
+    fn test_origin() {
+        // The type of the kernel is never configured
+        new_ucmd!()
+            .args(&["--origin", "kernel.ostype"])
+            .succeeds()
+            .stdout_is("kernel.ostype = Linux  # not configured\n");
+        new_ucmd!()
+            .args(&["--origin", "-N", "kernel.ostype"])
+            .succeeds()
+            .stdout_is("kernel.ostype  # not configured\n");
+    }
 }
 
 #[cfg(not(target_os = "linux"))]
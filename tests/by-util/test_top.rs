@@ -55,3 +55,218 @@ fn test_arg_p() {
         .succeeds()
         .code_is(0);
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_arg_p_stdin() {
+    let pids = |output: &str| {
+        output
+            .lines()
+            .filter_map(|line| line.split_whitespace().next()?.parse::<u32>().ok())
+            .collect::<Vec<_>>()
+    };
+
+    let result = new_ucmd!().args(&["-p", "-"]).pipe_in("1\n").succeeds();
+    assert_eq!(pids(result.stdout_str()), [1]);
+    // Added to the PIDs of the command line, commas separating them as with pgrep -d,
+    let id = std::process::id();
+    let result = new_ucmd!()
+        .args(&["-p", &id.to_string(), "-p", "-"])
+        .pipe_in("1,2147483647 ")
+        .succeeds();
+    let mut shown = pids(result.stdout_str());
+    shown.sort_unstable();
+    assert_eq!(shown, [1, id]);
+
+    new_ucmd!()
+        .args(&["-p", "-"])
+        .pipe_in("1\nfoo\n")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid PID 'foo' in the standard input");
+    new_ucmd!().args(&["-p", "x"]).fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_per_cpu() {
+    new_ucmd!()
+        .arg("-1")
+        .succeeds()
+        .stdout_contains("%Cpu0  :")
+        .stdout_does_not_contain("%Cpu(s)");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_help_screen() {
+    // Show the help, leave it and quit
+    new_ucmd!()
+        .terminal_simulation(true)
+        .pipe_in("hqq")
+        .succeeds()
+        .stdout_contains("Help for Interactive Commands")
+        .stdout_contains("Toggle a line per CPU in the summary area [off]")
+        .stdout_contains("%Cpu(s)");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_users_window() {
+    // Toggle the per-user window, sort it on the RES column and quit
+    new_ucmd!()
+        .terminal_simulation(true)
+        .pipe_in("A>hqq")
+        .succeeds()
+        .stdout_contains("TASKS  %CPU  RES")
+        .stdout_contains("Toggle the per-user summary window [on]")
+        .stdout_contains("column to the left [RES]");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_scale_mem() {
+    let result = new_ucmd!()
+        .args(&["-E", "g", "-e", "m", "-p", "1"])
+        .succeeds()
+        .stdout_contains("GiB Mem :")
+        .stdout_contains("GiB Swap:")
+        .stdout_str()
+        .to_string();
+
+    let task = result.lines().last().unwrap();
+    let columns = task.split_whitespace().collect::<Vec<_>>();
+    // VIRT, RES and SHR, with the NI column left to do
+    for column in &columns[4..7] {
+        assert!(column.ends_with('m'), "{task}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_scale_keys() {
+    // Cycle the units of both areas, then check them on the help screen
+    new_ucmd!()
+        .terminal_simulation(true)
+        .pipe_in("Eehqq")
+        .succeeds()
+        .stdout_contains("GiB Mem :")
+        .stdout_contains("memory unit of the summary area [GiB]")
+        .stdout_contains("memory unit of the task area [MiB]");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_width() {
+    let result = new_ucmd!().args(&["-w", "50", "-p", "1"]).succeeds();
+    let task = result.stdout_str().lines().last().unwrap();
+    assert_eq!(task.len(), 50);
+
+    let result = new_ucmd!()
+        .env("COLUMNS", "120")
+        .args(&["-p", "1", "-w"])
+        .succeeds();
+    let task = result.stdout_str().lines().last().unwrap();
+    assert_eq!(task.len(), 120);
+
+    new_ucmd!().args(&["-w", "513"]).fails().code_is(1);
+}
+
+#[test]
+#[cfg(all(target_os = "linux", feature = "top-net"))]
+fn test_net_columns() {
+    let result = new_ucmd!().args(&["-p", "1", "--net"]).succeeds();
+    let header = result
+        .stdout_str()
+        .lines()
+        .find(|line| line.contains("PID"))
+        .unwrap();
+    let columns = header.split_whitespace().collect::<Vec<_>>();
+    assert!(
+        columns.ends_with(&["SOCK", "RECVQ", "SENDQ", "COMMAND"]),
+        "{header}"
+    );
+
+    // The columns are only there with the flag
+    let result = new_ucmd!().args(&["-p", "1"]).succeeds();
+    assert!(!result.stdout_str().contains("SOCK"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cmdline_and_idle_toggles() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    // Wait for the exec
+    while !std::fs::read(format!("/proc/{}/cmdline", child.id()))
+        .unwrap_or_default()
+        .starts_with(b"sleep\0")
+    {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let pid = child.id().to_string();
+
+    let task = |args: &[&str]| {
+        let result = new_ucmd!().args(args).succeeds();
+        result
+            .stdout_str()
+            .lines()
+            .last()
+            .unwrap()
+            .trim_end()
+            .to_string()
+    };
+    assert!(task(&["-p", &pid]).ends_with(" sleep"));
+    assert!(task(&["-c", "-p", &pid]).ends_with(" sleep 10"));
+
+    // The sleeping task doesn't use any CPU
+    assert!(task(&["-i", "-p", &pid]).contains("PID"));
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_cumulative_time() {
+    let re = regex::Regex::new(r" \d+:\d\d\.\d\d ").unwrap();
+    let result = new_ucmd!().args(&["-S", "-p", "1"]).succeeds();
+    assert!(re.is_match(result.stdout_str().lines().last().unwrap()));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_secure_and_cumulative_modes_in_help() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["-s"])
+        .pipe_in("hqq")
+        .succeeds()
+        .stdout_contains("Cumulative mode Off.  System: Delay 3.0 secs; Secure mode On.");
+
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["-S"])
+        .pipe_in("hqq")
+        .succeeds()
+        .stdout_contains("Cumulative mode On.")
+        .stdout_contains("adding the time of dead children [on]");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_terminal_restored_on_sigterm() {
+    let mut child = new_ucmd!().terminal_simulation(true).run_no_wait();
+    child.delay(500);
+    // SAFETY: the pid is the one of the child, which is still running.
+    assert_eq!(unsafe { libc::kill(child.id() as i32, libc::SIGTERM) }, 0);
+
+    let result = child.wait().unwrap();
+    result.success();
+    // The cursor is shown and the alternate screen left
+    let output = result.stdout_str();
+    let restored = output.rfind("\x1b[?25h").unwrap();
+    assert!(output[restored..].contains("\x1b[?1049l"), "{output:?}");
+}
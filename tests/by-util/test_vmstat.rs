@@ -6,6 +6,8 @@
 #[cfg(target_os = "linux")]
 use std::time::Duration;
 use uutests::new_ucmd;
+#[cfg(target_os = "linux")]
+use uutests::util::run_ucmd_as_root;
 use uutests::util::TestScenario;
 use uutests::util_name;
 
@@ -30,6 +32,22 @@ fn test_unit() {
     new_ucmd!().args(&["-S", "M"]).succeeds();
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_unit_in_header() {
+    new_ucmd!()
+        .args(&["-S", "k"])
+        .succeeds()
+        .stdout_contains("-memory (kB)-");
+    new_ucmd!()
+        .args(&["-w", "-S", "M"])
+        .succeeds()
+        .stdout_contains("-memory (MiB)-");
+    new_ucmd!()
+        .succeeds()
+        .stdout_contains("-----------memory----------");
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_invalid_unit() {
@@ -50,6 +68,25 @@ fn test_wide_mode() {
     assert!(result.stdout_str().starts_with("--procs--"));
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_wide_mode_alignment() {
+    let result = new_ucmd!().args(&["-w", "-a"]).succeeds();
+    let lines = result.stdout_str().lines().collect::<Vec<_>>();
+    let (sections, titles, values) = (lines[0], lines[1], lines[2]);
+    assert_eq!(sections.len(), titles.len());
+
+    // Every value is right-aligned under its title, with room to spare for the memory
+    for (end, _) in titles.match_indices(|c: char| c.is_alphabetic()) {
+        if titles[end + 1..].starts_with(|c: char| c.is_alphabetic()) {
+            continue;
+        }
+        assert_ne!(values.as_bytes()[end], b' ', "{titles}\n{values}");
+        assert!(values[end + 1..].is_empty() || values[end + 1..].starts_with(' '));
+    }
+    assert!(titles.contains("        swpd         free        inact       active"));
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_no_first() {
@@ -58,6 +95,23 @@ fn test_no_first() {
     assert!(time.elapsed() >= Duration::from_secs(1));
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_delay_and_count() {
+    let time = std::time::Instant::now();
+    let result = new_ucmd!().args(&["1", "3"]).succeeds();
+    assert!(time.elapsed() >= Duration::from_secs(2));
+
+    let lines = result.stdout_str().lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5);
+    for line in &lines[2..] {
+        assert_eq!(
+            line.split_whitespace().count(),
+            lines[1].split_whitespace().count()
+        );
+    }
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_active() {
@@ -70,6 +124,27 @@ fn test_active() {
         .contains("active"));
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_active_replaces_buff_and_cache() {
+    for (args, titles) in [
+        (&["-a"][..], " r  b   swpd   free  inact active   si"),
+        (
+            &["-a", "-w"][..],
+            "   r    b         swpd         free        inact       active   si",
+        ),
+    ] {
+        let result = new_ucmd!().args(args).succeeds();
+        let lines: Vec<_> = result.stdout_str().lines().collect();
+        assert!(lines[1].starts_with(titles), "{}", lines[1]);
+        assert!(!lines[1].contains("buff"));
+        assert_eq!(
+            lines[2].split_whitespace().count(),
+            lines[1].split_whitespace().count()
+        );
+    }
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_timestamp() {
@@ -81,3 +156,303 @@ fn test_timestamp() {
         .unwrap()
         .contains("timestamp"));
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_timestamp_on_every_line() {
+    let is_timestamp = |line: &str| {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let [.., date, time] = fields[..] else {
+            return false;
+        };
+        chrono::NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M:%S")
+            .is_ok()
+    };
+
+    for args in [&["-t", "1", "2"][..], &["-t", "-d"]] {
+        let result = new_ucmd!().args(args).succeeds();
+        let lines = result.stdout_str().lines().collect::<Vec<_>>();
+        assert!(lines[0].ends_with(" -----timestamp-----"));
+        assert!(
+            lines[2..].iter().all(|line| is_timestamp(line)),
+            "{lines:?}"
+        );
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_snapshot_diff() {
+    let ts = TestScenario::new(util_name!());
+    ts.ucmd().args(&["--snapshot", "before"]).succeeds();
+    ts.ucmd().args(&["--snapshot", "after"]).succeeds();
+    assert!(ts.fixtures.read("before").starts_with("# vmstat snapshot"));
+
+    let result = ts.ucmd().args(&["--diff", "before", "after"]).succeeds();
+    assert_eq!(result.stdout_str().lines().count(), 3);
+    assert!(result.stdout_str().starts_with("procs"));
+
+    // the order of the snapshots doesn't matter
+    ts.ucmd().args(&["--diff", "after", "before"]).succeeds();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_mark_wraps() {
+    let ts = TestScenario::new(util_name!());
+    // The context switch counter wraps around at 2^32 between the snapshots
+    for (snapshot, ctxt) in [("before", u64::from(u32::MAX) - 5), ("after", 10)] {
+        ts.ucmd().args(&["--snapshot", snapshot]).succeeds();
+        let content: String = ts
+            .fixtures
+            .read(snapshot)
+            .lines()
+            .map(|line| {
+                if line.starts_with("ctxt ") {
+                    format!("ctxt {ctxt}\n")
+                } else {
+                    format!("{line}\n")
+                }
+            })
+            .collect();
+        ts.fixtures.write(snapshot, &content);
+    }
+
+    ts.ucmd()
+        .args(&["--diff", "before", "after", "--mark-wraps"])
+        .succeeds()
+        .stdout_contains(" wrapped: cs\n");
+    ts.ucmd()
+        .args(&["--diff", "before", "after"])
+        .succeeds()
+        .stdout_does_not_contain("wrapped");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_snapshot_diff_different_boots() {
+    let ts = TestScenario::new(util_name!());
+    ts.ucmd().args(&["--snapshot", "before"]).succeeds();
+    let rebooted: String = ts
+        .fixtures
+        .read("before")
+        .lines()
+        .map(|line| {
+            if line.starts_with("btime ") {
+                "btime 1\n".to_string()
+            } else {
+                format!("{line}\n")
+            }
+        })
+        .collect();
+    ts.fixtures.write("rebooted", &rebooted);
+
+    ts.ucmd()
+        .args(&["--diff", "rebooted", "before"])
+        .succeeds()
+        .stderr_contains("different boots");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_diff_invalid_snapshot() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.write("invalid", "not a snapshot");
+    ts.ucmd()
+        .args(&["--diff", "invalid", "invalid"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid snapshot");
+    ts.ucmd()
+        .args(&["--diff", "missing", "missing"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot read snapshot");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_metrics() {
+    let result = new_ucmd!().arg("--metrics").succeeds();
+    let stdout = result.stdout_str();
+
+    assert!(stdout.contains("# TYPE vmstat_cpu_ticks_total counter\n"));
+    assert!(stdout.contains("vmstat_cpu_ticks_total{cpu=\"all\",mode=\"idle\"} "));
+    assert!(stdout.contains("vmstat_memory_bytes{field=\"MemTotal\"} "));
+    assert!(stdout.contains("vmstat_vm_events{counter=\"pgpgin\"} "));
+    // Every sample has a numeric value
+    for line in stdout.lines().filter(|line| !line.starts_with('#')) {
+        let (_, value) = line.rsplit_once(' ').unwrap();
+        assert!(value.parse::<f64>().is_ok(), "{line}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_disk() {
+    let result = new_ucmd!().arg("-d").succeeds();
+    let mut lines = result.stdout_str().lines();
+    assert!(lines.next().unwrap().starts_with("disk- ---"));
+    assert!(lines.next().unwrap().contains("total merged sectors"));
+    for line in lines {
+        assert_eq!(line.split_whitespace().count(), 11, "{line}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_disk_count() {
+    let result = new_ucmd!().args(&["-d", "1", "2"]).succeeds();
+    let headers = result
+        .stdout_str()
+        .lines()
+        .filter(|line| line.starts_with("disk-"))
+        .count();
+    assert_eq!(headers, 2);
+
+    let result = new_ucmd!().args(&["-d", "-n", "1", "2"]).succeeds();
+    let headers = result
+        .stdout_str()
+        .lines()
+        .filter(|line| line.starts_with("disk-"))
+        .count();
+    assert_eq!(headers, 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_partition() {
+    let diskstats = std::fs::read_to_string("/proc/diskstats").unwrap();
+    let Some(name) = diskstats
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(2))
+    else {
+        return;
+    };
+
+    let result = new_ucmd!().args(&["-p", name, "1", "2"]).succeeds();
+    let lines: Vec<_> = result.stdout_str().lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(name));
+    assert!(lines[0].ends_with("reads     read sectors     writes requested writes"));
+    assert_eq!(lines[1].split_whitespace().count(), 4);
+
+    new_ucmd!()
+        .args(&["-p", &format!("/dev/{name}")])
+        .succeeds()
+        .stdout_contains("read sectors");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_invalid_partition() {
+    new_ucmd!()
+        .args(&["-p", "no_such_partition"])
+        .fails()
+        .code_is(1)
+        .no_stdout()
+        .stderr_contains("partition 'no_such_partition' not found");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_forks() {
+    let stat = std::fs::read_to_string("/proc/stat").unwrap();
+    let before = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("processes "))
+        .unwrap()
+        .parse::<u64>()
+        .unwrap();
+
+    let result = new_ucmd!().arg("-f").succeeds();
+    let stdout = result.stdout_str();
+    // Right-aligned in 13 columns
+    assert_eq!(stdout.len(), 13 + " forks\n".len());
+    let forks = stdout.trim_start().strip_suffix(" forks\n").unwrap();
+    // At least the test runner itself was forked in between
+    assert!(forks.parse::<u64>().unwrap() > before);
+
+    new_ucmd!().args(&["-f", "-s"]).fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_slabs_as_non_root() {
+    if uucore::process::geteuid() == 0 {
+        print!("Test skipped; requires non-root user");
+        return;
+    }
+
+    new_ucmd!()
+        .arg("-m")
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot read /proc/slabinfo: Permission denied");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_slabs_as_root() {
+    let ts = TestScenario::new(util_name!());
+
+    if let Ok(result) = run_ucmd_as_root(&ts, &["-m"]) {
+        let stdout = result.success().stdout_str().to_string();
+        let mut lines = stdout.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Cache                       Num  Total   Size  Pages")
+        );
+        for line in lines {
+            assert_eq!(line.split_whitespace().count(), 5, "{line}");
+        }
+    } else {
+        print!("Test skipped; requires root user");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_stats() {
+    let result = new_ucmd!().arg("-s").succeeds();
+    let stdout = result.stdout_str();
+
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap();
+    let total = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .unwrap()
+        .trim()
+        .trim_end_matches(" kB");
+    assert!(stdout.starts_with(&format!("{total:>13} K total memory\n")));
+    assert!(stdout.contains(" CPU context switches\n"));
+    assert!(stdout.ends_with(" forks\n"));
+    for line in stdout.lines() {
+        let value = line.split_whitespace().next().unwrap();
+        assert!(value.parse::<u64>().is_ok(), "{line}");
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_wide_mode_when_it_fits() {
+    use uutests::util::TerminalSimulation;
+
+    let terminal = |cols| TerminalSimulation {
+        size: Some(libc::winsize {
+            ws_row: 30,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }),
+        stdout: true,
+        ..Default::default()
+    };
+
+    let result = new_ucmd!().terminal_sim_stdio(terminal(200)).succeeds();
+    assert!(result.stdout_str().starts_with("--procs--"));
+
+    let result = new_ucmd!().terminal_sim_stdio(terminal(80)).succeeds();
+    assert!(result.stdout_str().starts_with("procs "));
+}
@@ -36,6 +36,22 @@ fn test_no_header() {
     }
 }
 
+#[test]
+fn test_from() {
+    for arg in ["-f", "--from"] {
+        new_ucmd!()
+            .arg(arg)
+            .arg("--no-resolve")
+            .succeeds()
+            .stdout_contains("USER     TTY       FROM             LOGIN@   IDLE   JCPU");
+    }
+
+    new_ucmd!()
+        .args(&["--short", "--from"])
+        .succeeds()
+        .stdout_contains("USER     TTY      FROM             IDLE   WHAT");
+}
+
 #[test]
 // As of now, --short is only implemented for Linux
 #[cfg(target_os = "linux")]
@@ -33,7 +33,7 @@ fn test_invalid_interval() {
     new_ucmd!()
         .args(&args)
         .fails()
-        .stderr_contains("Invalid argument");
+        .stderr_is("watch: failed to parse argument: 'definitely-not-valid': Invalid argument\n");
 }
 
 #[test]
@@ -87,3 +87,102 @@ fn test_interval_environment_variable() {
         .no_stderr()
         .stdout_is_bytes(b"hellohello");
 }
+
+#[test]
+#[cfg(not(windows))]
+fn test_cursor_movement_is_sanitized() {
+    const CMD: &str = r"printf '\033[H\033[2J\033[1mhello\033[0m'";
+
+    let mut p = new_ucmd!().args(&["-n", "10", CMD]).run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_is_bytes(b"\x1b[1mhello\x1b[0m");
+
+    let mut p = new_ucmd!().args(&["--raw", "-n", "10", CMD]).run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_is_bytes(b"\x1b[H\x1b[2J\x1b[1mhello\x1b[0m");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn test_exit_status_in_header() {
+    new_ucmd!()
+        .terminal_simulation(true)
+        .arg("exit 3")
+        .succeeds()
+        .stdout_contains("Every 2.0s: exit 3")
+        .stdout_contains("\x1b[1;31mexit 3 in ");
+
+    new_ucmd!()
+        .terminal_simulation(true)
+        .args(&["--no-title", "exit 3"])
+        .succeeds()
+        .stdout_does_not_contain("Every");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn test_environment_of_the_command() {
+    const CMD: &str =
+        r#"printf '%s:%s:%s;' "$WATCH_ITERATION" "$WATCH_INTERVAL" "${WATCH_PREV_EXIT-unset}""#;
+
+    let mut p = new_ucmd!().args(&["-n", "0.3", CMD]).run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_is("1:0.3:unset;2:0.3:0;");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn test_json_path() {
+    const CMD: &str = r#"printf '{"runs": [{"count": %s, "name": "watch"}]}' "$WATCH_ITERATION""#;
+
+    let mut p = new_ucmd!()
+        .args(&["-n", "0.3", "--json-path", ".runs[0].count", CMD])
+        .run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_is("1\n2  (+1, +100.0%)\n");
+
+    let mut p = new_ucmd!()
+        .args(&["-n", "10", "--json-path", "runs.0", CMD])
+        .run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_is(".count: 1\n.name: watch\n");
+}
+
+#[test]
+#[cfg(not(windows))]
+fn test_json_path_errors() {
+    new_ucmd!()
+        .args(&["--json-path", "a[x]", TRUE_CMD])
+        .fails()
+        .stderr_is("watch: invalid JSON path: 'a[x]': invalid index at offset 2\n");
+
+    let mut p = new_ucmd!()
+        .args(&["-n", "10", "--json-path", ".missing", "echo '{}'"])
+        .run_no_wait();
+    p.make_assertion_with_delay(500).is_alive();
+    p.kill()
+        .make_assertion()
+        .with_all_output()
+        .no_stderr()
+        .stdout_is("watch: no value at the path\n");
+}
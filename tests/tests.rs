@@ -75,6 +75,14 @@ mod test_pkill;
 #[path = "by-util/test_sysctl.rs"]
 mod test_sysctl;
 
+#[cfg(feature = "hugetop")]
+#[path = "by-util/test_hugetop.rs"]
+mod test_hugetop;
+
 #[cfg(feature = "tload")]
 #[path = "by-util/test_tload.rs"]
 mod test_tload;
+
+#[cfg(feature = "skill")]
+#[path = "by-util/test_skill.rs"]
+mod test_skill;